@@ -1,6 +1,9 @@
 //! This example shows how to implement a grapeheme iterator over the contents
 //! of a `Rope` or `RopeSlice`.  This also serves as a good starting point for
 //! iterators for other kinds of segementation, such as word boundaries.
+//!
+//! With the `graphemes` feature enabled, Ropey provides this iterator itself
+//! via `graphemes()`, so this is mainly useful as a reference.
 
 #![allow(clippy::redundant_field_names)]
 #![allow(dead_code)]
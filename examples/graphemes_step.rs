@@ -2,6 +2,10 @@
 //! grapheme boundaries on a `Rope` or `RopeSlice`.  This also serves as a
 //! good starting point for similar functions for other kinds of segementation,
 //! such as word boundaries.
+//!
+//! With the `graphemes` feature enabled, Ropey provides these functions
+//! itself as methods on `Rope` and `RopeSlice`, so this is mainly useful as a
+//! reference.
 
 #![allow(dead_code)]
 
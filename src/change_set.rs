@@ -0,0 +1,721 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use crate::rope::Rope;
+use crate::str_utils::{char_to_byte_idx, count_chars};
+use crate::{Error, Result};
+
+/// A single operation of a [`ChangeSet`].
+///
+/// All lengths are in `char`s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Leaves the next `n` chars of the text unchanged.
+    Retain(usize),
+    /// Removes the next `n` chars of the text.
+    Delete(usize),
+    /// Inserts the given text at the current position.
+    Insert(String),
+}
+
+/// A sequence of edits to a `Rope`, described as a list of retain, delete,
+/// and insert operations.
+///
+/// The operations are walked left-to-right over the text, and are always
+/// in terms of `char` indices.  This means that a `ChangeSet` can describe
+/// any number of edits without the caller ever having to adjust indices to
+/// account for earlier edits.
+///
+/// A `ChangeSet` is built against a specific text length, and any text
+/// left uncovered by its operations is implicitly retained.  Applying,
+/// composing, or inverting a `ChangeSet` against text of a different length
+/// returns an error rather than panicking.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{ChangeSet, Rope};
+/// #
+/// let mut rope = Rope::from_str("Hello world!");
+/// let original = rope.clone();
+///
+/// let mut changes = ChangeSet::new(&rope);
+/// changes.retain(6).delete(5).insert("everyone");
+/// changes.apply(&mut rope).unwrap();
+/// assert_eq!(rope, "Hello everyone!");
+///
+/// // Undo the change.
+/// changes.invert(&original).unwrap().apply(&mut rope).unwrap();
+/// assert_eq!(rope, "Hello world!");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeSet {
+    ops: Vec<Operation>,
+    len_before: usize,
+    len_after: usize,
+}
+
+impl ChangeSet {
+    /// Creates an empty `ChangeSet` for the text of `rope`.
+    #[inline]
+    pub fn new(rope: &Rope) -> Self {
+        Self::with_len(rope.len_chars())
+    }
+
+    /// Creates an empty `ChangeSet` for text that is `len` chars long.
+    #[inline]
+    pub(crate) fn with_len(len: usize) -> Self {
+        ChangeSet {
+            ops: Vec::new(),
+            len_before: len,
+            len_after: len,
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Building methods
+
+    /// Appends an operation that leaves the next `n` chars unchanged.
+    ///
+    /// Adjacent retains are merged together.
+    pub fn retain(&mut self, n: usize) -> &mut Self {
+        if n == 0 {
+            return self;
+        }
+
+        if let Some(Operation::Retain(ref mut count)) = self.ops.last_mut() {
+            *count += n;
+        } else {
+            self.ops.push(Operation::Retain(n));
+        }
+        self
+    }
+
+    /// Appends an operation that removes the next `n` chars.
+    ///
+    /// Adjacent deletions are merged together.
+    pub fn delete(&mut self, n: usize) -> &mut Self {
+        if n == 0 {
+            return self;
+        }
+
+        self.len_after = self.len_after.wrapping_sub(n);
+        if let Some(Operation::Delete(ref mut count)) = self.ops.last_mut() {
+            *count += n;
+        } else {
+            self.ops.push(Operation::Delete(n));
+        }
+        self
+    }
+
+    /// Appends an operation that inserts `text` at the current position.
+    ///
+    /// Adjacent insertions are merged together.  An insertion directly
+    /// following a deletion is placed before it, so that equivalent
+    /// `ChangeSet`s always have the same operations.
+    pub fn insert(&mut self, text: &str) -> &mut Self {
+        if text.is_empty() {
+            return self;
+        }
+
+        self.len_after = self.len_after.wrapping_add(count_chars(text));
+        match self.ops.last_mut() {
+            Some(Operation::Insert(ref mut existing)) => {
+                existing.push_str(text);
+            }
+            Some(Operation::Delete(_)) => {
+                let delete = self.ops.pop().unwrap();
+                if let Some(Operation::Insert(ref mut existing)) = self.ops.last_mut() {
+                    existing.push_str(text);
+                } else {
+                    self.ops.push(Operation::Insert(text.into()));
+                }
+                self.ops.push(delete);
+            }
+            _ => {
+                self.ops.push(Operation::Insert(text.into()));
+            }
+        }
+        self
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// The operations of the `ChangeSet`, in order.
+    ///
+    /// Note: text after the last operation is implicitly retained, so the
+    /// operations do not necessarily cover the whole text.
+    #[inline]
+    pub fn ops(&self) -> &[Operation] {
+        &self.ops
+    }
+
+    /// The length in chars of the text the `ChangeSet` applies to.
+    #[inline]
+    pub fn len_before(&self) -> usize {
+        self.len_before
+    }
+
+    /// The length in chars of the text after the `ChangeSet` is applied.
+    #[inline]
+    pub fn len_after(&self) -> usize {
+        self.len_after
+    }
+
+    /// Returns whether applying the `ChangeSet` would leave the text
+    /// unchanged.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ops
+            .iter()
+            .all(|op| matches!(*op, Operation::Retain(_)))
+    }
+
+    /// Returns the char ranges that the `ChangeSet` touches, in terms of
+    /// the text _after_ it is applied.
+    ///
+    /// Inserted text shows up as the range it ends up occupying, and removed
+    /// text as an empty range at the position it was removed from.  Touching
+    /// ranges are merged together.
+    pub fn changed_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut pos = 0;
+        for op in self.ops.iter() {
+            let range = match *op {
+                Operation::Retain(n) => {
+                    pos += n;
+                    continue;
+                }
+                Operation::Delete(_) => pos..pos,
+                Operation::Insert(ref text) => {
+                    let start = pos;
+                    pos += count_chars(text);
+                    start..pos
+                }
+            };
+
+            match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit methods
+
+    /// Applies the `ChangeSet` to `rope`.
+    ///
+    /// Runs in O(M + K log N) time, where N is the length of the `Rope`,
+    /// K is the number of operations, and M is the length of the inserted
+    /// and removed text.
+    ///
+    /// # Errors
+    ///
+    /// - If the `ChangeSet`'s operations run past the end of the text it
+    ///   was built for, returns `Error::CharRangeOutOfBounds` for the
+    ///   offending operation.
+    /// - If `rope` isn't the length the `ChangeSet` was built for, returns
+    ///   `Error::ChangeSetLengthMismatch` with the expected length and the
+    ///   actual length of `rope`, in that order.
+    ///
+    /// On error, `rope` is left unmodified.
+    pub fn apply(&self, rope: &mut Rope) -> Result<()> {
+        self.validate()?;
+        if rope.len_chars() != self.len_before {
+            return Err(Error::ChangeSetLengthMismatch(
+                self.len_before,
+                rope.len_chars(),
+            ));
+        }
+
+        // Convert to edits in terms of the original text, merging each
+        // insert with the delete that follows it.
+        let mut edits: Vec<(Range<usize>, &str)> = Vec::new();
+        let mut pos = 0;
+        for op in self.ops.iter() {
+            match *op {
+                Operation::Retain(n) => {
+                    pos += n;
+                }
+                Operation::Delete(n) => {
+                    match edits.last_mut() {
+                        Some(&mut (ref mut range, _)) if range.end == pos => range.end += n,
+                        _ => edits.push((pos..(pos + n), "")),
+                    }
+                    pos += n;
+                }
+                Operation::Insert(ref text) => {
+                    edits.push((pos..pos, text));
+                }
+            }
+        }
+
+        rope.try_apply_edits(edits)
+    }
+
+    /// Combines this `ChangeSet` with `other`, which is expected to apply
+    /// to the text produced by this one.
+    ///
+    /// Applying the returned `ChangeSet` is equivalent to applying `self`
+    /// followed by `other`.
+    ///
+    /// # Errors
+    ///
+    /// - If either `ChangeSet`'s operations run past the end of its text,
+    ///   returns `Error::CharRangeOutOfBounds` for the offending operation.
+    /// - If `other` wasn't built for text of length `self.len_after()`,
+    ///   returns `Error::ChangeSetLengthMismatch` with the expected length and
+    ///   `self.len_after()`, in that order.
+    pub fn compose(&self, other: &ChangeSet) -> Result<ChangeSet> {
+        use self::Operation::*;
+
+        self.validate()?;
+        other.validate()?;
+        if other.len_before != self.len_after {
+            return Err(Error::ChangeSetLengthMismatch(
+                other.len_before,
+                self.len_after,
+            ));
+        }
+
+        let mut changes = ChangeSet::with_len(self.len_before);
+        let mut ops_a = self.full_ops().into_iter();
+        let mut ops_b = other.full_ops().into_iter();
+        let mut head_a = ops_a.next();
+        let mut head_b = ops_b.next();
+
+        loop {
+            match (head_a, head_b) {
+                (None, None) => break,
+
+                // Deletions in `self` and insertions in `other` don't
+                // interact with the other side at all.
+                (Some(Delete(n)), b) => {
+                    changes.delete(n);
+                    head_a = ops_a.next();
+                    head_b = b;
+                }
+                (a, Some(Insert(text))) => {
+                    changes.insert(&text);
+                    head_a = a;
+                    head_b = ops_b.next();
+                }
+
+                (None, _) | (_, None) => {
+                    // Both sides cover the same text length, so they always
+                    // run out at the same time.
+                    unreachable!()
+                }
+
+                (Some(Retain(i)), Some(Retain(j))) => match i.cmp(&j) {
+                    Ordering::Less => {
+                        changes.retain(i);
+                        head_a = ops_a.next();
+                        head_b = Some(Retain(j - i));
+                    }
+                    Ordering::Equal => {
+                        changes.retain(i);
+                        head_a = ops_a.next();
+                        head_b = ops_b.next();
+                    }
+                    Ordering::Greater => {
+                        changes.retain(j);
+                        head_a = Some(Retain(i - j));
+                        head_b = ops_b.next();
+                    }
+                },
+
+                (Some(Insert(text)), Some(Delete(j))) => {
+                    let len = count_chars(&text);
+                    match len.cmp(&j) {
+                        Ordering::Less => {
+                            head_a = ops_a.next();
+                            head_b = Some(Delete(j - len));
+                        }
+                        Ordering::Equal => {
+                            head_a = ops_a.next();
+                            head_b = ops_b.next();
+                        }
+                        Ordering::Greater => {
+                            let byte_idx = char_to_byte_idx(&text, j);
+                            head_a = Some(Insert(text[byte_idx..].into()));
+                            head_b = ops_b.next();
+                        }
+                    }
+                }
+
+                (Some(Insert(text)), Some(Retain(j))) => {
+                    let len = count_chars(&text);
+                    match len.cmp(&j) {
+                        Ordering::Less => {
+                            changes.insert(&text);
+                            head_a = ops_a.next();
+                            head_b = Some(Retain(j - len));
+                        }
+                        Ordering::Equal => {
+                            changes.insert(&text);
+                            head_a = ops_a.next();
+                            head_b = ops_b.next();
+                        }
+                        Ordering::Greater => {
+                            let byte_idx = char_to_byte_idx(&text, j);
+                            changes.insert(&text[..byte_idx]);
+                            head_a = Some(Insert(text[byte_idx..].into()));
+                            head_b = ops_b.next();
+                        }
+                    }
+                }
+
+                (Some(Retain(i)), Some(Delete(j))) => match i.cmp(&j) {
+                    Ordering::Less => {
+                        changes.delete(i);
+                        head_a = ops_a.next();
+                        head_b = Some(Delete(j - i));
+                    }
+                    Ordering::Equal => {
+                        changes.delete(i);
+                        head_a = ops_a.next();
+                        head_b = ops_b.next();
+                    }
+                    Ordering::Greater => {
+                        changes.delete(j);
+                        head_a = Some(Retain(i - j));
+                        head_b = ops_b.next();
+                    }
+                },
+            }
+        }
+
+        changes.trim_trailing_retain();
+        Ok(changes)
+    }
+
+    /// Creates the inverse of this `ChangeSet`, given the `original` text it
+    /// applies to.
+    ///
+    /// Applying the returned `ChangeSet` to the edited text restores
+    /// `original`, which makes this useful for implementing undo.
+    ///
+    /// Runs in O(M + K log N) time, where N is the length of `original`,
+    /// K is the number of operations, and M is the length of the inserted
+    /// and removed text.
+    ///
+    /// # Errors
+    ///
+    /// The same as for [`apply()`](ChangeSet::apply), with `original` in
+    /// place of the rope being edited.
+    pub fn invert(&self, original: &Rope) -> Result<ChangeSet> {
+        self.validate()?;
+        if original.len_chars() != self.len_before {
+            return Err(Error::ChangeSetLengthMismatch(
+                self.len_before,
+                original.len_chars(),
+            ));
+        }
+
+        let mut inverse = ChangeSet::with_len(self.len_after);
+        let mut pos = 0;
+        for op in self.ops.iter() {
+            match *op {
+                Operation::Retain(n) => {
+                    inverse.retain(n);
+                    pos += n;
+                }
+                Operation::Delete(n) => {
+                    for chunk in original.slice(pos..(pos + n)).chunks() {
+                        inverse.insert(chunk);
+                    }
+                    pos += n;
+                }
+                Operation::Insert(ref text) => {
+                    inverse.delete(count_chars(text));
+                }
+            }
+        }
+
+        Ok(inverse)
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Checks that the operations don't run past the end of the text.
+    fn validate(&self) -> Result<()> {
+        let mut pos = 0;
+        for op in self.ops.iter() {
+            match *op {
+                Operation::Retain(n) | Operation::Delete(n) => {
+                    if pos + n > self.len_before {
+                        return Err(Error::CharRangeOutOfBounds(
+                            Some(pos),
+                            Some(pos + n),
+                            self.len_before,
+                        ));
+                    }
+                    pos += n;
+                }
+                Operation::Insert(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the operations with the implicit trailing retain made
+    /// explicit.
+    ///
+    /// Assumes the `ChangeSet` has already been validated.
+    fn full_ops(&self) -> Vec<Operation> {
+        let covered: usize = self
+            .ops
+            .iter()
+            .map(|op| match *op {
+                Operation::Retain(n) | Operation::Delete(n) => n,
+                Operation::Insert(_) => 0,
+            })
+            .sum();
+
+        let mut ops = self.ops.clone();
+        if covered < self.len_before {
+            ops.push(Operation::Retain(self.len_before - covered));
+        }
+        ops
+    }
+
+    /// Removes a trailing retain, since it's implicit anyway.
+    fn trim_trailing_retain(&mut self) {
+        if let Some(Operation::Retain(_)) = self.ops.last() {
+            self.ops.pop();
+        }
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 124 bytes, 100 chars, 4 lines
+    const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's \
+                              a fine day, isn't it?\nAren't you glad \
+                              we're alive?\nこんにちは、みんなさん！";
+
+    #[test]
+    fn build_01() {
+        let r = Rope::from_str("Hello world!");
+        let mut c = ChangeSet::new(&r);
+        c.retain(2)
+            .retain(3)
+            .delete(1)
+            .delete(2)
+            .insert("a")
+            .insert("b");
+
+        assert_eq!(
+            c.ops(),
+            &[
+                Operation::Retain(5),
+                Operation::Insert("ab".into()),
+                Operation::Delete(3),
+            ]
+        );
+        assert_eq!(c.len_before(), 12);
+        assert_eq!(c.len_after(), 11);
+    }
+
+    #[test]
+    fn build_02() {
+        let r = Rope::from_str("Hello world!");
+        let mut c = ChangeSet::new(&r);
+        assert!(c.is_empty());
+        c.retain(0).delete(0).insert("");
+        assert!(c.is_empty());
+        assert_eq!(c.ops(), &[]);
+        c.retain(5);
+        assert!(c.is_empty());
+        c.insert("!");
+        assert!(!c.is_empty());
+    }
+
+    #[test]
+    fn apply_01() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        let mut c = ChangeSet::new(&r);
+        c.retain(6)
+            .delete(5)
+            .insert("everybody")
+            .retain(83)
+            .insert("世界");
+        c.apply(&mut r).unwrap();
+
+        assert_eq!(
+            r,
+            "Hello everybody!  How're you doing?\nIt's \
+             a fine day, isn't it?\nAren't you glad \
+             we're alive?\nこんにちは、世界みんなさん！"
+        );
+        assert_eq!(r.len_chars(), c.len_after());
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn apply_02() {
+        let mut r = Rope::from_str("Hello\r\nworld");
+        let mut c = ChangeSet::new(&r);
+        c.retain(6).insert("\r\n").retain(6).insert("\n");
+        c.apply(&mut r).unwrap();
+
+        assert_eq!(r, "Hello\r\r\n\nworld\n");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn apply_03() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        let c = ChangeSet::new(&r);
+        c.apply(&mut r).unwrap();
+        assert_eq!(r, TEXT_LINES);
+    }
+
+    #[test]
+    fn apply_04() {
+        // Mismatched rope length.
+        let mut r = Rope::from_str("Hello world!");
+        let mut c = ChangeSet::new(&r);
+        c.retain(2).delete(3);
+        r.insert(0, "Oh, ");
+
+        assert!(matches!(
+            c.apply(&mut r),
+            Err(Error::ChangeSetLengthMismatch(12, 16))
+        ));
+        assert_eq!(r, "Oh, Hello world!");
+    }
+
+    #[test]
+    fn apply_05() {
+        // Operations running past the end.
+        let mut r = Rope::from_str("Hello world!");
+        let mut c = ChangeSet::new(&r);
+        c.retain(10).insert("!").delete(3);
+
+        assert!(c.apply(&mut r).is_err());
+        assert_eq!(r, "Hello world!");
+    }
+
+    #[test]
+    fn changed_ranges_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut c = ChangeSet::new(&r);
+        c.retain(6)
+            .delete(5)
+            .insert("everybody")
+            .retain(10)
+            .delete(3)
+            .retain(2)
+            .insert("abc");
+
+        assert_eq!(c.changed_ranges(), vec![6..15, 25..25, 27..30]);
+    }
+
+    #[test]
+    fn compose_01() {
+        let mut r1 = Rope::from_str(TEXT_LINES);
+        let mut r2 = r1.clone();
+
+        let mut a = ChangeSet::new(&r1);
+        a.retain(6)
+            .delete(5)
+            .insert("everybody")
+            .retain(20)
+            .delete(3);
+        let mut b = ChangeSet::with_len(a.len_after());
+        b.retain(3)
+            .insert("xyz")
+            .retain(5)
+            .delete(7)
+            .insert("!!")
+            .retain(30);
+
+        a.apply(&mut r1).unwrap();
+        b.apply(&mut r1).unwrap();
+
+        let c = a.compose(&b).unwrap();
+        c.apply(&mut r2).unwrap();
+
+        assert_eq!(r1, r2);
+        assert_eq!(c.len_before(), 100);
+        assert_eq!(c.len_after(), r2.len_chars());
+    }
+
+    #[test]
+    fn compose_02() {
+        // Deleting text that was just inserted.
+        let r = Rope::from_str("Hello world!");
+        let mut a = ChangeSet::new(&r);
+        a.retain(5).insert(", wonderful");
+        let mut b = ChangeSet::with_len(a.len_after());
+        b.retain(7).delete(4).insert("beautiful");
+
+        let c = a.compose(&b).unwrap();
+        assert_eq!(
+            c.ops(),
+            &[
+                Operation::Retain(5),
+                Operation::Insert(", beautifulerful".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn compose_03() {
+        let r = Rope::from_str("Hello world!");
+        let mut a = ChangeSet::new(&r);
+        a.retain(5).insert(", wonderful");
+        let b = ChangeSet::new(&r);
+
+        assert!(matches!(
+            a.compose(&b),
+            Err(Error::ChangeSetLengthMismatch(12, 23))
+        ));
+    }
+
+    #[test]
+    fn invert_01() {
+        let original = Rope::from_str(TEXT_LINES);
+        let mut r = original.clone();
+
+        let mut c = ChangeSet::new(&r);
+        c.retain(6)
+            .delete(5)
+            .insert("everybody")
+            .retain(30)
+            .delete(40)
+            .insert("こんにちは");
+        c.apply(&mut r).unwrap();
+
+        let inverse = c.invert(&original).unwrap();
+        assert_eq!(inverse.len_before(), c.len_after());
+        assert_eq!(inverse.len_after(), c.len_before());
+        inverse.apply(&mut r).unwrap();
+
+        assert_eq!(r, original);
+    }
+
+    #[test]
+    fn invert_02() {
+        let r = Rope::from_str("Hello world!");
+        let mut c = ChangeSet::new(&r);
+        c.delete(2);
+
+        assert!(matches!(
+            c.invert(&Rope::from_str("Hello")),
+            Err(Error::ChangeSetLengthMismatch(12, 5))
+        ));
+    }
+}
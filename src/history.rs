@@ -0,0 +1,693 @@
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::change_set::{ChangeSet, Operation};
+use crate::rope::Rope;
+use crate::str_utils::count_chars;
+use crate::{Error, Result};
+
+/// An undo/redo history of a `Rope`'s text.
+///
+/// Each revision stores a snapshot of the text along with the
+/// [`ChangeSet`] that produced it.  Because cloning a `Rope` is `O(1)` and
+/// clones share all unchanged data, keeping a snapshot per revision costs
+/// little more memory than the edits themselves, and undoing or redoing is
+/// just a matter of swapping snapshots.
+///
+/// Revisions form a tree rather than a list: committing after an undo
+/// starts a new branch instead of discarding the undone revisions.  `undo()`
+/// and `redo()` move along the most recently used branch, while
+/// [`earlier()`](History::earlier), [`later()`](History::later), and
+/// [`jump_to()`](History::jump_to) can reach any revision.  Revisions are
+/// identified by their index in commit order, with the initial text being
+/// revision `0`.
+///
+/// Consecutive single-char insertions (i.e. typing) are grouped into a
+/// single revision, so that they are undone together.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{ChangeSet, History, Rope};
+/// #
+/// let mut rope = Rope::from_str("Hello world!");
+/// let mut history = History::new(&rope);
+///
+/// let mut changes = ChangeSet::new(&rope);
+/// changes.retain(5).insert(",");
+/// changes.apply(&mut rope).unwrap();
+/// history.commit(changes, &rope).unwrap();
+/// assert_eq!(rope, "Hello, world!");
+///
+/// let touched = history.undo(&mut rope).unwrap();
+/// assert_eq!(rope, "Hello world!");
+/// assert_eq!(touched, vec![5..5]);
+///
+/// history.redo(&mut rope).unwrap();
+/// assert_eq!(rope, "Hello, world!");
+/// ```
+#[derive(Debug, Clone)]
+pub struct History {
+    revisions: Vec<Revision>,
+    current: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Revision {
+    /// The text at this revision.
+    text: Rope,
+    /// The revision this one was committed on top of.  The root revision
+    /// is its own parent.
+    parent: usize,
+    /// The most recently committed or visited child, which is where
+    /// `redo()` goes.
+    last_child: Option<usize>,
+    /// Changes from the parent revision's text to this one.
+    changes: ChangeSet,
+    /// Changes from this revision's text back to the parent's.
+    inversion: ChangeSet,
+    /// When the revision was committed.
+    timestamp: Instant,
+    /// If this revision is a group of char insertions, the char index
+    /// where an insertion would continue the group.
+    insert_group_end: Option<usize>,
+}
+
+impl History {
+    /// Creates a new `History`, with the text of `rope` as its initial
+    /// revision.
+    pub fn new(rope: &Rope) -> Self {
+        History {
+            revisions: vec![Revision {
+                text: rope.clone(),
+                parent: 0,
+                last_child: None,
+                changes: ChangeSet::new(rope),
+                inversion: ChangeSet::new(rope),
+                timestamp: Instant::now(),
+                insert_group_end: None,
+            }],
+            current: 0,
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// The text at the current revision.
+    #[inline]
+    pub fn text(&self) -> &Rope {
+        &self.revisions[self.current].text
+    }
+
+    /// The id of the current revision.
+    #[inline]
+    pub fn current_revision(&self) -> usize {
+        self.current
+    }
+
+    /// Total number of revisions, including the initial one.
+    #[inline]
+    pub fn len_revisions(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// Returns whether there is a revision to undo.
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    /// Returns whether there is an undone revision to redo.
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        self.revisions[self.current].last_child.is_some()
+    }
+
+    /// Returns the changes that turn the text of revision `from` into the
+    /// text of revision `to`.
+    ///
+    /// The changes are composed along the path between the two revisions
+    /// in the revision tree, so only the text that actually differs
+    /// between the two gets touched when they're applied.  This makes it
+    /// suitable for incrementally updating views of the text.
+    ///
+    /// Returns `None` if either revision doesn't exist.
+    ///
+    /// Runs in O(D * K) time, where D is the number of revisions between
+    /// the two and K is the number of operations in their changes.
+    pub fn changes_between(&self, from: usize, to: usize) -> Option<ChangeSet> {
+        if from >= self.revisions.len() || to >= self.revisions.len() {
+            return None;
+        }
+
+        // Walk up from both ends until the paths meet.  Parents always have
+        // lower ids than their children, so the higher id is the one that
+        // can't be the common ancestor.
+        let mut up = Vec::new();
+        let mut down = Vec::new();
+        let (mut a, mut b) = (from, to);
+        while a != b {
+            if a > b {
+                up.push(a);
+                a = self.revisions[a].parent;
+            } else {
+                down.push(b);
+                b = self.revisions[b].parent;
+            }
+        }
+
+        let mut changes = ChangeSet::new(&self.revisions[from].text);
+        for &i in up.iter() {
+            changes = changes.compose(&self.revisions[i].inversion).unwrap();
+        }
+        for &i in down.iter().rev() {
+            changes = changes.compose(&self.revisions[i].changes).unwrap();
+        }
+        Some(changes)
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit methods
+
+    /// Records a new revision.
+    ///
+    /// `changes` are the edits that were made to the current revision's
+    /// text, and `rope` is the resulting text.  The new revision becomes a
+    /// child of the current one.
+    ///
+    /// If both `changes` and the current revision are single-char
+    /// insertions, and `changes` inserts right after the current
+    /// revision's insertion, the two are merged into a single revision.
+    ///
+    /// Runs in O(M + K log N) time, where N is the length of the text, K
+    /// is the number of operations in `changes`, and M is the length of the
+    /// text removed by `changes`.
+    ///
+    /// # Errors
+    ///
+    /// - If `changes` wasn't built for the current revision's text, returns
+    ///   `Error::ChangeSetLengthMismatch` with `changes.len_before()` and the
+    ///   length of the current revision's text, in that order.
+    /// - If `rope` can't be the result of `changes`, returns
+    ///   `Error::ChangeSetLengthMismatch` with `changes.len_after()` and the
+    ///   length of `rope`, in that order.
+    #[inline]
+    pub fn commit(&mut self, changes: ChangeSet, rope: &Rope) -> Result<()> {
+        self.commit_at(changes, rope, Instant::now())
+    }
+
+    /// Reverts `rope` to the parent of the current revision.
+    ///
+    /// Returns the char ranges touched by the undo, in terms of the
+    /// reverted text, which is useful for restoring cursor positions.  See
+    /// [`ChangeSet::changed_ranges()`] for details.  Returns `None` if there
+    /// is nothing to undo.
+    ///
+    /// `rope` is expected to contain the current revision's text.
+    ///
+    /// Runs in O(K) time, where K is the number of operations in the
+    /// undone revision.
+    pub fn undo(&mut self, rope: &mut Rope) -> Option<Vec<Range<usize>>> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        let ranges = self.revisions[self.current].inversion.changed_ranges();
+        self.set_current(self.revisions[self.current].parent, rope);
+        Some(ranges)
+    }
+
+    /// Moves `rope` to the most recently used child of the current
+    /// revision.
+    ///
+    /// Returns the char ranges touched by the redo, in terms of the new
+    /// text.  Returns `None` if there is nothing to redo.
+    ///
+    /// `rope` is expected to contain the current revision's text.
+    ///
+    /// Runs in O(K) time, where K is the number of operations in the
+    /// redone revision.
+    pub fn redo(&mut self, rope: &mut Rope) -> Option<Vec<Range<usize>>> {
+        let child = self.revisions[self.current].last_child?;
+        self.set_current(child, rope);
+        Some(self.revisions[child].changes.changed_ranges())
+    }
+
+    /// Moves `rope` to the revision with id `revision`, which can be on any
+    /// branch of the revision tree.
+    ///
+    /// Afterwards, `undo()` and `redo()` move along the branch that
+    /// `revision` is on.
+    ///
+    /// Returns the char ranges touched, in terms of the new text.  Returns
+    /// `None` if the revision doesn't exist.
+    ///
+    /// `rope` is expected to contain the current revision's text.
+    pub fn jump_to(&mut self, revision: usize, rope: &mut Rope) -> Option<Vec<Range<usize>>> {
+        let changes = self.changes_between(self.current, revision)?;
+
+        // Make redo retrace the path to the target revision.
+        let mut i = revision;
+        while i != 0 && !self.is_ancestor(i, self.current) {
+            let parent = self.revisions[i].parent;
+            self.revisions[parent].last_child = Some(i);
+            i = parent;
+        }
+
+        self.set_current(revision, rope);
+        Some(changes.changed_ranges())
+    }
+
+    /// Moves `rope` back in time, to the revision that was current
+    /// `duration` before the current revision was committed.
+    ///
+    /// Unlike `undo()`, this walks revisions in the order they were
+    /// committed regardless of branches, so earlier states of the text
+    /// remain reachable even after undoing and making new edits.
+    ///
+    /// Returns the char ranges touched, in terms of the new text.  Returns
+    /// `None` if already at the initial revision.
+    ///
+    /// `rope` is expected to contain the current revision's text.
+    pub fn earlier(&mut self, duration: Duration, rope: &mut Rope) -> Option<Vec<Range<usize>>> {
+        if self.current == 0 {
+            return None;
+        }
+
+        let target = match self.revisions[self.current].timestamp.checked_sub(duration) {
+            Some(time) => self
+                .revisions
+                .partition_point(|r| r.timestamp <= time)
+                .saturating_sub(1),
+            None => 0,
+        };
+        self.jump_to(target.min(self.current - 1), rope)
+    }
+
+    /// Moves `rope` forward in time, to the last revision committed within
+    /// `duration` after the current revision was committed.
+    ///
+    /// This is the opposite of [`earlier()`](History::earlier).
+    ///
+    /// Returns the char ranges touched, in terms of the new text.  Returns
+    /// `None` if already at the most recently committed revision.
+    ///
+    /// `rope` is expected to contain the current revision's text.
+    pub fn later(&mut self, duration: Duration, rope: &mut Rope) -> Option<Vec<Range<usize>>> {
+        if self.current + 1 == self.revisions.len() {
+            return None;
+        }
+
+        let target = match self.revisions[self.current].timestamp.checked_add(duration) {
+            Some(time) => self.revisions.partition_point(|r| r.timestamp <= time) - 1,
+            None => self.revisions.len() - 1,
+        };
+        self.jump_to(target.max(self.current + 1), rope)
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Implementation of `commit()`, with an explicit timestamp.
+    pub(crate) fn commit_at(
+        &mut self,
+        changes: ChangeSet,
+        rope: &Rope,
+        timestamp: Instant,
+    ) -> Result<()> {
+        if changes.len_before() != self.text().len_chars() {
+            return Err(Error::ChangeSetLengthMismatch(
+                changes.len_before(),
+                self.text().len_chars(),
+            ));
+        }
+        if changes.len_after() != rope.len_chars() {
+            return Err(Error::ChangeSetLengthMismatch(
+                changes.len_after(),
+                rope.len_chars(),
+            ));
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+
+        let insert_pos = single_char_insert(&changes);
+        let current = &self.revisions[self.current];
+
+        // Only a revision without children can be merged into, since
+        // children are based on its text.
+        if self.current > 0
+            && current.last_child.is_none()
+            && insert_pos.is_some()
+            && insert_pos == current.insert_group_end
+        {
+            let parent = current.parent;
+            let changes = current.changes.compose(&changes)?;
+            let inversion = changes.invert(&self.revisions[parent].text)?;
+            self.revisions[self.current] = Revision {
+                text: rope.clone(),
+                parent: parent,
+                last_child: None,
+                changes: changes,
+                inversion: inversion,
+                timestamp: timestamp,
+                insert_group_end: insert_pos.map(|pos| pos + 1),
+            };
+        } else {
+            let inversion = changes.invert(&current.text)?;
+            let new_idx = self.revisions.len();
+            self.revisions[self.current].last_child = Some(new_idx);
+            self.revisions.push(Revision {
+                text: rope.clone(),
+                parent: self.current,
+                last_child: None,
+                changes: changes,
+                inversion: inversion,
+                timestamp: timestamp,
+                insert_group_end: insert_pos.map(|pos| pos + 1),
+            });
+            self.current = new_idx;
+        }
+
+        Ok(())
+    }
+
+    /// Makes `revision` the current revision, and sets `rope` to its text.
+    fn set_current(&mut self, revision: usize, rope: &mut Rope) {
+        // Don't continue an insertion group after moving around.
+        self.revisions[self.current].insert_group_end = None;
+        self.revisions[revision].insert_group_end = None;
+
+        self.current = revision;
+        let mode = rope.line_break_mode();
+        *rope = self.text().clone();
+        rope.set_line_break_mode(mode);
+    }
+
+    /// Returns whether `ancestor` is `revision` or one of its ancestors.
+    fn is_ancestor(&self, ancestor: usize, revision: usize) -> bool {
+        let mut i = revision;
+        loop {
+            if i == ancestor {
+                return true;
+            }
+            if i < ancestor || i == 0 {
+                return false;
+            }
+            i = self.revisions[i].parent;
+        }
+    }
+}
+
+/// Returns the insertion position if `changes` does nothing but insert a
+/// single char.
+fn single_char_insert(changes: &ChangeSet) -> Option<usize> {
+    let (pos, text) = match *changes.ops() {
+        [Operation::Insert(ref text)] => (0, text),
+        [Operation::Retain(n), Operation::Insert(ref text)] => (n, text),
+        _ => return None,
+    };
+
+    if count_chars(text) == 1 {
+        Some(pos)
+    } else {
+        None
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(ranges: Option<Vec<Range<usize>>>) -> Vec<(usize, usize)> {
+        ranges.unwrap().iter().map(|r| (r.start, r.end)).collect()
+    }
+
+    fn type_char(rope: &mut Rope, history: &mut History, char_idx: usize, ch: char) {
+        let mut buf = [0u8; 4];
+        let mut changes = ChangeSet::new(rope);
+        changes.retain(char_idx).insert(ch.encode_utf8(&mut buf));
+        changes.apply(rope).unwrap();
+        history.commit(changes, rope).unwrap();
+    }
+
+    #[test]
+    fn undo_redo_01() {
+        let mut r = Rope::from_str("Hello world!");
+        let mut h = History::new(&r);
+        assert!(!h.can_undo());
+        assert!(!h.can_redo());
+        assert!(h.undo(&mut r).is_none());
+        assert!(h.redo(&mut r).is_none());
+
+        let mut c = ChangeSet::new(&r);
+        c.retain(6).delete(5).insert("everyone");
+        c.apply(&mut r).unwrap();
+        h.commit(c, &r).unwrap();
+
+        let mut c = ChangeSet::new(&r);
+        c.delete(6);
+        c.apply(&mut r).unwrap();
+        h.commit(c, &r).unwrap();
+        assert_eq!(r, "everyone!");
+
+        assert_eq!(spans(h.undo(&mut r)), [(0, 6)]);
+        assert_eq!(r, "Hello everyone!");
+        assert_eq!(spans(h.undo(&mut r)), [(6, 11)]);
+        assert_eq!(r, "Hello world!");
+        assert!(h.undo(&mut r).is_none());
+
+        assert_eq!(spans(h.redo(&mut r)), [(6, 14)]);
+        assert_eq!(r, "Hello everyone!");
+        assert_eq!(spans(h.redo(&mut r)), [(0, 0)]);
+        assert_eq!(r, "everyone!");
+        assert!(h.redo(&mut r).is_none());
+        assert_eq!(h.text(), &r);
+    }
+
+    #[test]
+    fn undo_redo_02() {
+        // Committing after an undo starts a new branch.
+        let mut r = Rope::from_str("abc");
+        let mut h = History::new(&r);
+
+        let mut c = ChangeSet::new(&r);
+        c.delete(1);
+        c.apply(&mut r).unwrap();
+        h.commit(c, &r).unwrap();
+
+        h.undo(&mut r);
+        assert!(h.can_redo());
+
+        let mut c = ChangeSet::new(&r);
+        c.retain(3).insert("def");
+        c.apply(&mut r).unwrap();
+        h.commit(c, &r).unwrap();
+
+        assert!(!h.can_redo());
+        h.undo(&mut r);
+        assert_eq!(r, "abc");
+        assert!(!h.can_undo());
+    }
+
+    #[test]
+    fn group_01() {
+        let mut r = Rope::from_str("Hello!");
+        let mut h = History::new(&r);
+
+        for (i, ch) in " world".chars().enumerate() {
+            type_char(&mut r, &mut h, 5 + i, ch);
+        }
+        assert_eq!(r, "Hello world!");
+
+        // Typing somewhere else starts a new group.
+        type_char(&mut r, &mut h, 0, '¡');
+        type_char(&mut r, &mut h, 1, '¡');
+        assert_eq!(r, "¡¡Hello world!");
+
+        assert_eq!(spans(h.undo(&mut r)), [(0, 0)]);
+        assert_eq!(r, "Hello world!");
+        assert_eq!(spans(h.undo(&mut r)), [(5, 5)]);
+        assert_eq!(r, "Hello!");
+        assert!(!h.can_undo());
+    }
+
+    #[test]
+    fn group_02() {
+        // Typing after an undo doesn't continue the undone group.
+        let mut r = Rope::from_str("");
+        let mut h = History::new(&r);
+
+        type_char(&mut r, &mut h, 0, 'a');
+        type_char(&mut r, &mut h, 1, 'b');
+        type_char(&mut r, &mut h, 2, 'c');
+        let mut c = ChangeSet::new(&r);
+        c.retain(3).insert("de");
+        c.apply(&mut r).unwrap();
+        h.commit(c, &r).unwrap();
+
+        h.undo(&mut r);
+        assert_eq!(r, "abc");
+        type_char(&mut r, &mut h, 3, 'x');
+        assert_eq!(r, "abcx");
+
+        h.undo(&mut r);
+        assert_eq!(r, "abc");
+        h.undo(&mut r);
+        assert_eq!(r, "");
+    }
+
+    #[test]
+    fn commit_01() {
+        let mut r = Rope::from_str("Hello world!");
+        let mut h = History::new(&r);
+
+        let mut c = ChangeSet::new(&r);
+        c.delete(6);
+        assert!(matches!(
+            h.commit(c.clone(), &r),
+            Err(Error::ChangeSetLengthMismatch(6, 12))
+        ));
+
+        c.apply(&mut r).unwrap();
+        assert!(h.commit(c.clone(), &r).is_ok());
+        assert!(h.commit(c, &r).is_err());
+    }
+
+    fn commit_edit(
+        rope: &mut Rope,
+        history: &mut History,
+        time: Instant,
+        edit: impl Fn(&mut ChangeSet),
+    ) {
+        let mut changes = ChangeSet::new(rope);
+        edit(&mut changes);
+        changes.apply(rope).unwrap();
+        history.commit_at(changes, rope, time).unwrap();
+    }
+
+    #[test]
+    fn branch_01() {
+        let mut r = Rope::from_str("abc");
+        let mut h = History::new(&r);
+        let t = Instant::now();
+
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.retain(3).insert("d");
+        });
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.delete(1);
+        });
+        assert_eq!(r, "bcd");
+        h.undo(&mut r);
+        h.undo(&mut r);
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.insert("x");
+        });
+        assert_eq!(r, "xabc");
+        assert_eq!(h.current_revision(), 3);
+        assert_eq!(h.len_revisions(), 4);
+
+        // The old branch is still reachable.
+        assert_eq!(spans(h.jump_to(2, &mut r)), [(0, 0), (2, 3)]);
+        assert_eq!(r, "bcd");
+        assert_eq!(spans(h.undo(&mut r)), [(0, 1)]);
+        assert_eq!(r, "abcd");
+        assert_eq!(spans(h.undo(&mut r)), [(3, 3)]);
+        assert_eq!(r, "abc");
+
+        // Redo follows the branch that was jumped to.
+        h.redo(&mut r);
+        h.redo(&mut r);
+        assert_eq!(r, "bcd");
+        assert!(!h.can_redo());
+
+        assert!(h.jump_to(4, &mut r).is_none());
+        assert_eq!(r, "bcd");
+    }
+
+    #[test]
+    fn changes_between_01() {
+        let mut r = Rope::from_str("Hello world!");
+        let mut h = History::new(&r);
+        let t = Instant::now();
+        let mut texts = vec![r.clone()];
+
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.retain(5).insert(",");
+        });
+        texts.push(r.clone());
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.retain(7).delete(5).insert("everyone!");
+        });
+        texts.push(r.clone());
+        h.undo(&mut r);
+        h.undo(&mut r);
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.retain(12).insert("!!");
+        });
+        texts.push(r.clone());
+        commit_edit(&mut r, &mut h, t, |c| {
+            c.delete(6);
+        });
+        texts.push(r.clone());
+
+        for from in 0..texts.len() {
+            for to in 0..texts.len() {
+                let mut text = texts[from].clone();
+                h.changes_between(from, to)
+                    .unwrap()
+                    .apply(&mut text)
+                    .unwrap();
+                assert_eq!(text, texts[to]);
+            }
+        }
+
+        assert!(h.changes_between(0, 5).is_none());
+        assert!(h.changes_between(5, 0).is_none());
+        assert!(h.changes_between(1, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn earlier_later_01() {
+        let mut r = Rope::from_str("");
+        let mut h = History::new(&r);
+        let t = h.revisions[0].timestamp;
+        let sec = Duration::from_secs(1);
+
+        commit_edit(&mut r, &mut h, t + sec, |c| {
+            c.insert("one ");
+        });
+        commit_edit(&mut r, &mut h, t + sec * 2, |c| {
+            c.retain(4).insert("two ");
+        });
+        h.undo(&mut r);
+        commit_edit(&mut r, &mut h, t + sec * 3, |c| {
+            c.retain(4).insert("three ");
+        });
+        commit_edit(&mut r, &mut h, t + sec * 10, |c| {
+            c.retain(10).insert("four ");
+        });
+        assert_eq!(r, "one three four ");
+
+        // Time travel reaches the undone branch too.
+        h.earlier(sec * 5, &mut r);
+        assert_eq!(r, "one three ");
+        h.earlier(sec, &mut r);
+        assert_eq!(r, "one two ");
+        h.earlier(sec * 100, &mut r);
+        assert_eq!(r, "");
+        assert!(h.earlier(sec, &mut r).is_none());
+
+        h.later(sec * 2, &mut r);
+        assert_eq!(r, "one two ");
+        h.later(Duration::from_millis(1), &mut r);
+        assert_eq!(r, "one three ");
+        h.later(sec * 100, &mut r);
+        assert_eq!(r, "one three four ");
+        assert!(h.later(sec, &mut r).is_none());
+    }
+}
@@ -0,0 +1,785 @@
+use std::ops::Range;
+
+use crate::change_set::{ChangeSet, Operation};
+use crate::rope::Rope;
+use crate::str_utils::count_chars;
+use crate::tracked::EditObserver;
+
+/// Which edges of an interval grow to include text inserted exactly at
+/// them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Expand {
+    /// Text inserted at either edge ends up outside the interval.
+    Neither,
+    /// Text inserted at the start edge ends up inside the interval.
+    Start,
+    /// Text inserted at the end edge ends up inside the interval.
+    End,
+    /// Text inserted at either edge ends up inside the interval.
+    Both,
+}
+
+impl Expand {
+    #[inline]
+    fn start(self) -> bool {
+        self == Expand::Start || self == Expand::Both
+    }
+
+    #[inline]
+    fn end(self) -> bool {
+        self == Expand::End || self == Expand::Both
+    }
+}
+
+/// A set of char ranges with attached values, which follow edits to a
+/// `Rope`.
+///
+/// This is meant for things like syntax highlighting spans and diagnostic
+/// underlines.  How an interval reacts to text inserted at its edges is
+/// decided by its [`Expand`] rule, and intervals that become empty because
+/// their text was removed are removed from the set.
+///
+/// As with [`MarkerSet`](crate::MarkerSet), edits to the text have to be
+/// passed on to the set.  `IntervalSet` is an [`EditObserver`], so a
+/// [`TrackedRope`](crate::TrackedRope) can do that automatically, or it
+/// can be done by hand via the `update_for_*()` methods.
+///
+/// Intervals are stored in a balanced tree ordered by start, where each
+/// node also knows the max end of its subtree and carries a pending shift
+/// for its children.  This makes both overlap queries and edit updates
+/// fast: an edit shifts everything after it in one step, and only has to
+/// visit the intervals that touch it.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Expand, IntervalSet, Rope};
+/// #
+/// let mut rope = Rope::from_str("fn main() {}\n");
+/// let mut spans = IntervalSet::new();
+/// spans.insert(0..2, Expand::Neither, "keyword");
+/// spans.insert(3..7, Expand::End, "function");
+///
+/// rope.insert(7, "_two");
+/// spans.update_for_insert(7, 4);
+/// assert_eq!(
+///     spans.overlapping(3..4).collect::<Vec<_>>(),
+///     [(3..11, &"function")]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct IntervalSet<T> {
+    root: Link<T>,
+    len: usize,
+    /// State of the generator for node priorities.
+    seed: u64,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+/// A node of the tree, which is a treap: ordered by `start` as a binary
+/// search tree, and by `priority` as a max-heap.
+///
+/// All positions in a node are in terms of the pending shifts of its
+/// ancestors, i.e. the actual positions are found by adding the `shift`
+/// of every ancestor.
+#[derive(Debug, Clone)]
+struct Node<T> {
+    start: usize,
+    end: usize,
+    expand: Expand,
+    value: T,
+    /// The max end within the subtree rooted at this node.
+    max_end: usize,
+    /// A shift (added with wrapping arithmetic) that still needs to be
+    /// applied to both children.
+    shift: usize,
+    priority: u64,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+impl<T> IntervalSet<T> {
+    /// Creates an empty `IntervalSet`.
+    #[inline]
+    pub fn new() -> Self {
+        IntervalSet {
+            root: None,
+            len: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// Total number of intervals in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set has no intervals.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over all intervals, in order of their start.
+    #[inline]
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (Range<usize>, &'a T)> + 'a {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(&self.root, 0);
+        iter
+    }
+
+    /// Returns an iterator over the intervals that overlap `char_range`,
+    /// in order of their start.
+    ///
+    /// If `char_range` is empty, returns the intervals that contain its
+    /// position instead.
+    ///
+    /// Runs in O(log N + K) time, where N is the number of intervals and K
+    /// is the number of results.
+    pub fn overlapping<'a>(
+        &'a self,
+        char_range: Range<usize>,
+    ) -> impl Iterator<Item = (Range<usize>, &'a T)> + 'a {
+        let end = char_range.end.max(char_range.start + 1);
+        let mut found = Vec::new();
+        collect_overlapping(&self.root, 0, char_range.start, end, &mut found);
+        found.into_iter()
+    }
+
+    /// Returns an iterator over the intervals that overlap the lines in
+    /// `line_range` of `text`, in order of their start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_lines()`).
+    pub fn overlapping_lines<'a>(
+        &'a self,
+        text: &Rope,
+        line_range: Range<usize>,
+    ) -> impl Iterator<Item = (Range<usize>, &'a T)> + 'a {
+        assert!(line_range.start <= line_range.end);
+        let start = text.line_to_char(line_range.start);
+        let end = text.line_to_char(line_range.end);
+        self.overlapping(start..end)
+    }
+
+    //-----------------------------------------------------------------------
+    // Interval management methods
+
+    /// Adds an interval over `char_range` with value `value`.
+    ///
+    /// Empty intervals are ignored.  Intervals with the same start are
+    /// kept in the order they were added.
+    ///
+    /// Runs in O(log N) time.
+    pub fn insert(&mut self, char_range: Range<usize>, expand: Expand, value: T) {
+        if char_range.start >= char_range.end {
+            return;
+        }
+
+        let node = Box::new(Node {
+            start: char_range.start,
+            end: char_range.end,
+            expand: expand,
+            value: value,
+            max_end: char_range.end,
+            shift: 0,
+            priority: self.next_priority(),
+            left: None,
+            right: None,
+        });
+        let (left, right) = split(self.root.take(), char_range.start, true);
+        self.root = merge(merge(left, Some(node)), right);
+        self.len += 1;
+    }
+
+    /// Retains only the intervals for which `f` returns true.
+    ///
+    /// Runs in O(N) time.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Range<usize>, &mut T) -> bool,
+    {
+        let mut nodes = Vec::with_capacity(self.len);
+        flatten(self.root.take(), &mut nodes);
+        nodes.retain_mut(|node| f(node.start..node.end, &mut node.value));
+        self.len = nodes.len();
+        self.root = build(nodes);
+    }
+
+    /// Removes all intervals.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit tracking methods
+
+    /// Updates the intervals for `len_chars` chars of text having been
+    /// inserted at char index `char_idx`.
+    ///
+    /// Runs in O((K + 1) log N) time, where K is the number of intervals
+    /// that touch `char_idx`.
+    pub fn update_for_insert(&mut self, char_idx: usize, len_chars: usize) {
+        if len_chars == 0 {
+            return;
+        }
+
+        let (mut left, rest) = split(self.root.take(), char_idx, false);
+        let (at, mut right) = split(rest, char_idx, true);
+
+        // Intervals starting before the insertion point only have their
+        // ends moved, which doesn't change their order.
+        map_ends(&mut left, char_idx, &mut |end, expand| {
+            if end > char_idx || expand.end() {
+                end + len_chars
+            } else {
+                end
+            }
+        });
+
+        // Intervals starting at the insertion point are split into ones
+        // that stay put and ones that move, with the ones that stay put
+        // needing to come first.
+        let mut nodes = Vec::new();
+        flatten(at, &mut nodes);
+        let (stay, moved): (Vec<_>, Vec<_>) = nodes
+            .into_iter()
+            .map(|mut node| {
+                if !node.expand.start() {
+                    node.start += len_chars;
+                }
+                node.end += len_chars;
+                node.max_end = node.end;
+                node
+            })
+            .partition(|node| node.expand.start());
+
+        // Everything after the insertion point moves.
+        if let Some(ref mut node) = right {
+            node.apply_shift(len_chars);
+        }
+
+        self.root = merge(merge(merge(left, build(stay)), build(moved)), right);
+    }
+
+    /// Updates the intervals for the text in `char_range` having been
+    /// removed.
+    ///
+    /// Intervals that end up empty are removed.
+    ///
+    /// Runs in O((K + 1) log N) time, where K is the number of intervals
+    /// that touch `char_range`.
+    pub fn update_for_remove(&mut self, char_range: Range<usize>) {
+        if char_range.start >= char_range.end {
+            return;
+        }
+        let (start, end) = (char_range.start, char_range.end);
+        let len_chars = end - start;
+        let map = |pos: usize| {
+            if pos <= start {
+                pos
+            } else if pos >= end {
+                pos - len_chars
+            } else {
+                start
+            }
+        };
+
+        let (mut left, rest) = split(self.root.take(), start, false);
+        let (inside, mut right) = split(rest, end, false);
+
+        // Intervals starting before the range can't become empty, and
+        // only have their ends moved.
+        map_ends(&mut left, start + 1, &mut |pos, _| map(pos));
+
+        // Intervals starting inside the range all end up starting at its
+        // start, in the same order.
+        let mut nodes = Vec::new();
+        flatten(inside, &mut nodes);
+        let len_inside = nodes.len();
+        nodes.retain_mut(|node| {
+            node.start = start;
+            node.end = map(node.end);
+            node.max_end = node.end;
+            node.start < node.end
+        });
+        self.len -= len_inside - nodes.len();
+
+        // Everything after the range moves.
+        if let Some(ref mut node) = right {
+            node.apply_shift(len_chars.wrapping_neg());
+        }
+
+        self.root = merge(merge(left, build(nodes)), right);
+    }
+
+    /// Updates the intervals for `changes` having been applied to the
+    /// text.
+    ///
+    /// This is equivalent to calling `update_for_insert()` and
+    /// `update_for_remove()` for each of the edits in `changes`.
+    pub fn update_for_changes(&mut self, changes: &ChangeSet) {
+        let mut pos = 0;
+        for op in changes.ops() {
+            match *op {
+                Operation::Retain(n) => {
+                    pos += n;
+                }
+                Operation::Delete(n) => {
+                    self.update_for_remove(pos..(pos + n));
+                }
+                Operation::Insert(ref text) => {
+                    let len = count_chars(text);
+                    self.update_for_insert(pos, len);
+                    pos += len;
+                }
+            }
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Returns a pseudo-random priority for a new node (xorshift64*).
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl<T> Default for IntervalSet<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EditObserver for IntervalSet<T> {
+    fn replaced(&mut self, _text: &Rope, char_range: Range<usize>, len_chars: usize) {
+        let start = char_range.start;
+        self.update_for_remove(char_range);
+        self.update_for_insert(start, len_chars);
+    }
+
+    fn changed(&mut self, _text: &Rope, changes: &ChangeSet) {
+        self.update_for_changes(changes);
+    }
+}
+
+impl<T> Node<T> {
+    /// Shifts the whole subtree rooted at this node by `shift`.
+    #[inline]
+    fn apply_shift(&mut self, shift: usize) {
+        self.start = self.start.wrapping_add(shift);
+        self.end = self.end.wrapping_add(shift);
+        self.max_end = self.max_end.wrapping_add(shift);
+        self.shift = self.shift.wrapping_add(shift);
+    }
+
+    /// Applies the pending shift to the children.
+    #[inline]
+    fn push_shift(&mut self) {
+        if self.shift != 0 {
+            let shift = self.shift;
+            for child in [&mut self.left, &mut self.right] {
+                if let Some(ref mut child) = *child {
+                    child.apply_shift(shift);
+                }
+            }
+            self.shift = 0;
+        }
+    }
+
+    /// Recomputes `max_end` from the children.  The pending shift must
+    /// have been applied.
+    #[inline]
+    fn update_max_end(&mut self) {
+        let mut max_end = self.end;
+        for child in [&self.left, &self.right] {
+            if let Some(ref child) = *child {
+                max_end = max_end.max(child.max_end);
+            }
+        }
+        self.max_end = max_end;
+    }
+}
+
+/// Splits the tree into the nodes that start before `pos` (or at it, if
+/// `inclusive`) and the rest.
+fn split<T>(link: Link<T>, pos: usize, inclusive: bool) -> (Link<T>, Link<T>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            node.push_shift();
+            if node.start < pos || (inclusive && node.start == pos) {
+                let (left, right) = split(node.right.take(), pos, inclusive);
+                node.right = left;
+                node.update_max_end();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), pos, inclusive);
+                node.left = right;
+                node.update_max_end();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two trees, where all nodes of `a` come before those of `b`.
+fn merge<T>(a: Link<T>, b: Link<T>) -> Link<T> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                a.push_shift();
+                a.right = merge(a.right.take(), Some(b));
+                a.update_max_end();
+                Some(a)
+            } else {
+                b.push_shift();
+                b.left = merge(Some(a), b.left.take());
+                b.update_max_end();
+                Some(b)
+            }
+        }
+    }
+}
+
+/// Builds a tree from nodes that are in order and have no children.
+fn build<T>(nodes: Vec<Box<Node<T>>>) -> Link<T> {
+    nodes
+        .into_iter()
+        .fold(None, |link, node| merge(link, Some(node)))
+}
+
+/// Takes the tree apart into its nodes, in order.
+fn flatten<T>(link: Link<T>, nodes: &mut Vec<Box<Node<T>>>) {
+    if let Some(mut node) = link {
+        node.push_shift();
+        let left = node.left.take();
+        let right = node.right.take();
+        flatten(left, nodes);
+        node.shift = 0;
+        node.max_end = node.end;
+        nodes.push(node);
+        flatten(right, nodes);
+    }
+}
+
+/// Replaces the end of every interval that ends at or after `min_end`
+/// with `f(end, expand)`.  `f` must not move an end before its start.
+fn map_ends<T, F>(link: &mut Link<T>, min_end: usize, f: &mut F)
+where
+    F: FnMut(usize, Expand) -> usize,
+{
+    if let Some(ref mut node) = *link {
+        if node.max_end < min_end {
+            return;
+        }
+        node.push_shift();
+        map_ends(&mut node.left, min_end, f);
+        if node.end >= min_end {
+            node.end = f(node.end, node.expand);
+        }
+        map_ends(&mut node.right, min_end, f);
+        node.update_max_end();
+    }
+}
+
+/// Pushes the intervals of the tree that overlap `start..end` onto
+/// `found`, in order.  `shift` is the sum of the ancestors' pending
+/// shifts.
+fn collect_overlapping<'a, T>(
+    link: &'a Link<T>,
+    shift: usize,
+    start: usize,
+    end: usize,
+    found: &mut Vec<(Range<usize>, &'a T)>,
+) {
+    if let Some(ref node) = *link {
+        if node.max_end.wrapping_add(shift) <= start {
+            // Nothing in this subtree reaches the range.
+            return;
+        }
+        let child_shift = shift.wrapping_add(node.shift);
+        collect_overlapping(&node.left, child_shift, start, end, found);
+        let node_start = node.start.wrapping_add(shift);
+        if node_start < end {
+            let node_end = node.end.wrapping_add(shift);
+            if node_end > start {
+                found.push((node_start..node_end, &node.value));
+            }
+            collect_overlapping(&node.right, child_shift, start, end, found);
+        }
+    }
+}
+
+/// An in-order iterator over the tree.
+struct Iter<'a, T> {
+    /// Nodes still to be yielded, along with the sum of their ancestors'
+    /// pending shifts.
+    stack: Vec<(&'a Node<T>, usize)>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, mut link: &'a Link<T>, mut shift: usize) {
+        while let Some(ref node) = *link {
+            self.stack.push((node, shift));
+            shift = shift.wrapping_add(node.shift);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Range<usize>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, shift) = self.stack.pop()?;
+        self.push_left(&node.right, shift.wrapping_add(node.shift));
+        Some((
+            node.start.wrapping_add(shift)..node.end.wrapping_add(shift),
+            &node.value,
+        ))
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans<'a, T: Copy + 'a>(
+        iter: impl Iterator<Item = (Range<usize>, &'a T)>,
+    ) -> Vec<(usize, usize, T)> {
+        iter.map(|(r, &v)| (r.start, r.end, v)).collect()
+    }
+
+    #[test]
+    fn insert_01() {
+        let mut s = IntervalSet::new();
+        s.insert(5..9, Expand::Neither, 'a');
+        s.insert(1..3, Expand::Neither, 'b');
+        s.insert(4..4, Expand::Neither, 'c');
+        s.insert(5..6, Expand::Neither, 'd');
+        assert_eq!(s.len(), 3);
+        assert_eq!(spans(s.iter()), [(1, 3, 'b'), (5, 9, 'a'), (5, 6, 'd')]);
+    }
+
+    #[test]
+    fn overlapping_01() {
+        let mut s = IntervalSet::new();
+        s.insert(0..100, Expand::Neither, 0);
+        s.insert(2..4, Expand::Neither, 1);
+        s.insert(3..10, Expand::Neither, 2);
+        s.insert(10..12, Expand::Neither, 3);
+        s.insert(20..21, Expand::Neither, 4);
+        s.insert(40..50, Expand::Neither, 5);
+
+        let ids = |r: Range<usize>| s.overlapping(r).map(|(_, &v)| v).collect::<Vec<_>>();
+        assert_eq!(ids(0..1), [0]);
+        assert_eq!(ids(3..4), [0, 1, 2]);
+        assert_eq!(ids(4..10), [0, 2]);
+        assert_eq!(ids(4..11), [0, 2, 3]);
+        assert_eq!(ids(12..20), [0]);
+        assert_eq!(ids(12..45), [0, 4, 5]);
+        assert_eq!(ids(100..200), []);
+
+        // Empty ranges act as points.
+        assert_eq!(ids(10..10), [0, 3]);
+        assert_eq!(ids(21..21), [0]);
+    }
+
+    #[test]
+    fn overlapping_02() {
+        // Compare against a brute force search.
+        let mut s = IntervalSet::new();
+        let mut all = Vec::new();
+        for i in 0..200usize {
+            let start = (i * 37) % 101;
+            let end = start + 1 + (i * 13) % 17;
+            s.insert(start..end, Expand::Neither, i);
+            all.push((start, end, i));
+        }
+
+        for start in 0..120 {
+            for len in 0..5 {
+                let end = start + len;
+                let mut found: Vec<usize> = s.overlapping(start..end).map(|(_, &v)| v).collect();
+                let point_end = end.max(start + 1);
+                let mut expected: Vec<usize> = all
+                    .iter()
+                    .filter(|&&(s, e, _)| s < point_end && e > start)
+                    .map(|&(_, _, v)| v)
+                    .collect();
+                found.sort();
+                expected.sort();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_lines_01() {
+        let r = Rope::from_str("one\ntwo\nthree\nfour\n");
+        let mut s = IntervalSet::new();
+        s.insert(1..2, Expand::Neither, 'a');
+        s.insert(3..5, Expand::Neither, 'b');
+        s.insert(9..10, Expand::Neither, 'c');
+        s.insert(14..19, Expand::Neither, 'd');
+
+        let ids = |r: &Rope, l: Range<usize>| {
+            s.overlapping_lines(r, l)
+                .map(|(_, &v)| v)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&r, 0..1), ['a', 'b']);
+        assert_eq!(ids(&r, 1..2), ['b']);
+        assert_eq!(ids(&r, 1..3), ['b', 'c']);
+        assert_eq!(ids(&r, 3..5), ['d']);
+    }
+
+    #[test]
+    fn update_for_insert_01() {
+        let mut s = IntervalSet::new();
+        s.insert(2..5, Expand::Neither, 'n');
+        s.insert(2..5, Expand::Start, 's');
+        s.insert(2..5, Expand::End, 'e');
+        s.insert(2..5, Expand::Both, 'b');
+
+        s.update_for_insert(2, 3);
+        assert_eq!(
+            spans(s.iter()),
+            [(2, 8, 's'), (2, 8, 'b'), (5, 8, 'n'), (5, 8, 'e')]
+        );
+
+        s.update_for_insert(8, 1);
+        assert_eq!(
+            spans(s.iter()),
+            [(2, 8, 's'), (2, 9, 'b'), (5, 8, 'n'), (5, 9, 'e')]
+        );
+
+        s.update_for_insert(6, 2);
+        assert_eq!(
+            spans(s.iter()),
+            [(2, 10, 's'), (2, 11, 'b'), (5, 10, 'n'), (5, 11, 'e')]
+        );
+        assert_eq!(s.overlapping(10..10).count(), 2);
+    }
+
+    #[test]
+    fn update_for_remove_01() {
+        let mut s = IntervalSet::new();
+        s.insert(0..3, Expand::Neither, 'a');
+        s.insert(3..5, Expand::Neither, 'b');
+        s.insert(4..8, Expand::Neither, 'c');
+        s.insert(5..6, Expand::Both, 'd');
+        s.insert(9..12, Expand::Neither, 'e');
+
+        s.update_for_remove(2..7);
+        assert_eq!(spans(s.iter()), [(0, 2, 'a'), (2, 3, 'c'), (4, 7, 'e')]);
+        assert_eq!(s.overlapping(2..4).count(), 1);
+    }
+
+    #[test]
+    fn update_for_changes_01() {
+        let mut r = Rope::from_str("let x = 1;");
+        let mut s = IntervalSet::new();
+        s.insert(0..3, Expand::Neither, "keyword");
+        s.insert(4..5, Expand::Both, "ident");
+        s.insert(8..9, Expand::Neither, "number");
+
+        let mut c = ChangeSet::new(&r);
+        c.retain(5).insert("yz").retain(3).delete(1).insert("2.5");
+        c.apply(&mut r).unwrap();
+        s.update_for_changes(&c);
+
+        assert_eq!(r, "let xyz = 2.5;");
+        assert_eq!(spans(s.iter()), [(0, 3, "keyword"), (4, 7, "ident")]);
+    }
+
+    #[test]
+    fn update_random_01() {
+        // Compare against a brute force model over many edits.
+        let expands = [Expand::Neither, Expand::Start, Expand::End, Expand::Both];
+        let mut rng = 12345usize;
+        let mut rand = |n: usize| {
+            rng = rng
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (rng >> 33) % n
+        };
+
+        let mut s = IntervalSet::new();
+        let mut model: Vec<(usize, usize, Expand, usize)> = Vec::new();
+        for i in 0..300 {
+            let start = rand(200);
+            let end = start + 1 + rand(20);
+            let expand = expands[rand(4)];
+            s.insert(start..end, expand, i);
+            let j = model.partition_point(|m| m.0 <= start);
+            model.insert(j, (start, end, expand, i));
+        }
+
+        for _ in 0..500 {
+            let pos = rand(220);
+            let len = 1 + rand(8);
+            if rand(2) == 0 {
+                s.update_for_insert(pos, len);
+                for m in model.iter_mut() {
+                    if m.0 > pos || (m.0 == pos && !m.2.start()) {
+                        m.0 += len;
+                    }
+                    if m.1 > pos || (m.1 == pos && m.2.end()) {
+                        m.1 += len;
+                    }
+                }
+                model.sort_by_key(|m| m.0);
+            } else {
+                s.update_for_remove(pos..(pos + len));
+                let map = |p: usize| {
+                    if p <= pos {
+                        p
+                    } else if p >= pos + len {
+                        p - len
+                    } else {
+                        pos
+                    }
+                };
+                for m in model.iter_mut() {
+                    m.0 = map(m.0);
+                    m.1 = map(m.1);
+                }
+                model.retain(|m| m.0 < m.1);
+            }
+
+            assert_eq!(s.len(), model.len());
+            let expected: Vec<_> = model.iter().map(|m| (m.0, m.1, m.3)).collect();
+            assert_eq!(spans(s.iter()), expected);
+
+            let start = rand(220);
+            let end = start + rand(10);
+            let point_end = end.max(start + 1);
+            let expected: Vec<_> = model
+                .iter()
+                .filter(|m| m.0 < point_end && m.1 > start)
+                .map(|m| (m.0, m.1, m.3))
+                .collect();
+            assert_eq!(spans(s.overlapping(start..end)), expected);
+        }
+    }
+}
@@ -67,12 +67,15 @@
 use std::str;
 use std::sync::Arc;
 
+use crate::line_ending::{split_line_ending, LineEnding};
+#[cfg(feature = "graphemes")]
+use crate::segment::{GraphemeWalker, SegmentWalker, Segmentation};
 use crate::slice::{RSEnum, RopeSlice};
 use crate::str_utils::{
-    byte_to_line_idx, char_to_byte_idx, count_chars, count_utf16_surrogates, ends_with_line_break,
-    last_line_start_byte_idx, line_to_byte_idx, trim_line_break,
+    char_to_byte_idx, count_chars, count_utf16_surrogates, ends_with_line_break,
+    last_line_start_byte_idx, trim_line_break, LineBreakMode,
 };
-use crate::tree::{Count, Node, TextInfo};
+use crate::tree::{Count, LineBreaks, Node, TextInfo};
 
 //==========================================================
 
@@ -113,6 +116,7 @@ impl<'a> Bytes<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Bytes {
         Bytes::new_with_range_at(
             node,
@@ -120,6 +124,7 @@ impl<'a> Bytes<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         )
     }
 
@@ -129,6 +134,7 @@ impl<'a> Bytes<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Bytes {
         let (mut chunk_iter, mut chunk_byte_start, _, _) = Chunks::new_with_range_at_byte(
             node,
@@ -136,6 +142,7 @@ impl<'a> Bytes<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         );
 
         let cur_chunk = if byte_idx_range.0 == byte_idx_range.1 {
@@ -339,6 +346,7 @@ impl<'a> Chars<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Chars {
         Chars::new_with_range_at(
             node,
@@ -346,6 +354,7 @@ impl<'a> Chars<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         )
     }
 
@@ -355,6 +364,7 @@ impl<'a> Chars<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Chars {
         let (mut chunk_iter, _, mut chunk_char_start, _) = Chunks::new_with_range_at_char(
             node,
@@ -362,6 +372,7 @@ impl<'a> Chars<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         );
 
         let cur_chunk = if char_idx_range.0 == char_idx_range.1 {
@@ -557,6 +568,7 @@ pub struct Lines<'a> {
     at_end: bool,
     line_idx: usize,
     total_lines: usize,
+    mode: LineBreakMode,
 }
 
 #[derive(Debug, Clone)]
@@ -579,13 +591,14 @@ enum LinesEnum<'a> {
 
 impl<'a> Lines<'a> {
     #[inline(always)]
-    pub(crate) fn new(node: &Arc<Node>) -> Lines {
+    pub(crate) fn new(node: &Arc<Node>, mode: LineBreakMode) -> Lines {
         let info = node.text_info();
         Lines::new_with_range_at(
             node,
             0,
             (0, info.bytes as usize),
-            (0, info.line_breaks as usize + 1),
+            (0, info.line_breaks.get(mode) + 1),
+            mode,
         )
     }
 
@@ -594,8 +607,9 @@ impl<'a> Lines<'a> {
         node: &Arc<Node>,
         byte_idx_range: (usize, usize),
         line_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Lines {
-        Lines::new_with_range_at(node, line_idx_range.0, byte_idx_range, line_idx_range)
+        Lines::new_with_range_at(node, line_idx_range.0, byte_idx_range, line_idx_range, mode)
     }
 
     pub(crate) fn new_with_range_at(
@@ -603,6 +617,7 @@ impl<'a> Lines<'a> {
         line: usize,
         byte_idx_range: (usize, usize),
         line_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Lines {
         debug_assert!(node.is_char_boundary(byte_idx_range.0));
         debug_assert!(node.is_char_boundary(byte_idx_range.1));
@@ -621,13 +636,14 @@ impl<'a> Lines<'a> {
                 byte_idx: 0,
                 line_idx: 0,
                 total_lines: 1,
+                mode,
             };
         }
 
         // Special-case: root is a leaf.  Return light version of the iterator.
         if node.is_leaf() {
             let text = &node.leaf_text()[byte_idx_range.0..byte_idx_range.1];
-            return Lines::from_str_at(text, line - line_idx_range.0, total_lines);
+            return Lines::from_str_at(text, line - line_idx_range.0, total_lines, mode);
         }
 
         // Common case.  Traverse into the tree to build the iterator.
@@ -646,7 +662,7 @@ impl<'a> Lines<'a> {
                     let (child_i, acc) = children.search_by(|_, end_info| {
                         if (end_info.bytes as usize) >= end_byte_idx {
                             true
-                        } else if line_idx <= (end_info.line_breaks as usize) {
+                        } else if line_idx <= end_info.line_breaks.get(mode) {
                             (end_info.bytes as usize) > start_byte_idx
                         } else {
                             false
@@ -656,7 +672,7 @@ impl<'a> Lines<'a> {
                     // Update tracking info.
                     start_byte_idx = start_byte_idx.saturating_sub(acc.bytes as usize);
                     end_byte_idx -= acc.bytes as usize;
-                    line_idx -= acc.line_breaks as usize;
+                    line_idx -= acc.line_breaks.get(mode);
                     chunk_byte_start += acc.bytes as usize;
 
                     // Add to the node stack.
@@ -666,7 +682,8 @@ impl<'a> Lines<'a> {
 
                 // Create the iterator.
                 Node::Leaf(ref text) => {
-                    let leaf_byte_idx = line_to_byte_idx(text, line_idx)
+                    let leaf_byte_idx = mode
+                        .line_to_byte_idx(text, line_idx)
                         .max(start_byte_idx)
                         .min(end_byte_idx);
 
@@ -680,9 +697,11 @@ impl<'a> Lines<'a> {
                         text,
                         byte_idx: chunk_byte_start + leaf_byte_idx - byte_idx_range.0,
                         at_end: leaf_byte_idx == end_byte_idx
-                            && line_idx > byte_to_line_idx(&text[..end_byte_idx], end_byte_idx),
+                            && line_idx
+                                > mode.byte_to_line_idx(&text[..end_byte_idx], end_byte_idx),
                         line_idx: line - line_idx_range.0,
                         total_lines,
+                        mode,
                     };
 
                     return res;
@@ -696,11 +715,12 @@ impl<'a> Lines<'a> {
     /// This is only exposed publicly for use in property testing.
     #[doc(hidden)]
     pub fn from_str_pt(text: &str) -> Lines {
-        let line_count = byte_to_line_idx(text, text.len()) + 1;
-        Lines::from_str(text, line_count)
+        let mode = LineBreakMode::default();
+        let line_count = mode.byte_to_line_idx(text, text.len()) + 1;
+        Lines::from_str(text, line_count, mode)
     }
 
-    pub(crate) fn from_str(text: &str, lines: usize) -> Lines {
+    pub(crate) fn from_str(text: &str, lines: usize, mode: LineBreakMode) -> Lines {
         Lines {
             iter: LinesEnum::Light,
             is_reversed: false,
@@ -709,18 +729,20 @@ impl<'a> Lines<'a> {
             at_end: false,
             line_idx: 0,
             total_lines: lines,
+            mode,
         }
     }
 
-    pub(crate) fn from_str_at(text: &str, line: usize, lines: usize) -> Lines {
+    pub(crate) fn from_str_at(text: &str, line: usize, lines: usize, mode: LineBreakMode) -> Lines {
         Lines {
             iter: LinesEnum::Light,
             is_reversed: false,
             text: text,
-            byte_idx: line_to_byte_idx(text, line),
+            byte_idx: mode.line_to_byte_idx(text, line),
             at_end: line >= lines,
             line_idx: line.min(lines),
             total_lines: lines,
+            mode,
         }
     }
     /// Reverses the direction of the iterator in-place.
@@ -777,6 +799,7 @@ impl<'a> Lines<'a> {
                 ref mut text,
                 ref mut at_end,
                 ref mut line_idx,
+                mode,
                 ..
             } => {
                 let tail = &text[..*leaf_byte_idx as usize];
@@ -787,14 +810,17 @@ impl<'a> Lines<'a> {
                 // anyway we can save the result so we don't have to
                 // count newlines later.
                 let ends_with_line_break = if std::mem::take(at_end) {
-                    if ends_with_line_break(tail) {
+                    if ends_with_line_break(tail, mode) {
                         *line_idx -= 1;
-                        return Some(RopeSlice(RSEnum::Light {
-                            text: "",
-                            char_count: 0,
-                            utf16_surrogate_count: 0,
-                            line_break_count: 0,
-                        }));
+                        return Some(RopeSlice(
+                            RSEnum::Light {
+                                text: "",
+                                char_count: 0,
+                                utf16_surrogate_count: 0,
+                                line_break_count: 0,
+                            },
+                            mode,
+                        ));
                     }
                     false
                 } else if *byte_idx == 0 {
@@ -809,7 +835,7 @@ impl<'a> Lines<'a> {
                 // and whether we know if the line is contained entirely within
                 // the chunk or not.
                 let (line_start_idx, line_inside_chunk) = {
-                    let line_start = last_line_start_byte_idx(trim_line_break(tail));
+                    let line_start = last_line_start_byte_idx(trim_line_break(tail, mode), mode);
                     let line_len = *leaf_byte_idx as usize - line_start;
                     if line_len >= *byte_idx {
                         (*leaf_byte_idx as usize - *byte_idx, true)
@@ -824,12 +850,15 @@ impl<'a> Lines<'a> {
                 // If the line is contained entirely within the current chunk, return it.
                 if line_inside_chunk {
                     *leaf_byte_idx = line_start_idx as u32;
-                    return Some(RopeSlice(RSEnum::Light {
-                        text: chunk_line,
-                        char_count: count_chars(chunk_line) as Count,
-                        utf16_surrogate_count: count_utf16_surrogates(chunk_line) as Count,
-                        line_break_count: ends_with_line_break as Count,
-                    }));
+                    return Some(RopeSlice(
+                        RSEnum::Light {
+                            text: chunk_line,
+                            char_count: count_chars(chunk_line) as Count,
+                            utf16_surrogate_count: count_utf16_surrogates(chunk_line) as Count,
+                            line_break_count: ends_with_line_break as Count,
+                        },
+                        mode,
+                    ));
                 }
 
                 // We need to advance to the next (preceding) chunk that contains
@@ -892,7 +921,7 @@ impl<'a> Lines<'a> {
                     let info = node.children().info()[child_i];
                     let available_bytes = *byte_idx;
 
-                    if info.line_breaks != 0 {
+                    if info.line_breaks.get(mode) != 0 {
                         // This chunk contains a line break so it will contain the start of our line.
                         *text = node.children().nodes()[child_i].leaf_text();
                         // Find the start of the line within the chunk.
@@ -901,7 +930,7 @@ impl<'a> Lines<'a> {
                         // A line break at the end of the chunk is already the line break
                         // we are looking for.  The line break belonging to this line is
                         // always contained in the chunk we started this iteration at.
-                        let mut line_start = last_line_start_byte_idx(text);
+                        let mut line_start = last_line_start_byte_idx(text, mode);
                         // Cut off the line at the start of the iterator.
                         let line_len = text.len() - line_start;
                         if line_len >= available_bytes {
@@ -940,7 +969,7 @@ impl<'a> Lines<'a> {
                                 bytes: head.len() as Count,
                                 chars: head_chars,
                                 utf16_surrogates: head_surrogates,
-                                line_breaks: 0,
+                                line_breaks: LineBreaks::from_str(head),
                             }
                             - len,
                         end_info: pos_in_shared_parent,
@@ -953,7 +982,7 @@ impl<'a> Lines<'a> {
                         line_break_count: 0,
                     }
                 };
-                let line = RopeSlice(line);
+                let line = RopeSlice(line, mode);
 
                 Some(line)
             }
@@ -964,29 +993,34 @@ impl<'a> Lines<'a> {
                 ref mut byte_idx,
                 ref mut at_end,
                 ref mut line_idx,
+                mode,
                 ..
             } => {
                 if std::mem::take(at_end) {
-                    if text.is_empty() || ends_with_line_break(text) {
+                    if text.is_empty() || ends_with_line_break(text, mode) {
                         *line_idx -= 1;
-                        return Some("".into());
+                        return Some(RopeSlice::from_str_with_mode("", mode));
                     }
                 } else if *byte_idx == 0 {
                     return None;
                 }
 
                 let end_idx = *byte_idx;
-                let start_idx = last_line_start_byte_idx(trim_line_break(&text[..end_idx]));
+                let start_idx =
+                    last_line_start_byte_idx(trim_line_break(&text[..end_idx], mode), mode);
                 *byte_idx = start_idx;
                 *line_idx -= 1;
                 let line = &text[start_idx..end_idx];
 
-                return Some(RopeSlice(RSEnum::Light {
-                    text: line,
-                    char_count: count_chars(line) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(line) as Count,
-                    line_break_count: 1,
-                }));
+                return Some(RopeSlice(
+                    RSEnum::Light {
+                        text: line,
+                        char_count: count_chars(line) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(line) as Count,
+                        line_break_count: 1,
+                    },
+                    mode,
+                ));
             }
         }
     }
@@ -1004,6 +1038,7 @@ impl<'a> Lines<'a> {
                 ref mut text,
                 ref mut at_end,
                 ref mut line_idx,
+                mode,
                 ..
             } => {
                 if *at_end {
@@ -1011,17 +1046,20 @@ impl<'a> Lines<'a> {
                 } else if *byte_idx == total_bytes {
                     *at_end = true;
                     *line_idx += 1;
-                    return Some(RopeSlice(RSEnum::Light {
-                        text: "",
-                        char_count: 0,
-                        utf16_surrogate_count: 0,
-                        line_break_count: 0,
-                    }));
+                    return Some(RopeSlice(
+                        RSEnum::Light {
+                            text: "",
+                            char_count: 0,
+                            utf16_surrogate_count: 0,
+                            line_break_count: 0,
+                        },
+                        mode,
+                    ));
                 }
                 *line_idx += 1;
 
                 let head = &text[*leaf_byte_idx as usize..];
-                let mut line_len = line_to_byte_idx(head, 1);
+                let mut line_len = mode.line_to_byte_idx(head, 1);
 
                 // Check if the iterators needs to advance to the next chunk.
                 // During this check the number of newline (0 or 1) is yielded
@@ -1032,7 +1070,7 @@ impl<'a> Lines<'a> {
                     // Check if the last line has a line break to decide whether
                     // we still need to yield an empty line later.
                     line_len = available_bytes;
-                    let ends_with_line_break = ends_with_line_break(&head[..line_len]);
+                    let ends_with_line_break = ends_with_line_break(&head[..line_len], mode);
                     *at_end = !ends_with_line_break;
                     // Reached end of the text, so no need to advance.
                     (true, ends_with_line_break as u64)
@@ -1041,7 +1079,10 @@ impl<'a> Lines<'a> {
                     // if we've reached the chunk boundary and the last character
                     // is not a line break.  If the iterator is not exhausted, a
                     // line always ends with a line break.
-                    (line_len != head.len() || ends_with_line_break(head), 1)
+                    (
+                        line_len != head.len() || ends_with_line_break(head, mode),
+                        1,
+                    )
                 };
 
                 // Yield the current line if it is contained within the current chunk.
@@ -1050,12 +1091,15 @@ impl<'a> Lines<'a> {
                     *byte_idx += line_len;
                     *leaf_byte_idx += line_len as u32;
 
-                    return Some(RopeSlice(RSEnum::Light {
-                        text: line,
-                        char_count: count_chars(line) as Count,
-                        utf16_surrogate_count: count_utf16_surrogates(line) as Count,
-                        line_break_count,
-                    }));
+                    return Some(RopeSlice(
+                        RSEnum::Light {
+                            text: line,
+                            char_count: count_chars(line) as Count,
+                            utf16_surrogate_count: count_utf16_surrogates(line) as Count,
+                            line_break_count,
+                        },
+                        mode,
+                    ));
                 }
 
                 *byte_idx += head.len();
@@ -1121,16 +1165,16 @@ impl<'a> Lines<'a> {
                     let info = node.children().info()[child_i];
                     let available_bytes = total_bytes - *byte_idx;
 
-                    if info.line_breaks != 0 {
+                    if info.line_breaks.get(mode) != 0 {
                         // This chunk contains a line break so it will contain the start of our line.
                         *text = node.children().nodes()[child_i].leaf_text();
                         // Find the end of the line within the chunk.
-                        let mut line_end = line_to_byte_idx(text, 1);
+                        let mut line_end = mode.line_to_byte_idx(text, 1);
                         // Check if the iterator was exhausted.
                         let ends_with_newline = if line_end >= available_bytes {
                             // Handle terminating lines without a line break properly.
                             line_end = available_bytes;
-                            let ends_with_newline = ends_with_line_break(&text[..line_end]);
+                            let ends_with_newline = ends_with_line_break(&text[..line_end], mode);
                             *at_end = !ends_with_newline;
                             ends_with_newline
                         } else {
@@ -1170,7 +1214,7 @@ impl<'a> Lines<'a> {
                                 bytes: tail_len as Count,
                                 chars: line_tail_chars,
                                 utf16_surrogates: line_tail_surrogates,
-                                line_breaks: tail_ends_with_newline as Count,
+                                line_breaks: LineBreaks::from_str(line_tail),
                             },
                     }
                 } else {
@@ -1182,7 +1226,7 @@ impl<'a> Lines<'a> {
                     }
                 };
 
-                Some(RopeSlice(line))
+                Some(RopeSlice(line, mode))
             }
 
             Lines {
@@ -1191,6 +1235,7 @@ impl<'a> Lines<'a> {
                 ref mut byte_idx,
                 ref mut at_end,
                 ref mut line_idx,
+                mode,
                 ..
             } => {
                 if *at_end {
@@ -1198,19 +1243,22 @@ impl<'a> Lines<'a> {
                 } else if *byte_idx == text.len() {
                     *at_end = true;
                     *line_idx += 1;
-                    return Some("".into());
+                    return Some(RopeSlice::from_str_with_mode("", mode));
                 }
 
                 let start_idx = *byte_idx;
-                let end_idx = line_to_byte_idx(&text[start_idx..], 1) + start_idx;
+                let end_idx = mode.line_to_byte_idx(&text[start_idx..], 1) + start_idx;
                 *byte_idx = end_idx;
                 *line_idx += 1;
 
                 if end_idx == text.len() {
-                    *at_end = !ends_with_line_break(text);
+                    *at_end = !ends_with_line_break(text, mode);
                 }
 
-                return Some((&text[start_idx..end_idx]).into());
+                return Some(RopeSlice::from_str_with_mode(
+                    &text[start_idx..end_idx],
+                    mode,
+                ));
             }
         }
     }
@@ -1248,6 +1296,69 @@ impl ExactSizeIterator for Lines<'_> {}
 
 //==========================================================
 
+/// An iterator over a `Rope`'s lines, with their line breaks split off.
+///
+/// This is like [`Lines`], but yields each line's content without its
+/// line break, along with the kind of [`LineEnding`] it ended with.  The
+/// last line never has a line ending, and is returned even if blank.
+#[derive(Debug, Clone)]
+pub struct LinesWithEndings<'a> {
+    lines: Lines<'a>,
+}
+
+impl<'a> LinesWithEndings<'a> {
+    pub(crate) fn new(lines: Lines<'a>) -> LinesWithEndings<'a> {
+        LinesWithEndings { lines: lines }
+    }
+
+    /// Reverses the direction of the iterator in-place.
+    ///
+    /// In other words, swaps the behavior of
+    /// [`prev()`](LinesWithEndings::prev()) and
+    /// [`next()`](LinesWithEndings::next()).
+    #[inline]
+    pub fn reverse(&mut self) {
+        self.lines.reverse();
+    }
+
+    /// Same as `reverse()`, but returns itself.
+    #[inline]
+    #[must_use]
+    pub fn reversed(mut self) -> LinesWithEndings<'a> {
+        self.reverse();
+        self
+    }
+
+    /// Advances the iterator backwards and returns the previous value.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn prev(&mut self) -> Option<(RopeSlice<'a>, Option<LineEnding>)> {
+        self.lines.prev().map(split_line_ending)
+    }
+}
+
+impl<'a> Iterator for LinesWithEndings<'a> {
+    type Item = (RopeSlice<'a>, Option<LineEnding>);
+
+    /// Advances the iterator forward and returns the next value.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    fn next(&mut self) -> Option<(RopeSlice<'a>, Option<LineEnding>)> {
+        self.lines.next().map(split_line_ending)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.lines.size_hint()
+    }
+}
+
+impl ExactSizeIterator for LinesWithEndings<'_> {}
+
+//==========================================================
+
 /// An iterator over a `Rope`'s contiguous `str` chunks.
 ///
 /// Internally, each `Rope` stores text as a segemented collection of utf8
@@ -1294,12 +1405,14 @@ impl<'a> Chunks<'a> {
     #[inline(always)]
     pub(crate) fn new(node: &Arc<Node>) -> Chunks {
         let info = node.text_info();
+        let mode = LineBreakMode::default();
         Chunks::new_with_range_at_byte(
             node,
             0,
             (0, info.bytes as usize),
             (0, info.chars as usize),
-            (0, info.line_breaks as usize + 1),
+            (0, info.line_breaks.get(mode) + 1),
+            mode,
         )
         .0
     }
@@ -1310,6 +1423,7 @@ impl<'a> Chunks<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> Chunks {
         Chunks::new_with_range_at_byte(
             node,
@@ -1317,6 +1431,7 @@ impl<'a> Chunks<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         )
         .0
     }
@@ -1342,6 +1457,7 @@ impl<'a> Chunks<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> (Chunks, usize, usize, usize) {
         debug_assert!(at_byte >= byte_idx_range.0);
         debug_assert!(at_byte <= byte_idx_range.1);
@@ -1380,7 +1496,7 @@ impl<'a> Chunks<'a> {
                     },
                     text.len(),
                     count_chars(text),
-                    byte_to_line_idx(text, text.len()),
+                    mode.byte_to_line_idx(text, text.len()),
                 );
             } else {
                 return (
@@ -1401,6 +1517,7 @@ impl<'a> Chunks<'a> {
         // Create and populate the node stack, and determine the char index
         // within the first chunk, and byte index of the start of that chunk.
         let mut info = TextInfo::new();
+        let mut line_break_idx = None;
         let mut byte_idx = at_byte as isize;
         let node_stack = {
             let mut node_stack: Vec<(&Arc<Node>, usize)> = Vec::new();
@@ -1417,8 +1534,9 @@ impl<'a> Chunks<'a> {
                                 bytes: byte_idx_range.1 as u64,
                                 chars: char_idx_range.1 as u64,
                                 utf16_surrogates: 0, // Bogus value, not needed
-                                line_breaks: line_break_idx_range.1 as u64 - 1,
+                                line_breaks: LineBreaks::new(), // Ditto
                             };
+                            line_break_idx = Some(line_break_idx_range.1 - 1);
                             (*node_stack.last_mut().unwrap()).1 += 1;
                         }
                         break;
@@ -1447,7 +1565,9 @@ impl<'a> Chunks<'a> {
             },
             (info.bytes as usize).max(byte_idx_range.0),
             (info.chars as usize).max(char_idx_range.0),
-            (info.line_breaks as usize).max(line_break_idx_range.0),
+            line_break_idx
+                .unwrap_or_else(|| info.line_breaks.get(mode))
+                .max(line_break_idx_range.0),
         )
     }
 
@@ -1458,6 +1578,7 @@ impl<'a> Chunks<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> (Chunks, usize, usize, usize) {
         let at_byte = if at_char == char_idx_range.1 {
             byte_idx_range.1
@@ -1471,6 +1592,7 @@ impl<'a> Chunks<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         )
     }
 
@@ -1481,11 +1603,13 @@ impl<'a> Chunks<'a> {
         byte_idx_range: (usize, usize),
         char_idx_range: (usize, usize),
         line_break_idx_range: (usize, usize),
+        mode: LineBreakMode,
     ) -> (Chunks, usize, usize, usize) {
         let at_byte = if at_line_break == line_break_idx_range.1 {
             byte_idx_range.1
         } else {
-            (node.get_chunk_at_line_break(at_line_break).1.bytes as usize).max(byte_idx_range.0)
+            (node.get_chunk_at_line_break(at_line_break, mode).1.bytes as usize)
+                .max(byte_idx_range.0)
         };
 
         Chunks::new_with_range_at_byte(
@@ -1494,6 +1618,7 @@ impl<'a> Chunks<'a> {
             byte_idx_range,
             char_idx_range,
             line_break_idx_range,
+            mode,
         )
     }
 
@@ -1713,6 +1838,354 @@ impl<'a> Iterator for Chunks<'a> {
     }
 }
 
+//==========================================================
+
+/// An iterator over a `Rope`'s extended grapheme clusters.
+///
+/// Only available with the `graphemes` feature enabled.  Graphemes that
+/// span chunk boundaries are handled correctly, and each grapheme is
+/// returned as a `RopeSlice`.
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone)]
+pub struct Graphemes<'a> {
+    walker: GraphemeWalker<'a>,
+    text: RopeSlice<'a>,
+    is_reversed: bool,
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Graphemes<'a> {
+    pub(crate) fn new_at_byte(text: RopeSlice<'a>, byte_idx: usize) -> Graphemes<'a> {
+        Graphemes {
+            walker: GraphemeWalker::new(text, byte_idx),
+            text: text,
+            is_reversed: false,
+        }
+    }
+
+    /// Reverses the direction of the iterator in-place.
+    ///
+    /// In other words, swaps the behavior of [`prev()`](Graphemes::prev())
+    /// and [`next()`](Graphemes::next()).
+    #[inline]
+    pub fn reverse(&mut self) {
+        self.is_reversed = !self.is_reversed;
+    }
+
+    /// Same as `reverse()`, but returns itself.
+    ///
+    /// This is useful when chaining iterator methods:
+    ///
+    /// ```rust
+    /// # use ropey::Rope;
+    /// # let rope = Rope::from_str("Hello there\n world!\n");
+    /// // Print the rope's graphemes in reverse, starting from the end.
+    /// for g in rope.graphemes_at(rope.len_chars()).reversed() {
+    ///     println!("{}", g);
+    /// }
+    #[inline]
+    #[must_use]
+    pub fn reversed(mut self) -> Graphemes<'a> {
+        self.reverse();
+        self
+    }
+
+    /// Advances the iterator backwards and returns the previous value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    pub fn prev(&mut self) -> Option<RopeSlice<'a>> {
+        if !self.is_reversed {
+            self.prev_impl()
+        } else {
+            self.next_impl()
+        }
+    }
+
+    fn prev_impl(&mut self) -> Option<RopeSlice<'a>> {
+        let end = self.walker.byte_idx();
+        let start = self.walker.prev_boundary()?;
+        Some(self.grapheme(start, end))
+    }
+
+    fn next_impl(&mut self) -> Option<RopeSlice<'a>> {
+        let start = self.walker.byte_idx();
+        let end = self.walker.next_boundary()?;
+        Some(self.grapheme(start, end))
+    }
+
+    #[inline]
+    fn grapheme(&self, start: usize, end: usize) -> RopeSlice<'a> {
+        // Most graphemes are within a single chunk, which is much cheaper
+        // to make a slice of.
+        match self.walker.chunk_containing(start, end) {
+            Some(text) => text.into(),
+            None => self.text.byte_slice(start..end),
+        }
+    }
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Iterator for Graphemes<'a> {
+    type Item = RopeSlice<'a>;
+
+    /// Advances the iterator forward and returns the next value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        if !self.is_reversed {
+            self.next_impl()
+        } else {
+            self.prev_impl()
+        }
+    }
+}
+
+//==========================================================
+
+/// An iterator over the segments between a `Rope`'s word boundaries.
+///
+/// Only available with the `graphemes` feature enabled.  This yields
+/// every part of the text, including whitespace and punctuation, split at
+/// [UAX #29](https://www.unicode.org/reports/tr29/) word boundaries, like
+/// `unicode-segmentation`'s `split_word_bounds()`.  Each segment is
+/// returned as a `RopeSlice`.
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone)]
+pub struct WordBounds<'a> {
+    segments: Segments<'a>,
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> WordBounds<'a> {
+    pub(crate) fn new_at_byte(text: RopeSlice<'a>, byte_idx: usize) -> WordBounds<'a> {
+        WordBounds {
+            segments: Segments::new_at_byte(text, Segmentation::Word, byte_idx),
+        }
+    }
+
+    /// Reverses the direction of the iterator in-place.
+    ///
+    /// In other words, swaps the behavior of [`prev()`](WordBounds::prev())
+    /// and [`next()`](WordBounds::next()).
+    #[inline]
+    pub fn reverse(&mut self) {
+        self.segments.is_reversed = !self.segments.is_reversed;
+    }
+
+    /// Same as `reverse()`, but returns itself.
+    #[inline]
+    #[must_use]
+    pub fn reversed(mut self) -> WordBounds<'a> {
+        self.reverse();
+        self
+    }
+
+    /// Advances the iterator backwards and returns the previous value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    pub fn prev(&mut self) -> Option<RopeSlice<'a>> {
+        self.segments.prev()
+    }
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Iterator for WordBounds<'a> {
+    type Item = RopeSlice<'a>;
+
+    /// Advances the iterator forward and returns the next value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        self.segments.next()
+    }
+}
+
+//==========================================================
+
+/// An iterator over a `Rope`'s words.
+///
+/// Only available with the `graphemes` feature enabled.  This is like
+/// [`WordBounds`], but skips the segments that don't contain any
+/// alphanumeric chars, like `unicode-segmentation`'s `unicode_words()`.
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone)]
+pub struct Words<'a> {
+    bounds: WordBounds<'a>,
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Words<'a> {
+    pub(crate) fn new_at_byte(text: RopeSlice<'a>, byte_idx: usize) -> Words<'a> {
+        Words {
+            bounds: WordBounds::new_at_byte(text, byte_idx),
+        }
+    }
+
+    /// Reverses the direction of the iterator in-place.
+    ///
+    /// In other words, swaps the behavior of [`prev()`](Words::prev())
+    /// and [`next()`](Words::next()).
+    #[inline]
+    pub fn reverse(&mut self) {
+        self.bounds.reverse();
+    }
+
+    /// Same as `reverse()`, but returns itself.
+    #[inline]
+    #[must_use]
+    pub fn reversed(mut self) -> Words<'a> {
+        self.reverse();
+        self
+    }
+
+    /// Advances the iterator backwards and returns the previous value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    pub fn prev(&mut self) -> Option<RopeSlice<'a>> {
+        loop {
+            let segment = self.bounds.prev()?;
+            if segment.chars().any(char::is_alphanumeric) {
+                return Some(segment);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Iterator for Words<'a> {
+    type Item = RopeSlice<'a>;
+
+    /// Advances the iterator forward and returns the next value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        loop {
+            let segment = self.bounds.next()?;
+            if segment.chars().any(char::is_alphanumeric) {
+                return Some(segment);
+            }
+        }
+    }
+}
+
+//==========================================================
+
+/// An iterator over a `Rope`'s sentences.
+///
+/// Only available with the `graphemes` feature enabled.  This yields
+/// every part of the text, split at
+/// [UAX #29](https://www.unicode.org/reports/tr29/) sentence boundaries,
+/// like `unicode-segmentation`'s `split_sentence_bounds()`.  Each sentence
+/// is returned as a `RopeSlice`, including its trailing whitespace.
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone)]
+pub struct Sentences<'a> {
+    segments: Segments<'a>,
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Sentences<'a> {
+    pub(crate) fn new_at_byte(text: RopeSlice<'a>, byte_idx: usize) -> Sentences<'a> {
+        Sentences {
+            segments: Segments::new_at_byte(text, Segmentation::Sentence, byte_idx),
+        }
+    }
+
+    /// Reverses the direction of the iterator in-place.
+    ///
+    /// In other words, swaps the behavior of [`prev()`](Sentences::prev())
+    /// and [`next()`](Sentences::next()).
+    #[inline]
+    pub fn reverse(&mut self) {
+        self.segments.is_reversed = !self.segments.is_reversed;
+    }
+
+    /// Same as `reverse()`, but returns itself.
+    #[inline]
+    #[must_use]
+    pub fn reversed(mut self) -> Sentences<'a> {
+        self.reverse();
+        self
+    }
+
+    /// Advances the iterator backwards and returns the previous value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    pub fn prev(&mut self) -> Option<RopeSlice<'a>> {
+        self.segments.prev()
+    }
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Iterator for Sentences<'a> {
+    type Item = RopeSlice<'a>;
+
+    /// Advances the iterator forward and returns the next value.
+    ///
+    /// Runs in amortized O(1) time and worst-case O(log N) time.
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        self.segments.next()
+    }
+}
+
+/// The shared implementation of `WordBounds` and `Sentences`.
+#[cfg(feature = "graphemes")]
+#[derive(Debug, Clone)]
+struct Segments<'a> {
+    walker: SegmentWalker<'a>,
+    text: RopeSlice<'a>,
+    is_reversed: bool,
+}
+
+#[cfg(feature = "graphemes")]
+impl<'a> Segments<'a> {
+    fn new_at_byte(text: RopeSlice<'a>, kind: Segmentation, byte_idx: usize) -> Segments<'a> {
+        Segments {
+            walker: SegmentWalker::new_at_floor(text, kind, byte_idx),
+            text: text,
+            is_reversed: false,
+        }
+    }
+
+    #[inline]
+    fn prev(&mut self) -> Option<RopeSlice<'a>> {
+        if !self.is_reversed {
+            self.prev_impl()
+        } else {
+            self.next_impl()
+        }
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        if !self.is_reversed {
+            self.next_impl()
+        } else {
+            self.prev_impl()
+        }
+    }
+
+    fn prev_impl(&mut self) -> Option<RopeSlice<'a>> {
+        let end = self.walker.byte_idx();
+        let start = self.walker.prev_boundary()?;
+        Some(self.text.byte_slice(start..end))
+    }
+
+    fn next_impl(&mut self) -> Option<RopeSlice<'a>> {
+        let start = self.walker.byte_idx();
+        let end = self.walker.next_boundary()?;
+        Some(self.text.byte_slice(start..end))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::while_let_on_iterator)]
@@ -2885,7 +3358,7 @@ mod tests {
     #[test]
     #[cfg_attr(miri, ignore)]
     fn lines_reverse_04() {
-        let mut itr = Lines::from_str("a\n", 1);
+        let mut itr = Lines::from_str("a\n", 1, LineBreakMode::default());
 
         assert_eq!(Some("a\n".into()), itr.next());
         assert_eq!(Some("".into()), itr.next());
@@ -3481,6 +3954,117 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn lines_with_endings_01() {
+        use crate::LineEnding::*;
+
+        let text = "a\r\nb\nc\rd\u{2028}e\u{85}\r\n";
+        let mut r = Rope::from_str(text);
+        let expected: &[(LineBreakMode, &[(&str, Option<LineEnding>)])] = &[
+            (
+                LineBreakMode::Lf,
+                &[
+                    ("a\r", Some(Lf)),
+                    ("b", Some(Lf)),
+                    ("c\rd\u{2028}e\u{85}\r", Some(Lf)),
+                    ("", None),
+                ],
+            ),
+            (
+                LineBreakMode::Crlf,
+                &[
+                    ("a", Some(Crlf)),
+                    ("b", Some(Lf)),
+                    ("c", Some(Cr)),
+                    ("d\u{2028}e\u{85}", Some(Crlf)),
+                    ("", None),
+                ],
+            ),
+            (
+                LineBreakMode::Unicode,
+                &[
+                    ("a", Some(Crlf)),
+                    ("b", Some(Lf)),
+                    ("c", Some(Cr)),
+                    ("d", Some(Ls)),
+                    ("e", Some(Nel)),
+                    ("", Some(Crlf)),
+                    ("", None),
+                ],
+            ),
+        ];
+        for &(mode, lines) in expected {
+            r.set_line_break_mode(mode);
+            let found: Vec<_> = r.lines_with_endings().collect();
+            assert_eq!(found.len(), lines.len());
+            for (i, (&(content, ending), line)) in lines.iter().zip(r.lines()).enumerate() {
+                assert_eq!(found[i], (content.into(), ending));
+                assert_eq!(r.line_without_ending(i), (content.into(), ending));
+
+                // Content and ending put back together make the line.
+                let ending = ending.map(|e| e.as_str()).unwrap_or("");
+                assert_eq!(line, format!("{}{}", content, ending).as_str());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn lines_with_endings_02() {
+        // Same cursor behavior as `Lines`, from any starting line.
+        let r = Rope::from_str(TEXT);
+        let lines: Vec<_> = r.lines_with_endings().collect();
+        assert_eq!(lines.len(), r.len_lines());
+
+        for line_idx in 0..=r.len_lines() {
+            let mut itr = r.lines_with_endings_at(line_idx);
+            assert_eq!(itr.len(), r.len_lines() - line_idx);
+            if line_idx < r.len_lines() {
+                assert_eq!(itr.next(), Some(lines[line_idx]));
+                assert_eq!(itr.prev(), Some(lines[line_idx]));
+            }
+            itr.reverse();
+            assert_eq!(itr.len(), line_idx);
+            for i in (0..line_idx).rev() {
+                assert_eq!(itr.next(), Some(lines[i]));
+            }
+            assert_eq!(itr.next(), None);
+        }
+        assert!(r.get_lines_with_endings_at(r.len_lines() + 1).is_none());
+        assert!(r.get_line_without_ending(r.len_lines()).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn lines_with_endings_03() {
+        // A slice that splits a CRLF pair ends its last line with a CR.
+        let mut r = Rope::from_str("Hello\r\nworld\r\n");
+        r.set_line_break_mode(LineBreakMode::Crlf);
+        let s = r.slice(..13);
+        let lines: Vec<_> = s.lines_with_endings().collect();
+        assert_eq!(
+            lines,
+            [
+                ("Hello".into(), Some(LineEnding::Crlf)),
+                ("world".into(), Some(LineEnding::Cr)),
+                ("".into(), None),
+            ]
+        );
+        assert_eq!(
+            s.line_without_ending(1),
+            ("world".into(), Some(LineEnding::Cr))
+        );
+
+        r.set_line_break_mode(LineBreakMode::Lf);
+        let s = r.slice(..13);
+        assert_eq!(s.line_without_ending(1), ("world\r".into(), None));
+        assert_eq!(
+            s.lines_with_endings_at(2).prev(),
+            Some(("world\r".into(), None))
+        );
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn chunks_sliced_01() {
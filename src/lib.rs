@@ -6,7 +6,7 @@
 //! in terms of char indices, which prevents accidental creation of invalid
 //! utf8 data.
 //!
-//! The library is made up of four main components:
+//! The library is made up of five main components:
 //!
 //! - [`Rope`]: the main rope type.
 //! - [`RopeSlice`]: an immutable view into part of a
@@ -14,6 +14,15 @@
 //! - [`iter`]: iterators over `Rope`/`RopeSlice` data.
 //! - [`RopeBuilder`]: an efficient incremental
 //!   `Rope` builder.
+//! - [`ChangeSet`]: a composable, invertible list of
+//!   edits to a `Rope`.
+//!
+//! [`History`] builds undo/redo on top of `ChangeSet`s and
+//! cheap `Rope` clones, and [`MarkerSet`] and [`IntervalSet`]
+//! keep positions and ranges in the text up to date as it's
+//! edited, which a [`TrackedRope`] does for every edit.
+//! A [`Metric`] attached to a `Rope` is summarized in its tree, for
+//! building custom indexes.
 //!
 //!
 //! # A Basic Example
@@ -146,6 +155,11 @@
 //! (Note: `unicode_lines` is enabled by default, and always implies
 //! `cr_lines`.)
 //!
+//! These feature flags only choose the default [`LineBreakMode`] of new
+//! ropes.  The line breaks a particular rope recognizes can be changed at
+//! runtime with [`Rope::set_line_break_mode()`], and slices and iterators
+//! use the mode of the rope they were created from.
+//!
 //! CRLF pairs are always treated as a single line break, and are never split
 //! across chunks.  Note, however, that slicing can still split them.
 //!
@@ -160,6 +174,40 @@
 //! will also get disabled, and performance will suffer.  So be careful
 //! to explicitly re-enable the `simd` feature flag (if desired) when
 //! doing that.
+//!
+//!
+//! # Regex Search
+//!
+//! Ropey has a `regex` feature flag (disabled by default) that adds the
+//! [`regex`] module, whose [`Regex`](regex::Regex) type can search `Rope`s
+//! and `RopeSlice`s without first copying their text into a `String`.
+//!
+//!
+//! # Grapheme Clusters
+//!
+//! Ropey has a `graphemes` feature flag (disabled by default) that adds a
+//! [`Graphemes`](iter::Graphemes) iterator and grapheme boundary queries
+//! (`prev_grapheme_boundary()`, `next_grapheme_boundary()`, and
+//! `is_grapheme_boundary()`) to `Rope` and `RopeSlice`, using the
+//! `unicode-segmentation` crate's extended grapheme clusters.  The same
+//! feature also provides UAX #29 word and sentence segmentation via the
+//! [`Words`](iter::Words), [`WordBounds`](iter::WordBounds), and
+//! [`Sentences`](iter::Sentences) iterators, and grapheme-safe editing
+//! methods (`insert_at_grapheme()`, `remove_graphemes()`, and
+//! `slice_graphemes()`) that never split a grapheme cluster.
+//!
+//!
+//! # Display Width
+//!
+//! Ropey has a `width` feature flag (disabled by default) that adds
+//! `visual_column()` and `char_at_visual_column()` to `Rope` and
+//! `RopeSlice`, for converting between char indices and the columns that
+//! text takes up in a monospace terminal.  Tab stops are handled, and
+//! char widths come from the `unicode-width` crate.
+//!
+//! The same feature adds [`WrapIndex`], which soft-wraps a `Rope`'s lines
+//! to a given width and converts between visual rows and char indices,
+//! updating incrementally as the text is edited.
 
 #![allow(clippy::collapsible_if)]
 #![allow(clippy::inline_always)]
@@ -170,20 +218,55 @@
 extern crate smallvec;
 extern crate str_indices;
 
+#[cfg(feature = "regex")]
+extern crate regex_automata;
+#[cfg(feature = "graphemes")]
+extern crate unicode_segmentation;
+#[cfg(feature = "width")]
+extern crate unicode_width;
+
+mod change_set;
 mod crlf;
+mod history;
+mod interval;
+mod line_ending;
+mod marker;
+mod metric;
+mod position;
 mod rope;
 mod rope_builder;
+#[cfg(feature = "graphemes")]
+mod segment;
 mod slice;
+mod tracked;
 mod tree;
+#[cfg(feature = "width")]
+mod width;
+#[cfg(feature = "width")]
+mod wrap;
 
 pub mod iter;
+#[cfg(feature = "regex")]
+pub mod regex;
+pub mod search;
 pub mod str_utils;
 
 use std::ops::Bound;
 
+pub use crate::change_set::{ChangeSet, Operation};
+pub use crate::history::History;
+pub use crate::interval::{Expand, IntervalSet};
+pub use crate::line_ending::{LineEnding, LineEndingCounts};
+pub use crate::marker::{Gravity, MarkerId, MarkerSet, RemovePolicy};
+pub use crate::metric::{Metric, MetricSummaries};
+pub use crate::position::{ColumnEncoding, Position};
 pub use crate::rope::Rope;
 pub use crate::rope_builder::RopeBuilder;
 pub use crate::slice::RopeSlice;
+pub use crate::str_utils::LineBreakMode;
+pub use crate::tracked::{EditObserver, TrackedRope};
+#[cfg(feature = "width")]
+pub use crate::wrap::{VisualRows, WrapIndex, WrapOptions};
 
 /// NOT PART OF THE PUBLIC API (hidden from docs for a reason!)
 /// These are only exposed for tests that live in the `tests` directory.
@@ -225,11 +308,31 @@ pub enum Error {
     /// `Rope`/`RopeSlice` in utf16 code units, in that order.
     Utf16IndexOutOfBounds(usize, usize),
 
+    /// Indicates that the column of a passed line/column position was past
+    /// the end of its line.
+    ///
+    /// Contains the column attempted and the actual length of the line
+    /// (not counting its line break) in the position's column units, in
+    /// that order.
+    ColumnIndexOutOfBounds(usize, usize),
+
     /// Indicates that the passed byte index was not a char boundary.
     ///
     /// Contains the passed byte index.
     ByteIndexNotCharBoundary(usize),
 
+    /// Indicates that the passed utf16 code-unit index was in the middle
+    /// of a surrogate pair, rather than on a char boundary.
+    ///
+    /// Contains the passed utf16 code-unit index.
+    Utf16IndexNotCharBoundary(usize),
+
+    /// Indicates that the column of a passed line/column position was in
+    /// the middle of a char.
+    ///
+    /// Contains the column attempted.
+    ColumnNotCharBoundary(usize),
+
     /// Indicates that the passed byte range didn't line up with char
     /// boundaries.
     ///
@@ -259,6 +362,16 @@ pub enum Error {
         usize, // End.
     ),
 
+    /// Indicates that a reversed utf16 code-unit range (end < start) was
+    /// encountered.
+    ///
+    /// Contains the [start, end) utf16 code-unit indices of the range, in
+    /// that order.
+    Utf16RangeInvalid(
+        usize, // Start.
+        usize, // End.
+    ),
+
     /// Indicates that the passed byte-index range was partially or fully
     /// out of bounds.
     ///
@@ -282,6 +395,26 @@ pub enum Error {
         Option<usize>, // End.
         usize,         // Rope char length.
     ),
+
+    /// Indicates that char-index ranges that were required to be sorted
+    /// and non-overlapping weren't.
+    ///
+    /// Contains the end of the earlier range and the start of the later
+    /// range, in that order.
+    CharRangesOverlap(
+        usize, // Earlier end.
+        usize, // Later start.
+    ),
+
+    /// Indicates that a `ChangeSet` was used with text of a different
+    /// length than the one it was built for.
+    ///
+    /// Contains the expected length and the actual length of the text in
+    /// chars, in that order.
+    ChangeSetLengthMismatch(
+        usize, // Expected length.
+        usize, // Actual length.
+    ),
 }
 
 impl std::error::Error for Error {
@@ -327,6 +460,13 @@ impl std::fmt::Debug for Error {
             Error::Utf16IndexOutOfBounds(index, len) => {
                 write!(f, "Utf16 code-unit index out of bounds: utf16 index {}, Rope/RopeSlice utf16 length {}", index, len)
             }
+            Error::ColumnIndexOutOfBounds(index, len) => {
+                write!(
+                    f,
+                    "Column out of bounds: column {}, line length {}",
+                    index, len
+                )
+            }
             Error::ByteIndexNotCharBoundary(index) => {
                 write!(
                     f,
@@ -334,6 +474,16 @@ impl std::fmt::Debug for Error {
                     index
                 )
             }
+            Error::Utf16IndexNotCharBoundary(index) => {
+                write!(
+                    f,
+                    "Utf16 code-unit index is not a valid char boundary: utf16 index {}",
+                    index
+                )
+            }
+            Error::ColumnNotCharBoundary(index) => {
+                write!(f, "Column is not a valid char boundary: column {}", index)
+            }
             Error::ByteRangeNotCharBoundary(start_idx_opt, end_idx_opt) => {
                 write!(f, "Byte range does not align with char boundaries: range ")?;
                 write_range(f, start_idx_opt, end_idx_opt)
@@ -352,6 +502,13 @@ impl std::fmt::Debug for Error {
                     start_idx, end_idx
                 )
             }
+            Error::Utf16RangeInvalid(start_idx, end_idx) => {
+                write!(
+                    f,
+                    "Invalid utf16 code-unit range {}..{}: start must be <= end",
+                    start_idx, end_idx
+                )
+            }
             Error::ByteRangeOutOfBounds(start_idx_opt, end_idx_opt, len) => {
                 write!(f, "Byte range out of bounds: byte range ")?;
                 write_range(f, start_idx_opt, end_idx_opt)?;
@@ -362,6 +519,20 @@ impl std::fmt::Debug for Error {
                 write_range(f, start_idx_opt, end_idx_opt)?;
                 write!(f, ", Rope/RopeSlice char length {}", len)
             }
+            Error::CharRangesOverlap(end_idx, start_idx) => {
+                write!(
+                    f,
+                    "Overlapping or unsorted char ranges: range ending at {} followed by range starting at {}",
+                    end_idx, start_idx
+                )
+            }
+            Error::ChangeSetLengthMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "ChangeSet length mismatch: expected text of char length {}, got char length {}",
+                    expected, actual
+                )
+            }
         }
     }
 }
@@ -0,0 +1,396 @@
+//! Detection and conversion of the line endings used in text.
+
+use crate::rope::Rope;
+use crate::rope_builder::RopeBuilder;
+use crate::slice::RopeSlice;
+use crate::str_utils::LineBreakMode;
+
+/// A kind of line ending.
+///
+/// Text can be normalized to any of these with
+/// [`Rope::normalize_line_endings()`], though usually only the first three
+/// are used as line endings in files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `"\n"`, as used on Unix-like systems.
+    Lf,
+    /// `"\r\n"`, as used on Windows.
+    Crlf,
+    /// `"\r"`, as used on classic Mac OS.
+    Cr,
+    /// `U+000B`, Vertical Tab.
+    Vt,
+    /// `U+000C`, Form Feed.
+    Ff,
+    /// `U+0085`, Next Line.
+    Nel,
+    /// `U+2028`, Line Separator.
+    Ls,
+    /// `U+2029`, Paragraph Separator.
+    Ps,
+}
+
+impl LineEnding {
+    /// Returns the line ending as a string.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+            LineEnding::Vt => "\u{000B}",
+            LineEnding::Ff => "\u{000C}",
+            LineEnding::Nel => "\u{0085}",
+            LineEnding::Ls => "\u{2028}",
+            LineEnding::Ps => "\u{2029}",
+        }
+    }
+
+    /// Returns the length of the line ending in chars.
+    #[inline]
+    pub fn len_chars(&self) -> usize {
+        if *self == LineEnding::Crlf {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Returns the line ending that ends with `last`, if any, given the
+    /// char before it and the line break mode.
+    fn from_line_end(prev: Option<char>, last: char, mode: LineBreakMode) -> Option<LineEnding> {
+        use self::LineBreakMode::*;
+        match (last, mode) {
+            ('\u{000A}', Lf) => Some(LineEnding::Lf),
+            ('\u{000A}', _) if prev == Some('\u{000D}') => Some(LineEnding::Crlf),
+            ('\u{000A}', _) => Some(LineEnding::Lf),
+            ('\u{000D}', Crlf) | ('\u{000D}', Unicode) => Some(LineEnding::Cr),
+            ('\u{000B}', Unicode) => Some(LineEnding::Vt),
+            ('\u{000C}', Unicode) => Some(LineEnding::Ff),
+            ('\u{0085}', Unicode) => Some(LineEnding::Nel),
+            ('\u{2028}', Unicode) => Some(LineEnding::Ls),
+            ('\u{2029}', Unicode) => Some(LineEnding::Ps),
+            _ => None,
+        }
+    }
+}
+
+/// The number of each kind of line ending in some text, as returned by
+/// [`Rope::detect_line_ending()`].
+///
+/// CRLF pairs are only counted as `crlf`, not as a CR and an LF.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LineEndingCounts {
+    /// The number of LFs that aren't part of a CRLF pair.
+    pub lf: usize,
+    /// The number of CRLF pairs.
+    pub crlf: usize,
+    /// The number of CRs that aren't part of a CRLF pair.
+    pub cr: usize,
+    /// The number of Vertical Tabs.
+    pub vt: usize,
+    /// The number of Form Feeds.
+    pub ff: usize,
+    /// The number of Next Lines.
+    pub nel: usize,
+    /// The number of Line Separators.
+    pub ls: usize,
+    /// The number of Paragraph Separators.
+    pub ps: usize,
+}
+
+impl LineEndingCounts {
+    /// Returns the count of the given kind of line ending.
+    #[inline]
+    pub fn get(&self, ending: LineEnding) -> usize {
+        match ending {
+            LineEnding::Lf => self.lf,
+            LineEnding::Crlf => self.crlf,
+            LineEnding::Cr => self.cr,
+            LineEnding::Vt => self.vt,
+            LineEnding::Ff => self.ff,
+            LineEnding::Nel => self.nel,
+            LineEnding::Ls => self.ls,
+            LineEnding::Ps => self.ps,
+        }
+    }
+
+    /// Total number of line endings of all kinds.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.lf + self.crlf + self.cr + self.unicode()
+    }
+
+    /// Total number of the line endings that are only recognized as line
+    /// breaks in [`LineBreakMode::Unicode`].
+    #[inline]
+    pub fn unicode(&self) -> usize {
+        self.vt + self.ff + self.nel + self.ls + self.ps
+    }
+
+    /// Returns whether more than one of LF, CRLF, and CR occur.
+    #[inline]
+    pub fn is_mixed(&self) -> bool {
+        [self.lf, self.crlf, self.cr]
+            .iter()
+            .filter(|&&n| n > 0)
+            .count()
+            > 1
+    }
+
+    /// Returns the most common of LF, CRLF, and CR, or `None` if there
+    /// are none of them.
+    ///
+    /// Ties are broken in that order, so e.g. text with as many LFs as
+    /// CRLFs is considered LF.
+    pub fn dominant(&self) -> Option<LineEnding> {
+        let mut dominant = None;
+        let mut max = 0;
+        for &ending in &[LineEnding::Lf, LineEnding::Crlf, LineEnding::Cr] {
+            if self.get(ending) > max {
+                dominant = Some(ending);
+                max = self.get(ending);
+            }
+        }
+        dominant
+    }
+}
+
+/// Shared implementation of `detect_line_ending()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn detect(text: RopeSlice) -> LineEndingCounts {
+    let mut counts = LineEndingCounts::default();
+
+    // Slices can split a CRLF pair across chunks, so a CR at the end of a
+    // chunk is held onto until we see what comes next.
+    let mut pending_cr = false;
+    for chunk in text.chunks() {
+        let bytes = chunk.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if pending_cr {
+                pending_cr = false;
+                if byte == 0x0A {
+                    counts.crlf += 1;
+                    i += 1;
+                    continue;
+                }
+                counts.cr += 1;
+            }
+            match byte {
+                0x0A => counts.lf += 1,
+                0x0D => {
+                    if bytes.get(i + 1) == Some(&0x0A) {
+                        counts.crlf += 1;
+                        i += 1;
+                    } else {
+                        pending_cr = true;
+                    }
+                }
+                0x0B => counts.vt += 1,
+                0x0C => counts.ff += 1,
+                0xC2 if bytes.get(i + 1) == Some(&0x85) => counts.nel += 1,
+                0xE2 if bytes.get(i + 1) == Some(&0x80) => match bytes.get(i + 2) {
+                    Some(&0xA8) => counts.ls += 1,
+                    Some(&0xA9) => counts.ps += 1,
+                    _ => {}
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    if pending_cr {
+        counts.cr += 1;
+    }
+
+    counts
+}
+
+/// Implementation of `Rope::normalize_line_endings()`.
+pub(crate) fn normalize(rope: &mut Rope, ending: LineEnding) -> usize {
+    let counts = rope.detect_line_ending();
+    let count = [LineEnding::Lf, LineEnding::Crlf, LineEnding::Cr]
+        .iter()
+        .filter(|&&e| e != ending)
+        .map(|&e| counts.get(e))
+        .sum::<usize>();
+    if count == 0 {
+        return 0;
+    }
+
+    // CRLF pairs are never split across a `Rope`'s chunks, so each chunk
+    // can be converted on its own.  The builder takes care of not
+    // splitting any CRLF pairs in the new text.
+    let mut builder = RopeBuilder::new();
+    let mut buf = String::new();
+    for chunk in rope.chunks() {
+        buf.clear();
+        if convert(chunk, ending, &mut buf) == 0 {
+            builder.append(chunk);
+        } else {
+            builder.append(&buf);
+        }
+    }
+    rope.root = builder.finish().root;
+
+    count
+}
+
+/// Splits the line break off the end of `line`, returning the rest of the
+/// line and the kind of line break, if any.
+///
+/// Only line breaks recognized by the slice's [`LineBreakMode`] are split
+/// off.
+pub(crate) fn split_line_ending(line: RopeSlice) -> (RopeSlice, Option<LineEnding>) {
+    let len = line.len_chars();
+    let mut chars = line.chars_at(len);
+    let ending = match chars.prev() {
+        Some(last) => LineEnding::from_line_end(chars.prev(), last, line.line_break_mode()),
+        None => None,
+    };
+    match ending {
+        Some(ending) => (line.slice(..len - ending.len_chars()), Some(ending)),
+        None => (line, None),
+    }
+}
+
+/// Appends `text` to `dst` with its LF, CRLF, and CR line endings
+/// replaced by `ending`, returning the number that were changed.
+fn convert(text: &str, ending: LineEnding, dst: &mut String) -> usize {
+    let bytes = text.as_bytes();
+    let mut count = 0;
+    let mut last = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let found = match bytes[i] {
+            0x0A => LineEnding::Lf,
+            0x0D if bytes.get(i + 1) == Some(&0x0A) => LineEnding::Crlf,
+            0x0D => LineEnding::Cr,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let len = found.as_str().len();
+        if found != ending {
+            dst.push_str(&text[last..i]);
+            dst.push_str(ending.as_str());
+            last = i + len;
+            count += 1;
+        }
+        i += len;
+    }
+    if count > 0 {
+        dst.push_str(&text[last..]);
+    }
+
+    count
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LineBreakMode, Rope};
+
+    // All kinds of line endings, spread over several chunks by the small
+    // `MAX_BYTES` used in tests.
+    const TEXT: &str = "Hello\r\nworld\n\
+                        foo\rbar\r\n\r\n\
+                        \u{2028}baz\u{85}\x0B\x0C\r";
+
+    #[test]
+    fn detect_line_ending_01() {
+        let r = Rope::from_str(TEXT);
+        let counts = r.detect_line_ending();
+        assert_eq!(
+            counts,
+            LineEndingCounts {
+                lf: 1,
+                crlf: 3,
+                cr: 2,
+                vt: 1,
+                ff: 1,
+                nel: 1,
+                ls: 1,
+                ps: 0,
+            }
+        );
+        assert_eq!(counts.unicode(), 4);
+        assert_eq!(counts.total(), 10);
+        assert!(counts.is_mixed());
+        assert_eq!(counts.dominant(), Some(LineEnding::Crlf));
+
+        let counts = Rope::from_str("a\nb\r\nc").detect_line_ending();
+        assert_eq!(counts.dominant(), Some(LineEnding::Lf));
+        let counts = Rope::from_str("a\u{2029}b").detect_line_ending();
+        assert!(!counts.is_mixed());
+        assert_eq!(counts.dominant(), None);
+        assert_eq!(counts.total(), 1);
+    }
+
+    #[test]
+    fn detect_line_ending_02() {
+        // Slices that split CRLF pairs, at the ends and across chunks.
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r.slice(6..).detect_line_ending().crlf, 2);
+        assert_eq!(r.slice(6..).detect_line_ending().lf, 2);
+        assert_eq!(r.slice(..6).detect_line_ending().cr, 1);
+        assert_eq!(r.slice(..6).detect_line_ending().crlf, 0);
+
+        let s = "\r\n".repeat(20);
+        let r = Rope::from_str(&s);
+        for i in 0..s.len() {
+            let counts = r.slice(i..).detect_line_ending();
+            assert_eq!(counts.crlf, (s.len() - i) / 2);
+            assert_eq!(counts.lf, i % 2);
+        }
+    }
+
+    #[test]
+    fn normalize_line_endings_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(r.normalize_line_endings(LineEnding::Lf), 5);
+        assert_eq!(r, "Hello\nworld\nfoo\nbar\n\n\u{2028}baz\u{85}\x0B\x0C\n");
+        assert_eq!(r.normalize_line_endings(LineEnding::Lf), 0);
+
+        assert_eq!(r.normalize_line_endings(LineEnding::Crlf), 6);
+        assert_eq!(
+            r,
+            "Hello\r\nworld\r\nfoo\r\nbar\r\n\r\n\u{2028}baz\u{85}\x0B\x0C\r\n"
+        );
+        r.assert_integrity();
+        r.assert_invariants();
+
+        assert_eq!(r.normalize_line_endings(LineEnding::Cr), 6);
+        assert_eq!(r, "Hello\rworld\rfoo\rbar\r\r\u{2028}baz\u{85}\x0B\x0C\r");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn normalize_line_endings_02() {
+        // Lone CRs and LFs at chunk edges, which become CRLF pairs that
+        // must not be split.
+        let s = "\r\r\n\nab\r\n".repeat(30);
+        for &ending in &[LineEnding::Lf, LineEnding::Crlf, LineEnding::Cr] {
+            let mut r = Rope::from_str(&s);
+            r.set_line_break_mode(LineBreakMode::Lf);
+            r.normalize_line_endings(ending);
+
+            let expected = s
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .replace('\n', ending.as_str());
+            assert_eq!(r, expected.as_str());
+            assert_eq!(r.line_break_mode(), LineBreakMode::Lf);
+            let counts = r.detect_line_ending();
+            assert_eq!(counts.get(ending), counts.total());
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+    }
+}
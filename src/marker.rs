@@ -0,0 +1,783 @@
+use std::ops::{Range, RangeBounds};
+
+use crate::change_set::{ChangeSet, Operation};
+use crate::rope::Rope;
+use crate::str_utils::count_chars;
+use crate::tracked::EditObserver;
+use crate::{end_bound_to_num, start_bound_to_num};
+
+/// Which way a position moves when text is inserted exactly at it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Gravity {
+    /// Stays put, ending up before the inserted text.
+    Left,
+    /// Moves with the text after it, ending up after the inserted text.
+    Right,
+}
+
+/// What happens to a marker when the text around it is removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RemovePolicy {
+    /// The marker moves to the start of the removed range.
+    Collapse,
+    /// The marker is removed from its [`MarkerSet`].
+    Delete,
+}
+
+/// A handle to a marker in a [`MarkerSet`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MarkerId {
+    index: usize,
+    generation: usize,
+}
+
+/// A set of char positions that follow edits to a `Rope`.
+///
+/// Markers are useful for anything that needs to point into text while it
+/// is being edited: cursors, bookmarks, diagnostics, etc.  Whenever the
+/// text is edited, the edit is passed on to the `MarkerSet`, and all
+/// markers are shifted accordingly.
+///
+/// `MarkerSet` is an [`EditObserver`], so the simplest way to keep it in
+/// sync is to let a [`TrackedRope`](crate::TrackedRope) own it along with
+/// the text, which passes on every edit automatically.  Otherwise, each
+/// edit has to be passed on by hand via the `update_for_*()` methods.
+///
+/// Each marker has a [`Gravity`] that decides where it goes when text is
+/// inserted exactly at its position, and a [`RemovePolicy`] that decides
+/// what happens to it when it's strictly inside removed text.  Markers at
+/// the edges of removed text are never deleted.
+///
+/// Markers are stored in a balanced tree ordered by position, where each
+/// node carries a pending shift for its children, as in
+/// [`IntervalSet`](crate::IntervalSet).  An edit shifts all the markers
+/// after it in one step, so it only has to visit the markers at or inside
+/// the edited range.
+///
+/// # Example
+///
+/// Updating a `MarkerSet` by hand:
+///
+/// ```
+/// # use ropey::{Gravity, MarkerSet, RemovePolicy, Rope};
+/// #
+/// let mut rope = Rope::from_str("Hello world!");
+/// let mut markers = MarkerSet::new();
+/// let m = markers.add(6, Gravity::Left, RemovePolicy::Collapse);
+///
+/// rope.insert(0, "Oh, ");
+/// markers.update_for_insert(0, 4);
+/// assert_eq!(markers.get(m), Some(10));
+/// assert_eq!(rope.char(10), 'w');
+///
+/// rope.remove(7..13);
+/// markers.update_for_remove(7..13);
+/// assert_eq!(markers.get(m), Some(7));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkerSet {
+    /// The nodes of the tree, indexed by `MarkerId::index`.  The nodes of
+    /// removed markers stay here for reuse.
+    nodes: Vec<Node>,
+    /// Indices of free nodes, for reuse.
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+    /// State of the generator for node priorities.
+    seed: u64,
+}
+
+/// The index of no node.
+const NIL: usize = usize::MAX;
+
+/// A marker, which is also a node of the tree.  The tree is a treap:
+/// ordered by `pos` as a binary search tree, and by `priority` as a
+/// max-heap.
+///
+/// `pos` is in terms of the pending shifts of the node's ancestors, i.e.
+/// the actual position is found by adding the `shift` of every ancestor.
+#[derive(Debug, Clone)]
+struct Node {
+    /// Incremented whenever the node is freed, so that the ids of removed
+    /// markers don't match the marker that reuses the node.
+    generation: usize,
+    pos: usize,
+    gravity: Gravity,
+    remove_policy: RemovePolicy,
+    /// A shift (added with wrapping arithmetic) that still needs to be
+    /// applied to both children.
+    shift: usize,
+    priority: u64,
+    parent: usize,
+    left: usize,
+    right: usize,
+}
+
+impl MarkerSet {
+    /// Creates an empty `MarkerSet`.
+    #[inline]
+    pub fn new() -> Self {
+        MarkerSet {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// Total number of markers in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the set has no markers.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the char index of the marker `id`, or `None` if it has been
+    /// removed.
+    ///
+    /// Runs in O(log M) time, where M is the number of markers.
+    pub fn get(&self, id: MarkerId) -> Option<usize> {
+        let node = self.nodes.get(id.index)?;
+        if node.generation != id.generation {
+            return None;
+        }
+
+        let mut pos = node.pos;
+        let mut parent = node.parent;
+        while parent != NIL {
+            pos = pos.wrapping_add(self.nodes[parent].shift);
+            parent = self.nodes[parent].parent;
+        }
+        Some(pos)
+    }
+
+    /// Returns an iterator over the markers within `char_range`, in order
+    /// of position.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  Unlike most ranges
+    /// in Ropey, the end of the range may be past the end of the text.
+    ///
+    /// Runs in O(log M) time, plus the iteration.
+    pub fn range<R>(&self, char_range: R) -> impl Iterator<Item = (MarkerId, usize)> + '_
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = start_bound_to_num(char_range.start_bound()).unwrap_or(0);
+        let end = end_bound_to_num(char_range.end_bound()).unwrap_or(usize::MAX);
+        let mut iter = Iter {
+            nodes: &self.nodes,
+            stack: Vec::new(),
+            end: end,
+        };
+        iter.seek(self.root, start);
+        iter
+    }
+
+    /// Returns an iterator over all markers, in order of position.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (MarkerId, usize)> + '_ {
+        self.range(..)
+    }
+
+    //-----------------------------------------------------------------------
+    // Marker management methods
+
+    /// Adds a marker at char index `char_idx`, returning its id.
+    ///
+    /// Markers at the same position are kept in the order they were added.
+    ///
+    /// Runs in O(log M) time.
+    pub fn add(
+        &mut self,
+        char_idx: usize,
+        gravity: Gravity,
+        remove_policy: RemovePolicy,
+    ) -> MarkerId {
+        let priority = self.next_priority();
+        let index = if let Some(index) = self.free.pop() {
+            index
+        } else {
+            self.nodes.push(Node {
+                generation: 0,
+                pos: 0,
+                gravity: gravity,
+                remove_policy: remove_policy,
+                shift: 0,
+                priority: 0,
+                parent: NIL,
+                left: NIL,
+                right: NIL,
+            });
+            self.nodes.len() - 1
+        };
+        {
+            let node = &mut self.nodes[index];
+            node.pos = char_idx;
+            node.gravity = gravity;
+            node.remove_policy = remove_policy;
+            node.shift = 0;
+            node.priority = priority;
+            node.left = NIL;
+            node.right = NIL;
+        }
+
+        let (left, right) = self.split(self.root, char_idx, true);
+        let left = self.merge(left, index);
+        let root = self.merge(left, right);
+        self.set_root(root);
+        self.len += 1;
+
+        MarkerId {
+            index: index,
+            generation: self.nodes[index].generation,
+        }
+    }
+
+    /// Removes the marker `id`, returning its last position.
+    ///
+    /// Returns `None` if the marker has already been removed.
+    ///
+    /// Runs in O(log M) time.
+    pub fn remove(&mut self, id: MarkerId) -> Option<usize> {
+        let pos = self.get(id)?;
+
+        // Replace the node with the merge of its children.
+        self.push_shift(id.index);
+        let (parent, left, right) = {
+            let node = &self.nodes[id.index];
+            (node.parent, node.left, node.right)
+        };
+        let merged = self.merge(left, right);
+        if parent == NIL {
+            self.set_root(merged);
+        } else {
+            if self.nodes[parent].left == id.index {
+                self.nodes[parent].left = merged;
+            } else {
+                self.nodes[parent].right = merged;
+            }
+            if merged != NIL {
+                self.nodes[merged].parent = parent;
+            }
+        }
+        self.free_node(id.index);
+
+        Some(pos)
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit tracking methods
+
+    /// Updates the markers for `len_chars` chars of text having been
+    /// inserted at char index `char_idx`.
+    ///
+    /// Runs in O(log M + K) time, where K is the number of markers at
+    /// `char_idx`.
+    pub fn update_for_insert(&mut self, char_idx: usize, len_chars: usize) {
+        if len_chars == 0 {
+            return;
+        }
+
+        let (left, rest) = self.split(self.root, char_idx, false);
+        let (at, right) = self.split(rest, char_idx, true);
+
+        // Markers exactly at the insertion point.  Ones with left gravity
+        // stay put, so they need to stay in front of the ones that move.
+        let mut nodes = Vec::new();
+        self.flatten(at, &mut nodes);
+        let (stay, moved): (Vec<usize>, Vec<usize>) = nodes
+            .into_iter()
+            .partition(|&i| self.nodes[i].gravity == Gravity::Left);
+        for &i in moved.iter() {
+            self.nodes[i].pos += len_chars;
+        }
+
+        // Everything after the insertion point moves.
+        self.apply_shift(right, len_chars);
+
+        let stay = self.build(&stay);
+        let moved = self.build(&moved);
+        let left = self.merge(left, stay);
+        let left = self.merge(left, moved);
+        let root = self.merge(left, right);
+        self.set_root(root);
+    }
+
+    /// Updates the markers for the text in `char_range` having been
+    /// removed.
+    ///
+    /// Runs in O(log M + K) time, where K is the number of markers
+    /// strictly inside the range.
+    pub fn update_for_remove(&mut self, char_range: Range<usize>) {
+        if char_range.start >= char_range.end {
+            return;
+        }
+        let (start, end) = (char_range.start, char_range.end);
+
+        let (left, rest) = self.split(self.root, start, true);
+        let (inside, right) = self.split(rest, end, false);
+
+        // Markers strictly inside the range all end up at its start, in
+        // the same order, unless they're deleted.
+        let mut nodes = Vec::new();
+        self.flatten(inside, &mut nodes);
+        let mut kept = Vec::with_capacity(nodes.len());
+        for i in nodes {
+            match self.nodes[i].remove_policy {
+                RemovePolicy::Collapse => {
+                    self.nodes[i].pos = start;
+                    kept.push(i);
+                }
+                RemovePolicy::Delete => self.free_node(i),
+            }
+        }
+
+        // Everything after the range moves.
+        self.apply_shift(right, (end - start).wrapping_neg());
+
+        let kept = self.build(&kept);
+        let left = self.merge(left, kept);
+        let root = self.merge(left, right);
+        self.set_root(root);
+    }
+
+    /// Updates the markers for `changes` having been applied to the text.
+    ///
+    /// This is equivalent to calling `update_for_insert()` and
+    /// `update_for_remove()` for each of the edits in `changes`, so it
+    /// runs in O(log M) time per edit, plus the markers touched.
+    pub fn update_for_changes(&mut self, changes: &ChangeSet) {
+        let mut pos = 0;
+        for op in changes.ops() {
+            match *op {
+                Operation::Retain(n) => {
+                    pos += n;
+                }
+                Operation::Delete(n) => {
+                    self.update_for_remove(pos..(pos + n));
+                }
+                Operation::Insert(ref text) => {
+                    let len = count_chars(text);
+                    self.update_for_insert(pos, len);
+                    pos += len;
+                }
+            }
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Returns a pseudo-random priority for a new node (xorshift64*).
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Frees a node that has been taken out of the tree.
+    fn free_node(&mut self, index: usize) {
+        self.nodes[index].generation += 1;
+        self.free.push(index);
+        self.len -= 1;
+    }
+
+    fn set_root(&mut self, root: usize) {
+        self.root = root;
+        if root != NIL {
+            self.nodes[root].parent = NIL;
+        }
+    }
+
+    /// Shifts the whole subtree rooted at `index`, if any, by `shift`.
+    #[inline]
+    fn apply_shift(&mut self, index: usize, shift: usize) {
+        if index != NIL {
+            let node = &mut self.nodes[index];
+            node.pos = node.pos.wrapping_add(shift);
+            node.shift = node.shift.wrapping_add(shift);
+        }
+    }
+
+    /// Applies the pending shift of node `index` to its children.
+    #[inline]
+    fn push_shift(&mut self, index: usize) {
+        let (shift, left, right) = {
+            let node = &self.nodes[index];
+            (node.shift, node.left, node.right)
+        };
+        if shift != 0 {
+            self.apply_shift(left, shift);
+            self.apply_shift(right, shift);
+            self.nodes[index].shift = 0;
+        }
+    }
+
+    /// Points the children of node `index` back at it.
+    #[inline]
+    fn adopt_children(&mut self, index: usize) {
+        let (left, right) = (self.nodes[index].left, self.nodes[index].right);
+        for &child in [left, right].iter() {
+            if child != NIL {
+                self.nodes[child].parent = index;
+            }
+        }
+    }
+
+    /// Splits the tree rooted at `index` into the markers before `pos`
+    /// (or at it, if `inclusive`) and the rest, returning their roots.
+    fn split(&mut self, index: usize, pos: usize, inclusive: bool) -> (usize, usize) {
+        if index == NIL {
+            return (NIL, NIL);
+        }
+        self.push_shift(index);
+        let node_pos = self.nodes[index].pos;
+        if node_pos < pos || (inclusive && node_pos == pos) {
+            let (left, right) = self.split(self.nodes[index].right, pos, inclusive);
+            self.nodes[index].right = left;
+            self.adopt_children(index);
+            (index, right)
+        } else {
+            let (left, right) = self.split(self.nodes[index].left, pos, inclusive);
+            self.nodes[index].left = right;
+            self.adopt_children(index);
+            (left, index)
+        }
+    }
+
+    /// Joins the trees rooted at `a` and `b`, where all markers of `a`
+    /// come before those of `b`, returning the new root.
+    fn merge(&mut self, a: usize, b: usize) -> usize {
+        if a == NIL {
+            return b;
+        }
+        if b == NIL {
+            return a;
+        }
+        if self.nodes[a].priority >= self.nodes[b].priority {
+            self.push_shift(a);
+            let right = self.merge(self.nodes[a].right, b);
+            self.nodes[a].right = right;
+            self.adopt_children(a);
+            a
+        } else {
+            self.push_shift(b);
+            let left = self.merge(a, self.nodes[b].left);
+            self.nodes[b].left = left;
+            self.adopt_children(b);
+            b
+        }
+    }
+
+    /// Builds a tree from nodes that are in order and have no children,
+    /// returning its root.
+    fn build(&mut self, nodes: &[usize]) -> usize {
+        nodes
+            .iter()
+            .fold(NIL, |root, &index| self.merge(root, index))
+    }
+
+    /// Takes the tree rooted at `index` apart into its nodes, in order.
+    fn flatten(&mut self, index: usize, nodes: &mut Vec<usize>) {
+        if index != NIL {
+            self.push_shift(index);
+            let (left, right) = (self.nodes[index].left, self.nodes[index].right);
+            self.nodes[index].left = NIL;
+            self.nodes[index].right = NIL;
+            self.flatten(left, nodes);
+            nodes.push(index);
+            self.flatten(right, nodes);
+        }
+    }
+}
+
+impl Default for MarkerSet {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EditObserver for MarkerSet {
+    fn replaced(&mut self, _text: &Rope, char_range: Range<usize>, len_chars: usize) {
+        let start = char_range.start;
+        self.update_for_remove(char_range);
+        self.update_for_insert(start, len_chars);
+    }
+
+    fn changed(&mut self, _text: &Rope, changes: &ChangeSet) {
+        self.update_for_changes(changes);
+    }
+}
+
+/// An in-order iterator over the markers of the tree that are before
+/// `end`.
+struct Iter<'a> {
+    nodes: &'a [Node],
+    /// Nodes still to be yielded, along with the sum of their ancestors'
+    /// pending shifts.
+    stack: Vec<(usize, usize)>,
+    end: usize,
+}
+
+impl<'a> Iter<'a> {
+    /// Pushes the path from node `index` to its first marker at or after
+    /// `char_idx`.
+    fn seek(&mut self, mut index: usize, char_idx: usize) {
+        let mut shift = 0;
+        while index != NIL {
+            let node = &self.nodes[index];
+            if node.pos.wrapping_add(shift) >= char_idx {
+                self.stack.push((index, shift));
+                index = node.left;
+            } else {
+                index = node.right;
+            }
+            shift = shift.wrapping_add(node.shift);
+        }
+    }
+
+    fn push_left(&mut self, mut index: usize, mut shift: usize) {
+        while index != NIL {
+            let node = &self.nodes[index];
+            self.stack.push((index, shift));
+            shift = shift.wrapping_add(node.shift);
+            index = node.left;
+        }
+    }
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (MarkerId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, shift) = self.stack.pop()?;
+        let node = &self.nodes[index];
+        let pos = node.pos.wrapping_add(shift);
+        if pos >= self.end {
+            self.stack.clear();
+            return None;
+        }
+        self.push_left(node.right, shift.wrapping_add(node.shift));
+        Some((
+            MarkerId {
+                index: index,
+                generation: node.generation,
+            },
+            pos,
+        ))
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rope;
+
+    fn positions(markers: &MarkerSet) -> Vec<usize> {
+        markers.iter().map(|(_, pos)| pos).collect()
+    }
+
+    #[test]
+    fn add_remove_01() {
+        let mut m = MarkerSet::new();
+        assert!(m.is_empty());
+        let a = m.add(5, Gravity::Left, RemovePolicy::Collapse);
+        let b = m.add(2, Gravity::Left, RemovePolicy::Collapse);
+        let c = m.add(9, Gravity::Left, RemovePolicy::Collapse);
+        assert_eq!(m.len(), 3);
+        assert_eq!(positions(&m), [2, 5, 9]);
+
+        assert_eq!(m.remove(a), Some(5));
+        assert_eq!(m.remove(a), None);
+        assert_eq!(m.get(a), None);
+        assert_eq!(m.get(b), Some(2));
+        assert_eq!(m.get(c), Some(9));
+
+        // Reused slots don't resurrect old ids.
+        let d = m.add(7, Gravity::Left, RemovePolicy::Collapse);
+        assert_ne!(a, d);
+        assert_eq!(m.get(a), None);
+        assert_eq!(m.get(d), Some(7));
+        assert_eq!(positions(&m), [2, 7, 9]);
+    }
+
+    #[test]
+    fn range_01() {
+        let mut m = MarkerSet::new();
+        for &pos in [8, 1, 4, 4, 12, 0].iter() {
+            m.add(pos, Gravity::Left, RemovePolicy::Collapse);
+        }
+
+        let range = |r: Range<usize>| m.range(r).map(|(_, pos)| pos).collect::<Vec<_>>();
+        assert_eq!(range(0..4), [0, 1]);
+        assert_eq!(range(4..5), [4, 4]);
+        assert_eq!(range(4..12), [4, 4, 8]);
+        assert_eq!(range(5..8), []);
+        assert_eq!(range(9..100), [12]);
+        assert_eq!(m.range(8..).count(), 2);
+        assert_eq!(m.range(..).count(), 6);
+    }
+
+    #[test]
+    fn update_for_insert_01() {
+        let mut m = MarkerSet::new();
+        let a = m.add(3, Gravity::Right, RemovePolicy::Collapse);
+        let b = m.add(3, Gravity::Left, RemovePolicy::Collapse);
+        let c = m.add(2, Gravity::Right, RemovePolicy::Collapse);
+        let d = m.add(4, Gravity::Left, RemovePolicy::Collapse);
+
+        m.update_for_insert(3, 5);
+        assert_eq!(m.get(a), Some(8));
+        assert_eq!(m.get(b), Some(3));
+        assert_eq!(m.get(c), Some(2));
+        assert_eq!(m.get(d), Some(9));
+        assert_eq!(positions(&m), [2, 3, 8, 9]);
+
+        m.update_for_insert(100, 5);
+        assert_eq!(positions(&m), [2, 3, 8, 9]);
+        m.update_for_insert(0, 1);
+        assert_eq!(positions(&m), [3, 4, 9, 10]);
+    }
+
+    #[test]
+    fn update_for_remove_01() {
+        let mut m = MarkerSet::new();
+        let a = m.add(2, Gravity::Left, RemovePolicy::Delete);
+        let b = m.add(3, Gravity::Left, RemovePolicy::Delete);
+        let c = m.add(4, Gravity::Left, RemovePolicy::Collapse);
+        let d = m.add(6, Gravity::Left, RemovePolicy::Delete);
+        let e = m.add(10, Gravity::Left, RemovePolicy::Delete);
+
+        m.update_for_remove(2..6);
+        assert_eq!(m.get(a), Some(2));
+        assert_eq!(m.get(b), None);
+        assert_eq!(m.get(c), Some(2));
+        assert_eq!(m.get(d), Some(2));
+        assert_eq!(m.get(e), Some(6));
+        assert_eq!(m.len(), 4);
+        assert_eq!(positions(&m), [2, 2, 2, 6]);
+    }
+
+    #[test]
+    fn update_for_changes_01() {
+        let mut r = Rope::from_str("Hello world! How are you?");
+        let mut m = MarkerSet::new();
+        let hello = m.add(0, Gravity::Left, RemovePolicy::Collapse);
+        let world = m.add(6, Gravity::Left, RemovePolicy::Delete);
+        let how = m.add(13, Gravity::Right, RemovePolicy::Delete);
+        let you = m.add(21, Gravity::Left, RemovePolicy::Delete);
+
+        let mut c = ChangeSet::new(&r);
+        c.insert("Oh, ")
+            .retain(5)
+            .delete(7)
+            .insert("!")
+            .retain(1)
+            .insert("So, ")
+            .retain(8)
+            .delete(3)
+            .insert("they");
+        c.apply(&mut r).unwrap();
+        m.update_for_changes(&c);
+
+        assert_eq!(r, "Oh, Hello! So, How are they?");
+        assert_eq!(m.get(hello), Some(0));
+        assert_eq!(m.get(world), None);
+        assert_eq!(m.get(how), Some(15));
+        assert_eq!(m.get(you), Some(23));
+        assert_eq!(r.char(15), 'H');
+    }
+
+    #[test]
+    fn update_random_01() {
+        // Compare against a brute force model over many edits.
+        let mut rng = 12345usize;
+        let mut rand = |n: usize| {
+            rng = rng
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (rng >> 33) % n
+        };
+        let gravities = [Gravity::Left, Gravity::Right];
+        let policies = [RemovePolicy::Collapse, RemovePolicy::Delete];
+
+        let mut m = MarkerSet::new();
+        let mut model: Vec<(usize, Gravity, RemovePolicy, MarkerId)> = Vec::new();
+        let mut removed = Vec::new();
+        for i in 0..1000 {
+            match rand(4) {
+                0 => {
+                    let pos = rand(220);
+                    let (gravity, policy) = (gravities[rand(2)], policies[rand(2)]);
+                    let id = m.add(pos, gravity, policy);
+                    let j = model.partition_point(|e| e.0 <= pos);
+                    model.insert(j, (pos, gravity, policy, id));
+                }
+                1 if !model.is_empty() => {
+                    let (pos, _, _, id) = model.remove(rand(model.len()));
+                    assert_eq!(m.remove(id), Some(pos));
+                    assert_eq!(m.remove(id), None);
+                    removed.push(id);
+                }
+                2 => {
+                    let (pos, len) = (rand(220), 1 + rand(8));
+                    m.update_for_insert(pos, len);
+                    for e in model.iter_mut() {
+                        if e.0 > pos || (e.0 == pos && e.1 == Gravity::Right) {
+                            e.0 += len;
+                        }
+                    }
+                    model.sort_by_key(|e| e.0);
+                }
+                _ => {
+                    let (pos, len) = (rand(220), 1 + rand(8));
+                    m.update_for_remove(pos..(pos + len));
+                    model.retain(|e| {
+                        e.0 <= pos || e.0 >= pos + len || e.2 == RemovePolicy::Collapse
+                    });
+                    for e in model.iter_mut() {
+                        if e.0 >= pos + len {
+                            e.0 -= len;
+                        } else if e.0 > pos {
+                            e.0 = pos;
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(m.len(), model.len());
+            let expected: Vec<_> = model.iter().map(|e| (e.3, e.0)).collect();
+            assert_eq!(m.iter().collect::<Vec<_>>(), expected);
+            if i % 10 == 0 {
+                for &(pos, _, _, id) in model.iter() {
+                    assert_eq!(m.get(id), Some(pos));
+                }
+                for &id in removed.iter() {
+                    assert_eq!(m.get(id), None);
+                }
+
+                let start = rand(220);
+                let end = start + rand(20);
+                let expected: Vec<_> = expected
+                    .iter()
+                    .cloned()
+                    .filter(|&(_, pos)| pos >= start && pos < end)
+                    .collect();
+                assert_eq!(m.range(start..end).collect::<Vec<_>>(), expected);
+            }
+        }
+    }
+}
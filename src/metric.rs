@@ -0,0 +1,621 @@
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::rope::Rope;
+use crate::str_utils::char_to_byte_idx;
+use crate::tree::{Node, NodeChildren};
+use crate::{Error, Result};
+
+/// A user-defined summary of text, which can be attached to a `Rope` with
+/// [`Rope::set_metric()`].
+///
+/// A metric summarizes a piece of text as a value of type `Summary`, and
+/// combines the summaries of two adjacent pieces of text into the summary
+/// of their concatenation.  Bracket balance, the number of times some char
+/// occurs, and token counts are all examples of metrics.  To attach
+/// several metrics to one `Rope`, combine them into one whose summary is
+/// a tuple.
+///
+/// Implementations must uphold two rules, or the results of the queries
+/// in [`MetricSummaries`] will depend on how the text happens to be split
+/// into chunks:
+///
+/// - `combine()` must be associative.
+/// - For any text split at a char boundary into `a` and `b`,
+///   `combine(summarize(a), summarize(b))` must equal `summarize(a + b)`.
+///   In particular, `summarize("")` is the identity of `combine()`.
+///
+/// `combine()` doesn't need to be commutative.
+pub trait Metric: Send + Sync + 'static {
+    /// The summary of a piece of text.
+    type Summary: Clone + Send + Sync + 'static;
+
+    /// Summarizes `text`.
+    fn summarize(&self, text: &str) -> Self::Summary;
+
+    /// Combines the summaries of two adjacent pieces of text, `left`
+    /// coming before `right`.
+    fn combine(&self, left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+/// A metric attached to a `Rope`, which is always an `Attached<M>`.
+pub(crate) type AttachedMetric = Arc<dyn Any + Send + Sync>;
+
+struct Attached<M> {
+    /// Unique to each call of `Rope::set_metric()`, so that summaries left
+    /// in the tree by other metrics (even of the same type) are never
+    /// mistaken for this one's.
+    id: usize,
+    metric: M,
+}
+
+/// The summaries of the children of an internal node, as stored in its
+/// `NodeChildren`.
+struct NodeSummaries<S> {
+    /// The id of the attached metric they were computed with.
+    metric_id: usize,
+    children: Vec<S>,
+    /// All of `children` combined.
+    total: S,
+}
+
+pub(crate) fn attach<M: Metric>(metric: M) -> AttachedMetric {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    Arc::new(Attached {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        metric: metric,
+    })
+}
+
+/// The summaries of a [`Metric`] over the text of a `Rope`, as returned
+/// by [`Rope::metric_summaries()`].
+///
+/// The summaries are stored in the `Rope`'s tree, with each node holding
+/// the summaries of its children next to their other text info.  This
+/// makes it possible to get the summary of any prefix of the text, or to
+/// find where a summary is first reached, in O(log N) time plus the time
+/// to summarize part of a single chunk.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Metric, Rope};
+/// #
+/// // Counts the occurrences of a char.
+/// struct CharCount(char);
+///
+/// impl Metric for CharCount {
+///     type Summary = usize;
+///
+///     fn summarize(&self, text: &str) -> usize {
+///         text.matches(self.0).count()
+///     }
+///
+///     fn combine(&self, left: &usize, right: &usize) -> usize {
+///         left + right
+///     }
+/// }
+///
+/// let mut rope = Rope::from_str("a|b|c");
+/// rope.set_metric(CharCount('|'));
+/// assert_eq!(rope.metric_summaries::<CharCount>().unwrap().summary(), 2);
+///
+/// rope.insert(3, "|d");
+/// let summaries = rope.metric_summaries::<CharCount>().unwrap();
+/// assert_eq!(summaries.summary_to_char(4), 2);
+///
+/// // The char index just after the third '|'.
+/// assert_eq!(summaries.seek_char(|&n| n >= 3), Some(6));
+/// ```
+pub struct MetricSummaries<'a, M: Metric> {
+    metric: &'a M,
+    metric_id: usize,
+    root: &'a Arc<Node>,
+}
+
+impl<'a, M: Metric> MetricSummaries<'a, M> {
+    /// Brings the summaries in the tree of `rope` up to date, if a metric
+    /// of type `M` is attached to it.
+    pub(crate) fn new(rope: &'a mut Rope) -> Option<Self> {
+        let metric_id = {
+            let attached = rope.metric.as_ref()?.downcast_ref::<Attached<M>>()?;
+            refresh(&mut rope.root, &attached.metric, attached.id);
+            attached.id
+        };
+
+        let rope: &'a Rope = rope;
+        let attached = rope
+            .metric
+            .as_ref()
+            .unwrap()
+            .downcast_ref::<Attached<M>>()
+            .unwrap();
+        Some(MetricSummaries {
+            metric: &attached.metric,
+            metric_id: metric_id,
+            root: &rope.root,
+        })
+    }
+
+    /// The metric that the text is summarized with.
+    #[inline]
+    pub fn metric(&self) -> &'a M {
+        self.metric
+    }
+
+    //-----------------------------------------------------------------------
+    // Query methods
+
+    /// The summary of the whole text.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn summary(&self) -> M::Summary {
+        match **self.root {
+            Node::Leaf(ref text) => self.metric.summarize(text),
+            Node::Internal(ref children) => self.get(children).total.clone(),
+        }
+    }
+
+    /// Returns the summary of the text before `char_idx`.
+    ///
+    /// Runs in O(log N) time, plus the time to summarize part of a chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn summary_to_char(&self, char_idx: usize) -> M::Summary {
+        self.try_summary_to_char(char_idx).unwrap()
+    }
+
+    /// Returns the summary of the text before `byte_idx`.
+    ///
+    /// Runs in O(log N) time, plus the time to summarize part of a chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`)
+    /// or isn't on a char boundary.
+    #[inline]
+    pub fn summary_to_byte(&self, byte_idx: usize) -> M::Summary {
+        self.try_summary_to_byte(byte_idx).unwrap()
+    }
+
+    /// Returns the first char index whose [`summary_to_char()`] satisfies
+    /// `pred`, or `None` if not even the summary of the whole text does.
+    ///
+    /// `pred` must be monotonic: once it's true for the summary of some
+    /// text, it must be true for the summary of any longer text starting
+    /// at the same place.  For example, with a metric that counts lines,
+    /// `|&n| n >= 10` finds the end of the tenth line.
+    ///
+    /// Runs in O(log N) time, plus the time to summarize each char of a
+    /// chunk.
+    ///
+    /// [`summary_to_char()`]: MetricSummaries::summary_to_char
+    pub fn seek_char<F>(&self, mut pred: F) -> Option<usize>
+    where
+        F: FnMut(&M::Summary) -> bool,
+    {
+        let mut acc = self.metric.summarize("");
+        if pred(&acc) {
+            return Some(0);
+        }
+
+        let mut char_idx = 0;
+        let mut node = self.root;
+        loop {
+            match **node {
+                Node::Internal(ref children) => {
+                    let summaries = self.get(children);
+                    let mut found = None;
+                    for ((info, child), summary) in children.iter().zip(&summaries.children) {
+                        let next_acc = self.metric.combine(&acc, summary);
+                        if pred(&next_acc) {
+                            found = Some(child);
+                            break;
+                        }
+                        acc = next_acc;
+                        char_idx += info.chars as usize;
+                    }
+                    node = found?;
+                }
+                Node::Leaf(ref text) => {
+                    let mut buf = [0u8; 4];
+                    for (i, c) in text.chars().enumerate() {
+                        let summary = self.metric.summarize(c.encode_utf8(&mut buf));
+                        acc = self.metric.combine(&acc, &summary);
+                        if pred(&acc) {
+                            return Some(char_idx + i + 1);
+                        }
+                    }
+                    // Only reachable if `pred` isn't monotonic.
+                    return None;
+                }
+            }
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Non-panicking versions.
+
+    /// Non-panicking version of
+    /// [`summary_to_char()`](MetricSummaries::summary_to_char).
+    pub fn try_summary_to_char(&self, char_idx: usize) -> Result<M::Summary> {
+        if char_idx > self.root.char_count() {
+            return Err(Error::CharIndexOutOfBounds(
+                char_idx,
+                self.root.char_count(),
+            ));
+        }
+        Ok(self.summary_before(
+            |children, char_idx| {
+                let (child_i, acc_info) = children.search_char_idx(char_idx);
+                (child_i, char_idx - acc_info.chars as usize)
+            },
+            |text, char_idx| &text[..char_to_byte_idx(text, char_idx)],
+            char_idx,
+        ))
+    }
+
+    /// Non-panicking version of
+    /// [`summary_to_byte()`](MetricSummaries::summary_to_byte).
+    pub fn try_summary_to_byte(&self, byte_idx: usize) -> Result<M::Summary> {
+        if byte_idx > self.root.byte_count() {
+            return Err(Error::ByteIndexOutOfBounds(
+                byte_idx,
+                self.root.byte_count(),
+            ));
+        }
+        let (chunk, chunk_info) = self.root.get_chunk_at_byte(byte_idx);
+        if !chunk.is_char_boundary(byte_idx - chunk_info.bytes as usize) {
+            return Err(Error::ByteIndexNotCharBoundary(byte_idx));
+        }
+        Ok(self.summary_before(
+            |children, byte_idx| {
+                let (child_i, acc_info) = children.search_byte_idx(byte_idx);
+                (child_i, byte_idx - acc_info.bytes as usize)
+            },
+            |text, byte_idx| &text[..byte_idx],
+            byte_idx,
+        ))
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Returns the summary of the text before `idx`, which must be in
+    /// bounds.
+    ///
+    /// `search` returns the index of the child containing `idx` and the
+    /// index within that child, and `prefix` returns the text before
+    /// `idx` within a chunk.
+    fn summary_before<S, P>(&self, search: S, prefix: P, mut idx: usize) -> M::Summary
+    where
+        S: Fn(&NodeChildren, usize) -> (usize, usize),
+        P: for<'b> Fn(&'b str, usize) -> &'b str,
+    {
+        let mut acc: Option<M::Summary> = None;
+        let mut node = self.root;
+        loop {
+            match **node {
+                Node::Internal(ref children) => {
+                    let (child_i, child_idx) = search(children, idx);
+                    for summary in &self.get(children).children[..child_i] {
+                        acc = Some(match acc {
+                            Some(acc) => self.metric.combine(&acc, summary),
+                            None => summary.clone(),
+                        });
+                    }
+                    node = &children.nodes()[child_i];
+                    idx = child_idx;
+                }
+                Node::Leaf(ref text) => {
+                    let tail = self.metric.summarize(prefix(text, idx));
+                    return match acc {
+                        Some(acc) => self.metric.combine(&acc, &tail),
+                        None => tail,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Returns the summaries of `children`, which `new()` made sure are up
+    /// to date.
+    #[inline]
+    fn get(&self, children: &'a NodeChildren) -> &'a NodeSummaries<M::Summary> {
+        node_summaries::<M>(children, self.metric_id).unwrap()
+    }
+}
+
+/// Returns the summaries of `children`, if they were computed with the
+/// metric attached with id `metric_id`.
+fn node_summaries<M: Metric>(
+    children: &NodeChildren,
+    metric_id: usize,
+) -> Option<&NodeSummaries<M::Summary>> {
+    let summaries = children
+        .summaries()?
+        .downcast_ref::<NodeSummaries<M::Summary>>()?;
+    if summaries.metric_id == metric_id {
+        Some(summaries)
+    } else {
+        None
+    }
+}
+
+/// Brings the summaries in the subtree at `node` up to date, returning
+/// the summary of the whole subtree.
+///
+/// Edits drop the summaries of every internal node they touch, so only
+/// those nodes (and ones summarized with other metrics) are visited.
+/// Since they're modified, any of them that are shared with another
+/// `Rope` get copied, as with an edit.
+fn refresh<M: Metric>(node: &mut Arc<Node>, metric: &M, metric_id: usize) -> M::Summary {
+    match **node {
+        Node::Leaf(ref text) => return metric.summarize(text),
+        Node::Internal(ref children) => {
+            if let Some(summaries) = node_summaries::<M>(children, metric_id) {
+                return summaries.total.clone();
+            }
+        }
+    }
+
+    let children = Arc::make_mut(node).children_mut();
+    let summaries: Vec<M::Summary> = children
+        .nodes_mut()
+        .iter_mut()
+        .map(|child| refresh(child, metric, metric_id))
+        .collect();
+    let mut total = summaries[0].clone();
+    for summary in &summaries[1..] {
+        total = metric.combine(&total, summary);
+    }
+    children.set_summaries(Arc::new(NodeSummaries {
+        metric_id: metric_id,
+        children: summaries,
+        total: total.clone(),
+    }));
+    total
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CharCount(char);
+
+    impl Metric for CharCount {
+        type Summary = usize;
+
+        fn summarize(&self, text: &str) -> usize {
+            text.matches(self.0).count()
+        }
+
+        fn combine(&self, left: &usize, right: &usize) -> usize {
+            left + right
+        }
+    }
+
+    /// The number of unmatched closing and opening brackets, in that
+    /// order.  Unlike `CharCount`, this isn't commutative.
+    struct BracketBalance;
+
+    impl Metric for BracketBalance {
+        type Summary = (usize, usize);
+
+        fn summarize(&self, text: &str) -> (usize, usize) {
+            text.chars().fold((0, 0), |acc, c| match c {
+                '(' => self.combine(&acc, &(0, 1)),
+                ')' => self.combine(&acc, &(1, 0)),
+                _ => acc,
+            })
+        }
+
+        fn combine(&self, left: &(usize, usize), right: &(usize, usize)) -> (usize, usize) {
+            let matched = left.1.min(right.0);
+            (left.0 + right.0 - matched, left.1 - matched + right.1)
+        }
+    }
+
+    /// Counts how many chunks it has summarized.
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Metric for Counted {
+        type Summary = ();
+
+        fn summarize(&self, text: &str) {
+            if !text.is_empty() {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn combine(&self, _: &(), _: &()) {}
+    }
+
+    /// Checks the summaries of `text` against summaries computed directly
+    /// from the text, and that every internal node holds up to date
+    /// summaries of its children.
+    fn check<M: Metric>(text: &mut Rope)
+    where
+        M::Summary: PartialEq + std::fmt::Debug,
+    {
+        let s = text.to_string();
+        let char_to_byte: Vec<usize> = (0..=text.len_chars())
+            .map(|i| text.char_to_byte(i))
+            .collect();
+        let summaries = text.metric_summaries::<M>().unwrap();
+        let metric = summaries.metric();
+        for &byte_idx in char_to_byte.iter() {
+            let expected = metric.summarize(&s[..byte_idx]);
+            assert_eq!(summaries.summary_to_byte(byte_idx), expected);
+        }
+        for (char_idx, &byte_idx) in char_to_byte.iter().enumerate() {
+            let expected = metric.summarize(&s[..byte_idx]);
+            assert_eq!(summaries.summary_to_char(char_idx), expected);
+        }
+        assert_eq!(summaries.summary(), metric.summarize(&s));
+
+        fn check_node<M: Metric>(node: &Arc<Node>, metric: &M, metric_id: usize) -> String
+        where
+            M::Summary: PartialEq + std::fmt::Debug,
+        {
+            match **node {
+                Node::Leaf(ref text) => text.to_string(),
+                Node::Internal(ref children) => {
+                    let node_summaries = node_summaries::<M>(children, metric_id).unwrap();
+                    let mut node_text = String::new();
+                    for (i, child) in children.nodes().iter().enumerate() {
+                        let child_text = check_node(child, metric, metric_id);
+                        assert_eq!(node_summaries.children[i], metric.summarize(&child_text));
+                        node_text.push_str(&child_text);
+                    }
+                    assert_eq!(node_summaries.total, metric.summarize(&node_text));
+                    node_text
+                }
+            }
+        }
+        check_node(summaries.root, metric, summaries.metric_id);
+    }
+
+    #[test]
+    fn metric_summaries_01() {
+        let text = "a|bc||d|こんにちは|\r\n|e".repeat(10);
+        let mut r = Rope::from_str(&text);
+        assert!(r.metric_summaries::<CharCount>().is_none());
+        r.set_metric(CharCount('|'));
+        assert!(r.metric_summaries::<BracketBalance>().is_none());
+        check::<CharCount>(&mut r);
+
+        // The char index just after each '|'.
+        let ends: Vec<usize> = text
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '|')
+            .map(|(i, _)| i + 1)
+            .collect();
+        let summaries = r.metric_summaries::<CharCount>().unwrap();
+        assert_eq!(summaries.seek_char(|_| true), Some(0));
+        for (n, &end) in ends.iter().enumerate() {
+            assert_eq!(summaries.seek_char(|&count| count > n), Some(end));
+        }
+        assert_eq!(summaries.seek_char(|&n| n > ends.len()), None);
+
+        r.clear_metric();
+        assert!(r.metric_summaries::<CharCount>().is_none());
+    }
+
+    #[test]
+    fn metric_summaries_02() {
+        let mut r = Rope::from_str("a|こ|");
+        r.set_metric(CharCount('|'));
+        let summaries = r.metric_summaries::<CharCount>().unwrap();
+        assert!(matches!(
+            summaries.try_summary_to_char(5),
+            Err(Error::CharIndexOutOfBounds(5, 4))
+        ));
+        assert!(matches!(
+            summaries.try_summary_to_byte(7),
+            Err(Error::ByteIndexOutOfBounds(7, 6))
+        ));
+        assert!(matches!(
+            summaries.try_summary_to_byte(3),
+            Err(Error::ByteIndexNotCharBoundary(3))
+        ));
+        assert!(matches!(summaries.try_summary_to_byte(2), Ok(1)));
+
+        let mut r = Rope::new();
+        r.set_metric(CharCount('|'));
+        check::<CharCount>(&mut r);
+        assert_eq!(
+            r.metric_summaries::<CharCount>()
+                .unwrap()
+                .seek_char(|&n| n > 0),
+            None
+        );
+    }
+
+    #[test]
+    fn metric_summaries_03() {
+        // Edits, followed by queries.
+        let mut r = Rope::from_str(&"(a(b)c)d(".repeat(20));
+        r.set_metric(BracketBalance);
+        check::<BracketBalance>(&mut r);
+
+        let mut seed = 12345u32;
+        for i in 0..100 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let len = r.len_chars();
+            let idx = (seed >> 8) as usize % (len + 1);
+            if i % 3 == 2 && len > 0 {
+                let end = (idx + 1 + (seed >> 20) as usize % 20).min(len);
+                r.remove(idx.min(end - 1)..end);
+            } else {
+                r.insert(idx, ["(", ")", "x)(", "((y))"][(seed >> 16) as usize % 4]);
+            }
+            check::<BracketBalance>(&mut r);
+
+            // The first char index where an unmatched ')' has been seen.
+            let expected = (0..=r.len_chars())
+                .find(|&i| BracketBalance.summarize(&r.slice(..i).to_string()).0 > 0);
+            let summaries = r.metric_summaries::<BracketBalance>().unwrap();
+            assert_eq!(summaries.seek_char(|s| s.0 > 0), expected);
+        }
+    }
+
+    #[test]
+    fn metric_summaries_04() {
+        // Sharing subtrees between and within ropes.
+        let mut r = Rope::from_str(&"ab|cd|".repeat(30));
+        r.set_metric(CharCount('|'));
+        check::<CharCount>(&mut r);
+        let r2 = r.clone();
+        r.append(r2);
+        check::<CharCount>(&mut r);
+        assert_eq!(r.metric_summaries::<CharCount>().unwrap().summary(), 120);
+
+        // Split off parts keep the metric.
+        let mut r3 = r.split_off(50);
+        check::<CharCount>(&mut r);
+        check::<CharCount>(&mut r3);
+        assert_eq!(r3.metric_summaries::<CharCount>().unwrap().summary(), 104);
+
+        // Summaries of other metrics, even of the same type, aren't used.
+        let mut r4 = Rope::from_str(&"ab|cd|".repeat(30));
+        r4.set_metric(CharCount('c'));
+        check::<CharCount>(&mut r4);
+        r.append(r4);
+        check::<CharCount>(&mut r);
+        assert_eq!(r.metric_summaries::<CharCount>().unwrap().summary(), 76);
+        r.set_metric(CharCount('a'));
+        check::<CharCount>(&mut r);
+        assert_eq!(r.metric_summaries::<CharCount>().unwrap().summary(), 39);
+
+        // Querying doesn't change the clones.
+        let r5 = r.clone();
+        r.insert(0, "a");
+        check::<CharCount>(&mut r);
+        assert_eq!(r5.len_chars() + 1, r.len_chars());
+    }
+
+    #[test]
+    fn metric_summaries_05() {
+        // Only the chunks next to an edit are summarized again.
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut r = Rope::from_str(&"Hello world! ".repeat(200));
+        r.set_metric(Counted(Arc::clone(&count)));
+        r.metric_summaries::<Counted>().unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), r.chunks().count());
+
+        r.insert(1000, "a");
+        count.store(0, Ordering::Relaxed);
+        r.metric_summaries::<Counted>().unwrap();
+        assert!(count.load(Ordering::Relaxed) <= 2 * crate::tree::MAX_CHILDREN);
+        check::<Counted>(&mut r);
+    }
+}
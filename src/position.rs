@@ -0,0 +1,328 @@
+use crate::slice::RopeSlice;
+use crate::str_utils::{count_chars, trim_line_break};
+use crate::{Error, Result};
+
+/// A line/column position in text.
+///
+/// Both the line and column are zero-indexed.  What unit the column is
+/// measured in is decided by a [`ColumnEncoding`], which is passed
+/// alongside the `Position` wherever one is used.
+///
+/// This mirrors how positions are represented by e.g. the Language Server
+/// Protocol.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    /// The line index.
+    pub line: usize,
+    /// The column within the line, not counting its line break.
+    pub column: usize,
+}
+
+impl Position {
+    /// Creates a new `Position`.
+    #[inline]
+    pub fn new(line: usize, column: usize) -> Self {
+        Position {
+            line: line,
+            column: column,
+        }
+    }
+}
+
+/// The unit that the column of a [`Position`] is measured in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColumnEncoding {
+    /// Bytes of utf8.
+    Byte,
+    /// Utf16 code units.
+    Utf16,
+    /// Chars, i.e. Unicode scalar values.
+    Char,
+}
+
+/// Shared implementation of `position_to_char()` and friends for `Rope`
+/// and `RopeSlice`.
+///
+/// When `clamp` is true, lines past the end are clamped to the last line,
+/// columns past the end of the line are clamped to the end of the line,
+/// and columns within a char are rounded down to its start.  Otherwise
+/// those are errors.
+pub(crate) fn position_to_char(
+    text: RopeSlice,
+    pos: Position,
+    encoding: ColumnEncoding,
+    clamp: bool,
+) -> Result<usize> {
+    let len_lines = text.len_lines();
+    let (line_idx, column) = if pos.line < len_lines {
+        (pos.line, pos.column)
+    } else if clamp {
+        (len_lines - 1, usize::MAX)
+    } else {
+        return Err(Error::LineIndexOutOfBounds(pos.line, len_lines));
+    };
+
+    let line_start = text.line_to_char(line_idx);
+    let line = text.line(line_idx);
+    let content = line.slice(..line_content_len_chars(line));
+
+    let content_len = match encoding {
+        ColumnEncoding::Byte => content.len_bytes(),
+        ColumnEncoding::Utf16 => content.len_utf16_cu(),
+        ColumnEncoding::Char => content.len_chars(),
+    };
+    let column = if column <= content_len {
+        column
+    } else if clamp {
+        content_len
+    } else {
+        return Err(Error::ColumnIndexOutOfBounds(column, content_len));
+    };
+
+    let (char_idx, is_boundary) = match encoding {
+        ColumnEncoding::Byte => {
+            let char_idx = content.byte_to_char(column);
+            (char_idx, content.char_to_byte(char_idx) == column)
+        }
+        ColumnEncoding::Utf16 => {
+            let char_idx = content.utf16_cu_to_char(column);
+            (char_idx, content.char_to_utf16_cu(char_idx) == column)
+        }
+        ColumnEncoding::Char => (column, true),
+    };
+    if !is_boundary && !clamp {
+        return Err(Error::ColumnNotCharBoundary(column));
+    }
+
+    Ok(line_start + char_idx)
+}
+
+/// Shared implementation of `char_to_position()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn char_to_position(
+    text: RopeSlice,
+    char_idx: usize,
+    encoding: ColumnEncoding,
+) -> Result<Position> {
+    let line_idx = text.try_char_to_line(char_idx)?;
+    let line = text.line(line_idx);
+    // An index between the CR and LF of a CRLF pair is clamped to the end
+    // of the line's content, so that the column is always one that
+    // `position_to_char()` accepts.
+    let char_column = (char_idx - text.line_to_char(line_idx)).min(line_content_len_chars(line));
+    let column = match encoding {
+        ColumnEncoding::Byte => line.char_to_byte(char_column),
+        ColumnEncoding::Utf16 => line.char_to_utf16_cu(char_column),
+        ColumnEncoding::Char => char_column,
+    };
+    Ok(Position::new(line_idx, column))
+}
+
+/// Returns the length in chars of `line`, not counting its line break.
+pub(crate) fn line_content_len_chars(line: RopeSlice) -> usize {
+    let len = line.len_chars();
+
+    // A line break is at most two chars (CRLF), so only the last two chars
+    // need checking.  They're copied to a small buffer to avoid allocating.
+    let mut chars = line.chars_at(len);
+    let last = chars.prev();
+    let second_last = chars.prev();
+    let mut buf = [0u8; 8];
+    let mut tail_len = 0;
+    for c in [second_last, last].iter().flatten() {
+        tail_len += c.encode_utf8(&mut buf[tail_len..]).len();
+    }
+    let tail = std::str::from_utf8(&buf[..tail_len]).unwrap();
+    len - count_chars(tail) + count_chars(trim_line_break(tail, line.line_break_mode()))
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::str_utils::LineBreakMode;
+    use crate::Rope;
+
+    // 5 lines, with a 4-byte char on line 1 and 3-byte chars on line 2.
+    const TEXT: &str = "Hello\n\u{1F600}world!\nこんにちは\n\n";
+
+    #[test]
+    fn position_to_char_01() {
+        let r = Rope::from_str(TEXT);
+        let pos = |line, column| Position::new(line, column);
+
+        assert_eq!(r.position_to_char(pos(0, 0), ColumnEncoding::Byte), 0);
+        assert_eq!(r.position_to_char(pos(0, 5), ColumnEncoding::Byte), 5);
+        assert_eq!(r.position_to_char(pos(1, 4), ColumnEncoding::Byte), 7);
+        assert_eq!(r.position_to_char(pos(1, 2), ColumnEncoding::Utf16), 7);
+        assert_eq!(r.position_to_char(pos(1, 1), ColumnEncoding::Char), 7);
+        assert_eq!(r.position_to_char(pos(2, 6), ColumnEncoding::Byte), 16);
+        assert_eq!(r.position_to_char(pos(2, 2), ColumnEncoding::Utf16), 16);
+        assert_eq!(r.position_to_char(pos(2, 5), ColumnEncoding::Char), 19);
+        assert_eq!(r.position_to_char(pos(3, 0), ColumnEncoding::Char), 20);
+        assert_eq!(r.position_to_char(pos(4, 0), ColumnEncoding::Utf16), 21);
+    }
+
+    #[test]
+    fn position_to_char_02() {
+        let r = Rope::from_str(TEXT);
+        let pos = |line, column| Position::new(line, column);
+
+        assert!(matches!(
+            r.try_position_to_char(pos(5, 0), ColumnEncoding::Char),
+            Err(Error::LineIndexOutOfBounds(5, 5))
+        ));
+        assert!(matches!(
+            r.try_position_to_char(pos(0, 6), ColumnEncoding::Char),
+            Err(Error::ColumnIndexOutOfBounds(6, 5))
+        ));
+        assert!(matches!(
+            r.try_position_to_char(pos(1, 11), ColumnEncoding::Byte),
+            Err(Error::ColumnIndexOutOfBounds(11, 10))
+        ));
+        assert!(matches!(
+            r.try_position_to_char(pos(3, 1), ColumnEncoding::Utf16),
+            Err(Error::ColumnIndexOutOfBounds(1, 0))
+        ));
+        assert!(matches!(
+            r.try_position_to_char(pos(1, 2), ColumnEncoding::Byte),
+            Err(Error::ColumnNotCharBoundary(2))
+        ));
+        assert!(matches!(
+            r.try_position_to_char(pos(1, 1), ColumnEncoding::Utf16),
+            Err(Error::ColumnNotCharBoundary(1))
+        ));
+    }
+
+    #[test]
+    fn position_to_char_03() {
+        // On a slice that starts and ends mid-line.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(3..17);
+        let pos = |line, column| Position::new(line, column);
+
+        assert_eq!(s.position_to_char(pos(0, 2), ColumnEncoding::Char), 2);
+        assert_eq!(s.position_to_char(pos(1, 3), ColumnEncoding::Utf16), 5);
+        assert_eq!(s.position_to_char(pos(2, 6), ColumnEncoding::Byte), 13);
+        assert!(s
+            .try_position_to_char(pos(2, 4), ColumnEncoding::Char)
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "cr_lines")]
+    fn position_to_char_04() {
+        let r = Rope::from_str("a\r\nbc\r\n");
+        let pos = |line, column| Position::new(line, column);
+
+        assert_eq!(r.position_to_char(pos(0, 1), ColumnEncoding::Char), 1);
+        assert!(r
+            .try_position_to_char(pos(0, 2), ColumnEncoding::Char)
+            .is_err());
+        assert_eq!(r.position_to_char(pos(1, 2), ColumnEncoding::Byte), 5);
+        assert_eq!(
+            r.position_to_char_clamped(pos(1, 100), ColumnEncoding::Utf16),
+            5
+        );
+    }
+
+    #[test]
+    fn position_to_char_clamped_01() {
+        let r = Rope::from_str(TEXT);
+        let pos = |line, column| Position::new(line, column);
+
+        assert_eq!(
+            r.position_to_char_clamped(pos(0, 100), ColumnEncoding::Char),
+            5
+        );
+        assert_eq!(
+            r.position_to_char_clamped(pos(1, 2), ColumnEncoding::Byte),
+            6
+        );
+        assert_eq!(
+            r.position_to_char_clamped(pos(1, 1), ColumnEncoding::Utf16),
+            6
+        );
+        assert_eq!(
+            r.position_to_char_clamped(pos(2, 100), ColumnEncoding::Byte),
+            19
+        );
+        assert_eq!(
+            r.position_to_char_clamped(pos(100, 100), ColumnEncoding::Byte),
+            21
+        );
+        assert_eq!(
+            Rope::new().position_to_char_clamped(pos(3, 3), ColumnEncoding::Char),
+            0
+        );
+    }
+
+    #[test]
+    fn char_to_position_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(
+            r.char_to_position(0, ColumnEncoding::Byte),
+            Position::new(0, 0)
+        );
+        assert_eq!(
+            r.char_to_position(7, ColumnEncoding::Byte),
+            Position::new(1, 4)
+        );
+        assert_eq!(
+            r.char_to_position(7, ColumnEncoding::Utf16),
+            Position::new(1, 2)
+        );
+        assert_eq!(
+            r.char_to_position(7, ColumnEncoding::Char),
+            Position::new(1, 1)
+        );
+        assert_eq!(
+            r.char_to_position(16, ColumnEncoding::Byte),
+            Position::new(2, 6)
+        );
+        assert_eq!(
+            r.char_to_position(21, ColumnEncoding::Char),
+            Position::new(4, 0)
+        );
+        assert!(matches!(
+            r.try_char_to_position(22, ColumnEncoding::Char),
+            Err(Error::CharIndexOutOfBounds(22, 21))
+        ));
+
+        // Round trip.
+        for char_idx in 0..=r.len_chars() {
+            for &enc in [
+                ColumnEncoding::Byte,
+                ColumnEncoding::Utf16,
+                ColumnEncoding::Char,
+            ]
+            .iter()
+            {
+                let pos = r.char_to_position(char_idx, enc);
+                assert_eq!(r.position_to_char(pos, enc), char_idx);
+            }
+        }
+    }
+
+    #[test]
+    fn char_to_position_02() {
+        let mut r = Rope::from_str("a\r\nbc\r\n");
+        r.set_line_break_mode(LineBreakMode::Crlf);
+
+        // Between the CR and LF is clamped to the end of the line content.
+        assert_eq!(
+            r.char_to_position(2, ColumnEncoding::Char),
+            Position::new(0, 1)
+        );
+        assert_eq!(
+            r.char_to_position(6, ColumnEncoding::Byte),
+            Position::new(1, 2)
+        );
+        for char_idx in 0..=r.len_chars() {
+            let pos = r.char_to_position(char_idx, ColumnEncoding::Utf16);
+            assert!(r.try_position_to_char(pos, ColumnEncoding::Utf16).is_ok());
+        }
+    }
+}
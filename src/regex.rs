@@ -0,0 +1,735 @@
+//! Regex search over a `Rope`'s text.
+//!
+//! This module is only available with the `regex` feature enabled.
+//!
+//! The regex methods on `Rope` and `RopeSlice` (`regex_find_iter()`,
+//! `regex_rfind_iter()`, and `regex_captures()`) feed the text's chunks
+//! straight into lazy DFAs, so the text isn't copied to find matches.
+//! Matches are reported as [`Match`]es, whose char ranges can be passed
+//! directly to `Rope::slice()` and friends.
+//!
+//! The one exception is patterns with Unicode word boundaries (`\b` and
+//! `\B`), which the lazy DFAs can't handle on non-ASCII text.  When a
+//! search with such a pattern runs into non-ASCII text, it falls back to
+//! copying the text into a `String` and searching that instead.  Using
+//! ASCII word boundaries (`(?-u:\b)`) avoids this.
+
+use std::fmt;
+use std::ops::Range;
+
+use regex_automata::hybrid::dfa::{Cache, DFA};
+use regex_automata::nfa::thompson::{self, WhichCaptures};
+use regex_automata::util::captures::GroupInfo;
+use regex_automata::util::interpolate;
+use regex_automata::util::start;
+use regex_automata::{meta, Anchored, Input, MatchKind, PatternID};
+
+use crate::search::Match;
+use crate::slice::RopeSlice;
+
+/// A compiled regular expression, for searching `Rope`s and `RopeSlice`s.
+///
+/// The syntax is the same as the `regex` crate's.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+
+    // Searching forward finds where the leftmost-first match ends with
+    // `fwd_first`, and then where it starts with `rev_all`.  Searching
+    // backward is the mirror image of that, with `rev_first` and
+    // `fwd_all`.
+    fwd_first: DFA,
+    rev_all: DFA,
+    rev_first: DFA,
+    fwd_all: DFA,
+
+    meta: meta::Regex, // For captures, and when the DFAs give up.
+}
+
+impl Regex {
+    /// Compiles `pattern` into a `Regex`.
+    pub fn new(pattern: &str) -> Result<Regex, BuildError> {
+        let meta = meta::Regex::new(pattern).map_err(build_error)?;
+        let nfa_config = thompson::Config::new().which_captures(WhichCaptures::None);
+        let fwd_nfa = thompson::Compiler::new()
+            .configure(nfa_config.clone())
+            .build(pattern)
+            .map_err(build_error)?;
+        let rev_nfa = thompson::Compiler::new()
+            .configure(nfa_config.reverse(true))
+            .build(pattern)
+            .map_err(build_error)?;
+
+        let dfa = |nfa: &thompson::NFA, match_kind| {
+            DFA::builder()
+                .configure(
+                    DFA::config()
+                        .match_kind(match_kind)
+                        .unicode_word_boundary(true),
+                )
+                .build_from_nfa(nfa.clone())
+                .map_err(build_error)
+        };
+
+        Ok(Regex {
+            pattern: pattern.into(),
+            fwd_first: dfa(&fwd_nfa, MatchKind::LeftmostFirst)?,
+            rev_all: dfa(&rev_nfa, MatchKind::All)?,
+            rev_first: dfa(&rev_nfa, MatchKind::LeftmostFirst)?,
+            fwd_all: dfa(&fwd_nfa, MatchKind::All)?,
+            meta: meta,
+        })
+    }
+
+    /// Returns the pattern this `Regex` was compiled from.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+/// An error from compiling a [`Regex`], e.g. because of a syntax error.
+#[derive(Debug, Clone)]
+pub struct BuildError(String);
+
+impl std::error::Error for BuildError {}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn build_error<E: fmt::Display>(error: E) -> BuildError {
+    BuildError(error.to_string())
+}
+
+/// An iterator over the non-overlapping matches of a [`Regex`], from left
+/// to right.
+///
+/// Created by `Rope::regex_find_iter()` and `RopeSlice::regex_find_iter()`.
+/// As with the `regex` crate, an empty match right after the previous
+/// match is skipped.
+#[derive(Debug, Clone)]
+pub struct RegexMatches<'a, 'r> {
+    text: RopeSlice<'a>,
+    searcher: Searcher<'r>,
+    byte_idx: usize, // Where the next search starts.
+    last_end: Option<usize>,
+    done: bool,
+}
+
+impl<'a, 'r> RegexMatches<'a, 'r> {
+    pub(crate) fn new(text: RopeSlice<'a>, regex: &'r Regex) -> RegexMatches<'a, 'r> {
+        RegexMatches {
+            text: text,
+            searcher: Searcher::new(regex),
+            byte_idx: 0,
+            last_end: None,
+            done: false,
+        }
+    }
+}
+
+impl<'a, 'r> Iterator for RegexMatches<'a, 'r> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        while !self.done {
+            let m = match self.searcher.find(self.text, self.byte_idx) {
+                Some(m) => m,
+                None => break,
+            };
+
+            if m.start == m.end && Some(m.start) == self.last_end {
+                // Try again from the next char.
+                if m.start == self.text.len_bytes() {
+                    break;
+                }
+                self.byte_idx = next_char_boundary(self.text, m.start);
+                continue;
+            }
+
+            self.byte_idx = m.end;
+            self.last_end = Some(m.end);
+            return Some(to_match(self.text, m));
+        }
+
+        self.done = true;
+        None
+    }
+}
+
+/// An iterator over the non-overlapping matches of a [`Regex`], from right
+/// to left.
+///
+/// Created by `Rope::regex_rfind_iter()` and `RopeSlice::regex_rfind_iter()`.
+/// This mirrors `RegexMatches`: each match is the one ending furthest right
+/// before the previous match, extended as far left as it can go.  So where
+/// matches could overlap, the matches found may differ from
+/// `regex_find_iter()`'s.  An empty match right before the previous match
+/// is skipped.
+#[derive(Debug, Clone)]
+pub struct RegexRMatches<'a, 'r> {
+    text: RopeSlice<'a>,
+    searcher: Searcher<'r>,
+    byte_idx: usize, // Where the next search ends.
+    last_start: Option<usize>,
+    done: bool,
+}
+
+impl<'a, 'r> RegexRMatches<'a, 'r> {
+    pub(crate) fn new(text: RopeSlice<'a>, regex: &'r Regex) -> RegexRMatches<'a, 'r> {
+        RegexRMatches {
+            text: text,
+            searcher: Searcher::new(regex),
+            byte_idx: text.len_bytes(),
+            last_start: None,
+            done: false,
+        }
+    }
+}
+
+impl<'a, 'r> Iterator for RegexRMatches<'a, 'r> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        while !self.done {
+            let m = match self.searcher.rfind(self.text, self.byte_idx) {
+                Some(m) => m,
+                None => break,
+            };
+
+            if m.start == m.end && Some(m.end) == self.last_start {
+                // Try again from the previous char.
+                if m.end == 0 {
+                    break;
+                }
+                self.byte_idx = self.text.char_to_byte(self.text.byte_to_char(m.end) - 1);
+                continue;
+            }
+
+            self.byte_idx = m.start;
+            self.last_start = Some(m.start);
+            return Some(to_match(self.text, m));
+        }
+
+        self.done = true;
+        None
+    }
+}
+
+/// The capture groups of a [`Regex`] match.
+///
+/// Created by `Rope::regex_captures()` and `RopeSlice::regex_captures()`.
+#[derive(Debug, Clone)]
+pub struct Captures {
+    groups: Vec<Option<Match>>,
+    group_info: GroupInfo,
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl Captures {
+    /// Returns the number of groups, including group 0 for the whole
+    /// match.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Returns group `i`, or `None` if it didn't participate in the match
+    /// or doesn't exist.
+    ///
+    /// Group 0 is always the whole match.
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&Match> {
+        self.groups.get(i).and_then(|g| g.as_ref())
+    }
+
+    /// Returns the group named `name`, or `None` if it didn't participate
+    /// in the match or doesn't exist.
+    #[inline]
+    pub fn name(&self, name: &str) -> Option<&Match> {
+        self.group_info
+            .to_index(PatternID::ZERO, name)
+            .and_then(|i| self.get(i))
+    }
+}
+
+/// Shared implementation of `regex_captures()` for `Rope` and `RopeSlice`.
+pub(crate) fn captures(text: RopeSlice, regex: &Regex) -> Option<Captures> {
+    let m = Searcher::new(regex).find(text, 0)?;
+    Some(captures_at(text, regex, m))
+}
+
+/// Appends `replacement` to `dst`, with references to capture groups like
+/// `$1` and `${name}` replaced by the groups' text in match `m`.
+///
+/// This uses the same syntax as the `regex` crate's `Captures::expand()`.
+pub(crate) fn expand(
+    text: RopeSlice,
+    regex: &Regex,
+    m: &Match,
+    replacement: &str,
+    dst: &mut String,
+) {
+    if !replacement.contains('$') {
+        dst.push_str(replacement);
+        return;
+    }
+
+    let caps = captures_at(text, regex, m.byte_range.clone());
+    interpolate::string(
+        replacement,
+        |i, dst| {
+            if let Some(group) = caps.get(i) {
+                for chunk in text.byte_slice(group.byte_range.clone()).chunks() {
+                    dst.push_str(chunk);
+                }
+            }
+        },
+        |name| caps.group_info.to_index(PatternID::ZERO, name),
+        dst,
+    );
+}
+
+/// Returns the capture groups of the match of `regex` at byte range `m`.
+fn captures_at(text: RopeSlice, regex: &Regex, m: Range<usize>) -> Captures {
+    // Only the match itself is copied out, plus a char on either side for
+    // look-around assertions like `\b` to see.
+    let start = if m.start > 0 {
+        text.char_to_byte(text.byte_to_char(m.start) - 1)
+    } else {
+        0
+    };
+    let end = if m.end < text.len_bytes() {
+        next_char_boundary(text, m.end)
+    } else {
+        m.end
+    };
+    let haystack = text.byte_slice(start..end).to_string();
+    let input = Input::new(&haystack)
+        .span((m.start - start)..(m.end - start))
+        .anchored(Anchored::Yes);
+
+    let mut caps = regex.meta.create_captures();
+    regex.meta.search_captures(&input, &mut caps);
+    let groups = (0..caps.group_len())
+        .map(|i| {
+            caps.get_group(i)
+                .map(|span| to_match(text, (start + span.start)..(start + span.end)))
+        })
+        .collect();
+
+    Captures {
+        groups: groups,
+        group_info: regex.meta.group_info().clone(),
+    }
+}
+
+//-------------------------------------------------------------
+
+/// A search in progress with a `Regex`.
+///
+/// Holds the lazy DFAs' caches, and the copy of the text that's searched
+/// instead once the DFAs give up.
+#[derive(Debug, Clone)]
+struct Searcher<'r> {
+    regex: &'r Regex,
+    fwd_first_cache: Cache,
+    rev_all_cache: Cache,
+    rev_first_cache: Cache,
+    fwd_all_cache: Cache,
+    fallback: Option<String>,
+    fallback_matches: Option<Vec<Range<usize>>>,
+}
+
+/// Returned when a lazy DFA gives up on a search.
+struct GaveUp;
+
+impl<'r> Searcher<'r> {
+    fn new(regex: &'r Regex) -> Searcher<'r> {
+        Searcher {
+            regex: regex,
+            fwd_first_cache: regex.fwd_first.create_cache(),
+            rev_all_cache: regex.rev_all.create_cache(),
+            rev_first_cache: regex.rev_first.create_cache(),
+            fwd_all_cache: regex.fwd_all.create_cache(),
+            fallback: None,
+            fallback_matches: None,
+        }
+    }
+
+    /// Returns the byte range of the leftmost-first match that starts at
+    /// or after `byte_idx`.
+    fn find(&mut self, text: RopeSlice, byte_idx: usize) -> Option<Range<usize>> {
+        if self.fallback.is_none() {
+            match self.find_dfa(text, byte_idx) {
+                Ok(m) => return m,
+                Err(GaveUp) => self.fallback = Some(text.to_string()),
+            }
+        }
+
+        let haystack = self.fallback.as_ref().unwrap();
+        let input = Input::new(haystack).span(byte_idx..haystack.len());
+        self.regex.meta.search(&input).map(|m| m.range())
+    }
+
+    fn find_dfa(
+        &mut self,
+        text: RopeSlice,
+        byte_idx: usize,
+    ) -> Result<Option<Range<usize>>, GaveUp> {
+        let end = match scan_fwd(
+            &self.regex.fwd_first,
+            &mut self.fwd_first_cache,
+            text,
+            byte_idx..text.len_bytes(),
+            Anchored::No,
+        )? {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+
+        // Find where the match starts by running the reverse DFA back
+        // from its end.
+        match scan_rev(
+            &self.regex.rev_all,
+            &mut self.rev_all_cache,
+            text,
+            byte_idx..end,
+            Anchored::Yes,
+        )? {
+            Some(start) => Ok(Some(start..end)),
+            None => Err(GaveUp), // Shouldn't happen, but the fallback copes.
+        }
+    }
+
+    /// Returns the byte range of the match that ends furthest right at or
+    /// before `byte_idx`, extended as far left as it can go.
+    fn rfind(&mut self, text: RopeSlice, byte_idx: usize) -> Option<Range<usize>> {
+        if self.fallback.is_none() {
+            match self.rfind_dfa(text, byte_idx) {
+                Ok(m) => return m,
+                Err(GaveUp) => self.fallback = Some(text.to_string()),
+            }
+        }
+
+        // Without the DFAs we can only search forward, so collect all the
+        // matches before `byte_idx` in one go and hand them out from the
+        // back.  Later searches never end further right than earlier ones,
+        // so the collected matches stay valid.
+        if self.fallback_matches.is_none() {
+            let haystack = self.fallback.as_ref().unwrap();
+            let input = Input::new(haystack).span(0..byte_idx);
+            self.fallback_matches = Some(
+                self.regex
+                    .meta
+                    .find_iter(input)
+                    .map(|m| m.range())
+                    .collect(),
+            );
+        }
+        let matches = self.fallback_matches.as_mut().unwrap();
+        while let Some(m) = matches.pop() {
+            if m.end <= byte_idx {
+                return Some(m);
+            }
+        }
+        None
+    }
+
+    fn rfind_dfa(
+        &mut self,
+        text: RopeSlice,
+        byte_idx: usize,
+    ) -> Result<Option<Range<usize>>, GaveUp> {
+        let start = match scan_rev(
+            &self.regex.rev_first,
+            &mut self.rev_first_cache,
+            text,
+            0..byte_idx,
+            Anchored::No,
+        )? {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        // Find where the match ends by running the forward DFA from its
+        // start.
+        match scan_fwd(
+            &self.regex.fwd_all,
+            &mut self.fwd_all_cache,
+            text,
+            start..byte_idx,
+            Anchored::Yes,
+        )? {
+            Some(end) => Ok(Some(start..end)),
+            None => Err(GaveUp), // Shouldn't happen, but the fallback copes.
+        }
+    }
+}
+
+/// Runs `dfa` forward over the byte range `range` of `text`, and returns
+/// the end of the last match it saw before dying.
+///
+/// The text outside of `range` is still used by look-around assertions.
+fn scan_fwd(
+    dfa: &DFA,
+    cache: &mut Cache,
+    text: RopeSlice,
+    range: Range<usize>,
+    anchored: Anchored,
+) -> Result<Option<usize>, GaveUp> {
+    let look_behind = if range.start > 0 {
+        Some(text.byte(range.start - 1))
+    } else {
+        None
+    };
+    let config = start::Config::new()
+        .anchored(anchored)
+        .look_behind(look_behind);
+    let mut sid = dfa.start_state(cache, &config).map_err(|_| GaveUp)?;
+    let mut last_match = None;
+
+    let (mut chunks, mut chunk_start, _, _) = text.chunks_at_byte(range.start);
+    while chunk_start < range.end {
+        let chunk = match chunks.next() {
+            Some(chunk) => chunk.as_bytes(),
+            None => break,
+        };
+        let from = range.start.saturating_sub(chunk_start);
+        let to = chunk.len().min(range.end - chunk_start);
+        for (i, &byte) in chunk[from..to].iter().enumerate() {
+            sid = dfa.next_state(cache, sid, byte).map_err(|_| GaveUp)?;
+            if sid.is_tagged() {
+                // Matches are reported one byte late.
+                if sid.is_match() {
+                    last_match = Some(chunk_start + from + i);
+                } else if sid.is_dead() {
+                    return Ok(last_match);
+                } else if sid.is_quit() {
+                    return Err(GaveUp);
+                }
+            }
+        }
+        chunk_start += chunk.len();
+    }
+
+    sid = if range.end < text.len_bytes() {
+        dfa.next_state(cache, sid, text.byte(range.end))
+    } else {
+        dfa.next_eoi_state(cache, sid)
+    }
+    .map_err(|_| GaveUp)?;
+    if sid.is_match() {
+        last_match = Some(range.end);
+    } else if sid.is_quit() {
+        return Err(GaveUp);
+    }
+
+    Ok(last_match)
+}
+
+/// Runs `dfa`, which must be a reverse DFA, backward over the byte range
+/// `range` of `text`, and returns the start of the last match it saw
+/// before dying.
+///
+/// The text outside of `range` is still used by look-around assertions.
+fn scan_rev(
+    dfa: &DFA,
+    cache: &mut Cache,
+    text: RopeSlice,
+    range: Range<usize>,
+    anchored: Anchored,
+) -> Result<Option<usize>, GaveUp> {
+    let look_behind = if range.end < text.len_bytes() {
+        Some(text.byte(range.end))
+    } else {
+        None
+    };
+    let config = start::Config::new()
+        .anchored(anchored)
+        .look_behind(look_behind);
+    let mut sid = dfa.start_state(cache, &config).map_err(|_| GaveUp)?;
+    let mut last_match = None;
+
+    // `prev()` doesn't yield the chunk containing `range.end`, so we step
+    // over it with `next()` and back before going backward from there.
+    let (mut chunks, chunk_start, _, _) = text.chunks_at_byte(range.end);
+    let mut first: &[u8] = &[];
+    if chunk_start < range.end {
+        if let Some(chunk) = chunks.next() {
+            first = &chunk.as_bytes()[..(range.end - chunk_start)];
+            chunks.prev();
+        }
+    }
+    let rest = std::iter::from_fn(|| chunks.prev().map(str::as_bytes));
+
+    let mut chunk_end = range.end;
+    for chunk in std::iter::once(first).chain(rest) {
+        if chunk_end <= range.start {
+            break;
+        }
+        let from = chunk.len().saturating_sub(chunk_end - range.start);
+        let offset = chunk_end - chunk.len();
+        for (i, &byte) in chunk.iter().enumerate().skip(from).rev() {
+            sid = dfa.next_state(cache, sid, byte).map_err(|_| GaveUp)?;
+            if sid.is_tagged() {
+                // Matches are reported one byte late.
+                if sid.is_match() {
+                    last_match = Some(offset + i + 1);
+                } else if sid.is_dead() {
+                    return Ok(last_match);
+                } else if sid.is_quit() {
+                    return Err(GaveUp);
+                }
+            }
+        }
+        chunk_end = offset;
+    }
+
+    sid = if range.start > 0 {
+        dfa.next_state(cache, sid, text.byte(range.start - 1))
+    } else {
+        dfa.next_eoi_state(cache, sid)
+    }
+    .map_err(|_| GaveUp)?;
+    if sid.is_match() {
+        last_match = Some(range.start);
+    } else if sid.is_quit() {
+        return Err(GaveUp);
+    }
+
+    Ok(last_match)
+}
+
+/// Converts a byte range of `text` to a `Match`.
+fn to_match(text: RopeSlice, byte_range: Range<usize>) -> Match {
+    Match {
+        char_range: text.byte_to_char(byte_range.start)..text.byte_to_char(byte_range.end),
+        byte_range: byte_range,
+    }
+}
+
+/// Returns the byte index of the char boundary after `byte_idx`.
+fn next_char_boundary(text: RopeSlice, byte_idx: usize) -> usize {
+    text.char_to_byte(text.byte_to_char(byte_idx) + 1)
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rope;
+
+    const TEXT: &str = "Hello there!  How're you doing?  It's \
+                        a fine day, isn't it?  Aren't you glad \
+                        we're alive?  こんにちは、みんなさん！";
+
+    /// All the matches of `pattern` in `text`, according to the `meta`
+    /// regex engine.
+    fn meta_matches(text: &str, pattern: &str) -> Vec<Match> {
+        meta::Regex::new(pattern)
+            .unwrap()
+            .find_iter(text)
+            .map(|m| Match {
+                byte_range: m.range(),
+                char_range: text[..m.start()].chars().count()..text[..m.end()].chars().count(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn regex_find_iter_01() {
+        let r = Rope::from_str(TEXT);
+        for &pattern in [
+            r"you",
+            r"[a-z]+'[a-z]+",
+            r"\w+",
+            r"(?m)^|$",
+            r"[ちはみ]+",
+            r"i?",
+            r"\bt",
+            r"(?-u:\b)t",
+            r"nope",
+        ]
+        .iter()
+        {
+            let found: Vec<_> = r.regex_find_iter(&Regex::new(pattern).unwrap()).collect();
+            assert_eq!(found, meta_matches(TEXT, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn regex_find_iter_02() {
+        // Slices that start and end mid-chunk and mid-word, to check that
+        // look-around only sees the slice.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(16..98);
+        let text = s.to_string();
+        for &pattern in [r"\w+", r"(?-u:\b)\w", r"\Ao", r"ん\z", r"a*"].iter() {
+            let found: Vec<_> = s.regex_find_iter(&Regex::new(pattern).unwrap()).collect();
+            assert_eq!(found, meta_matches(&text, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn regex_rfind_iter_01() {
+        let r = Rope::from_str(TEXT);
+        for &pattern in [r"you", r"\w+", r"[ちはみ]+", r"(?-u:\b)t", r"\bt", r"nope"].iter() {
+            let regex = Regex::new(pattern).unwrap();
+            let mut found: Vec<_> = r.regex_rfind_iter(&regex).collect();
+            found.reverse();
+            assert_eq!(found, meta_matches(TEXT, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn regex_rfind_iter_02() {
+        let r = Rope::from_str("aa-a");
+        let regex = Regex::new(r"a*").unwrap();
+        let found: Vec<_> = r.regex_rfind_iter(&regex).map(|m| m.char_range).collect();
+        assert_eq!(found, [3..4, 0..2]);
+    }
+
+    #[test]
+    fn regex_captures_01() {
+        let r = Rope::from_str(TEXT);
+        let regex = Regex::new(r"(?<word>[a-z]+)'(re|t)? ").unwrap();
+        let caps = r.regex_captures(&regex).unwrap();
+        assert_eq!(caps.len(), 3);
+        assert_eq!(caps.get(0).unwrap().char_range, 15..21);
+        assert_eq!(caps.name("word").unwrap().char_range, 15..17);
+        assert_eq!(caps.get(2).unwrap().char_range, 18..20);
+        assert_eq!(caps.get(3), None);
+        assert_eq!(caps.name("nope"), None);
+
+        let regex = Regex::new(r"(ち)(x)?(は)").unwrap();
+        let caps = r.slice(90..).regex_captures(&regex).unwrap();
+        assert_eq!(caps.get(0).unwrap().char_range, 4..6);
+        assert_eq!(caps.get(0).unwrap().byte_range, 10..16);
+        assert_eq!(caps.get(2), None);
+        assert_eq!(caps.get(3).unwrap().char_range, 5..6);
+
+        assert!(r.regex_captures(&Regex::new("nope").unwrap()).is_none());
+    }
+
+    #[test]
+    fn regex_captures_02() {
+        // Look-around at the edges of the match.
+        let r = Rope::from_str("ab abc abcd");
+        let regex = Regex::new(r"\b(abc)\b").unwrap();
+        assert_eq!(
+            r.regex_captures(&regex).unwrap().get(1).unwrap().char_range,
+            3..6
+        );
+    }
+
+    #[test]
+    fn regex_new_01() {
+        assert!(Regex::new(r"(").is_err());
+        assert_eq!(Regex::new(r"a+b").unwrap().as_str(), r"a+b");
+    }
+}
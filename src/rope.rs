@@ -1,17 +1,32 @@
 use std::io;
 use std::iter::FromIterator;
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
 use crate::crlf;
-use crate::iter::{Bytes, Chars, Chunks, Lines};
+use crate::iter::{Bytes, Chars, Chunks, Lines, LinesWithEndings};
+#[cfg(feature = "graphemes")]
+use crate::iter::{Graphemes, Sentences, WordBounds, Words};
+use crate::line_ending::{self, split_line_ending, LineEnding, LineEndingCounts};
+use crate::metric::{self, AttachedMetric, Metric, MetricSummaries};
+use crate::position::{self, ColumnEncoding, Position};
+#[cfg(feature = "regex")]
+use crate::regex::{self, Captures, Regex, RegexMatches, RegexRMatches};
 use crate::rope_builder::RopeBuilder;
+use crate::search::{
+    self, Direction, Match, Matches, MultiMatches, MultiMatchesOverlapping, MultiPattern,
+    SearchOptions,
+};
+#[cfg(feature = "graphemes")]
+use crate::segment;
 use crate::slice::RopeSlice;
 use crate::str_utils::{
-    byte_to_char_idx, byte_to_line_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx,
-    char_to_line_idx, line_to_byte_idx, line_to_char_idx, utf16_code_unit_to_char_idx,
+    byte_to_char_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx, count_chars,
+    utf16_code_unit_to_char_idx, LineBreakMode,
 };
-use crate::tree::{Count, Node, NodeChildren, TextInfo, MAX_BYTES, MIN_BYTES};
+use crate::tree::{Count, Node, NodeChildren, TextInfo, Unit, MAX_BYTES, MIN_BYTES};
+#[cfg(feature = "width")]
+use crate::width;
 use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
 
 /// A utf8 text rope.
@@ -81,6 +96,9 @@ use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
 #[derive(Clone)]
 pub struct Rope {
     pub(crate) root: Arc<Node>,
+    pub(crate) line_break_mode: LineBreakMode,
+    /// The metric attached with `set_metric()`, if any.
+    pub(crate) metric: Option<AttachedMetric>,
 }
 
 impl Rope {
@@ -92,6 +110,8 @@ impl Rope {
     pub fn new() -> Self {
         Rope {
             root: Arc::new(Node::new()),
+            line_break_mode: LineBreakMode::default(),
+            metric: None,
         }
     }
 
@@ -245,7 +265,7 @@ impl Rope {
     /// Runs in O(1) time.
     #[inline]
     pub fn len_lines(&self) -> usize {
-        self.root.line_break_count() + 1
+        self.root.line_break_count(self.line_break_mode) + 1
     }
 
     /// Total number of utf16 code units that would be in `Rope` if it were
@@ -263,6 +283,70 @@ impl Rope {
         (info.chars + info.utf16_surrogates) as usize
     }
 
+    /// The [`LineBreakMode`] of the `Rope`, which determines what its
+    /// line-based methods and iterators recognize as line breaks.
+    ///
+    /// Defaults to the mode selected by the `cr_lines` and `unicode_lines`
+    /// cargo features.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn line_break_mode(&self) -> LineBreakMode {
+        self.line_break_mode
+    }
+
+    /// Sets the [`LineBreakMode`] of the `Rope`.
+    ///
+    /// Slices and iterators created afterwards use the new mode.  Line
+    /// breaks are tracked for all modes at once, so no re-scan of the text
+    /// is needed.
+    ///
+    /// Runs in O(1) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{LineBreakMode, Rope};
+    /// let mut rope = Rope::from_str("Hello\rworld\u{2028}!\n");
+    ///
+    /// rope.set_line_break_mode(LineBreakMode::Lf);
+    /// assert_eq!(rope.len_lines(), 2);
+    ///
+    /// rope.set_line_break_mode(LineBreakMode::Crlf);
+    /// assert_eq!(rope.len_lines(), 3);
+    /// assert_eq!(rope.line(1), "world\u{2028}!\n");
+    ///
+    /// rope.set_line_break_mode(LineBreakMode::Unicode);
+    /// assert_eq!(rope.len_lines(), 4);
+    /// ```
+    #[inline]
+    pub fn set_line_break_mode(&mut self, mode: LineBreakMode) {
+        self.line_break_mode = mode;
+    }
+
+    /// Counts each kind of line ending in the `Rope`, e.g. to find out
+    /// whether it uses LF or CRLF line endings, or a mix of them.
+    ///
+    /// All kinds of line endings are counted, regardless of the `Rope`'s
+    /// [`LineBreakMode`].
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{LineEnding, Rope};
+    /// let rope = Rope::from_str("Hello\r\nworld\r\n!\n");
+    /// let counts = rope.detect_line_ending();
+    ///
+    /// assert_eq!((counts.lf, counts.crlf, counts.cr), (1, 2, 0));
+    /// assert!(counts.is_mixed());
+    /// assert_eq!(counts.dominant(), Some(LineEnding::Crlf));
+    /// ```
+    pub fn detect_line_ending(&self) -> LineEndingCounts {
+        line_ending::detect(self.slice(..))
+    }
+
     //-----------------------------------------------------------------------
     // Memory management methods
 
@@ -305,7 +389,7 @@ impl Rope {
         let mut builder = RopeBuilder::new();
 
         node_stack.push(self.root.clone());
-        *self = Rope::new();
+        self.root = Arc::new(Node::new());
 
         loop {
             if node_stack.is_empty() {
@@ -325,7 +409,7 @@ impl Rope {
             }
         }
 
-        *self = builder.finish();
+        self.root = builder.finish().root;
     }
 
     //-----------------------------------------------------------------------
@@ -358,7 +442,8 @@ impl Rope {
     }
 
     /// Private internal-only method that does a single insertion of
-    /// sufficiently small text.
+    /// sufficiently small text at byte or char index `idx`, depending on
+    /// `unit`.
     ///
     /// This only works correctly for insertion texts smaller than or equal to
     /// `MAX_BYTES - 4`.
@@ -366,17 +451,18 @@ impl Rope {
     /// Note that a lot of the complexity in this method comes from avoiding
     /// splitting CRLF pairs and, when possible, avoiding re-scanning text for
     /// text info.  It is otherwise conceptually fairly straightforward.
-    fn insert_internal(&mut self, char_idx: usize, ins_text: &str) {
+    fn insert_internal(&mut self, idx: usize, unit: Unit, ins_text: &str) {
         let mut ins_text = ins_text;
         let mut left_seam = false;
         let root_info = self.root.text_info();
 
-        let (l_info, residual) = Arc::make_mut(&mut self.root).edit_chunk_at_char(
-            char_idx,
+        let (l_info, residual) = Arc::make_mut(&mut self.root).edit_chunk_at(
+            idx,
+            unit,
             root_info,
-            |idx, cur_info, leaf_text| {
+            |leaf_idx, cur_info, leaf_text| {
                 // First check if we have a left seam.
-                if idx == 0 && char_idx > 0 && ins_text.as_bytes()[0] == 0x0A {
+                if leaf_idx == 0 && idx > 0 && ins_text.as_bytes()[0] == 0x0A {
                     left_seam = true;
                     ins_text = &ins_text[1..];
                     // Early out if it was only an LF.
@@ -386,39 +472,35 @@ impl Rope {
                 }
 
                 // Find our byte index
-                let byte_idx = char_to_byte_idx(leaf_text, idx);
+                let byte_idx = unit.to_byte_idx(leaf_text, leaf_idx);
 
                 // No node splitting
                 if (leaf_text.len() + ins_text.len()) <= MAX_BYTES {
                     // Calculate new info without doing a full re-scan of cur_text.
                     let new_info = {
                         // Get summed info of current text and to-be-inserted text.
-                        #[allow(unused_mut)]
                         let mut info = cur_info + TextInfo::from_str(ins_text);
                         // Check for CRLF pairs on the insertion seams, and
                         // adjust line break counts accordingly.
-                        #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
-                        {
-                            if byte_idx > 0 {
-                                if leaf_text.as_bytes()[byte_idx - 1] == 0x0D
-                                    && ins_text.as_bytes()[0] == 0x0A
-                                {
-                                    info.line_breaks -= 1;
-                                }
-                                if byte_idx < leaf_text.len()
-                                    && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
-                                    && leaf_text.as_bytes()[byte_idx] == 0x0A
-                                {
-                                    info.line_breaks += 1;
-                                }
+                        if byte_idx > 0 {
+                            if leaf_text.as_bytes()[byte_idx - 1] == 0x0D
+                                && ins_text.as_bytes()[0] == 0x0A
+                            {
+                                info.line_breaks.cr = info.line_breaks.cr.wrapping_sub(1);
                             }
                             if byte_idx < leaf_text.len()
-                                && *ins_text.as_bytes().last().unwrap() == 0x0D
+                                && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
                                 && leaf_text.as_bytes()[byte_idx] == 0x0A
                             {
-                                info.line_breaks -= 1;
+                                info.line_breaks.cr = info.line_breaks.cr.wrapping_add(1);
                             }
                         }
+                        if byte_idx < leaf_text.len()
+                            && *ins_text.as_bytes().last().unwrap() == 0x0D
+                            && leaf_text.as_bytes()[byte_idx] == 0x0A
+                        {
+                            info.line_breaks.cr = info.line_breaks.cr.wrapping_sub(1);
+                        }
                         info
                     };
                     // Insert the text and return the new info
@@ -461,8 +543,9 @@ impl Rope {
         if left_seam {
             // Do the insertion
             let root_info = self.root.text_info();
-            let (l_info, residual) = Arc::make_mut(&mut self.root).edit_chunk_at_char(
-                char_idx - 1,
+            let (l_info, residual) = Arc::make_mut(&mut self.root).edit_chunk_at(
+                idx - 1,
+                unit,
                 root_info,
                 |_, cur_info, leaf_text| {
                     let byte_idx = leaf_text.len();
@@ -473,13 +556,9 @@ impl Rope {
                         let mut new_info = cur_info;
                         new_info.bytes += 1;
                         new_info.chars += 1;
-                        #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
-                        {
-                            new_info.line_breaks += 1;
-                        }
-                        #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
-                        if *leaf_text.as_bytes().last().unwrap() != 0x0D {
-                            new_info.line_breaks += 1;
+                        new_info.line_breaks.lf += 1;
+                        if *leaf_text.as_bytes().last().unwrap() == 0x0D {
+                            new_info.line_breaks.cr -= 1;
                         }
                         // Insert the text and return the new info
                         leaf_text.insert_str(byte_idx, "\n");
@@ -546,6 +625,297 @@ impl Rope {
         self.try_remove(char_range).unwrap()
     }
 
+    /// Replaces the text in the given char index range with `text`.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in `char`
+    /// indices.
+    ///
+    /// This is equivalent to a `remove()` followed by an `insert()`, but
+    /// unless `text` is large it's done in one traversal of the tree, even
+    /// when the range spans many chunks.
+    ///
+    /// Runs in O(M + K + log N) time, where N is the length of the `Rope`,
+    /// M is the length of the range being replaced, and K is the length of
+    /// `text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// rope.replace(6..11, "there");
+    ///
+    /// assert_eq!("Hello there!", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    pub fn replace<R>(&mut self, char_range: R, text: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_replace(char_range, text).unwrap()
+    }
+
+    /// Inserts `text` at the grapheme boundary at or before `char_idx`,
+    /// and returns the char index it was inserted at.
+    ///
+    /// This is like [`insert()`](Rope::insert), except that an insertion
+    /// in the middle of a grapheme cluster is moved to the start of the
+    /// cluster instead of splitting it.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(M + log N + K) time, where N is the length of the `Rope`,
+    /// M is the length of `text`, and K is the length in bytes of the
+    /// grapheme at `char_idx`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hi \u{1F1EC}\u{1F1E7}!");
+    /// assert_eq!(3, rope.insert_at_grapheme(4, "there "));
+    ///
+    /// assert_eq!("Hi there \u{1F1EC}\u{1F1E7}!", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    pub fn insert_at_grapheme(&mut self, char_idx: usize, text: &str) -> usize {
+        self.try_insert_at_grapheme(char_idx, text).unwrap()
+    }
+
+    /// Removes the text in the given char index range, widened outward to
+    /// the nearest grapheme boundaries, and returns the char range that
+    /// was removed.
+    ///
+    /// This is like [`remove()`](Rope::remove), except that it never
+    /// leaves part of a grapheme cluster behind.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(M + log N + K) time, where N is the length of the `Rope`,
+    /// M is the length of the range being removed, and K is the length in
+    /// bytes of the graphemes at either end of the range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hi \u{1F1EC}\u{1F1E7}!");
+    /// assert_eq!(3..5, rope.remove_graphemes(4..5));
+    ///
+    /// assert_eq!("Hi !", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    pub fn remove_graphemes<R>(&mut self, char_range: R) -> Range<usize>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_remove_graphemes(char_range).unwrap()
+    }
+
+    /// Private internal-only method that replaces the bytes or chars
+    /// (depending on `unit`) in `start..end` with `text` in a single
+    /// traversal of the tree, followed by a single fix-up of the seam it
+    /// leaves.
+    ///
+    /// This only works correctly for replacement texts smaller than or
+    /// equal to `MAX_BYTES - 4`.  `start..end` must be non-empty and not
+    /// the whole text, and `text` must be non-empty.
+    fn replace_internal(&mut self, start: usize, end: usize, unit: Unit, text: &str) {
+        let root_info = self.root.text_info();
+        let (l_info, residual, crlf_seam, needs_fix) =
+            Arc::make_mut(&mut self.root).replace_range(start, end, unit, text, root_info);
+
+        // Handle root splitting, if any.
+        if let Some((r_info, r_node)) = residual {
+            let mut l_node = Arc::new(Node::new());
+            std::mem::swap(&mut l_node, &mut self.root);
+
+            let mut children = NodeChildren::new();
+            children.push((l_info, l_node));
+            children.push((r_info, r_node));
+
+            *Arc::make_mut(&mut self.root) = Node::Internal(children);
+        }
+
+        let root = Arc::make_mut(&mut self.root);
+        let seam_idx = start
+            + match unit {
+                Unit::Byte => text.len(),
+                Unit::Char => count_chars(text),
+            };
+
+        // Either edge of the new text may have ended up at the edge of a
+        // leaf, next to a CR or LF it pairs with.
+        if crlf_seam {
+            let start_byte = root.idx_to_byte(start, unit);
+            root.fix_crlf_seam(start_byte as Count, false);
+            let end_byte = root.idx_to_byte(seam_idx, unit);
+            root.fix_crlf_seam(end_byte as Count, false);
+        }
+
+        if needs_fix {
+            root.fix_tree_seam(seam_idx, unit);
+        }
+
+        self.pull_up_singular_nodes();
+    }
+
+    /// Applies a batch of edits, each replacing a char range with new
+    /// text.
+    ///
+    /// The ranges are all in terms of the text before any of the edits,
+    /// and must be sorted and non-overlapping.  Ranges may touch, and
+    /// multiple empty ranges at the same index insert their text in the
+    /// order given.
+    ///
+    /// Rather than applying the edits one at a time, this builds the new
+    /// text in a single left-to-right pass, sharing the unedited parts
+    /// with the original `Rope`.
+    ///
+    /// Runs in O(M + K log N) time, where N is the length of the `Rope`,
+    /// K is the number of edits, and M is the total length of the inserted
+    /// text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// rope.apply_edits(vec![(0..5, "Goodbye"), (6..11, "moon"), (12..12, "?")]);
+    ///
+    /// assert_eq!("Goodbye moon!?", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if any range's start is greater than its end, if any range
+    /// is out of bounds (i.e. `end > len_chars()`), or if the ranges
+    /// aren't sorted and non-overlapping.  No edits are applied in that
+    /// case.
+    pub fn apply_edits<'a, I>(&mut self, edits: I)
+    where
+        I: IntoIterator<Item = (Range<usize>, &'a str)>,
+    {
+        self.try_apply_edits(edits).unwrap()
+    }
+
+    /// Replaces all non-overlapping matches of `pattern` with
+    /// `replacement`, returning the number of replacements made.
+    ///
+    /// The matches are the ones [`matches()`](Rope::matches) finds in the
+    /// text before any replacements.
+    ///
+    /// There are several ways a search-and-replace could be done on a
+    /// `Rope`:
+    ///
+    /// 1. Search a clone while replacing in the original.  Cloning is
+    ///    constant-time and the two share most of their storage, but
+    ///    replacing a large part of the text this way ends up holding onto
+    ///    both versions of it.
+    /// 2. Collect all the matches first, then replace them in place.  This
+    ///    is good when there are few matches, but every in-place
+    ///    replacement costs O(log N), and storing the matches takes a lot
+    ///    of space when there are many of them.
+    /// 3. Find the first match, replace it, and restart the search from
+    ///    there.  This needs little memory, but restarting the search for
+    ///    every match is expensive when there are many of them.
+    /// 4. Build the new text from scratch in a single left-to-right pass,
+    ///    copying the text between matches and appending the replacements.
+    ///    This costs O(N) no matter how many matches there are.
+    ///
+    /// This method combines #2 and #4: if there are only a few matches,
+    /// relative to the length of the `Rope`, they're collected and replaced
+    /// one at a time in place.  Otherwise the text is rebuilt in a single
+    /// pass, searching the old text as it goes so that the matches never
+    /// need to be stored.
+    ///
+    /// Runs in O(N + M) time, where N is the length of the `Rope` and M is
+    /// the total length of the inserted text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("One fish, two fish.");
+    ///
+    /// assert_eq!(2, rope.replace_all("fish", "cat"));
+    /// assert_eq!("One cat, two cat.", rope);
+    /// ```
+    pub fn replace_all(&mut self, pattern: &str, replacement: &str) -> usize {
+        let original = self.clone();
+        self.replace_matches(original.matches(pattern), |_, dst| {
+            dst.push_str(replacement)
+        })
+    }
+
+    /// Replaces all non-overlapping matches of `regex` with
+    /// `replacement`, returning the number of replacements made.
+    ///
+    /// Only available with the `regex` feature enabled.
+    ///
+    /// `replacement` can refer to capture groups with `$1`, `${name}`,
+    /// etc., using the same syntax as the `regex` crate's `replace_all()`.
+    /// Otherwise this works like [`replace_all()`](Rope::replace_all), with
+    /// the matches that [`regex_find_iter()`](Rope::regex_find_iter) finds.
+    ///
+    /// Runs in O(N + M) time, where N is the length of the `Rope` and M is
+    /// the total length of the inserted text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{regex::Regex, Rope};
+    /// let mut rope = Rope::from_str("One fish, two fish.");
+    /// let regex = Regex::new(r"(\w+) fish").unwrap();
+    ///
+    /// assert_eq!(2, rope.regex_replace_all(&regex, "$1 cats"));
+    /// assert_eq!("One cats, two cats.", rope);
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn regex_replace_all(&mut self, regex: &Regex, replacement: &str) -> usize {
+        let original = self.clone();
+        let text = original.slice(..);
+        self.replace_matches(original.regex_find_iter(regex), |m, dst| {
+            regex::expand(text, regex, m, replacement, dst)
+        })
+    }
+
+    /// Converts all LF, CRLF, and CR line endings to `ending`, returning
+    /// the number of line endings that were changed.
+    ///
+    /// The Unicode-only line breaks (VT, FF, NEL, Line Separator, and
+    /// Paragraph Separator) are left as they are.  If there's nothing to
+    /// change, the `Rope` isn't touched.  Otherwise the text is rebuilt in
+    /// a single pass, a chunk at a time.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{LineEnding, Rope};
+    /// let mut rope = Rope::from_str("Hello\r\nworld\r!\n");
+    ///
+    /// assert_eq!(2, rope.normalize_line_endings(LineEnding::Lf));
+    /// assert_eq!("Hello\nworld\n!\n", rope);
+    /// ```
+    pub fn normalize_line_endings(&mut self, ending: LineEnding) -> usize {
+        line_ending::normalize(self, ending)
+    }
+
     /// Splits the `Rope` at `char_idx`, returning the right part of
     /// the split.
     ///
@@ -558,14 +928,141 @@ impl Rope {
         self.try_split_off(char_idx).unwrap()
     }
 
+    /// Inserts `text` at byte index `byte_idx`.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`)
+    /// or isn't on a char boundary.
+    #[inline]
+    pub fn insert_at_byte(&mut self, byte_idx: usize, text: &str) {
+        self.try_insert_at_byte(byte_idx, text).unwrap()
+    }
+
+    /// Removes the text in the given byte index range.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in byte
+    /// indices.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of the range being removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("こんにちは world!");
+    /// rope.remove_bytes(..15);
+    ///
+    /// assert_eq!(" world!", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the
+    /// end is out of bounds (i.e. `end > len_bytes()`), or if either end
+    /// isn't on a char boundary.
+    pub fn remove_bytes<R>(&mut self, byte_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_remove_bytes(byte_range).unwrap()
+    }
+
+    /// Replaces the text in the given byte index range with `text`.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in byte
+    /// indices.  See [`replace()`](Rope::replace) for details.
+    ///
+    /// Runs in O(M + K + log N) time, where N is the length of the `Rope`,
+    /// M is the length of the range being replaced, and K is the length of
+    /// `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the
+    /// end is out of bounds (i.e. `end > len_bytes()`), or if either end
+    /// isn't on a char boundary.
+    pub fn replace_bytes<R>(&mut self, byte_range: R, text: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_replace_bytes(byte_range, text).unwrap()
+    }
+
+    /// Splits the `Rope` at `byte_idx`, returning the right part of
+    /// the split.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`)
+    /// or isn't on a char boundary.
+    pub fn split_off_at_byte(&mut self, byte_idx: usize) -> Self {
+        self.try_split_off_at_byte(byte_idx).unwrap()
+    }
+
+    /// Inserts `text` at utf16 code unit index `utf16_cu_idx`.
+    ///
+    /// Ropey stores text internally as utf8, but sometimes it is necessary
+    /// to interact with external APIs that still use utf16.  This function is
+    /// primarily intended for such situations, and is otherwise not very
+    /// useful.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `utf16_cu_idx` is out of bounds
+    /// (i.e. `utf16_cu_idx > len_utf16_cu()`) or is in the middle of a
+    /// surrogate pair.
+    #[inline]
+    pub fn insert_at_utf16(&mut self, utf16_cu_idx: usize, text: &str) {
+        self.try_insert_at_utf16(utf16_cu_idx, text).unwrap()
+    }
+
+    /// Removes the text in the given utf16 code unit index range.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in utf16
+    /// code unit indices.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of the range being removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("\u{1F600} Hello world!");
+    /// rope.remove_utf16(..3);
+    ///
+    /// assert_eq!("Hello world!", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the
+    /// end is out of bounds (i.e. `end > len_utf16_cu()`), or if either end
+    /// is in the middle of a surrogate pair.
+    pub fn remove_utf16<R>(&mut self, utf16_cu_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_remove_utf16(utf16_cu_range).unwrap()
+    }
+
     /// Appends a `Rope` to the end of this one, consuming the other `Rope`.
     ///
     /// Runs in O(log N) time.
     pub fn append(&mut self, other: Self) {
         if self.len_chars() == 0 {
             // Special case
-            let mut other = other;
-            std::mem::swap(self, &mut other);
+            self.root = other.root;
         } else if other.len_chars() > 0 {
             let left_info = self.root.text_info();
             let right_info = other.root.text_info();
@@ -598,7 +1095,7 @@ impl Rope {
                     children.push((other.root.text_info(), Arc::clone(&other.root)));
                     other.root = Arc::new(Node::Internal(children));
                 }
-                *self = other;
+                self.root = other.root;
             };
 
             // Fix up any mess left behind.
@@ -607,7 +1104,7 @@ impl Rope {
                 root.fix_crlf_seam(i, true);
             }
             if (left_info.bytes as usize) < MIN_BYTES || (right_info.bytes as usize) < MIN_BYTES {
-                root.fix_tree_seam(left_info.chars as usize);
+                root.fix_tree_seam(left_info.chars as usize, Unit::Char);
             }
             self.pull_up_singular_nodes();
         }
@@ -764,14 +1261,146 @@ impl Rope {
         self.try_line_to_char(line_idx).unwrap()
     }
 
-    //-----------------------------------------------------------------------
-    // Fetch methods
-
-    /// Returns the byte at `byte_idx`.
+    /// Returns the char index of the given line/column position.
+    ///
+    /// The column is measured in the units given by `encoding`, and
+    /// doesn't include the line's line break.  A column at the end of a
+    /// line (i.e. just before its line break) is valid.
     ///
     /// Runs in O(log N) time.
     ///
-    /// # Panics
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{ColumnEncoding, Position, Rope};
+    /// let rope = Rope::from_str("Hello\n\u{1F600}world!\n");
+    /// let pos = Position::new(1, 2);
+    ///
+    /// assert_eq!(rope.position_to_char(pos, ColumnEncoding::Utf16), 7);
+    /// assert_eq!(rope.position_to_char(pos, ColumnEncoding::Char), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line is out of bounds (i.e. `line >= len_lines()`), if
+    /// the column is past the end of the line, or if the column is in the
+    /// middle of a char.
+    #[inline]
+    pub fn position_to_char(&self, pos: Position, encoding: ColumnEncoding) -> usize {
+        self.try_position_to_char(pos, encoding).unwrap()
+    }
+
+    /// Returns the char index of the given line/column position, clamping
+    /// it to the text.
+    ///
+    /// Like [`position_to_char()`](Rope::position_to_char), except that
+    /// rather than panicking, lines past the end are clamped to the last
+    /// line, columns past the end of a line are clamped to the end of the
+    /// line, and columns in the middle of a char are rounded down to the
+    /// start of that char.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn position_to_char_clamped(&self, pos: Position, encoding: ColumnEncoding) -> usize {
+        position::position_to_char(self.slice(..), pos, encoding, true).unwrap()
+    }
+
+    /// Returns the line/column position of the given char.
+    ///
+    /// The column is measured in the units given by `encoding`.
+    ///
+    /// Notes:
+    ///
+    /// - `char_idx` can be one-past-the-end, which will return the
+    ///   position at the end of the last line.
+    /// - A `char_idx` between the CR and LF of a CRLF line break returns the
+    ///   position at the end of that line's content, i.e. the same position
+    ///   as the CR.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn char_to_position(&self, char_idx: usize, encoding: ColumnEncoding) -> Position {
+        self.try_char_to_position(char_idx, encoding).unwrap()
+    }
+
+    /// Returns the visual column of the given char within its line, i.e.
+    /// how many columns the text before it on that line takes up in a
+    /// monospace terminal.
+    ///
+    /// Tabs advance to the next multiple of `tab_width` (or take up no
+    /// columns if `tab_width` is 0), East Asian wide chars take up two
+    /// columns, and zero-width and control chars take up none.
+    ///
+    /// Text is measured by grapheme cluster, with each cluster taking up
+    /// the width of its first char, so that e.g. combining marks and the
+    /// later emoji of an emoji ZWJ sequence add nothing.  With the
+    /// `graphemes` feature enabled these are true extended grapheme
+    /// clusters.  Otherwise they're approximated by joining zero-width
+    /// chars, emoji modifiers, and the char after a zero width joiner onto
+    /// the char before them.  A `char_idx` in the middle of a cluster
+    /// returns the column the cluster starts at.
+    ///
+    /// Only available with the `width` feature enabled.
+    ///
+    /// Runs in O(log N + M) time, where M is the length in chars of the
+    /// line up to `char_idx`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a\tこんにちは\n\tb");
+    ///
+    /// assert_eq!(rope.visual_column(3, 4), 6);
+    /// assert_eq!(rope.visual_column(9, 4), 4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn visual_column(&self, char_idx: usize, tab_width: usize) -> usize {
+        self.try_visual_column(char_idx, tab_width).unwrap()
+    }
+
+    /// Returns the char index of the char that covers the given visual
+    /// column of a line.
+    ///
+    /// This is the inverse of [`visual_column()`](Rope::visual_column),
+    /// and measures text the same way.  The returned index is always the
+    /// start of a grapheme cluster: columns in the middle of a tab or wide
+    /// cluster return its start, zero-width clusters are skipped over, and
+    /// columns past the end of the line return the end of the line (i.e.
+    /// just before its line break).
+    ///
+    /// Only available with the `width` feature enabled.
+    ///
+    /// Runs in O(log N + M) time, where M is the length in chars of the
+    /// line up to the returned char.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn char_at_visual_column(&self, line_idx: usize, column: usize, tab_width: usize) -> usize {
+        self.try_char_at_visual_column(line_idx, column, tab_width)
+            .unwrap()
+    }
+
+    //-----------------------------------------------------------------------
+    // Fetch methods
+
+    /// Returns the byte at `byte_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
     ///
     /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx >= len_bytes()`).
     #[inline]
@@ -830,6 +1459,31 @@ impl Rope {
         }
     }
 
+    /// Returns the line at `line_idx`, without its line break, along with
+    /// the kind of line break it ended with.
+    ///
+    /// The last line never has a line ending.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{LineEnding, Rope};
+    /// let rope = Rope::from_str("Hello\r\nworld!");
+    ///
+    /// assert_eq!(rope.line_without_ending(0), ("Hello".into(), Some(LineEnding::Crlf)));
+    /// assert_eq!(rope.line_without_ending(1), ("world!".into(), None));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    #[inline]
+    pub fn line_without_ending(&self, line_idx: usize) -> (RopeSlice<'_>, Option<LineEnding>) {
+        split_line_ending(self.line(line_idx))
+    }
+
     /// Returns the chunk containing the given byte index.
     ///
     /// Also returns the byte and char indices of the beginning of the chunk
@@ -974,6 +1628,51 @@ impl Rope {
         }
     }
 
+    /// Gets an immutable slice of the `Rope`, using utf16 code unit
+    /// indices.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    /// - The start of the range is greater than the end.
+    /// - The end is out of bounds (i.e. `end > len_utf16_cu()`).
+    /// - Either end of the range is in the middle of a surrogate pair.
+    pub fn slice_utf16<R>(&self, utf16_cu_range: R) -> RopeSlice<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        match self.get_slice_utf16_impl(utf16_cu_range) {
+            Ok(s) => s,
+            Err(e) => panic!("slice_utf16(): {}", e),
+        }
+    }
+
+    /// Gets an immutable slice of the `Rope`, using char indices widened
+    /// outward to the nearest grapheme boundaries.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// graphemes at either end of the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    pub fn slice_graphemes<R>(&self, char_range: R) -> RopeSlice<'_>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.get_slice_graphemes(char_range).unwrap()
+    }
+
     //-----------------------------------------------------------------------
     // Iterator methods
 
@@ -1041,12 +1740,142 @@ impl Rope {
         }
     }
 
+    /// Creates an iterator over the chars of the `Rope`, starting at utf16
+    /// code unit `utf16_cu_idx`.
+    ///
+    /// If `utf16_cu_idx == len_utf16_cu()` then an iterator at the end of
+    /// the `Rope` is created (i.e. `next()` will return `None`).
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `utf16_cu_idx` is out of bounds
+    /// (i.e. `utf16_cu_idx > len_utf16_cu()`) or is in the middle of a
+    /// surrogate pair.
+    #[inline]
+    pub fn chars_at_utf16(&self, utf16_cu_idx: usize) -> Chars<'_> {
+        match self.utf16_cu_to_char_boundary(utf16_cu_idx) {
+            Ok(char_idx) => self.chars_at(char_idx),
+            Err(e) => panic!("chars_at_utf16(): {}", e),
+        }
+    }
+
+    /// Creates an iterator over the extended grapheme clusters of the
+    /// `Rope`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn graphemes(&self) -> Graphemes<'_> {
+        Graphemes::new_at_byte(self.slice(..), 0)
+    }
+
+    /// Creates an iterator over the extended grapheme clusters of the
+    /// `Rope`, starting at the grapheme containing char `char_idx`.
+    ///
+    /// If `char_idx` is in the middle of a grapheme, the iterator starts
+    /// at the start of that grapheme.  If `char_idx == len_chars()` then an
+    /// iterator at the end of the `Rope` is created (i.e. `next()` will
+    /// return `None`).
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn graphemes_at(&self, char_idx: usize) -> Graphemes<'_> {
+        if let Some(out) = self.get_graphemes_at(char_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of Rope: char index {}, Rope char length {}",
+                char_idx,
+                self.len_chars()
+            );
+        }
+    }
+
+    /// Creates an iterator over the words of the `Rope`.
+    ///
+    /// Only yields the segments between word boundaries that contain
+    /// alphanumeric chars.  Use [`word_bounds()`](Rope::word_bounds) to
+    /// get every segment.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn words(&self) -> Words<'_> {
+        Words::new_at_byte(self.slice(..), 0)
+    }
+
+    /// Creates an iterator over the segments between the word boundaries
+    /// of the `Rope`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn word_bounds(&self) -> WordBounds<'_> {
+        WordBounds::new_at_byte(self.slice(..), 0)
+    }
+
+    /// Creates an iterator over the segments between the word boundaries
+    /// of the `Rope`, starting at the segment containing char `char_idx`.
+    ///
+    /// If `char_idx` is in the middle of a segment, the iterator starts
+    /// at the start of that segment.  If `char_idx == len_chars()` then an
+    /// iterator at the end of the `Rope` is created (i.e. `next()` will
+    /// return `None`).
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length of the text
+    /// segmented to find the segment's start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn word_bounds_at(&self, char_idx: usize) -> WordBounds<'_> {
+        if let Some(out) = self.get_word_bounds_at(char_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of Rope: char index {}, Rope char length {}",
+                char_idx,
+                self.len_chars()
+            );
+        }
+    }
+
+    /// Creates an iterator over the sentences of the `Rope`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn sentences(&self) -> Sentences<'_> {
+        Sentences::new_at_byte(self.slice(..), 0)
+    }
+
     /// Creates an iterator over the lines of the `Rope`.
     ///
     /// Runs in O(log N) time.
     #[inline]
     pub fn lines(&self) -> Lines {
-        Lines::new(&self.root)
+        Lines::new(&self.root, self.line_break_mode)
     }
 
     /// Creates an iterator over the lines of the `Rope`, starting at line
@@ -1073,6 +1902,31 @@ impl Rope {
         }
     }
 
+    /// Creates an iterator over the lines of the `Rope`, with their line
+    /// breaks split off.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn lines_with_endings(&self) -> LinesWithEndings<'_> {
+        LinesWithEndings::new(self.lines())
+    }
+
+    /// Creates an iterator over the lines of the `Rope`, with their line
+    /// breaks split off, starting at line `line_idx`.
+    ///
+    /// If `line_idx == len_lines()` then an iterator at the end of the
+    /// `Rope` is created (i.e. `next()` will return `None`).
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    #[inline]
+    pub fn lines_with_endings_at(&self, line_idx: usize) -> LinesWithEndings<'_> {
+        LinesWithEndings::new(self.lines_at(line_idx))
+    }
+
     /// Creates an iterator over the chunks of the `Rope`.
     ///
     /// Runs in O(log N) time.
@@ -1157,43 +2011,337 @@ impl Rope {
     /// The return value is organized as
     /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
     ///
-    /// Runs in O(log N) time.
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    #[inline]
+    pub fn chunks_at_line_break(&self, line_break_idx: usize) -> (Chunks, usize, usize, usize) {
+        if let Some(out) = self.get_chunks_at_line_break(line_break_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of Rope: line break index {}, max index {}",
+                line_break_idx,
+                self.len_lines()
+            );
+        }
+    }
+
+    /// Returns true if this rope and `other` point to precisely the same
+    /// in-memory data.
+    ///
+    /// This happens when one of the ropes is a clone of the other and
+    /// neither have been modified since then.  Because clones initially
+    /// share all the same data, it can be useful to check if they still
+    /// point to precisely the same memory as a way of determining
+    /// whether they are both still unmodified.
+    ///
+    /// Note: this is distinct from checking for equality: two ropes can
+    /// have the same *contents* (equal) but be stored in different
+    /// memory locations (not instances).  Importantly, two clones that
+    /// post-cloning are modified identically will *not* be instances
+    /// anymore, even though they will have equal contents.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn is_instance(&self, other: &Rope) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+
+    //-----------------------------------------------------------------------
+    // Grapheme methods
+
+    /// Returns the char index of the grapheme boundary before `char_idx`.
+    ///
+    /// If `char_idx` is 0, returns 0.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// grapheme before `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn prev_grapheme_boundary(&self, char_idx: usize) -> usize {
+        self.try_prev_grapheme_boundary(char_idx).unwrap()
+    }
+
+    /// Returns the char index of the grapheme boundary after `char_idx`.
+    ///
+    /// If `char_idx == len_chars()`, returns `len_chars()`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// grapheme after `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn next_grapheme_boundary(&self, char_idx: usize) -> usize {
+        self.try_next_grapheme_boundary(char_idx).unwrap()
+    }
+
+    /// Returns whether `char_idx` is on a grapheme boundary.
+    ///
+    /// The start and end of the `Rope` are always grapheme boundaries.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn is_grapheme_boundary(&self, char_idx: usize) -> bool {
+        self.try_is_grapheme_boundary(char_idx).unwrap()
+    }
+
+    //-----------------------------------------------------------------------
+    // Search methods
+
+    /// Returns the first match of `pattern` in the `Rope`, if any.
+    ///
+    /// An empty `pattern` matches at the start of the `Rope`.
+    ///
+    /// Runs in O(N + M) time, where M is the length of `pattern`.
+    #[inline]
+    pub fn find(&self, pattern: &str) -> Option<Match> {
+        self.matches(pattern).next()
+    }
+
+    /// Returns the last match of `pattern` in the `Rope`, if any.
+    ///
+    /// An empty `pattern` matches at the end of the `Rope`.
+    ///
+    /// Runs in O(N + M) time, where M is the length of `pattern`.
+    #[inline]
+    pub fn rfind(&self, pattern: &str) -> Option<Match> {
+        search::rfind(self.slice(..), pattern)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `pattern`
+    /// in the `Rope`, from left to right.
+    ///
+    /// Runs in O(M + log N) time to create, and O(N) time to iterate over
+    /// all matches, where M is the length of `pattern`.
+    #[inline]
+    pub fn matches(&self, pattern: &str) -> Matches<'_> {
+        Matches::new(self.slice(..), pattern, SearchOptions::default())
+    }
+
+    /// Like `find()`, but with the given search options.
+    ///
+    /// Runs in O(N + M) time, plus O(log N) time for each match rejected
+    /// by [`whole_word`](SearchOptions::whole_word).
+    #[inline]
+    pub fn find_with(&self, pattern: &str, options: SearchOptions) -> Option<Match> {
+        self.matches_with(pattern, options).next()
+    }
+
+    /// Like `rfind()`, but with the given search options.
+    ///
+    /// Runs in O(N + M) time, plus O(log N) time for each match rejected
+    /// by [`whole_word`](SearchOptions::whole_word).
+    #[inline]
+    pub fn rfind_with(&self, pattern: &str, options: SearchOptions) -> Option<Match> {
+        search::rfind_with(self.slice(..), pattern, options)
+    }
+
+    /// Like `matches()`, but with the given search options.
+    ///
+    /// Runs in O(M + log N) time to create, and O(N) time to iterate over
+    /// all matches, plus O(log N) time for each match that is
+    /// case-insensitive or checked for being a whole word.
+    #[inline]
+    pub fn matches_with(&self, pattern: &str, options: SearchOptions) -> Matches<'_> {
+        Matches::new(self.slice(..), pattern, options)
+    }
+
+    /// Returns the index of the first char satisfying `predicate`, scanning
+    /// from `char_idx` in the given direction.
+    ///
+    /// Scanning forward starts with the char at `char_idx`, and scanning
+    /// backward starts with the char before it.  To bound how far the scan
+    /// can go, use [`position_where()`](Rope::position_where) instead.
+    ///
+    /// Runs in O(log N + K) time, where K is the number of chars scanned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// use ropey::search::Direction;
+    ///
+    /// let rope = Rope::from_str("let x =   42;");
+    /// let next = rope.find_char_from(7, Direction::Forward, |c| !c.is_whitespace());
+    ///
+    /// assert_eq!(next, Some(10));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn find_char_from<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        predicate: F,
+    ) -> Option<usize>
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.try_find_char_from(char_idx, direction, predicate)
+            .unwrap()
+    }
+
+    /// Returns the index of the first char satisfying `predicate`, scanning
+    /// at most `limit` chars from `char_idx` in the given direction.
+    ///
+    /// Like [`find_char_from()`](Rope::find_char_from), except that the
+    /// scan gives up after `limit` chars if a limit is given, which keeps
+    /// scans that are driven by user input cheap on huge texts.
+    ///
+    /// Runs in O(log N + K) time, where K is the number of chars scanned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn position_where<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        limit: Option<usize>,
+        predicate: F,
+    ) -> Option<usize>
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.try_position_where(char_idx, direction, limit, predicate)
+            .unwrap()
+    }
+
+    /// Creates an iterator over the leftmost-longest non-overlapping
+    /// matches of `patterns` in the `Rope`, from left to right.
+    ///
+    /// Yields the index of the matched pattern along with each match.  See
+    /// [`MultiMatches`] for details.
+    ///
+    /// Runs in O(log N) time to create, and O(N * L) time to iterate over
+    /// all matches in the worst case, where L is the length of the longest
+    /// pattern.  Typically it is closer to O(N).
+    #[inline]
+    pub fn multi_find_iter<'p>(&self, patterns: &'p MultiPattern) -> MultiMatches<'_, 'p> {
+        MultiMatches::new(self.slice(..), patterns)
+    }
+
+    /// Creates an iterator over all matches of `patterns` in the `Rope`,
+    /// including overlapping ones.
+    ///
+    /// Yields the index of the matched pattern along with each match.  See
+    /// [`MultiMatchesOverlapping`] for the order they are yielded in.
+    ///
+    /// Runs in O(log N) time to create, and O(N + K) time to iterate over
+    /// all matches, where K is the number of matches.
+    #[inline]
+    pub fn multi_find_overlapping_iter<'p>(
+        &self,
+        patterns: &'p MultiPattern,
+    ) -> MultiMatchesOverlapping<'_, 'p> {
+        MultiMatchesOverlapping::new(self.slice(..), patterns)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `regex` in
+    /// the `Rope`, from left to right.
+    ///
+    /// Only available with the `regex` feature enabled.
+    ///
+    /// Runs in O(log N) time to create, and O(N) time to iterate over all
+    /// matches.
+    #[cfg(feature = "regex")]
+    #[inline]
+    pub fn regex_find_iter<'r>(&self, regex: &'r Regex) -> RegexMatches<'_, 'r> {
+        RegexMatches::new(self.slice(..), regex)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `regex` in
+    /// the `Rope`, from right to left.
+    ///
+    /// Only available with the `regex` feature enabled.  See
+    /// [`RegexRMatches`](crate::regex::RegexRMatches) for how the matches differ from
+    /// `regex_find_iter()`'s.
+    ///
+    /// Runs in O(log N) time to create, and O(N) time to iterate over all
+    /// matches.
+    #[cfg(feature = "regex")]
+    #[inline]
+    pub fn regex_rfind_iter<'r>(&self, regex: &'r Regex) -> RegexRMatches<'_, 'r> {
+        RegexRMatches::new(self.slice(..), regex)
+    }
+
+    /// Returns the capture groups of the first match of `regex` in the
+    /// `Rope`, if any.
     ///
-    /// # Panics
+    /// Only available with the `regex` feature enabled.
     ///
-    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    /// Runs in O(N) time.
+    #[cfg(feature = "regex")]
     #[inline]
-    pub fn chunks_at_line_break(&self, line_break_idx: usize) -> (Chunks, usize, usize, usize) {
-        if let Some(out) = self.get_chunks_at_line_break(line_break_idx) {
-            out
-        } else {
-            panic!(
-                "Attempt to index past end of Rope: line break index {}, max index {}",
-                line_break_idx,
-                self.len_lines()
-            );
-        }
+    pub fn regex_captures(&self, regex: &Regex) -> Option<Captures> {
+        regex::captures(self.slice(..), regex)
     }
 
-    /// Returns true if this rope and `other` point to precisely the same
-    /// in-memory data.
+    //-----------------------------------------------------------------------
+    // Metric methods
+
+    /// Attaches `metric` to the `Rope`, replacing the metric attached
+    /// before, if any.
     ///
-    /// This happens when one of the ropes is a clone of the other and
-    /// neither have been modified since then.  Because clones initially
-    /// share all the same data, it can be useful to check if they still
-    /// point to precisely the same memory as a way of determining
-    /// whether they are both still unmodified.
+    /// The metric's summaries are stored in the `Rope`'s tree, and are
+    /// queried through [`metric_summaries()`](Rope::metric_summaries).
+    /// Clones of the `Rope` and parts split off of it keep the metric,
+    /// and share its summaries.
     ///
-    /// Note: this is distinct from checking for equality: two ropes can
-    /// have the same *contents* (equal) but be stored in different
-    /// memory locations (not instances).  Importantly, two clones that
-    /// post-cloning are modified identically will *not* be instances
-    /// anymore, even though they will have equal contents.
+    /// Runs in O(1) time.  The text is summarized when the summaries are
+    /// first queried.
+    #[inline]
+    pub fn set_metric<M: Metric>(&mut self, metric: M) {
+        self.metric = Some(metric::attach(metric));
+    }
+
+    /// Detaches the metric attached with [`set_metric()`](Rope::set_metric),
+    /// if any.
+    #[inline]
+    pub fn clear_metric(&mut self) {
+        self.metric = None;
+    }
+
+    /// Returns the summaries of the attached metric, or `None` if no
+    /// metric of type `M` is attached.
     ///
-    /// Runs in O(1) time.
+    /// Edits drop the summaries stored in the tree nodes they touch, so
+    /// those nodes are summarized again first.  This takes `&mut self`
+    /// because of that, and, as with an edit, copies any of those nodes
+    /// that are shared with clones of the `Rope`.
+    ///
+    /// Runs in O(N) time the first time after `set_metric()`.  After
+    /// that, runs in O(K log N) time, plus the time to summarize the
+    /// chunks next to each edit, where K is the number of edits since the
+    /// last call.
     #[inline]
-    pub fn is_instance(&self, other: &Rope) -> bool {
-        Arc::ptr_eq(&self.root, &other.root)
+    pub fn metric_summaries<M: Metric>(&mut self) -> Option<MetricSummaries<'_, M>> {
+        MetricSummaries::new(self)
     }
 
     //-----------------------------------------------------------------------
@@ -1243,6 +2391,248 @@ impl Rope {
     //-----------------------------------------------------------------------
     // Internal utilities
 
+    /// Shared implementation of `replace_all()` and `regex_replace_all()`.
+    ///
+    /// `matches` iterates over the matches in a clone of `self`, and
+    /// `expand` appends a match's replacement text to the given `String`.
+    fn replace_matches<I, F>(&mut self, matches: I, mut expand: F) -> usize
+    where
+        I: Iterator<Item = Match> + Clone,
+        F: FnMut(&Match, &mut String),
+    {
+        let mut buf = String::new();
+
+        // Up to about one match per chunk, it's cheaper to do the edits in
+        // place than to rebuild the whole text.
+        let max_in_place = self.len_bytes() / MAX_BYTES;
+        let first_matches: Vec<Match> = matches.clone().take(max_in_place + 1).collect();
+        if first_matches.len() <= max_in_place {
+            // Back to front, so the remaining matches' indices stay valid.
+            for m in first_matches.iter().rev() {
+                buf.clear();
+                expand(m, &mut buf);
+                self.replace(m.char_range.clone(), &buf);
+            }
+            return first_matches.len();
+        }
+
+        let mut builder = RopeBuilder::new();
+        let mut count = 0;
+        let mut last_end = 0;
+        for m in matches {
+            for chunk in self.byte_slice(last_end..m.byte_range.start).chunks() {
+                builder.append(chunk);
+            }
+            buf.clear();
+            expand(&m, &mut buf);
+            builder.append(&buf);
+            count += 1;
+            last_end = m.byte_range.end;
+        }
+        for chunk in self.byte_slice(last_end..).chunks() {
+            builder.append(chunk);
+        }
+
+        self.root = builder.finish().root;
+        count
+    }
+
+    /// Returns the char index of the given byte, which must be on a char
+    /// boundary.
+    fn byte_to_char_boundary(&self, byte_idx: usize) -> Result<usize> {
+        self.check_byte_boundary(byte_idx)?;
+        Ok(self.byte_to_char(byte_idx))
+    }
+
+    /// Checks that the given byte index is in bounds and on a char
+    /// boundary.
+    fn check_byte_boundary(&self, byte_idx: usize) -> Result<()> {
+        if byte_idx > self.len_bytes() {
+            Err(Error::ByteIndexOutOfBounds(byte_idx, self.len_bytes()))
+        } else if !self.root.is_char_boundary(byte_idx) {
+            Err(Error::ByteIndexNotCharBoundary(byte_idx))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolves the given byte range, checking that it's valid and lines
+    /// up with char boundaries.
+    fn check_byte_range<R>(&self, byte_range: R) -> Result<Range<usize>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start_opt = start_bound_to_num(byte_range.start_bound());
+        let end_opt = end_bound_to_num(byte_range.end_bound());
+        let start = start_opt.unwrap_or(0);
+        let end = end_opt.unwrap_or_else(|| self.len_bytes());
+        if end.max(start) > self.len_bytes() {
+            return Err(Error::ByteRangeOutOfBounds(
+                start_opt,
+                end_opt,
+                self.len_bytes(),
+            ));
+        } else if start > end {
+            return Err(Error::ByteRangeInvalid(start, end));
+        }
+
+        if self.root.is_char_boundary(start) && self.root.is_char_boundary(end) {
+            Ok(start..end)
+        } else {
+            Err(Error::ByteRangeNotCharBoundary(start_opt, end_opt))
+        }
+    }
+
+    /// Returns the char index of the given utf16 code unit, which must be
+    /// on a char boundary.
+    fn utf16_cu_to_char_boundary(&self, utf16_cu_idx: usize) -> Result<usize> {
+        let char_idx = self.try_utf16_cu_to_char(utf16_cu_idx)?;
+        if self.char_to_utf16_cu(char_idx) == utf16_cu_idx {
+            Ok(char_idx)
+        } else {
+            Err(Error::Utf16IndexNotCharBoundary(utf16_cu_idx))
+        }
+    }
+
+    /// Converts the given utf16 code unit range to a char range, checking
+    /// that it's valid and lines up with char boundaries.
+    fn utf16_cu_range_to_char_range<R>(&self, utf16_cu_range: R) -> Result<Range<usize>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = start_bound_to_num(utf16_cu_range.start_bound()).unwrap_or(0);
+        let end =
+            end_bound_to_num(utf16_cu_range.end_bound()).unwrap_or_else(|| self.len_utf16_cu());
+        if start > end && start <= self.len_utf16_cu() {
+            return Err(Error::Utf16RangeInvalid(start, end));
+        }
+        let start = self.utf16_cu_to_char_boundary(start)?;
+        let end = self.utf16_cu_to_char_boundary(end)?;
+        Ok(start..end)
+    }
+
+    /// Inserts `text` at byte or char index `idx`, depending on `unit`.
+    ///
+    /// `idx` must already be bounds checked, and on a char boundary.
+    fn insert_at(&mut self, idx: usize, unit: Unit, text: &str) {
+        // We have three cases here:
+        // 1. The insertion text is very large, in which case building a new
+        //    Rope out of it and splicing it into the existing Rope is most
+        //    efficient.
+        // 2. The insertion text is somewhat large, in which case splitting it
+        //    up into chunks and repeatedly inserting them is the most
+        //    efficient.  The splitting is necessary because the insertion code
+        //    only works correctly below a certain insertion size.
+        // 3. The insertion text is small, in which case we can simply insert
+        //    it.
+        //
+        // Cases #2 and #3 are rolled into one case here, where case #3 just
+        // results in the text being "split" into only one chunk.
+        //
+        // The boundary for what constitutes "very large" text was arrived at
+        // experimentally, by testing at what point Rope build + splice becomes
+        // faster than split + repeated insert.  This constant is likely worth
+        // revisiting from time to time as Ropey evolves.
+        if text.len() > MAX_BYTES * 6 {
+            // Case #1: very large text, build rope and splice it in.
+            // The byte to char conversion here is O(log N), which is
+            // dwarfed by building the new Rope.
+            let char_idx = match unit {
+                Unit::Byte => self.byte_to_char(idx),
+                Unit::Char => idx,
+            };
+            let text_rope = Rope::from_str(text);
+            let right = self.split_off(char_idx);
+            self.append(text_rope);
+            self.append(right);
+        } else {
+            // Cases #2 and #3: split into chunks and repeatedly insert.
+            let mut text = text;
+            while !text.is_empty() {
+                // Split a chunk off from the end of the text.
+                // We do this from the end instead of the front so that
+                // the repeated insertions can keep re-using the same
+                // insertion point.
+                let split_idx = crlf::find_good_split(
+                    text.len() - (MAX_BYTES - 4).min(text.len()),
+                    text.as_bytes(),
+                    false,
+                );
+                let ins_text = &text[split_idx..];
+                text = &text[..split_idx];
+
+                // Do the insertion.
+                self.insert_internal(idx, unit, ins_text);
+            }
+        }
+    }
+
+    /// Removes the bytes or chars (depending on `unit`) in `start..end`.
+    ///
+    /// The range must already be bounds checked, and on char boundaries.
+    fn remove_at(&mut self, start: usize, end: usize, unit: Unit) {
+        // A special case that the rest of the logic doesn't handle
+        // correctly.
+        if start == 0 && end == unit.len(&self.root.text_info()) {
+            self.root = Arc::new(Node::new());
+            return;
+        }
+
+        let root = Arc::make_mut(&mut self.root);
+
+        let root_info = root.text_info();
+        let (_, _, crlf_seam, needs_fix) = root.replace_range(start, end, unit, "", root_info);
+
+        if crlf_seam {
+            let seam_idx = root.idx_to_byte(start, unit);
+            root.fix_crlf_seam(seam_idx as Count, false);
+        }
+
+        if needs_fix {
+            root.fix_tree_seam(start, unit);
+        }
+
+        self.pull_up_singular_nodes();
+    }
+
+    /// Replaces the bytes or chars (depending on `unit`) in `start..end`
+    /// with `text`.
+    ///
+    /// The range must already be bounds checked, and on char boundaries.
+    fn replace_at(&mut self, start: usize, end: usize, unit: Unit, text: &str) {
+        if start == end {
+            self.insert_at(start, unit, text);
+        } else if text.is_empty() {
+            self.remove_at(start, end, unit);
+        } else if start == 0 && end == unit.len(&self.root.text_info()) {
+            // A special case that the rest of the logic doesn't handle
+            // correctly.
+            self.root = Rope::from_str(text).root;
+        } else if text.len() > (MAX_BYTES - 4) {
+            // Text this large spans multiple leaves anyway, so it goes
+            // through the regular insertion code, which handles that.
+            self.remove_at(start, end, unit);
+            self.insert_at(start, unit, text);
+        } else {
+            self.replace_internal(start, end, unit, text);
+        }
+    }
+
+    /// Used by `try_apply_edits()` to append the unedited text in
+    /// `start..end` to `rope`, via `buf` if it's small.
+    fn push_edit_gap(&self, rope: &mut Rope, buf: &mut String, start: usize, end: usize) {
+        let gap = self.slice(start..end);
+        if gap.len_bytes() < MAX_BYTES * 2 {
+            for chunk in gap.chunks() {
+                buf.push_str(chunk);
+            }
+        } else {
+            rope.append(Rope::from_str(buf));
+            buf.clear();
+            rope.append(Rope::from(gap));
+        }
+    }
+
     /// Iteratively replaces the root node with its child if it only has
     /// one child.
     pub(crate) fn pull_up_singular_nodes(&mut self) {
@@ -1269,50 +2659,7 @@ impl Rope {
     pub fn try_insert(&mut self, char_idx: usize, text: &str) -> Result<()> {
         // Bounds check
         if char_idx <= self.len_chars() {
-            // We have three cases here:
-            // 1. The insertion text is very large, in which case building a new
-            //    Rope out of it and splicing it into the existing Rope is most
-            //    efficient.
-            // 2. The insertion text is somewhat large, in which case splitting it
-            //    up into chunks and repeatedly inserting them is the most
-            //    efficient.  The splitting is necessary because the insertion code
-            //    only works correctly below a certain insertion size.
-            // 3. The insertion text is small, in which case we can simply insert
-            //    it.
-            //
-            // Cases #2 and #3 are rolled into one case here, where case #3 just
-            // results in the text being "split" into only one chunk.
-            //
-            // The boundary for what constitutes "very large" text was arrived at
-            // experimentally, by testing at what point Rope build + splice becomes
-            // faster than split + repeated insert.  This constant is likely worth
-            // revisiting from time to time as Ropey evolves.
-            if text.len() > MAX_BYTES * 6 {
-                // Case #1: very large text, build rope and splice it in.
-                let text_rope = Rope::from_str(text);
-                let right = self.split_off(char_idx);
-                self.append(text_rope);
-                self.append(right);
-            } else {
-                // Cases #2 and #3: split into chunks and repeatedly insert.
-                let mut text = text;
-                while !text.is_empty() {
-                    // Split a chunk off from the end of the text.
-                    // We do this from the end instead of the front so that
-                    // the repeated insertions can keep re-using the same
-                    // insertion point.
-                    let split_idx = crlf::find_good_split(
-                        text.len() - (MAX_BYTES - 4).min(text.len()),
-                        text.as_bytes(),
-                        false,
-                    );
-                    let ins_text = &text[split_idx..];
-                    text = &text[..split_idx];
-
-                    // Do the insertion.
-                    self.insert_internal(char_idx, ins_text);
-                }
-            }
+            self.insert_at(char_idx, Unit::Char, text);
             Ok(())
         } else {
             Err(Error::CharIndexOutOfBounds(char_idx, self.len_chars()))
@@ -1325,7 +2672,7 @@ impl Rope {
         // Bounds check
         if char_idx <= self.len_chars() {
             let mut buf = [0u8; 4];
-            self.insert_internal(char_idx, ch.encode_utf8(&mut buf));
+            self.insert_internal(char_idx, Unit::Char, ch.encode_utf8(&mut buf));
             Ok(())
         } else {
             Err(Error::CharIndexOutOfBounds(char_idx, self.len_chars()))
@@ -1350,30 +2697,82 @@ impl Rope {
         } else if start > end {
             Err(Error::CharRangeInvalid(start, end))
         } else {
-            // A special case that the rest of the logic doesn't handle
-            // correctly.
-            if start == 0 && end == self.len_chars() {
-                self.root = Arc::new(Node::new());
-                return Ok(());
-            }
-
-            let root = Arc::make_mut(&mut self.root);
+            self.remove_at(start, end, Unit::Char);
+            Ok(())
+        }
+    }
 
-            let root_info = root.text_info();
-            let (_, crlf_seam, needs_fix) = root.remove_char_range(start, end, root_info);
+    /// Non-panicking version of [`replace()`](Rope::replace).
+    pub fn try_replace<R>(&mut self, char_range: R, text: &str) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start_opt = start_bound_to_num(char_range.start_bound());
+        let end_opt = end_bound_to_num(char_range.end_bound());
+        let start = start_opt.unwrap_or(0);
+        let end = end_opt.unwrap_or_else(|| self.len_chars());
+        if end.max(start) > self.len_chars() {
+            Err(Error::CharRangeOutOfBounds(
+                start_opt,
+                end_opt,
+                self.len_chars(),
+            ))
+        } else if start > end {
+            Err(Error::CharRangeInvalid(start, end))
+        } else {
+            self.replace_at(start, end, Unit::Char, text);
+            Ok(())
+        }
+    }
 
-            if crlf_seam {
-                let seam_idx = root.char_to_text_info(start).bytes;
-                root.fix_crlf_seam(seam_idx as Count, false);
+    /// Non-panicking version of [`apply_edits()`](Rope::apply_edits).
+    ///
+    /// On error, the `Rope` is left unmodified.
+    pub fn try_apply_edits<'a, I>(&mut self, edits: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (Range<usize>, &'a str)>,
+    {
+        let edits: Vec<(Range<usize>, &str)> = edits.into_iter().collect();
+
+        // Validate everything up front, so that errors don't leave us
+        // half-edited.
+        let mut last_end = 0;
+        for (range, _) in edits.iter() {
+            if range.start.max(range.end) > self.len_chars() {
+                return Err(Error::CharRangeOutOfBounds(
+                    Some(range.start),
+                    Some(range.end),
+                    self.len_chars(),
+                ));
+            } else if range.start > range.end {
+                return Err(Error::CharRangeInvalid(range.start, range.end));
+            } else if range.start < last_end {
+                return Err(Error::CharRangesOverlap(last_end, range.start));
             }
+            last_end = range.end;
+        }
 
-            if needs_fix {
-                root.fix_tree_seam(start);
-            }
+        if edits.len() == 1 {
+            let (ref range, text) = edits[0];
+            return self.try_replace(range.clone(), text);
+        }
 
-            self.pull_up_singular_nodes();
-            Ok(())
+        // Build the new text left to right.  Small pieces of text are
+        // gathered into a buffer, while large unedited stretches are
+        // spliced in directly, sharing their nodes with the old tree.
+        let mut new_rope = Rope::new();
+        let mut buf = String::new();
+        let mut last_end = 0;
+        for (range, text) in edits.iter() {
+            self.push_edit_gap(&mut new_rope, &mut buf, last_end, range.start);
+            buf.push_str(text);
+            last_end = range.end;
         }
+        self.push_edit_gap(&mut new_rope, &mut buf, last_end, self.len_chars());
+        new_rope.append(Rope::from_str(&buf));
+
+        self.root = new_rope.root;
+        Ok(())
     }
 
     /// Non-panicking version of [`split_off()`](Rope::split_off).
@@ -1382,16 +2781,24 @@ impl Rope {
         if char_idx <= self.len_chars() {
             if char_idx == 0 {
                 // Special case 1
-                let mut new_rope = Rope::new();
-                std::mem::swap(self, &mut new_rope);
-                Ok(new_rope)
+                Ok(Rope {
+                    root: std::mem::replace(&mut self.root, Arc::new(Node::new())),
+                    line_break_mode: self.line_break_mode,
+                    metric: self.metric.clone(),
+                })
             } else if char_idx == self.len_chars() {
                 // Special case 2
-                Ok(Rope::new())
+                Ok(Rope {
+                    root: Arc::new(Node::new()),
+                    line_break_mode: self.line_break_mode,
+                    metric: self.metric.clone(),
+                })
             } else {
                 // Do the split
                 let mut new_rope = Rope {
                     root: Arc::new(Arc::make_mut(&mut self.root).split(char_idx)),
+                    line_break_mode: self.line_break_mode,
+                    metric: self.metric.clone(),
                 };
 
                 // Fix up the edges
@@ -1407,6 +2814,79 @@ impl Rope {
         }
     }
 
+    /// Non-panicking version of
+    /// [`insert_at_grapheme()`](Rope::insert_at_grapheme).
+    #[cfg(feature = "graphemes")]
+    pub fn try_insert_at_grapheme(&mut self, char_idx: usize, text: &str) -> Result<usize> {
+        let byte_idx = segment::floor_grapheme_boundary(self.slice(..), char_idx)?;
+        let char_idx = self.byte_to_char(byte_idx);
+        self.try_insert(char_idx, text)?;
+        Ok(char_idx)
+    }
+
+    /// Non-panicking version of
+    /// [`remove_graphemes()`](Rope::remove_graphemes).
+    #[cfg(feature = "graphemes")]
+    pub fn try_remove_graphemes<R>(&mut self, char_range: R) -> Result<Range<usize>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = segment::grapheme_range(self.slice(..), char_range)?;
+        self.try_remove(char_range.clone())?;
+        Ok(char_range)
+    }
+
+    /// Non-panicking version of [`insert_at_byte()`](Rope::insert_at_byte).
+    #[inline]
+    pub fn try_insert_at_byte(&mut self, byte_idx: usize, text: &str) -> Result<()> {
+        self.check_byte_boundary(byte_idx)?;
+        self.insert_at(byte_idx, Unit::Byte, text);
+        Ok(())
+    }
+
+    /// Non-panicking version of [`remove_bytes()`](Rope::remove_bytes).
+    pub fn try_remove_bytes<R>(&mut self, byte_range: R) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let byte_range = self.check_byte_range(byte_range)?;
+        self.remove_at(byte_range.start, byte_range.end, Unit::Byte);
+        Ok(())
+    }
+
+    /// Non-panicking version of [`replace_bytes()`](Rope::replace_bytes).
+    pub fn try_replace_bytes<R>(&mut self, byte_range: R, text: &str) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let byte_range = self.check_byte_range(byte_range)?;
+        self.replace_at(byte_range.start, byte_range.end, Unit::Byte, text);
+        Ok(())
+    }
+
+    /// Non-panicking version of [`split_off_at_byte()`](Rope::split_off_at_byte).
+    #[inline]
+    pub fn try_split_off_at_byte(&mut self, byte_idx: usize) -> Result<Self> {
+        let char_idx = self.byte_to_char_boundary(byte_idx)?;
+        self.try_split_off(char_idx)
+    }
+
+    /// Non-panicking version of [`insert_at_utf16()`](Rope::insert_at_utf16).
+    #[inline]
+    pub fn try_insert_at_utf16(&mut self, utf16_cu_idx: usize, text: &str) -> Result<()> {
+        let char_idx = self.utf16_cu_to_char_boundary(utf16_cu_idx)?;
+        self.try_insert(char_idx, text)
+    }
+
+    /// Non-panicking version of [`remove_utf16()`](Rope::remove_utf16).
+    pub fn try_remove_utf16<R>(&mut self, utf16_cu_range: R) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = self.utf16_cu_range_to_char_range(utf16_cu_range)?;
+        self.try_remove(char_range)
+    }
+
     /// Non-panicking version of [`byte_to_char()`](Rope::byte_to_char).
     #[inline]
     pub fn try_byte_to_char(&self, byte_idx: usize) -> Result<usize> {
@@ -1425,7 +2905,7 @@ impl Rope {
         // Bounds check
         if byte_idx <= self.len_bytes() {
             let (chunk, b, _, l) = self.chunk_at_byte(byte_idx);
-            Ok(l + byte_to_line_idx(chunk, byte_idx - b))
+            Ok(l + self.line_break_mode.byte_to_line_idx(chunk, byte_idx - b))
         } else {
             Err(Error::ByteIndexOutOfBounds(byte_idx, self.len_bytes()))
         }
@@ -1449,7 +2929,7 @@ impl Rope {
         // Bounds check
         if char_idx <= self.len_chars() {
             let (chunk, _, c, l) = self.chunk_at_char(char_idx);
-            Ok(l + char_to_line_idx(chunk, char_idx - c))
+            Ok(l + self.line_break_mode.char_to_line_idx(chunk, char_idx - c))
         } else {
             Err(Error::CharIndexOutOfBounds(char_idx, self.len_chars()))
         }
@@ -1499,7 +2979,7 @@ impl Rope {
                 Ok(self.len_bytes())
             } else {
                 let (chunk, b, _, l) = self.chunk_at_line_break(line_idx);
-                Ok(b + line_to_byte_idx(chunk, line_idx - l))
+                Ok(b + self.line_break_mode.line_to_byte_idx(chunk, line_idx - l))
             }
         } else {
             Err(Error::LineIndexOutOfBounds(line_idx, self.len_lines()))
@@ -1515,13 +2995,102 @@ impl Rope {
                 Ok(self.len_chars())
             } else {
                 let (chunk, _, c, l) = self.chunk_at_line_break(line_idx);
-                Ok(c + line_to_char_idx(chunk, line_idx - l))
+                Ok(c + self.line_break_mode.line_to_char_idx(chunk, line_idx - l))
             }
         } else {
             Err(Error::LineIndexOutOfBounds(line_idx, self.len_lines()))
         }
     }
 
+    /// Non-panicking version of [`position_to_char()`](Rope::position_to_char).
+    #[inline]
+    pub fn try_position_to_char(&self, pos: Position, encoding: ColumnEncoding) -> Result<usize> {
+        position::position_to_char(self.slice(..), pos, encoding, false)
+    }
+
+    /// Non-panicking version of [`char_to_position()`](Rope::char_to_position).
+    #[inline]
+    pub fn try_char_to_position(
+        &self,
+        char_idx: usize,
+        encoding: ColumnEncoding,
+    ) -> Result<Position> {
+        position::char_to_position(self.slice(..), char_idx, encoding)
+    }
+
+    /// Non-panicking version of [`visual_column()`](Rope::visual_column).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn try_visual_column(&self, char_idx: usize, tab_width: usize) -> Result<usize> {
+        width::visual_column(self.slice(..), char_idx, tab_width)
+    }
+
+    /// Non-panicking version of
+    /// [`char_at_visual_column()`](Rope::char_at_visual_column).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn try_char_at_visual_column(
+        &self,
+        line_idx: usize,
+        column: usize,
+        tab_width: usize,
+    ) -> Result<usize> {
+        width::char_at_visual_column(self.slice(..), line_idx, column, tab_width)
+    }
+
+    /// Non-panicking version of [`find_char_from()`](Rope::find_char_from).
+    #[inline]
+    pub fn try_find_char_from<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        predicate: F,
+    ) -> Result<Option<usize>>
+    where
+        F: FnMut(char) -> bool,
+    {
+        search::position_where(self.slice(..), char_idx, direction, None, predicate)
+    }
+
+    /// Non-panicking version of [`position_where()`](Rope::position_where).
+    #[inline]
+    pub fn try_position_where<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        limit: Option<usize>,
+        predicate: F,
+    ) -> Result<Option<usize>>
+    where
+        F: FnMut(char) -> bool,
+    {
+        search::position_where(self.slice(..), char_idx, direction, limit, predicate)
+    }
+
+    /// Non-panicking version of
+    /// [`prev_grapheme_boundary()`](Rope::prev_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_prev_grapheme_boundary(&self, char_idx: usize) -> Result<usize> {
+        segment::prev_grapheme_boundary(self.slice(..), char_idx)
+    }
+
+    /// Non-panicking version of
+    /// [`next_grapheme_boundary()`](Rope::next_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_next_grapheme_boundary(&self, char_idx: usize) -> Result<usize> {
+        segment::next_grapheme_boundary(self.slice(..), char_idx)
+    }
+
+    /// Non-panicking version of
+    /// [`is_grapheme_boundary()`](Rope::is_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_is_grapheme_boundary(&self, char_idx: usize) -> Result<bool> {
+        segment::is_grapheme_boundary(self.slice(..), char_idx)
+    }
+
     /// Non-panicking version of [`byte()`](Rope::byte).
     #[inline]
     pub fn get_byte(&self, byte_idx: usize) -> Option<u8> {
@@ -1561,17 +3130,28 @@ impl Rope {
             let (chunk_1, _, c1, l1) = self.chunk_at_line_break(line_idx);
             let (chunk_2, _, c2, l2) = self.chunk_at_line_break(line_idx + 1);
             if c1 == c2 {
-                let text1 = &chunk_1[line_to_byte_idx(chunk_1, line_idx - l1)..];
-                let text2 = &text1[..line_to_byte_idx(text1, 1)];
-                Some(RopeSlice(RSEnum::Light {
-                    text: text2,
-                    char_count: count_chars(text2) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(text2) as Count,
-                    line_break_count: if line_idx == (len_lines - 1) { 0 } else { 1 },
-                }))
+                let text1 = &chunk_1[self
+                    .line_break_mode
+                    .line_to_byte_idx(chunk_1, line_idx - l1)..];
+                let text2 = &text1[..self.line_break_mode.line_to_byte_idx(text1, 1)];
+                Some(RopeSlice(
+                    RSEnum::Light {
+                        text: text2,
+                        char_count: count_chars(text2) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(text2) as Count,
+                        line_break_count: if line_idx == (len_lines - 1) { 0 } else { 1 },
+                    },
+                    self.line_break_mode,
+                ))
             } else {
-                let start = c1 + line_to_char_idx(chunk_1, line_idx - l1);
-                let end = c2 + line_to_char_idx(chunk_2, line_idx + 1 - l2);
+                let start = c1
+                    + self
+                        .line_break_mode
+                        .line_to_char_idx(chunk_1, line_idx - l1);
+                let end = c2
+                    + self
+                        .line_break_mode
+                        .line_to_char_idx(chunk_2, line_idx + 1 - l2);
                 Some(self.slice(start..end))
             }
         } else {
@@ -1579,6 +3159,16 @@ impl Rope {
         }
     }
 
+    /// Non-panicking version of
+    /// [`line_without_ending()`](Rope::line_without_ending).
+    #[inline]
+    pub fn get_line_without_ending(
+        &self,
+        line_idx: usize,
+    ) -> Option<(RopeSlice<'_>, Option<LineEnding>)> {
+        self.get_line(line_idx).map(split_line_ending)
+    }
+
     /// Non-panicking version of [`chunk_at_byte()`](Rope::chunk_at_byte).
     #[inline]
     pub fn get_chunk_at_byte(&self, byte_idx: usize) -> Option<(&str, usize, usize, usize)> {
@@ -1589,7 +3179,7 @@ impl Rope {
                 chunk,
                 info.bytes as usize,
                 info.chars as usize,
-                info.line_breaks as usize,
+                info.line_breaks.get(self.line_break_mode),
             ))
         } else {
             None
@@ -1606,7 +3196,7 @@ impl Rope {
                 chunk,
                 info.bytes as usize,
                 info.chars as usize,
-                info.line_breaks as usize,
+                info.line_breaks.get(self.line_break_mode),
             ))
         } else {
             None
@@ -1621,12 +3211,14 @@ impl Rope {
     ) -> Option<(&str, usize, usize, usize)> {
         // Bounds check
         if line_break_idx <= self.len_lines() {
-            let (chunk, info) = self.root.get_chunk_at_line_break(line_break_idx);
+            let (chunk, info) = self
+                .root
+                .get_chunk_at_line_break(line_break_idx, self.line_break_mode);
             Some((
                 chunk,
                 info.bytes as usize,
                 info.chars as usize,
-                info.line_breaks as usize,
+                info.line_breaks.get(self.line_break_mode),
             ))
         } else {
             None
@@ -1644,7 +3236,12 @@ impl Rope {
 
         // Bounds check
         if start <= end && end <= self.len_chars() {
-            Some(RopeSlice::new_with_range(&self.root, start, end))
+            Some(RopeSlice::new_with_range(
+                &self.root,
+                start,
+                end,
+                self.line_break_mode,
+            ))
         } else {
             None
         }
@@ -1659,6 +3256,39 @@ impl Rope {
         self.get_byte_slice_impl(byte_range).ok()
     }
 
+    /// Non-panicking version of [`slice_utf16()`](Rope::slice_utf16).
+    #[inline]
+    pub fn get_slice_utf16<R>(&self, utf16_cu_range: R) -> Option<RopeSlice<'_>>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.get_slice_utf16_impl(utf16_cu_range).ok()
+    }
+
+    /// Non-panicking version of [`slice_graphemes()`](Rope::slice_graphemes).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_slice_graphemes<R>(&self, char_range: R) -> Option<RopeSlice<'_>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = segment::grapheme_range(self.slice(..), char_range).ok()?;
+        Some(self.slice(char_range))
+    }
+
+    fn get_slice_utf16_impl<R>(&self, utf16_cu_range: R) -> Result<RopeSlice<'_>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = self.utf16_cu_range_to_char_range(utf16_cu_range)?;
+        Ok(RopeSlice::new_with_range(
+            &self.root,
+            char_range.start,
+            char_range.end,
+            self.line_break_mode,
+        ))
+    }
+
     pub(crate) fn get_byte_slice_impl<R>(&self, byte_range: R) -> Result<RopeSlice>
     where
         R: RangeBounds<usize>,
@@ -1705,7 +3335,7 @@ impl Rope {
             end_range.unwrap_or_else(|| self.len_bytes()),
         );
 
-        RopeSlice::new_with_byte_range(&self.root, start, end).map_err(|e| {
+        RopeSlice::new_with_byte_range(&self.root, start, end, self.line_break_mode).map_err(|e| {
             if let Error::ByteRangeNotCharBoundary(_, _) = e {
                 Error::ByteRangeNotCharBoundary(start_range, end_range)
             } else {
@@ -1725,7 +3355,8 @@ impl Rope {
                 byte_idx,
                 (0, info.bytes as usize),
                 (0, info.chars as usize),
-                (0, info.line_breaks as usize + 1),
+                (0, info.line_breaks.get(self.line_break_mode) + 1),
+                self.line_break_mode,
             ))
         } else {
             None
@@ -1743,13 +3374,38 @@ impl Rope {
                 char_idx,
                 (0, info.bytes as usize),
                 (0, info.chars as usize),
-                (0, info.line_breaks as usize + 1),
+                (0, info.line_breaks.get(self.line_break_mode) + 1),
+                self.line_break_mode,
             ))
         } else {
             None
         }
     }
 
+    /// Non-panicking version of [`chars_at_utf16()`](Rope::chars_at_utf16).
+    #[inline]
+    pub fn get_chars_at_utf16(&self, utf16_cu_idx: usize) -> Option<Chars<'_>> {
+        let char_idx = self.utf16_cu_to_char_boundary(utf16_cu_idx).ok()?;
+        self.get_chars_at(char_idx)
+    }
+
+    /// Non-panicking version of [`graphemes_at()`](Rope::graphemes_at).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_graphemes_at(&self, char_idx: usize) -> Option<Graphemes<'_>> {
+        let text = self.slice(..);
+        let byte_idx = segment::floor_grapheme_boundary(text, char_idx).ok()?;
+        Some(Graphemes::new_at_byte(text, byte_idx))
+    }
+
+    /// Non-panicking version of [`word_bounds_at()`](Rope::word_bounds_at).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_word_bounds_at(&self, char_idx: usize) -> Option<WordBounds<'_>> {
+        let byte_idx = self.try_char_to_byte(char_idx).ok()?;
+        Some(WordBounds::new_at_byte(self.slice(..), byte_idx))
+    }
+
     /// Non-panicking version of [`lines_at()`](Rope::lines_at).
     #[inline]
     pub fn get_lines_at(&self, line_idx: usize) -> Option<Lines> {
@@ -1760,12 +3416,20 @@ impl Rope {
                 line_idx,
                 (0, self.len_bytes()),
                 (0, self.len_lines()),
+                self.line_break_mode,
             ))
         } else {
             None
         }
     }
 
+    /// Non-panicking version of
+    /// [`lines_with_endings_at()`](Rope::lines_with_endings_at).
+    #[inline]
+    pub fn get_lines_with_endings_at(&self, line_idx: usize) -> Option<LinesWithEndings<'_>> {
+        self.get_lines_at(line_idx).map(LinesWithEndings::new)
+    }
+
     /// Non-panicking version of [`chunks_at_byte()`](Rope::chunks_at_byte).
     #[inline]
     pub fn get_chunks_at_byte(&self, byte_idx: usize) -> Option<(Chunks, usize, usize, usize)> {
@@ -1777,6 +3441,7 @@ impl Rope {
                 (0, self.len_bytes()),
                 (0, self.len_chars()),
                 (0, self.len_lines()),
+                self.line_break_mode,
             ))
         } else {
             None
@@ -1794,6 +3459,7 @@ impl Rope {
                 (0, self.len_bytes()),
                 (0, self.len_chars()),
                 (0, self.len_lines()),
+                self.line_break_mode,
             ))
         } else {
             None
@@ -1814,6 +3480,7 @@ impl Rope {
                 (0, self.len_bytes()),
                 (0, self.len_chars()),
                 (0, self.len_lines()),
+                self.line_break_mode,
             ))
         } else {
             None
@@ -1852,13 +3519,18 @@ impl<'a> From<RopeSlice<'a>> for Rope {
     fn from(s: RopeSlice<'a>) -> Self {
         use crate::slice::RSEnum;
         match s {
-            RopeSlice(RSEnum::Full {
-                node,
-                start_info,
-                end_info,
-            }) => {
+            RopeSlice(
+                RSEnum::Full {
+                    node,
+                    start_info,
+                    end_info,
+                },
+                mode,
+            ) => {
                 let mut rope = Rope {
                     root: Arc::clone(node),
+                    line_break_mode: mode,
+                    metric: None,
                 };
 
                 // Chop off right end if needed
@@ -1884,7 +3556,11 @@ impl<'a> From<RopeSlice<'a>> for Rope {
                 // Return the rope
                 rope
             }
-            RopeSlice(RSEnum::Light { text, .. }) => Rope::from_str(text),
+            RopeSlice(RSEnum::Light { text, .. }, mode) => {
+                let mut rope = Rope::from_str(text);
+                rope.line_break_mode = mode;
+                rope
+            }
         }
     }
 }
@@ -2413,6 +4089,504 @@ mod tests {
         r.remove(104..105); // Removing past the end
     }
 
+    #[test]
+    fn replace_01() {
+        let mut r = Rope::from_str(TEXT);
+
+        r.replace(6..11, "there");
+        r.replace(0..5, "Howdy");
+        r.replace(38..44, "an ok");
+        assert_eq!(
+            r,
+            "Howdy there!  How're you doing?  It's an ok day, \
+             isn't it?  Aren't you glad we're alive?  \
+             こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn replace_02() {
+        // Replacements spanning many chunks.
+        let mut r = Rope::from_str(TEXT);
+
+        r.replace(5..100, "!!!");
+        assert_eq!(r, "Hello!!!さん！");
+        r.replace(.., TEXT);
+        assert_eq!(r, TEXT);
+        r.replace(3.., "");
+        assert_eq!(r, "Hel");
+        r.replace(3..3, "lo");
+        assert_eq!(r, "Hello");
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn replace_03() {
+        // CRLF pairs across replacement seams.
+        let mut r = Rope::from_str("\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r\r");
+        for i in 0..11 {
+            r.replace((i * 2 + 1)..(i * 2 + 2), "\n");
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        assert_eq!(r, "\r\n".repeat(11));
+        assert_eq!(r.len_lines(), 12);
+
+        for i in 0..11 {
+            r.replace((i * 2)..(i * 2 + 1), "a");
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        assert_eq!(r, "a\n".repeat(11));
+        assert_eq!(r.len_lines(), 12);
+
+        for i in 0..11 {
+            r.replace((i * 2)..(i * 2 + 1), "\r");
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        assert_eq!(r, "\r\n".repeat(11));
+        assert_eq!(r.len_lines(), 12);
+    }
+
+    #[test]
+    fn replace_04() {
+        // Every range of a small text, against the equivalent remove and
+        // insert.
+        let text = "a\r\nb\rc\nこ\r\r\nd";
+        let len = Rope::from_str(text).len_chars();
+        for start in 0..=len {
+            for end in start..=len {
+                for &ins in ["", "x", "\n", "\r", "\r\nみ\r"].iter() {
+                    let mut r1 = Rope::from_str(text);
+                    let mut r2 = Rope::from_str(text);
+                    r1.replace(start..end, ins);
+                    r2.remove(start..end);
+                    r2.insert(start, ins);
+                    assert_eq!(r1, r2);
+                    assert_eq!(r1.len_lines(), r2.len_lines());
+                    r1.assert_integrity();
+                    r1.assert_invariants();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn replace_06() {
+        // Replacements crossing chunk boundaries, which are done in one
+        // pass, against a String.
+        let text = "Hello\r\nthere\r\r\nこんにちは\nworld\r\n!";
+        let len = text.chars().count();
+        let mut crossing = 0;
+        for start in 0..len {
+            for end in (start + 1)..=len {
+                for &ins in ["x", "\n", "\r", "みん", "\nab\r"].iter() {
+                    let mut r = Rope::new();
+                    for (i, ch) in text.chars().enumerate() {
+                        r.insert_char(i, ch);
+                    }
+                    if r.chunk_at_char(start).1 != r.chunk_at_char(end - 1).1 {
+                        crossing += 1;
+                    }
+                    r.replace(start..end, ins);
+
+                    let mut expected: String = text.chars().take(start).collect();
+                    expected.push_str(ins);
+                    expected.extend(text.chars().skip(end));
+                    assert_eq!(r, expected.as_str());
+                    assert_eq!(r.len_lines(), Rope::from_str(&expected).len_lines());
+                    r.assert_integrity();
+                    r.assert_invariants();
+                }
+            }
+        }
+        assert!(crossing > 0);
+    }
+
+    #[test]
+    fn replace_07() {
+        // Replacing across many chunks of a deep tree, with text that makes
+        // the leaf it lands in split.
+        let mut r = Rope::from_str(TEXT);
+        let mut s = String::from(TEXT);
+        let mut i = 0;
+        while r.len_chars() > 10 {
+            let len = r.len_chars();
+            let start = (i * 7) % (len - 5);
+            let end = (start + 3 + (i * 13) % 40).min(len);
+            let ins = ["abcde", "\r\n\r", "ん", "\n\n"][i % 4];
+
+            r.replace(start..end, ins);
+            let byte_start = s.char_indices().nth(start).map_or(s.len(), |(b, _)| b);
+            let byte_end = s.char_indices().nth(end).map_or(s.len(), |(b, _)| b);
+            s.replace_range(byte_start..byte_end, ins);
+
+            assert_eq!(r, s.as_str());
+            r.assert_integrity();
+            r.assert_invariants();
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn replace_08() {
+        // Short replacements across the boundaries of full leaves in full
+        // nodes, which makes nodes split on the way back up.
+        let mut text = "abcdefghij".repeat(30);
+        let mut base = Rope::from_str(&text);
+        for i in 0..8 {
+            // `from_str()` leaves room for two more leaves in each node,
+            // and splitting two of their leaves fills them up.
+            let idx = i * (3 * MAX_BYTES + 4) + 2;
+            for &idx in [idx, idx + MAX_BYTES + 2].iter() {
+                base.insert(idx, "xy");
+                text.insert_str(idx, "xy");
+            }
+        }
+
+        for start in 0..(text.len() - 4) {
+            for end in (start + 1)..(start + 4) {
+                for &ins in ["vwxyz", "\r\n\r\n\r"].iter() {
+                    let mut r = base.clone();
+                    r.replace(start..end, ins);
+
+                    let mut expected = text.clone();
+                    expected.replace_range(start..end, ins);
+                    assert_eq!(r, expected.as_str());
+                    r.assert_integrity();
+                    r.assert_invariants();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn replace_05() {
+        let mut r = Rope::from_str(TEXT);
+        assert!(r.try_replace(5..104, "").is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let invalid = r.try_replace(6..5, ""); // Wrong ordering of start/end on purpose.
+        assert!(invalid.is_err());
+        assert_eq!(r, TEXT);
+    }
+
+    #[test]
+    fn apply_edits_01() {
+        let mut r = Rope::from_str(TEXT);
+
+        r.apply_edits(vec![
+            (0..0, "Oh, "),
+            (6..11, "everyone"),
+            (38..44, "an ok"),
+            (44..44, " and sunny"),
+            (91..103, "!"),
+        ]);
+        assert_eq!(
+            r,
+            "Oh, Hello everyone!  How're you doing?  It's an ok \
+             and sunny day, isn't it?  Aren't you glad we're alive?  !"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn apply_edits_02() {
+        // Many edits over a larger text, against applying them one by one
+        // from the back.
+        let text = TEXT_LINES.repeat(20);
+        let mut r1 = Rope::from_str(&text);
+        let mut r2 = Rope::from_str(&text);
+        let edits: Vec<(Range<usize>, &str)> = (0..100)
+            .map(|i| {
+                let start = i * 19 + (i % 3);
+                let ins = ["", "x", "\n", "\r\n", "こんにちは"][i % 5];
+                (start..(start + i % 4), ins)
+            })
+            .collect();
+
+        r1.apply_edits(edits.iter().cloned());
+        for (range, text) in edits.iter().rev() {
+            r2.remove(range.clone());
+            r2.insert(range.start, text);
+        }
+        assert_eq!(r1, r2);
+        assert_eq!(r1.len_lines(), r2.len_lines());
+
+        r1.assert_integrity();
+        r1.assert_invariants();
+    }
+
+    #[test]
+    fn apply_edits_03() {
+        // CRLF pairs formed and split at edit seams.
+        let mut r = Rope::from_str("\r\r\r\r\n\n\n\n");
+        r.apply_edits(vec![
+            (0..1, "a\r"),
+            (1..1, "\n"),
+            (2..2, "\n"),
+            (5..6, "\rb"),
+        ]);
+        let expected = "a\r\n\r\n\r\r\n\rb\n\n";
+        assert_eq!(r, expected);
+        assert_eq!(r.len_lines(), Rope::from_str(expected).len_lines());
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn apply_edits_04() {
+        let mut r = Rope::from_str(TEXT);
+        r.apply_edits(vec![]);
+        r.apply_edits(vec![(5..5, "1"), (5..5, "2"), (5..5, "3")]);
+        assert_eq!(&r.to_string()[..10], "Hello123 t");
+        r.apply_edits(vec![(0..5, ""), (5..8, "")]);
+        assert_eq!(&r.to_string()[..6], " there");
+    }
+
+    #[test]
+    fn apply_edits_05() {
+        let mut r = Rope::from_str(TEXT);
+        assert!(matches!(
+            r.try_apply_edits(vec![(0..5, "a"), (4..6, "b")]),
+            Err(Error::CharRangesOverlap(5, 4))
+        ));
+        assert!(matches!(
+            r.try_apply_edits(vec![(10..12, "a"), (0..1, "b")]),
+            Err(Error::CharRangesOverlap(12, 0))
+        ));
+        assert!(matches!(
+            r.try_apply_edits(vec![(0..1, "a"), (100..104, "b")]),
+            Err(Error::CharRangeOutOfBounds(Some(100), Some(104), 103))
+        ));
+        assert_eq!(r, TEXT);
+    }
+
+    #[test]
+    fn replace_all_01() {
+        // Few and many matches, which take different paths.
+        for &(pattern, replacement) in [
+            ("you", "y'all"),
+            ("'", ""),
+            (" ", "_"),
+            ("e", "eee"),
+            ("んな", "x"),
+            ("nope", "x"),
+        ]
+        .iter()
+        {
+            let mut r = Rope::from_str(TEXT);
+            let count = r.replace_all(pattern, replacement);
+
+            assert_eq!(count, TEXT.matches(pattern).count());
+            assert_eq!(r, TEXT.replace(pattern, replacement));
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn replace_all_02() {
+        let mut r = Rope::from_str("abc");
+        assert_eq!(r.replace_all("", "-"), 4);
+        assert_eq!(r, "-a-b-c-");
+
+        let mut r = Rope::new();
+        assert_eq!(r.replace_all("", "-"), 1);
+        assert_eq!(r, "-");
+
+        // Replacements that form CRLF pairs with the surrounding text.
+        let text = "a\nb\ra\n".repeat(10);
+        let mut r = Rope::from_str(&text);
+        assert_eq!(r.replace_all("a", "\r"), 20);
+        assert_eq!(r, text.replace("a", "\r"));
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn regex_replace_all_01() {
+        use crate::regex::Regex;
+
+        let mut r = Rope::from_str(TEXT);
+        let regex = Regex::new(r"(?<first>\w)(\w*)'(\w+)").unwrap();
+        assert_eq!(r.regex_replace_all(&regex, "${3}'$2$first$$"), 5);
+        assert_eq!(
+            r,
+            "Hello there!  re'owH$ you doing?  s'tI$ a fine day, t'sni$ it?  \
+             t'renA$ you glad re'ew$ alive?  こんにちは、みんなさん！"
+        );
+        r.assert_integrity();
+        r.assert_invariants();
+
+        let mut r = Rope::from_str(TEXT);
+        let regex = Regex::new(r"\s+").unwrap();
+        assert_eq!(r.regex_replace_all(&regex, " "), 16);
+        assert_eq!(r.regex_find_iter(&Regex::new("  ").unwrap()).count(), 0);
+    }
+
+    #[test]
+    fn insert_at_byte_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert_at_byte(94, "み");
+        r.insert_at_byte(12, "!!");
+        r.insert_at_byte(0, "Oh, ");
+        assert_eq!(
+            r,
+            "Oh, Hello there!!!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こみんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_at_byte_02() {
+        let mut r = Rope::from_str(TEXT);
+        assert!(matches!(
+            r.try_insert_at_byte(101, "a"),
+            Err(Error::ByteIndexNotCharBoundary(101))
+        ));
+        assert!(matches!(
+            r.try_insert_at_byte(200, "a"),
+            Err(Error::ByteIndexOutOfBounds(200, 127))
+        ));
+        assert_eq!(r, TEXT);
+    }
+
+    #[test]
+    fn remove_bytes_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove_bytes(94..100);
+        r.remove_bytes(5..11);
+        r.remove_bytes(..1);
+        assert_eq!(
+            r,
+            "ello!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_bytes_02() {
+        let mut r = Rope::from_str(TEXT);
+        assert!(matches!(
+            r.try_remove_bytes(98..101),
+            Err(Error::ByteRangeNotCharBoundary(Some(98), Some(101)))
+        ));
+        assert!(matches!(
+            r.try_remove_bytes(99..),
+            Err(Error::ByteRangeNotCharBoundary(Some(99), None))
+        ));
+        assert!(matches!(
+            r.try_remove_bytes(..200),
+            Err(Error::ByteRangeOutOfBounds(None, Some(200), 127))
+        ));
+        #[allow(clippy::reversed_empty_ranges)]
+        let invalid = r.try_remove_bytes(6..5); // Wrong ordering of start/end on purpose.
+        assert!(matches!(invalid, Err(Error::ByteRangeInvalid(6, 5))));
+        assert_eq!(r, TEXT);
+    }
+
+    #[test]
+    fn replace_bytes_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.replace_bytes(91..109, "hi");
+        r.replace_bytes(0..5, "Howdy");
+        assert_eq!(
+            r,
+            "Howdy there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  hiみんなさん！"
+        );
+        assert!(r.try_replace_bytes(97..98, "").is_err());
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn replace_bytes_02() {
+        // Byte-indexed edits on multi-byte text with CRLF pairs, in a deep
+        // tree, checked against a `String`.
+        let mut r = Rope::new();
+        let mut s = String::new();
+        for i in 0..200 {
+            let text = ["a", "é", "\r", "\n", "こ", "\r\n", "😀"][i % 7];
+            r.insert(r.len_chars() / 2, text);
+            let idx = s
+                .char_indices()
+                .nth(s.chars().count() / 2)
+                .map_or(s.len(), |(i, _)| i);
+            s.insert_str(idx, text);
+        }
+
+        let mut seed = 7usize;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 8) % n
+        };
+        for i in 0..300 {
+            let mut start = next(s.len() + 1);
+            while !s.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end = (start + next(12)).min(s.len());
+            while !s.is_char_boundary(end) {
+                end += 1;
+            }
+            let text = ["", "x", "\n", "\r", "ñ\r\n", "日本"][i % 6];
+            match i % 3 {
+                0 => r.insert_at_byte(start, text),
+                1 => r.remove_bytes(start..end),
+                _ => r.replace_bytes(start..end, text),
+            }
+            match i % 3 {
+                0 => s.insert_str(start, text),
+                1 => s.replace_range(start..end, ""),
+                _ => s.replace_range(start..end, text),
+            }
+
+            assert_eq!(r, s.as_str());
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn split_off_at_byte_01() {
+        let mut r = Rope::from_str(TEXT);
+        let r2 = r.split_off_at_byte(109);
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、"
+        );
+        assert_eq!(r2, "みんなさん！");
+        assert!(r.try_split_off_at_byte(98).is_err());
+
+        r.assert_integrity();
+        r.assert_invariants();
+        r2.assert_integrity();
+        r2.assert_invariants();
+    }
+
     #[test]
     fn split_off_01() {
         let mut r = Rope::from_str(TEXT);
@@ -3245,6 +5419,96 @@ mod tests {
         r.byte_slice(96..);
     }
 
+    const TEXT_ASTRAL: &str = "Hello \u{1F600} world \u{1D11E}!";
+    // 16 chars, 18 utf16 code units
+
+    #[test]
+    fn insert_at_utf16_01() {
+        let mut r = Rope::from_str(TEXT_ASTRAL);
+        r.insert_at_utf16(17, "?");
+        r.insert_at_utf16(8, "big");
+        r.insert_at_utf16(0, "\u{1F600}");
+        assert_eq!(r, "\u{1F600}Hello \u{1F600}big world \u{1D11E}?!");
+
+        assert!(matches!(
+            r.try_insert_at_utf16(1, "a"),
+            Err(Error::Utf16IndexNotCharBoundary(1))
+        ));
+        assert!(matches!(
+            r.try_insert_at_utf16(100, "a"),
+            Err(Error::Utf16IndexOutOfBounds(100, 24))
+        ));
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_utf16_01() {
+        let mut r = Rope::from_str(TEXT_ASTRAL);
+        r.remove_utf16(15..17);
+        r.remove_utf16(5..9);
+        assert_eq!(r, "Helloworld !");
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_utf16_02() {
+        let mut r = Rope::from_str(TEXT_ASTRAL);
+        assert!(matches!(
+            r.try_remove_utf16(7..10),
+            Err(Error::Utf16IndexNotCharBoundary(7))
+        ));
+        assert!(matches!(
+            r.try_remove_utf16(..16),
+            Err(Error::Utf16IndexNotCharBoundary(16))
+        ));
+        assert!(matches!(
+            r.try_remove_utf16(3..19),
+            Err(Error::Utf16IndexOutOfBounds(19, 18))
+        ));
+        #[allow(clippy::reversed_empty_ranges)]
+        let invalid = r.try_remove_utf16(6..5); // Wrong ordering of start/end on purpose.
+        assert!(matches!(invalid, Err(Error::Utf16RangeInvalid(6, 5))));
+        assert_eq!(r, TEXT_ASTRAL);
+    }
+
+    #[test]
+    fn slice_utf16_01() {
+        let r = Rope::from_str(TEXT_ASTRAL);
+        assert_eq!(r.slice_utf16(6..8), "\u{1F600}");
+        assert_eq!(r.slice_utf16(9..14), "world");
+        assert_eq!(r.slice_utf16(15..), "\u{1D11E}!");
+        assert_eq!(r.slice_utf16(..), TEXT_ASTRAL);
+        assert!(r.get_slice_utf16(7..8).is_none());
+        assert!(r.get_slice_utf16(..19).is_none());
+
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r.slice_utf16(6..11), "there");
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_utf16_02() {
+        let r = Rope::from_str(TEXT_ASTRAL);
+        // Not a char boundary.
+        r.slice_utf16(16..);
+    }
+
+    #[test]
+    fn chars_at_utf16_01() {
+        let r = Rope::from_str(TEXT_ASTRAL);
+        let mut chars = r.chars_at_utf16(8);
+        assert_eq!(chars.next(), Some(' '));
+        assert_eq!(chars.prev(), Some(' '));
+        assert_eq!(chars.prev(), Some('\u{1F600}'));
+        assert_eq!(r.chars_at_utf16(18).next(), None);
+        assert!(r.get_chars_at_utf16(7).is_none());
+        assert!(r.get_chars_at_utf16(19).is_none());
+    }
+
     #[test]
     fn eq_rope_01() {
         let r = Rope::from_str("");
@@ -3451,5 +5715,128 @@ mod tests {
         assert!(!c1.is_instance(&c2));
     }
 
+    #[test]
+    fn line_break_mode_01() {
+        let text = "a\nb\rc\r\nd\u{2028}e\u{000B}f";
+        let mut r = Rope::from_str(text);
+        assert_eq!(r.line_break_mode(), LineBreakMode::default());
+
+        r.set_line_break_mode(LineBreakMode::Lf);
+        assert_eq!(r.len_lines(), 3);
+        assert_eq!(r.line_to_char(1), 2);
+        assert_eq!(r.line_to_char(2), 7);
+        assert_eq!(r.char_to_line(4), 1);
+        assert_eq!(r.line(1), "b\rc\r\n");
+        assert_eq!(
+            r.lines().collect::<Vec<_>>(),
+            ["a\n", "b\rc\r\n", "d\u{2028}e\u{000B}f"]
+        );
+
+        r.set_line_break_mode(LineBreakMode::Crlf);
+        assert_eq!(r.len_lines(), 4);
+        assert_eq!(r.line_to_char(2), 4);
+        assert_eq!(r.char_to_line(4), 2);
+        assert_eq!(r.line(1), "b\r");
+        assert_eq!(
+            r.lines().collect::<Vec<_>>(),
+            ["a\n", "b\r", "c\r\n", "d\u{2028}e\u{000B}f"]
+        );
+
+        r.set_line_break_mode(LineBreakMode::Unicode);
+        assert_eq!(r.len_lines(), 6);
+        assert_eq!(r.line_to_char(4), 9);
+        assert_eq!(r.line_to_char(5), 11);
+        assert_eq!(
+            r.lines().collect::<Vec<_>>(),
+            ["a\n", "b\r", "c\r\n", "d\u{2028}", "e\u{000B}", "f"]
+        );
+    }
+
+    #[test]
+    fn line_break_mode_02() {
+        // Slices, split-off parts, appends, and ropes created from slices
+        // all keep the mode.
+        let mut r = Rope::from_str(TEXT_LINES);
+        r.set_line_break_mode(LineBreakMode::Lf);
+        r.insert(10, "\r\u{2028}");
+
+        let s = r.slice(5..);
+        assert_eq!(s.line_break_mode(), LineBreakMode::Lf);
+        assert_eq!(s.line(0).line_break_mode(), LineBreakMode::Lf);
+        assert_eq!(s.len_lines(), 4);
+        assert_eq!(Rope::from(s).line_break_mode(), LineBreakMode::Lf);
+
+        let mut r2 = r.split_off(20);
+        assert_eq!(r2.line_break_mode(), LineBreakMode::Lf);
+        assert_eq!(r.len_lines(), 1);
+
+        let mut empty = Rope::new();
+        empty.set_line_break_mode(LineBreakMode::Unicode);
+        empty.append(r.clone());
+        assert_eq!(empty.line_break_mode(), LineBreakMode::Unicode);
+        assert_eq!(empty.len_lines(), 3);
+
+        r2.set_line_break_mode(LineBreakMode::Unicode);
+        r2.insert(0, "\u{0085}");
+        assert_eq!(r2.len_lines(), 5);
+    }
+
+    #[test]
+    fn line_break_mode_03() {
+        // Edits that split and join CRLF pairs across chunk boundaries.
+        let mut r = Rope::new();
+        let mut s = String::new();
+        let mut seed = 12345u32;
+        for i in 0..400 {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let len = s.chars().count();
+            let idx = (seed >> 8) as usize % (len + 1);
+            if i % 3 == 2 && len > 0 {
+                let end = (idx + 1 + (seed >> 20) as usize % 4).min(len);
+                let start = idx.min(end - 1);
+                r.remove(start..end);
+                let bs = char_to_byte_idx(&s, start);
+                let be = char_to_byte_idx(&s, end);
+                s.replace_range(bs..be, "");
+            } else {
+                let ins = ["\r", "\n", "\r\n", "a\r", "\nb", "\u{2028}"][(seed >> 16) as usize % 6];
+                r.insert(idx, ins);
+                s.insert_str(char_to_byte_idx(&s, idx), ins);
+            }
+            r.assert_integrity();
+
+            let len = r.len_chars();
+            let (start, end) = (idx.min(len) / 2, (idx + 7).min(len));
+            let sub = &s[char_to_byte_idx(&s, start)..char_to_byte_idx(&s, end)];
+            for &mode in &[
+                LineBreakMode::Lf,
+                LineBreakMode::Crlf,
+                LineBreakMode::Unicode,
+            ] {
+                r.set_line_break_mode(mode);
+                assert_eq!(r.len_lines(), mode.count_breaks(&s) + 1);
+                let lines: Vec<String> = r.lines().map(String::from).collect();
+                assert_eq!(lines.concat(), s);
+                assert_eq!(lines.len(), r.len_lines());
+                let mut rev: Vec<String> = r
+                    .lines_at(r.len_lines())
+                    .reversed()
+                    .map(String::from)
+                    .collect();
+                rev.reverse();
+                if !s.is_empty() {
+                    assert_eq!(rev, lines);
+                }
+
+                let slice = r.slice(start..end);
+                assert_eq!(slice.len_lines(), mode.count_breaks(sub) + 1);
+                let lines: Vec<String> = slice.lines().map(String::from).collect();
+                assert_eq!(lines.concat(), sub);
+                assert_eq!(lines.len(), slice.len_lines());
+            }
+        }
+        r.assert_invariants();
+    }
+
     // Iterator tests are in the iter module
 }
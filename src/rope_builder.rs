@@ -4,7 +4,8 @@ use smallvec::SmallVec;
 
 use crate::crlf;
 use crate::rope::Rope;
-use crate::tree::{Node, NodeChildren, NodeText, MAX_BYTES, MAX_CHILDREN, MIN_BYTES};
+use crate::str_utils::LineBreakMode;
+use crate::tree::{Node, NodeChildren, NodeText, Unit, MAX_BYTES, MAX_CHILDREN, MIN_BYTES};
 
 /// An efficient incremental `Rope` builder.
 ///
@@ -167,6 +168,8 @@ impl RopeBuilder {
         // Create the rope.
         let mut rope = Rope {
             root: self.stack.pop().unwrap(),
+            line_break_mode: LineBreakMode::default(),
+            metric: None,
         };
 
         // Fix up the tree to be well-formed.
@@ -178,7 +181,7 @@ impl RopeBuilder {
                 // Merge the last chunk if it was too small.
                 let idx = rope.len_chars()
                     - rope.byte_to_char(rope.len_bytes() - self.last_chunk_len_bytes);
-                Arc::make_mut(&mut rope.root).fix_tree_seam(idx);
+                Arc::make_mut(&mut rope.root).fix_tree_seam(idx, Unit::Char);
             }
             rope.pull_up_singular_nodes();
         }
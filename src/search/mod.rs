@@ -0,0 +1,862 @@
+//! Searching a `Rope`'s text.
+//!
+//! The search methods on `Rope` and `RopeSlice` (`find()`, `rfind()`, and
+//! `matches()`) stream over the text's chunks, so matches that span chunk
+//! boundaries are found without copying the text.  They run in linear time
+//! with respect to the length of the searched text, and report each match
+//! as a [`Match`] with both its byte and char range.
+//!
+//! Case-insensitive and whole-word searches can be done with the `_with()`
+//! variants of those methods, which take [`SearchOptions`].
+//!
+//! For scanning char by char, e.g. to jump to the next non-whitespace
+//! char, there are `find_char_from()` and `position_where()`.
+//!
+//! To search for many literals at once, build a [`MultiPattern`] and use
+//! `multi_find_iter()` or `multi_find_overlapping_iter()`.
+
+use std::ops::Range;
+
+use crate::iter::{Chars, Chunks};
+use crate::slice::RopeSlice;
+use crate::{Error, Result};
+
+mod multi;
+
+pub use self::multi::{MultiMatches, MultiMatchesOverlapping, MultiPattern};
+
+/// A match found by a search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Match {
+    /// The byte range of the match.
+    pub byte_range: Range<usize>,
+    /// The char range of the match.
+    pub char_range: Range<usize>,
+}
+
+/// Options for the `find_with()`, `rfind_with()`, and `matches_with()`
+/// search methods.
+///
+/// The default options search for the pattern exactly as given, which is
+/// what `find()`, `rfind()`, and `matches()` do.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    /// Ignore case differences, using Unicode simple case folding.
+    ///
+    /// Simple case folding maps each char to a single char, so the text
+    /// matched always has as many chars as the pattern (though possibly
+    /// a different number of bytes).
+    pub case_insensitive: bool,
+    /// Only report matches that start and end on a word boundary, where a
+    /// word char is an alphanumeric char or `_`.
+    pub whole_word: bool,
+}
+
+impl SearchOptions {
+    /// Creates `SearchOptions` with everything turned off.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to ignore case differences.
+    #[inline]
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Sets whether to only report whole-word matches.
+    #[inline]
+    pub fn whole_word(mut self, yes: bool) -> Self {
+        self.whole_word = yes;
+        self
+    }
+}
+
+/// An iterator over the non-overlapping matches of a pattern, from left
+/// to right.
+///
+/// Created by `Rope::matches()`, `RopeSlice::matches()`, and their
+/// `matches_with()` counterparts.  Like `str::matches()`, an empty pattern
+/// matches at every char boundary (or, with
+/// [`whole_word`](SearchOptions::whole_word), at every word boundary).
+#[derive(Debug, Clone)]
+pub struct Matches<'a> {
+    text: RopeSlice<'a>,
+    whole_word: bool,
+    inner: MatchesInner<'a>,
+}
+
+#[derive(Debug, Clone)]
+enum MatchesInner<'a> {
+    Exact(ExactMatches<'a>),
+    Folded(FoldedMatches<'a>),
+}
+
+impl<'a> Matches<'a> {
+    pub(crate) fn new(text: RopeSlice<'a>, pattern: &str, options: SearchOptions) -> Matches<'a> {
+        let inner = if options.case_insensitive && !pattern.is_empty() {
+            MatchesInner::Folded(FoldedMatches::new(text, pattern))
+        } else {
+            MatchesInner::Exact(ExactMatches::new(text, pattern))
+        };
+        Matches {
+            text: text,
+            whole_word: options.whole_word,
+            inner: inner,
+        }
+    }
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            let m = match self.inner {
+                MatchesInner::Exact(ref mut inner) => inner.next()?,
+                MatchesInner::Folded(ref mut inner) => inner.next()?,
+            };
+            if !self.whole_word || is_whole_word(self.text, &m.char_range) {
+                return Some(m);
+            }
+
+            // Rejected, so a match overlapping it may still be accepted.
+            match self.inner {
+                MatchesInner::Exact(ref mut inner) => inner.resume_overlapping(),
+                MatchesInner::Folded(ref mut inner) => inner.resume_overlapping(),
+            }
+        }
+    }
+}
+
+/// Finds exact matches of a pattern, one byte at a time.
+#[derive(Debug, Clone)]
+struct ExactMatches<'a> {
+    chunks: Chunks<'a>,
+    chunk: &'a [u8],
+    chunk_i: usize,
+    byte_idx: usize, // Byte index of `chunk[chunk_i]` in the text.
+    char_idx: usize, // Char index of `chunk[chunk_i]` in the text.
+    pattern: Pattern<u8>,
+    pattern_char_len: usize,
+    state: usize,
+    done: bool,
+}
+
+impl<'a> ExactMatches<'a> {
+    fn new(text: RopeSlice<'a>, pattern: &str) -> ExactMatches<'a> {
+        ExactMatches {
+            chunks: text.chunks(),
+            chunk: &[],
+            chunk_i: 0,
+            byte_idx: 0,
+            char_idx: 0,
+            pattern: Pattern::new(pattern.as_bytes().to_vec()),
+            pattern_char_len: pattern.chars().count(),
+            state: 0,
+            done: false,
+        }
+    }
+
+    /// Continues the search from within the last match, as if it hadn't
+    /// been a match.
+    #[inline]
+    fn resume_overlapping(&mut self) {
+        if let Some(&state) = self.pattern.failure.last() {
+            self.state = state;
+        }
+    }
+
+    /// Moves on to the next chunk, returning false if there isn't one.
+    #[inline]
+    fn next_chunk(&mut self) -> bool {
+        if let Some(chunk) = self.chunks.next() {
+            self.chunk = chunk.as_bytes();
+            self.chunk_i = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// `next()` for empty patterns, which match at every char boundary.
+    fn next_empty(&mut self) -> Option<Match> {
+        if self.done {
+            return None;
+        }
+        let m = Match {
+            byte_range: self.byte_idx..self.byte_idx,
+            char_range: self.char_idx..self.char_idx,
+        };
+
+        // Step over one char.
+        while self.chunk_i >= self.chunk.len() {
+            if !self.next_chunk() {
+                self.done = true;
+                return Some(m);
+            }
+        }
+        let len = utf8_len(self.chunk[self.chunk_i]);
+        self.chunk_i += len;
+        self.byte_idx += len;
+        self.char_idx += 1;
+
+        Some(m)
+    }
+}
+
+impl<'a> Iterator for ExactMatches<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        if self.pattern.items.is_empty() {
+            return self.next_empty();
+        }
+
+        loop {
+            if self.chunk_i >= self.chunk.len() {
+                if self.next_chunk() {
+                    continue;
+                } else {
+                    return None;
+                }
+            }
+
+            if self.state == 0 {
+                // Skip ahead to the next byte that can start a match.
+                let rest = &self.chunk[self.chunk_i..];
+                let first = self.pattern.items[0];
+                let skip = rest.iter().position(|&b| b == first).unwrap_or(rest.len());
+                self.chunk_i += skip;
+                self.byte_idx += skip;
+                self.char_idx += count_char_starts(&rest[..skip]);
+                if skip == rest.len() {
+                    continue;
+                }
+            }
+
+            let byte = self.chunk[self.chunk_i];
+            self.chunk_i += 1;
+            self.byte_idx += 1;
+            if is_char_start(byte) {
+                self.char_idx += 1;
+            }
+
+            self.state = self.pattern.step(self.state, byte);
+            if self.state == self.pattern.items.len() {
+                self.state = 0;
+                return Some(Match {
+                    byte_range: (self.byte_idx - self.pattern.items.len())..self.byte_idx,
+                    char_range: (self.char_idx - self.pattern_char_len)..self.char_idx,
+                });
+            }
+        }
+    }
+}
+
+/// Finds case-insensitive matches of a non-empty pattern, one char at a
+/// time.
+#[derive(Debug, Clone)]
+struct FoldedMatches<'a> {
+    text: RopeSlice<'a>,
+    chars: Chars<'a>,
+    char_idx: usize, // Char index of the next char in `chars`.
+    pattern: Pattern<char>,
+    state: usize,
+}
+
+impl<'a> FoldedMatches<'a> {
+    fn new(text: RopeSlice<'a>, pattern: &str) -> FoldedMatches<'a> {
+        FoldedMatches {
+            text: text,
+            chars: text.chars(),
+            char_idx: 0,
+            pattern: Pattern::new(pattern.chars().map(fold_case).collect()),
+            state: 0,
+        }
+    }
+
+    /// Continues the search from within the last match, as if it hadn't
+    /// been a match.
+    #[inline]
+    fn resume_overlapping(&mut self) {
+        self.state = *self.pattern.failure.last().unwrap();
+    }
+}
+
+impl<'a> Iterator for FoldedMatches<'a> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        for c in &mut self.chars {
+            self.char_idx += 1;
+            self.state = self.pattern.step(self.state, fold_case(c));
+            if self.state == self.pattern.items.len() {
+                self.state = 0;
+                let start = self.char_idx - self.pattern.items.len();
+                return Some(char_range_match(self.text, start..self.char_idx));
+            }
+        }
+        None
+    }
+}
+
+/// Shared implementation of `rfind()` for `Rope` and `RopeSlice`.
+pub(crate) fn rfind(text: RopeSlice, pattern: &str) -> Option<Match> {
+    let pattern_char_len = pattern.chars().count();
+    let pattern = Pattern::new(pattern.bytes().rev().collect());
+    let len = pattern.items.len();
+
+    let mut byte_idx = text.len_bytes();
+    let mut char_idx = text.len_chars();
+    if len == 0 {
+        return Some(Match {
+            byte_range: byte_idx..byte_idx,
+            char_range: char_idx..char_idx,
+        });
+    }
+
+    let mut chunks = text.chunks_at_byte(byte_idx).0;
+    let mut state = 0;
+    while let Some(chunk) = chunks.prev() {
+        for &byte in chunk.as_bytes().iter().rev() {
+            byte_idx -= 1;
+            if is_char_start(byte) {
+                char_idx -= 1;
+            }
+
+            state = pattern.step(state, byte);
+            if state == len {
+                return Some(Match {
+                    byte_range: byte_idx..(byte_idx + len),
+                    char_range: char_idx..(char_idx + pattern_char_len),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Shared implementation of `rfind_with()` for `Rope` and `RopeSlice`.
+pub(crate) fn rfind_with(text: RopeSlice, pattern: &str, options: SearchOptions) -> Option<Match> {
+    if !options.case_insensitive && !options.whole_word {
+        return rfind(text, pattern);
+    }
+
+    let fold = |c| {
+        if options.case_insensitive {
+            fold_case(c)
+        } else {
+            c
+        }
+    };
+    let pattern = Pattern::new(pattern.chars().rev().map(fold).collect());
+    let len = pattern.items.len();
+    let is_accepted = |range: &Range<usize>| !options.whole_word || is_whole_word(text, range);
+
+    let mut char_idx = text.len_chars();
+    let mut chars = text.chars_at(char_idx);
+    if len == 0 {
+        loop {
+            if is_accepted(&(char_idx..char_idx)) {
+                return Some(char_range_match(text, char_idx..char_idx));
+            }
+            chars.prev()?;
+            char_idx -= 1;
+        }
+    }
+
+    let mut state = 0;
+    while let Some(c) = chars.prev() {
+        char_idx -= 1;
+        state = pattern.step(state, fold(c));
+        if state == len {
+            let range = char_idx..(char_idx + len);
+            if is_accepted(&range) {
+                return Some(char_range_match(text, range));
+            }
+            state = pattern.failure[len - 1];
+        }
+    }
+
+    None
+}
+
+/// The direction to scan in, for `find_char_from()` and
+/// `position_where()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Towards the end of the text, starting with the char at the given
+    /// index.
+    Forward,
+    /// Towards the start of the text, starting with the char before the
+    /// given index.
+    Backward,
+}
+
+/// Shared implementation of `position_where()` for `Rope` and `RopeSlice`.
+pub(crate) fn position_where<F>(
+    text: RopeSlice,
+    char_idx: usize,
+    direction: Direction,
+    limit: Option<usize>,
+    predicate: F,
+) -> Result<Option<usize>>
+where
+    F: FnMut(char) -> bool,
+{
+    let chars = text
+        .get_chars_at(char_idx)
+        .ok_or_else(|| Error::CharIndexOutOfBounds(char_idx, text.len_chars()))?;
+    let limit = limit.unwrap_or(usize::MAX);
+
+    Ok(match direction {
+        Direction::Forward => chars.take(limit).position(predicate).map(|i| char_idx + i),
+        Direction::Backward => chars
+            .reversed()
+            .take(limit)
+            .position(predicate)
+            .map(|i| char_idx - i - 1),
+    })
+}
+
+//-------------------------------------------------------------
+
+/// A search pattern, with its Knuth-Morris-Pratt failure table.
+#[derive(Debug, Clone)]
+struct Pattern<T> {
+    items: Vec<T>,
+    /// For each prefix `items[..=i]`, the length of its longest proper
+    /// prefix that's also a suffix.
+    failure: Vec<usize>,
+}
+
+impl<T: Copy + PartialEq> Pattern<T> {
+    fn new(items: Vec<T>) -> Pattern<T> {
+        let mut failure = vec![0; items.len()];
+        let mut k = 0;
+        for i in 1..items.len() {
+            while k > 0 && items[i] != items[k] {
+                k = failure[k - 1];
+            }
+            if items[i] == items[k] {
+                k += 1;
+            }
+            failure[i] = k;
+        }
+
+        Pattern {
+            items: items,
+            failure: failure,
+        }
+    }
+
+    /// Returns the new match state after feeding `item` in state `state`.
+    ///
+    /// The state is the length of the pattern prefix matched so far, and
+    /// must be less than the pattern's length.
+    #[inline]
+    fn step(&self, mut state: usize, item: T) -> usize {
+        while state > 0 && self.items[state] != item {
+            state = self.failure[state - 1];
+        }
+        if self.items[state] == item {
+            state + 1
+        } else {
+            0
+        }
+    }
+}
+
+/// Returns the `Match` for the given char range of `text`.
+fn char_range_match(text: RopeSlice, char_range: Range<usize>) -> Match {
+    Match {
+        byte_range: text.char_to_byte(char_range.start)..text.char_to_byte(char_range.end),
+        char_range: char_range,
+    }
+}
+
+/// Returns whether the given char range of `text` starts and ends on word
+/// boundaries.
+fn is_whole_word(text: RopeSlice, char_range: &Range<usize>) -> bool {
+    let is_boundary = |char_idx| {
+        let mut chars = text.chars_at(char_idx);
+        let before = chars.prev();
+        if before.is_some() {
+            chars.next();
+        }
+        let after = chars.next();
+        matches!(before, Some(c) if is_word_char(c)) != matches!(after, Some(c) if is_word_char(c))
+    };
+    is_boundary(char_range.start) && is_boundary(char_range.end)
+}
+
+/// Returns whether `c` is a word char, for whole-word search.
+#[inline]
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the Unicode simple case folding of `c`.
+///
+/// This is derived from the standard library's case mappings: a char is
+/// folded to the lowercase of its uppercase, so that e.g. 'ς' and 'σ' fold
+/// to the same char, as long as both mappings are to a single char.
+fn fold_case(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase();
+    }
+
+    let upper = single_char(c.to_uppercase()).unwrap_or(c);
+    single_char(upper.to_lowercase())
+        .or_else(|| single_char(c.to_lowercase()))
+        .unwrap_or(c)
+}
+
+/// Returns the only char of `chars`, if it has exactly one.
+#[inline]
+fn single_char<I: Iterator<Item = char>>(mut chars: I) -> Option<char> {
+    let c = chars.next()?;
+    if chars.next().is_none() {
+        Some(c)
+    } else {
+        None
+    }
+}
+
+/// Returns whether `byte` is the first byte of a utf8-encoded char.
+#[inline]
+fn is_char_start(byte: u8) -> bool {
+    (byte & 0xC0) != 0x80
+}
+
+/// Counts the chars starting within `bytes`.
+#[inline]
+fn count_char_starts(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| is_char_start(b)).count()
+}
+
+/// Returns the length of the utf8-encoded char starting with `byte`.
+#[inline]
+fn utf8_len(byte: u8) -> usize {
+    match byte {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        _ => 4,
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rope;
+
+    const TEXT: &str = "Hello there!  How're you doing?  It's \
+                        a fine day, isn't it?  Aren't you glad \
+                        we're alive?  こんにちは、みんなさん！";
+
+    /// All the matches of `pattern` in `text`, according to std.
+    fn std_matches(text: &str, pattern: &str) -> Vec<Match> {
+        text.match_indices(pattern)
+            .map(|(i, m)| {
+                let start = text[..i].chars().count();
+                Match {
+                    byte_range: i..(i + m.len()),
+                    char_range: start..(start + m.chars().count()),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_01() {
+        let r = Rope::from_str(TEXT);
+        for &pattern in [
+            "e",
+            "you",
+            "  ",
+            "'re",
+            "ちは、み",
+            "！",
+            "？",
+            TEXT,
+            "isn't it? ",
+        ]
+        .iter()
+        {
+            let found: Vec<_> = r.matches(pattern).collect();
+            assert_eq!(found, std_matches(TEXT, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn matches_02() {
+        // Repetitive text and patterns, which exercise the failure table.
+        let text = "aabaabaaabaabaabaaabaaaab".repeat(5);
+        let r = Rope::from_str(&text);
+        for &pattern in ["aab", "aabaab", "abaaab", "aaaa", "baaabaa", "aa"].iter() {
+            let found: Vec<_> = r.matches(pattern).collect();
+            assert_eq!(found, std_matches(&text, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn matches_03() {
+        // Slices that start and end mid-chunk.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(11..97);
+        let text = s.to_string();
+        for &pattern in ["e", "! ", "you", "は、み"].iter() {
+            let found: Vec<_> = s.matches(pattern).collect();
+            assert_eq!(found, std_matches(&text, pattern), "{:?}", pattern);
+        }
+    }
+
+    #[test]
+    fn matches_04() {
+        let r = Rope::from_str("aこb");
+        let found: Vec<_> = r.matches("").map(|m| m.char_range.start).collect();
+        assert_eq!(found, [0, 1, 2, 3]);
+        let found: Vec<_> = r.matches("").map(|m| m.byte_range.start).collect();
+        assert_eq!(found, [0, 1, 4, 5]);
+        assert_eq!(Rope::new().matches("").count(), 1);
+        assert_eq!(Rope::new().matches("a").count(), 0);
+    }
+
+    #[test]
+    fn find_01() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(
+            r.find("you"),
+            Some(Match {
+                byte_range: 21..24,
+                char_range: 21..24,
+            })
+        );
+        assert_eq!(
+            r.rfind("you"),
+            Some(Match {
+                byte_range: 68..71,
+                char_range: 68..71,
+            })
+        );
+        assert_eq!(
+            r.find("みん"),
+            Some(Match {
+                byte_range: 109..115,
+                char_range: 97..99,
+            })
+        );
+        assert_eq!(r.rfind("みん"), r.find("みん"));
+        assert_eq!(r.find("nope"), None);
+        assert_eq!(r.rfind("nope"), None);
+        assert_eq!(r.find("").unwrap().char_range, 0..0);
+        assert_eq!(r.rfind("").unwrap().char_range, 103..103);
+    }
+
+    #[test]
+    fn rfind_01() {
+        let text = "aabaabaaabaabaabaaabaaaab".repeat(5);
+        let r = Rope::from_str(&text);
+        let s = r.slice(3..100);
+        let s_text = s.to_string();
+        for &pattern in ["aab", "aabaab", "abaaab", "aaaa", "baaabaa", "ba"].iter() {
+            let expected = s_text.rfind(pattern).map(|i| i..(i + pattern.len()));
+            assert_eq!(s.rfind(pattern).map(|m| m.byte_range), expected);
+        }
+    }
+
+    /// All the char ranges of case-insensitive matches of `pattern` in
+    /// `text`, found by folding the whole text.
+    fn folded_matches(text: &str, pattern: &str) -> Vec<Range<usize>> {
+        let text: Vec<char> = text.chars().map(fold_case).collect();
+        let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+        let mut found = Vec::new();
+        let mut i = 0;
+        while i + pattern.len() <= text.len() {
+            if text[i..(i + pattern.len())] == pattern[..] {
+                found.push(i..(i + pattern.len()));
+                i += pattern.len();
+            } else {
+                i += 1;
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn matches_with_01() {
+        let text = "Straße STRASSE Σίσυφος ΣΊΣΥΦΟΣ \u{212A}elvin kelvin AaAaA";
+        let r = Rope::from_str(text);
+        let options = SearchOptions::new().case_insensitive(true);
+        for &pattern in ["straße", "σίσυφοσ", "Kelvin", "aa", "aAa", "s"].iter() {
+            let found: Vec<_> = r.matches_with(pattern, options).collect();
+            assert_eq!(
+                found
+                    .iter()
+                    .map(|m| m.char_range.clone())
+                    .collect::<Vec<_>>(),
+                folded_matches(text, pattern),
+                "{:?}",
+                pattern
+            );
+            for m in found.iter() {
+                assert_eq!(m.byte_range.start, r.char_to_byte(m.char_range.start));
+                assert_eq!(m.byte_range.end, r.char_to_byte(m.char_range.end));
+            }
+        }
+        assert_eq!(r.matches_with("Kelvin", options).count(), 2);
+        assert_eq!(r.matches_with("STRASSE", options).count(), 1);
+    }
+
+    #[test]
+    fn matches_with_02() {
+        let r = Rope::from_str("cat concat cat_ cat. (cat)category cat");
+        let options = SearchOptions::new().whole_word(true);
+        let found: Vec<_> = r
+            .matches_with("cat", options)
+            .map(|m| m.char_range)
+            .collect();
+        assert_eq!(found, [0..3, 16..19, 22..25, 35..38]);
+
+        // Rejected matches don't hide overlapping ones.
+        let r = Rope::from_str("aaa aa a");
+        let found: Vec<_> = r
+            .matches_with("aa", options)
+            .map(|m| m.char_range)
+            .collect();
+        assert_eq!(found, vec![4..6]);
+        let found: Vec<_> = r
+            .matches_with("aa a", options)
+            .map(|m| m.char_range)
+            .collect();
+        assert_eq!(found, vec![4..8]);
+
+        // Empty patterns match at word boundaries.
+        let found: Vec<_> = r
+            .matches_with("", options)
+            .map(|m| m.char_range.start)
+            .collect();
+        assert_eq!(found, [0, 3, 4, 6, 7, 8]);
+    }
+
+    #[test]
+    fn matches_with_03() {
+        // Both options, on a slice.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(13..);
+        let options = SearchOptions::new().case_insensitive(true).whole_word(true);
+        let found: Vec<_> = s
+            .matches_with("YOU", options)
+            .map(|m| m.char_range)
+            .collect();
+        assert_eq!(found, [8..11, 55..58]);
+        assert_eq!(s.find_with("it", options).unwrap().char_range, 20..22);
+        assert_eq!(s.rfind_with("it", options).unwrap().char_range, 43..45);
+        assert_eq!(s.find_with("OW", options), None);
+        assert_eq!(
+            s.find_with("こんにちは", options).unwrap().char_range,
+            78..83
+        );
+    }
+
+    #[test]
+    fn rfind_with_01() {
+        let r = Rope::from_str("Cat concat CAT_ cAt. catalog");
+        let both = SearchOptions::new().case_insensitive(true).whole_word(true);
+        let ci = SearchOptions::new().case_insensitive(true);
+        let ww = SearchOptions::new().whole_word(true);
+        assert_eq!(r.rfind_with("cat", both).unwrap().char_range, 16..19);
+        assert_eq!(r.rfind_with("CAT", ci).unwrap().char_range, 21..24);
+        assert_eq!(r.rfind_with("Cat", ww).unwrap().char_range, 0..3);
+        assert_eq!(r.rfind_with("cat", ww), None);
+        assert_eq!(r.rfind_with("", ww).unwrap().char_range, 28..28);
+        assert_eq!(r.rfind_with("", both).unwrap().char_range, 28..28);
+        assert_eq!(r.rfind_with("g", ww), None);
+
+        // Rejected matches don't hide overlapping ones.
+        let r = Rope::from_str("a aa aaa");
+        assert_eq!(r.rfind_with("aa", ww).unwrap().char_range, 2..4);
+        assert_eq!(
+            r.rfind_with("AA", both),
+            Some(Match {
+                byte_range: 2..4,
+                char_range: 2..4,
+            })
+        );
+    }
+
+    #[test]
+    fn position_where_01() {
+        let r = Rope::from_str("  foo(\"bar\", 42)  ");
+        let s = r.slice(1..);
+        let not_space = |c: char| !c.is_whitespace();
+
+        assert_eq!(r.find_char_from(0, Direction::Forward, not_space), Some(2));
+        assert_eq!(r.find_char_from(2, Direction::Forward, not_space), Some(2));
+        assert_eq!(
+            r.find_char_from(18, Direction::Backward, not_space),
+            Some(15)
+        );
+        assert_eq!(r.find_char_from(2, Direction::Backward, not_space), None);
+        assert_eq!(
+            r.find_char_from(7, Direction::Forward, |c| c == '"'),
+            Some(10)
+        );
+        assert_eq!(
+            r.find_char_from(7, Direction::Backward, |c| c == '"'),
+            Some(6)
+        );
+        assert_eq!(r.find_char_from(18, Direction::Forward, not_space), None);
+        assert_eq!(
+            s.find_char_from(17, Direction::Backward, |c| c.is_ascii_digit()),
+            Some(13)
+        );
+        assert_eq!(
+            s.find_char_from(0, Direction::Forward, |c| c == '('),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn position_where_02() {
+        let r = Rope::from_str("  foo(\"bar\", 42)  ");
+        let digit = |c: char| c.is_ascii_digit();
+
+        assert_eq!(
+            r.position_where(0, Direction::Forward, Some(13), digit),
+            None
+        );
+        assert_eq!(
+            r.position_where(0, Direction::Forward, Some(14), digit),
+            Some(13)
+        );
+        assert_eq!(
+            r.position_where(18, Direction::Backward, Some(3), digit),
+            None
+        );
+        assert_eq!(
+            r.position_where(18, Direction::Backward, Some(4), digit),
+            Some(14)
+        );
+        assert_eq!(
+            r.position_where(5, Direction::Forward, Some(0), |_| true),
+            None
+        );
+        assert_eq!(
+            r.position_where(5, Direction::Backward, None, |_| true),
+            Some(4)
+        );
+        assert!(matches!(
+            r.try_position_where(19, Direction::Forward, None, digit),
+            Err(Error::CharIndexOutOfBounds(19, 18))
+        ));
+        assert!(r.try_find_char_from(18, Direction::Backward, digit).is_ok());
+    }
+}
@@ -0,0 +1,490 @@
+use crate::iter::Chunks;
+use crate::slice::RopeSlice;
+
+use super::{count_char_starts, is_char_start, Match};
+
+const ROOT: u32 = 0;
+const NONE: u32 = u32::MAX;
+
+/// A set of literal patterns to search for all at once.
+///
+/// This is an Aho-Corasick automaton, so searching for thousands of
+/// patterns takes about as long as searching for one.  Patterns are
+/// identified by their index in the list the set was created from.  Empty
+/// patterns never match.
+///
+/// Searches are done with the `multi_find_iter()` and
+/// `multi_find_overlapping_iter()` methods of `Rope` and `RopeSlice`.
+#[derive(Debug, Clone)]
+pub struct MultiPattern {
+    states: Vec<State>,
+    root_trans: Box<[u32; 256]>,
+    pattern_lens: Vec<(usize, usize)>, // (bytes, chars) of each pattern.
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    trans: Vec<(u8, u32)>, // Sorted by byte.
+    fail: u32,
+    depth: usize,
+    pattern: u32, // The pattern ending at this state, if any.
+    out: u32,     // The deepest state on the failure chain that ends a pattern.
+}
+
+impl MultiPattern {
+    /// Creates a `MultiPattern` from a list of patterns.
+    ///
+    /// If a pattern appears more than once, matches are reported for its
+    /// first appearance.
+    ///
+    /// Runs in O(M) time, where M is the total length of the patterns.
+    pub fn new<I, P>(patterns: I) -> MultiPattern
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<str>,
+    {
+        let mut states = vec![State::new(0)];
+        let mut pattern_lens = Vec::new();
+
+        // Build the trie.
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            let id = pattern_lens.len() as u32;
+            pattern_lens.push((pattern.len(), count_char_starts(pattern.as_bytes())));
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut s = ROOT;
+            for (depth, &byte) in pattern.as_bytes().iter().enumerate() {
+                s = match states[s as usize].find_trans(byte) {
+                    Ok(i) => states[s as usize].trans[i].1,
+                    Err(i) => {
+                        let next = states.len() as u32;
+                        states[s as usize].trans.insert(i, (byte, next));
+                        states.push(State::new(depth + 1));
+                        next
+                    }
+                };
+            }
+            if states[s as usize].pattern == NONE {
+                states[s as usize].pattern = id;
+            }
+        }
+
+        let mut root_trans = Box::new([ROOT; 256]);
+        for &(byte, s) in states[0].trans.iter() {
+            root_trans[byte as usize] = s;
+        }
+
+        // Fill in the failure and output links, breadth first so that the
+        // links of shallower states are always done first.
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back(ROOT);
+        while let Some(s) = queue.pop_front() {
+            for i in 0..states[s as usize].trans.len() {
+                let (byte, child) = states[s as usize].trans[i];
+                let fail = if s == ROOT {
+                    ROOT
+                } else {
+                    let mut f = states[s as usize].fail;
+                    loop {
+                        if let Ok(j) = states[f as usize].find_trans(byte) {
+                            break states[f as usize].trans[j].1;
+                        } else if f == ROOT {
+                            break ROOT;
+                        }
+                        f = states[f as usize].fail;
+                    }
+                };
+
+                let fail_out = states[fail as usize].out;
+                let child_state = &mut states[child as usize];
+                child_state.fail = fail;
+                child_state.out = if child_state.pattern != NONE {
+                    child
+                } else {
+                    fail_out
+                };
+                queue.push_back(child);
+            }
+        }
+
+        MultiPattern {
+            states: states,
+            root_trans: root_trans,
+            pattern_lens: pattern_lens,
+        }
+    }
+
+    /// Returns the number of patterns, including empty and repeated ones.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pattern_lens.len()
+    }
+
+    /// Returns whether there are no patterns.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pattern_lens.is_empty()
+    }
+
+    #[inline]
+    fn next_state(&self, mut s: u32, byte: u8) -> u32 {
+        loop {
+            if s == ROOT {
+                return self.root_trans[byte as usize];
+            }
+            let state = &self.states[s as usize];
+            if let Ok(i) = state.find_trans(byte) {
+                return state.trans[i].1;
+            }
+            s = state.fail;
+        }
+    }
+
+    /// Returns the match of the pattern that ends at state `s`, where the
+    /// match ends at the given byte and char indices.
+    #[inline]
+    fn match_at(&self, s: u32, byte_end: usize, char_end: usize) -> (usize, Match) {
+        let pattern = self.states[s as usize].pattern as usize;
+        let (len_bytes, len_chars) = self.pattern_lens[pattern];
+        (
+            pattern,
+            Match {
+                byte_range: (byte_end - len_bytes)..byte_end,
+                char_range: (char_end - len_chars)..char_end,
+            },
+        )
+    }
+}
+
+impl State {
+    fn new(depth: usize) -> State {
+        State {
+            trans: Vec::new(),
+            fail: ROOT,
+            depth: depth,
+            pattern: NONE,
+            out: NONE,
+        }
+    }
+
+    #[inline]
+    fn find_trans(&self, byte: u8) -> Result<usize, usize> {
+        self.trans.binary_search_by_key(&byte, |t| t.0)
+    }
+}
+
+/// Feeds the bytes of a `RopeSlice` through a `MultiPattern`'s automaton.
+#[derive(Debug, Clone)]
+struct Scanner<'a> {
+    text: RopeSlice<'a>,
+    chunks: Chunks<'a>,
+    chunk: &'a [u8],
+    chunk_i: usize,
+    byte_idx: usize, // Byte index of `chunk[chunk_i]` in the text.
+    char_idx: usize, // Char index of `chunk[chunk_i]` in the text.
+    state: u32,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: RopeSlice<'a>) -> Scanner<'a> {
+        Scanner {
+            text: text,
+            chunks: text.chunks(),
+            chunk: &[],
+            chunk_i: 0,
+            byte_idx: 0,
+            char_idx: 0,
+            state: ROOT,
+        }
+    }
+
+    /// Feeds the next byte to the automaton, returning false if there are
+    /// none left.
+    #[inline]
+    fn step(&mut self, patterns: &MultiPattern) -> bool {
+        while self.chunk_i >= self.chunk.len() {
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.chunk = chunk.as_bytes();
+                    self.chunk_i = 0;
+                }
+                None => return false,
+            }
+        }
+
+        let byte = self.chunk[self.chunk_i];
+        self.chunk_i += 1;
+        self.byte_idx += 1;
+        if is_char_start(byte) {
+            self.char_idx += 1;
+        }
+        self.state = patterns.next_state(self.state, byte);
+        true
+    }
+
+    /// Moves back to the given byte and char index, and resets the
+    /// automaton to its start state.
+    fn restart_at(&mut self, byte_idx: usize, char_idx: usize) {
+        let (mut chunks, chunk_byte_idx, _, _) = self.text.chunks_at_byte(byte_idx);
+        match chunks.next() {
+            Some(chunk) => {
+                self.chunk = chunk.as_bytes();
+                self.chunk_i = byte_idx - chunk_byte_idx;
+            }
+            None => {
+                self.chunk = &[];
+                self.chunk_i = 0;
+            }
+        }
+        self.chunks = chunks;
+        self.byte_idx = byte_idx;
+        self.char_idx = char_idx;
+        self.state = ROOT;
+    }
+}
+
+/// An iterator over the leftmost-longest non-overlapping matches of a
+/// [`MultiPattern`], from left to right.
+///
+/// Of the matches that start furthest left, the longest is yielded, and
+/// the search continues from its end.  Yields the pattern index along with
+/// each match.
+///
+/// Created by `Rope::multi_find_iter()` and `RopeSlice::multi_find_iter()`.
+#[derive(Debug, Clone)]
+pub struct MultiMatches<'a, 'p> {
+    patterns: &'p MultiPattern,
+    scanner: Scanner<'a>,
+}
+
+impl<'a, 'p> MultiMatches<'a, 'p> {
+    pub(crate) fn new(text: RopeSlice<'a>, patterns: &'p MultiPattern) -> MultiMatches<'a, 'p> {
+        MultiMatches {
+            patterns: patterns,
+            scanner: Scanner::new(text),
+        }
+    }
+}
+
+impl<'a, 'p> Iterator for MultiMatches<'a, 'p> {
+    type Item = (usize, Match);
+
+    fn next(&mut self) -> Option<(usize, Match)> {
+        let patterns = self.patterns;
+        let scanner = &mut self.scanner;
+        let mut best: Option<(usize, Match)> = None;
+
+        while scanner.step(patterns) {
+            // The longest match ending here is the one starting furthest
+            // left.  Later matches can only beat it by starting further
+            // left still, or at the same place and being longer.
+            let out = patterns.states[scanner.state as usize].out;
+            if out != NONE {
+                let m = patterns.match_at(out, scanner.byte_idx, scanner.char_idx);
+                let is_better = match best {
+                    Some((_, ref b)) => m.1.byte_range.start <= b.byte_range.start,
+                    None => true,
+                };
+                if is_better {
+                    best = Some(m);
+                }
+            }
+
+            // Once the automaton's state no longer reaches back to where
+            // the best match starts, nothing can beat it.
+            let depth = patterns.states[scanner.state as usize].depth;
+            if let Some(ref b) = best {
+                if scanner.byte_idx - depth > b.1.byte_range.start {
+                    break;
+                }
+            }
+        }
+
+        if let Some((_, ref m)) = best {
+            if scanner.byte_idx != m.byte_range.end {
+                scanner.restart_at(m.byte_range.end, m.char_range.end);
+            } else {
+                scanner.state = ROOT;
+            }
+        }
+        best
+    }
+}
+
+/// An iterator over all matches of a [`MultiPattern`], including
+/// overlapping ones.
+///
+/// Matches are yielded in order of where they end, and the longest first
+/// where several end at the same place.  Yields the pattern index along
+/// with each match.
+///
+/// Created by `Rope::multi_find_overlapping_iter()` and
+/// `RopeSlice::multi_find_overlapping_iter()`.
+#[derive(Debug, Clone)]
+pub struct MultiMatchesOverlapping<'a, 'p> {
+    patterns: &'p MultiPattern,
+    scanner: Scanner<'a>,
+    pending: u32, // Next state on the output chain to yield a match for.
+}
+
+impl<'a, 'p> MultiMatchesOverlapping<'a, 'p> {
+    pub(crate) fn new(
+        text: RopeSlice<'a>,
+        patterns: &'p MultiPattern,
+    ) -> MultiMatchesOverlapping<'a, 'p> {
+        MultiMatchesOverlapping {
+            patterns: patterns,
+            scanner: Scanner::new(text),
+            pending: NONE,
+        }
+    }
+}
+
+impl<'a, 'p> Iterator for MultiMatchesOverlapping<'a, 'p> {
+    type Item = (usize, Match);
+
+    fn next(&mut self) -> Option<(usize, Match)> {
+        while self.pending == NONE {
+            if !self.scanner.step(self.patterns) {
+                return None;
+            }
+            self.pending = self.patterns.states[self.scanner.state as usize].out;
+        }
+
+        let s = self.pending;
+        let fail = self.patterns.states[s as usize].fail;
+        self.pending = self.patterns.states[fail as usize].out;
+        Some(
+            self.patterns
+                .match_at(s, self.scanner.byte_idx, self.scanner.char_idx),
+        )
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rope;
+
+    const TEXT: &str = "Hello there!  How're you doing?  It's \
+                        a fine day, isn't it?  Aren't you glad \
+                        we're alive?  こんにちは、みんなさん！";
+
+    /// All the matches of `patterns` in `text`, found by brute force.
+    /// Returns them as (pattern, byte range) pairs.
+    fn brute_force(text: &str, patterns: &[&str], overlapping: bool) -> Vec<(usize, usize, usize)> {
+        let mut all = Vec::new();
+        for end in 1..=text.len() {
+            let mut here: Vec<_> = patterns
+                .iter()
+                .enumerate()
+                .filter(|&(_, p)| !p.is_empty() && text.as_bytes()[..end].ends_with(p.as_bytes()))
+                .map(|(i, p)| (i, end - p.len(), end))
+                .collect();
+            // Longest first, and only the first of repeated patterns.
+            here.sort_by_key(|&(i, start, _)| (start, i));
+            here.dedup_by_key(|m| m.1);
+            all.extend(here);
+        }
+        if overlapping {
+            return all;
+        }
+
+        let mut out: Vec<(usize, usize, usize)> = Vec::new();
+        let mut pos = 0;
+        loop {
+            let next = all
+                .iter()
+                .filter(|m| m.1 >= pos)
+                .min_by_key(|m| (m.1, !0 - m.2));
+            match next {
+                Some(&m) => {
+                    pos = m.2;
+                    out.push(m);
+                }
+                None => return out,
+            }
+        }
+    }
+
+    fn check(text: &str, patterns: &[&str]) {
+        let r = Rope::from_str(text);
+        let mp = MultiPattern::new(patterns);
+        for &overlapping in [false, true].iter() {
+            let found: Vec<_> = if overlapping {
+                r.multi_find_overlapping_iter(&mp).collect()
+            } else {
+                r.multi_find_iter(&mp).collect()
+            };
+            for (_, m) in found.iter() {
+                assert_eq!(
+                    m.char_range.start,
+                    text[..m.byte_range.start].chars().count()
+                );
+                assert_eq!(m.char_range.end, text[..m.byte_range.end].chars().count());
+            }
+            let found: Vec<_> = found
+                .into_iter()
+                .map(|(i, m)| (i, m.byte_range.start, m.byte_range.end))
+                .collect();
+            assert_eq!(
+                found,
+                brute_force(text, patterns, overlapping),
+                "{:?}",
+                patterns
+            );
+        }
+    }
+
+    #[test]
+    fn multi_find_iter_01() {
+        check(TEXT, &["you", "the", "there", "here", "e", "!"]);
+        check(
+            TEXT,
+            &["'re", "re", "we're", "w", "にち", "ち", "んな", "！"],
+        );
+        check(TEXT, &["nope", ""]);
+        check(TEXT, &[]);
+    }
+
+    #[test]
+    fn multi_find_iter_02() {
+        // Repetitive text and patterns, which exercise the failure links
+        // and restarting after matches.
+        let text = "aabaabaaabaabaabaaabaaaab".repeat(4);
+        check(&text, &["a", "aa", "aaa", "aab"]);
+        check(&text, &["aabaab", "ba", "baa", "abaaab", "b"]);
+        check(&text, &["aaab", "aab", "ab", "aab"]);
+        check(&text, &["baaabaaaa", "aaaab", "a"]);
+    }
+
+    #[test]
+    fn multi_find_iter_03() {
+        // On a slice.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(13..96);
+        let mp = MultiPattern::new(["you", "'", "'re", "こん"]);
+        let found: Vec<_> = s
+            .multi_find_iter(&mp)
+            .map(|(i, m)| (i, m.char_range))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, 4..7),
+                (0, 8..11),
+                (1, 22..23),
+                (1, 40..41),
+                (1, 52..53),
+                (0, 55..58),
+                (2, 66..69),
+                (3, 78..80),
+            ]
+        );
+    }
+}
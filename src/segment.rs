@@ -0,0 +1,713 @@
+//! Grapheme cluster, word, and sentence segmentation of `Rope`s and
+//! `RopeSlice`s, built on `unicode-segmentation`.
+
+use std::borrow::Cow;
+use std::ops::{Range, RangeBounds};
+
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete, UnicodeSegmentation};
+
+use crate::slice::RopeSlice;
+use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
+
+/// A `GraphemeCursor` over a `RopeSlice`.
+///
+/// Keeps hold of the chunk the cursor is in, so that stepping from one
+/// grapheme boundary to the next is amortized O(1).
+#[derive(Debug, Clone)]
+pub(crate) struct GraphemeWalker<'a> {
+    text: RopeSlice<'a>,
+    cursor: GraphemeCursor,
+    chunk: &'a str,
+    chunk_byte_idx: usize,
+}
+
+impl<'a> GraphemeWalker<'a> {
+    /// Creates a walker with its cursor at `byte_idx`, which must be a char
+    /// boundary.
+    pub(crate) fn new(text: RopeSlice<'a>, byte_idx: usize) -> GraphemeWalker<'a> {
+        let (chunk, chunk_byte_idx, _, _) = text.chunk_at_byte(byte_idx);
+        GraphemeWalker {
+            text: text,
+            cursor: GraphemeCursor::new(byte_idx, text.len_bytes(), true),
+            chunk: chunk,
+            chunk_byte_idx: chunk_byte_idx,
+        }
+    }
+
+    /// The byte index of the cursor.
+    #[inline]
+    pub(crate) fn byte_idx(&self) -> usize {
+        self.cursor.cur_cursor()
+    }
+
+    /// Returns the given byte range of the text, if it's within the
+    /// current chunk.
+    #[inline]
+    pub(crate) fn chunk_containing(&self, start: usize, end: usize) -> Option<&'a str> {
+        if start >= self.chunk_byte_idx && end <= self.chunk_byte_idx + self.chunk.len() {
+            Some(&self.chunk[(start - self.chunk_byte_idx)..(end - self.chunk_byte_idx)])
+        } else {
+            None
+        }
+    }
+
+    /// Moves the cursor to the next grapheme boundary, returning its byte
+    /// index, or `None` if the cursor is already at the end.
+    pub(crate) fn next_boundary(&mut self) -> Option<usize> {
+        let byte_idx = self.byte_idx();
+        if byte_idx >= self.text.len_bytes() {
+            return None;
+        }
+        if byte_idx < self.chunk_byte_idx || byte_idx >= self.chunk_byte_idx + self.chunk.len() {
+            self.load_chunk(byte_idx);
+        }
+
+        loop {
+            match self.cursor.next_boundary(self.chunk, self.chunk_byte_idx) {
+                Ok(boundary) => return boundary,
+                Err(GraphemeIncomplete::NextChunk) => {
+                    // The cursor's carried-over state double counts regional
+                    // indicators when it asks for pre-context at the start of
+                    // the next chunk, so restart it fresh at the chunk edge.
+                    let byte_idx = self.byte_idx();
+                    self.cursor = GraphemeCursor::new(byte_idx, self.text.len_bytes(), true);
+                    self.load_chunk(byte_idx);
+                    if self.is_boundary() {
+                        return Some(byte_idx);
+                    }
+                }
+                Err(GraphemeIncomplete::PreContext(idx)) => self.provide_context(idx),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Moves the cursor to the previous grapheme boundary, returning its
+    /// byte index, or `None` if the cursor is already at the start.
+    pub(crate) fn prev_boundary(&mut self) -> Option<usize> {
+        let byte_idx = self.byte_idx();
+        if byte_idx == 0 {
+            return None;
+        }
+        if byte_idx <= self.chunk_byte_idx || byte_idx > self.chunk_byte_idx + self.chunk.len() {
+            self.load_chunk(byte_idx - 1);
+        }
+
+        loop {
+            match self.cursor.prev_boundary(self.chunk, self.chunk_byte_idx) {
+                Ok(boundary) => return boundary,
+                Err(GraphemeIncomplete::PrevChunk) => self.load_chunk(self.chunk_byte_idx - 1),
+                Err(GraphemeIncomplete::PreContext(idx)) => self.provide_context(idx),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    /// Returns whether the cursor is on a grapheme boundary.
+    pub(crate) fn is_boundary(&mut self) -> bool {
+        let byte_idx = self.byte_idx();
+        if byte_idx < self.chunk_byte_idx || byte_idx >= self.chunk_byte_idx + self.chunk.len() {
+            self.load_chunk(byte_idx);
+        }
+
+        loop {
+            match self.cursor.is_boundary(self.chunk, self.chunk_byte_idx) {
+                Ok(is_boundary) => return is_boundary,
+                Err(GraphemeIncomplete::PreContext(idx)) => self.provide_context(idx),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[inline]
+    fn load_chunk(&mut self, byte_idx: usize) {
+        let (chunk, chunk_byte_idx, _, _) = self.text.chunk_at_byte(byte_idx);
+        self.chunk = chunk;
+        self.chunk_byte_idx = chunk_byte_idx;
+    }
+
+    #[inline]
+    fn provide_context(&mut self, byte_idx: usize) {
+        let (chunk, chunk_byte_idx, _, _) = self.text.chunk_at_byte(byte_idx - 1);
+        self.cursor.provide_context(chunk, chunk_byte_idx);
+    }
+}
+
+/// Shared implementation of `prev_grapheme_boundary()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn prev_grapheme_boundary(text: RopeSlice, char_idx: usize) -> Result<usize> {
+    let byte_idx = text.try_char_to_byte(char_idx)?;
+    let boundary = GraphemeWalker::new(text, byte_idx)
+        .prev_boundary()
+        .unwrap_or(0);
+    Ok(text.byte_to_char(boundary))
+}
+
+/// Shared implementation of `next_grapheme_boundary()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn next_grapheme_boundary(text: RopeSlice, char_idx: usize) -> Result<usize> {
+    let byte_idx = text.try_char_to_byte(char_idx)?;
+    let boundary = GraphemeWalker::new(text, byte_idx)
+        .next_boundary()
+        .unwrap_or_else(|| text.len_bytes());
+    Ok(text.byte_to_char(boundary))
+}
+
+/// Shared implementation of `is_grapheme_boundary()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn is_grapheme_boundary(text: RopeSlice, char_idx: usize) -> Result<bool> {
+    let byte_idx = text.try_char_to_byte(char_idx)?;
+    Ok(GraphemeWalker::new(text, byte_idx).is_boundary())
+}
+
+/// Returns the byte index of the grapheme boundary at or before
+/// `char_idx`.
+pub(crate) fn floor_grapheme_boundary(text: RopeSlice, char_idx: usize) -> Result<usize> {
+    let byte_idx = text.try_char_to_byte(char_idx)?;
+    let mut walker = GraphemeWalker::new(text, byte_idx);
+    if walker.is_boundary() {
+        Ok(byte_idx)
+    } else {
+        Ok(walker.prev_boundary().unwrap_or(0))
+    }
+}
+
+/// Shared implementation of the grapheme-safe edit and slice methods of
+/// `Rope` and `RopeSlice`: widens `char_range` outward to the nearest
+/// grapheme boundaries.
+pub(crate) fn grapheme_range<R>(text: RopeSlice, char_range: R) -> Result<Range<usize>>
+where
+    R: RangeBounds<usize>,
+{
+    let start_opt = start_bound_to_num(char_range.start_bound());
+    let end_opt = end_bound_to_num(char_range.end_bound());
+    let start = start_opt.unwrap_or(0);
+    let end = end_opt.unwrap_or_else(|| text.len_chars());
+    if end.max(start) > text.len_chars() {
+        return Err(Error::CharRangeOutOfBounds(
+            start_opt,
+            end_opt,
+            text.len_chars(),
+        ));
+    } else if start > end {
+        return Err(Error::CharRangeInvalid(start, end));
+    }
+
+    let start = text.byte_to_char(floor_grapheme_boundary(text, start)?);
+    let end = if is_grapheme_boundary(text, end)? {
+        end
+    } else {
+        next_grapheme_boundary(text, end)?
+    };
+    Ok(start..end)
+}
+
+/// The kinds of segmentation that `SegmentWalker` can do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Segmentation {
+    /// UAX #29 word boundaries.
+    Word,
+    /// UAX #29 sentence boundaries.
+    Sentence,
+}
+
+/// A cursor that steps between the word or sentence boundaries of a
+/// `RopeSlice`.
+///
+/// Unlike graphemes, `unicode-segmentation` can only find word and
+/// sentence boundaries in contiguous text, so this segments windows of the
+/// text, copying them out of the rope when they span chunks.  Segmenting a
+/// window that starts or ends in the middle of the text can get the
+/// boundaries near its edges wrong, so only the boundaries with at least
+/// `MARGIN` other boundaries between them and each edge are trusted, and
+/// windows are grown until the boundary being looked for is trusted.
+///
+/// The trusted boundaries of the last window are kept, so that stepping
+/// through the text one boundary at a time is amortized O(1).
+#[derive(Debug, Clone)]
+pub(crate) struct SegmentWalker<'a> {
+    text: RopeSlice<'a>,
+    kind: Segmentation,
+    byte_idx: usize,
+    /// Trusted boundaries, in order.  Every boundary between the first and
+    /// last of these is included.
+    known: Vec<usize>,
+}
+
+const MARGIN: usize = 2;
+
+impl<'a> SegmentWalker<'a> {
+    /// Creates a walker with its cursor at `byte_idx`, which must be a char
+    /// boundary.
+    pub(crate) fn new(text: RopeSlice<'a>, kind: Segmentation, byte_idx: usize) -> Self {
+        SegmentWalker {
+            text: text,
+            kind: kind,
+            byte_idx: byte_idx,
+            known: Vec::new(),
+        }
+    }
+
+    /// Creates a walker with its cursor at the boundary at or before
+    /// `byte_idx`, which must be a char boundary.
+    pub(crate) fn new_at_floor(text: RopeSlice<'a>, kind: Segmentation, byte_idx: usize) -> Self {
+        let mut walker = SegmentWalker::new(text, kind, byte_idx);
+        let i = walker.find(false, |known| known.iter().rposition(|&b| b <= byte_idx));
+        walker.byte_idx = walker.known[i];
+        walker
+    }
+
+    /// The byte index of the cursor.
+    #[inline]
+    pub(crate) fn byte_idx(&self) -> usize {
+        self.byte_idx
+    }
+
+    /// Moves the cursor to the next boundary, returning its byte index, or
+    /// `None` if the cursor is already at the end.
+    pub(crate) fn next_boundary(&mut self) -> Option<usize> {
+        let byte_idx = self.byte_idx;
+        if byte_idx >= self.text.len_bytes() {
+            return None;
+        }
+        let i = self.find(true, |known| known.iter().position(|&b| b > byte_idx));
+        self.byte_idx = self.known[i];
+        Some(self.byte_idx)
+    }
+
+    /// Moves the cursor to the previous boundary, returning its byte
+    /// index, or `None` if the cursor is already at the start.
+    pub(crate) fn prev_boundary(&mut self) -> Option<usize> {
+        let byte_idx = self.byte_idx;
+        if byte_idx == 0 {
+            return None;
+        }
+        let i = self.find(false, |known| known.iter().rposition(|&b| b < byte_idx));
+        self.byte_idx = self.known[i];
+        Some(self.byte_idx)
+    }
+
+    /// Returns the index in `self.known` of the boundary picked out by
+    /// `pick`, segmenting more of the text around the cursor until that
+    /// boundary is trusted.
+    ///
+    /// `pick` must pick a boundary after the cursor if `forward` is true,
+    /// and otherwise one at or before it, and there must be such a
+    /// boundary in the text.
+    fn find<F>(&mut self, forward: bool, pick: F) -> usize
+    where
+        F: Fn(&[usize]) -> Option<usize>,
+    {
+        let covers_cursor = |known: &[usize], byte_idx: usize| {
+            !known.is_empty() && known[0] <= byte_idx && byte_idx <= known[known.len() - 1]
+        };
+        if covers_cursor(&self.known, self.byte_idx) {
+            if let Some(i) = pick(&self.known) {
+                return i;
+            }
+        }
+
+        // Start with the chunk the cursor is in, and grow from there.
+        let len = self.text.len_bytes();
+        let (chunk, chunk_byte_idx, _, _) = self.text.chunk_at_byte(self.byte_idx);
+        let mut start = chunk_byte_idx;
+        let mut end = chunk_byte_idx + chunk.len();
+        loop {
+            self.segment(start, end);
+            if covers_cursor(&self.known, self.byte_idx) {
+                if let Some(i) = pick(&self.known) {
+                    return i;
+                }
+            }
+
+            // Grow whichever sides aren't trusted yet, doubling the window
+            // each time.  The grown edges are always on chunk boundaries,
+            // and so on char boundaries.
+            let grow = (end - start).max(1);
+            let picked = pick(&self.known).is_some();
+            let grow_start = !matches!(self.known.first(), Some(&b) if b <= self.byte_idx)
+                || (!forward && !picked);
+            let grow_end = !matches!(self.known.last(), Some(&b) if b >= self.byte_idx)
+                || (forward && !picked);
+            if grow_start && start > 0 {
+                start = self.text.chunk_at_byte(start.saturating_sub(grow)).1;
+            }
+            if grow_end && end < len {
+                let (chunk, chunk_byte_idx, _, _) = self.text.chunk_at_byte((end + grow).min(len));
+                end = chunk_byte_idx + chunk.len();
+            }
+        }
+    }
+
+    /// Segments the text in `start..end`, and sets `self.known` to the
+    /// boundaries that can be trusted.
+    fn segment(&mut self, start: usize, end: usize) {
+        // Regional indicators pair up from the start of their run, so a
+        // window that starts inside a run would pair them wrongly.
+        let start = match self.kind {
+            Segmentation::Word => self.regional_run_start(start),
+            Segmentation::Sentence => start,
+        };
+        let window: Cow<str> = self.text.byte_slice(start..end).into();
+        let mut boundaries: Vec<usize> = match self.kind {
+            Segmentation::Word => window
+                .split_word_bound_indices()
+                .map(|(i, _)| start + i)
+                .collect(),
+            Segmentation::Sentence => window
+                .split_sentence_bound_indices()
+                .map(|(i, _)| start + i)
+                .collect(),
+        };
+        boundaries.push(end);
+
+        // The window's own edges only count as boundaries if they're the
+        // edges of the text.
+        let first = if start == 0 { 0 } else { MARGIN + 1 };
+        let last = if end == self.text.len_bytes() {
+            boundaries.len()
+        } else {
+            boundaries.len().saturating_sub(MARGIN + 1)
+        };
+        self.known.clear();
+        if first < last {
+            self.known.extend_from_slice(&boundaries[first..last]);
+        }
+    }
+
+    /// Walks back from `byte_idx` over any regional indicators, and the
+    /// chars that word segmentation ignores between them.
+    fn regional_run_start(&self, mut byte_idx: usize) -> usize {
+        let mut chars = self.text.chars_at(self.text.byte_to_char(byte_idx));
+        while let Some(c) = chars.prev() {
+            if !is_regional_indicator(c) && !is_word_ignorable(c) {
+                break;
+            }
+            byte_idx -= c.len_utf8();
+        }
+        byte_idx
+    }
+}
+
+#[inline]
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Whether `c` is Extend, Format, or ZWJ for word segmentation (rule WB4),
+/// i.e. whether it attaches to a preceding full stop.
+fn is_word_ignorable(c: char) -> bool {
+    let mut buf = [0u8; 5];
+    buf[0] = b'.';
+    let len = 1 + c.encode_utf8(&mut buf[1..]).len();
+    let text = std::str::from_utf8(&buf[..len]).unwrap();
+    text.split_word_bounds().nth(1).is_none()
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::Rope;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    // Flags, combining sequences, an emoji ZWJ sequence, and CRLF, long
+    // enough to span many chunks.
+    const TEXT: &str = "Hello 🇬🇧🇫🇷! e\u{301}\u{302}le\u{301}ve\r\n\
+                        👨\u{200D}👩\u{200D}👧 नमस्ते 각\u{11A8}\r\n\
+                        🇯🇵🇯🇵🇯🇵 a\u{308}\u{308}\u{308}\u{308}\u{308}\u{308}b";
+
+    /// The char indices of the grapheme boundaries of `text`, including
+    /// its start and end.
+    fn std_boundaries(text: &str) -> Vec<usize> {
+        let mut boundaries: Vec<usize> = text
+            .grapheme_indices(true)
+            .map(|(i, _)| text[..i].chars().count())
+            .collect();
+        boundaries.push(text.chars().count());
+        boundaries
+    }
+
+    #[test]
+    fn grapheme_boundary_01() {
+        let r = Rope::from_str(TEXT);
+        let boundaries = std_boundaries(TEXT);
+        for i in 0..=r.len_chars() {
+            let is_boundary = boundaries.contains(&i);
+            assert_eq!(r.is_grapheme_boundary(i), is_boundary, "{}", i);
+
+            let next = *boundaries.iter().find(|&&b| b > i).unwrap_or(&i);
+            assert_eq!(r.next_grapheme_boundary(i), next, "{}", i);
+
+            let prev = *boundaries.iter().rev().find(|&&b| b < i).unwrap_or(&0);
+            assert_eq!(r.prev_grapheme_boundary(i), prev, "{}", i);
+        }
+    }
+
+    #[test]
+    fn grapheme_boundary_02() {
+        // A slice that starts and ends in the middle of graphemes.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(7..50);
+        let text = s.to_string();
+        let boundaries = std_boundaries(&text);
+        for i in 0..=s.len_chars() {
+            assert_eq!(s.is_grapheme_boundary(i), boundaries.contains(&i), "{}", i);
+        }
+        assert!(s.try_is_grapheme_boundary(s.len_chars() + 1).is_err());
+        assert!(s.try_next_grapheme_boundary(s.len_chars() + 1).is_err());
+        assert!(s.try_prev_grapheme_boundary(s.len_chars() + 1).is_err());
+    }
+
+    #[test]
+    fn graphemes_01() {
+        let r = Rope::from_str(TEXT);
+        let expected: Vec<&str> = TEXT.graphemes(true).collect();
+        let found: Vec<String> = r.graphemes().map(|g| g.to_string()).collect();
+        assert_eq!(found, expected);
+
+        // Backwards, from the end.
+        let mut found = Vec::new();
+        let mut graphemes = r.graphemes_at(r.len_chars());
+        while let Some(g) = graphemes.prev() {
+            found.push(g.to_string());
+        }
+        found.reverse();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn graphemes_02() {
+        // Switching directions, as a cursor.
+        let r = Rope::from_str(TEXT);
+        let mut graphemes = r.graphemes();
+        assert_eq!(graphemes.next().unwrap(), "H");
+        assert_eq!(graphemes.prev().unwrap(), "H");
+        assert_eq!(graphemes.prev(), None);
+        for _ in 0..7 {
+            graphemes.next();
+        }
+        assert_eq!(graphemes.next().unwrap(), "🇫🇷");
+        assert_eq!(graphemes.prev().unwrap(), "🇫🇷");
+        assert_eq!(graphemes.prev().unwrap(), "🇬🇧");
+
+        let mut graphemes = graphemes.reversed();
+        assert_eq!(graphemes.next().unwrap(), " ");
+        assert_eq!(graphemes.prev().unwrap(), " ");
+    }
+
+    #[test]
+    fn graphemes_03() {
+        // Starting in the middle of a grapheme, on a slice.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(10..);
+        let mut graphemes = s.graphemes_at(4);
+        assert_eq!(graphemes.next().unwrap(), "e\u{301}\u{302}");
+        assert_eq!(s.graphemes_at(s.len_chars()).next(), None);
+        assert!(s.get_graphemes_at(s.len_chars() + 1).is_none());
+        assert_eq!(Rope::new().graphemes().next(), None);
+    }
+
+    #[test]
+    fn graphemes_04() {
+        // Regional indicator pairs that straddle chunk boundaries.
+        for pad in 0..12 {
+            let text = format!("{}\u{1F1EC}\u{1F1E7}\u{1F1EB}x", "a".repeat(pad));
+            let r = Rope::from_str(&text);
+            let expected: Vec<&str> = text.graphemes(true).collect();
+            let found: Vec<String> = r.graphemes().map(|g| g.to_string()).collect();
+            assert_eq!(found, expected, "{}", pad);
+            for i in 0..=r.len_chars() {
+                let is_boundary = std_boundaries(&text).contains(&i);
+                assert_eq!(r.is_grapheme_boundary(i), is_boundary, "{} {}", pad, i);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_graphemes_01() {
+        let r = Rope::from_str(TEXT);
+        let boundaries = std_boundaries(TEXT);
+        for start in 0..=r.len_chars() {
+            for end in start..=r.len_chars() {
+                let expected_start = *boundaries.iter().rev().find(|&&b| b <= start).unwrap();
+                let expected_end = *boundaries.iter().find(|&&b| b >= end).unwrap();
+                let mut expected = Rope::from_str(TEXT);
+                expected.remove(expected_start..expected_end);
+
+                let mut r = r.clone();
+                let removed = r.remove_graphemes(start..end);
+                assert_eq!(removed, expected_start..expected_end, "{}..{}", start, end);
+                assert_eq!(r, expected, "{}..{}", start, end);
+            }
+        }
+
+        let mut r = r.clone();
+        let len = r.len_chars();
+        #[allow(clippy::reversed_empty_ranges)]
+        let invalid = 2..1;
+        assert!(r.try_remove_graphemes(invalid).is_err());
+        assert!(r.try_remove_graphemes(..len + 1).is_err());
+        assert_eq!(r.remove_graphemes(..), 0..len);
+        assert_eq!(r, "");
+    }
+
+    #[test]
+    fn insert_at_grapheme_01() {
+        let boundaries = std_boundaries(TEXT);
+        for char_idx in 0..=TEXT.chars().count() {
+            let expected_idx = *boundaries.iter().rev().find(|&&b| b <= char_idx).unwrap();
+            let mut expected = Rope::from_str(TEXT);
+            expected.insert(expected_idx, "[\u{301}]");
+
+            let mut r = Rope::from_str(TEXT);
+            assert_eq!(r.insert_at_grapheme(char_idx, "[\u{301}]"), expected_idx);
+            assert_eq!(r, expected, "{}", char_idx);
+        }
+
+        let mut r = Rope::from_str(TEXT);
+        let len = r.len_chars();
+        assert!(r.try_insert_at_grapheme(len + 1, "x").is_err());
+        assert_eq!(r, TEXT);
+    }
+
+    #[test]
+    fn slice_graphemes_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(7..50);
+        let text = s.to_string();
+        let boundaries = std_boundaries(&text);
+        for start in 0..=s.len_chars() {
+            for end in start..=s.len_chars() {
+                let expected_start = *boundaries.iter().rev().find(|&&b| b <= start).unwrap();
+                let expected_end = *boundaries.iter().find(|&&b| b >= end).unwrap();
+                let expected = s.slice(expected_start..expected_end);
+                assert_eq!(
+                    s.slice_graphemes(start..end),
+                    expected,
+                    "{}..{}",
+                    start,
+                    end
+                );
+            }
+        }
+        #[allow(clippy::reversed_empty_ranges)]
+        let invalid = 3..2;
+        assert!(s.get_slice_graphemes(invalid).is_none());
+        assert!(s.get_slice_graphemes(..s.len_chars() + 1).is_none());
+
+        // The flag pair at chars 6..10, sliced through the middle.
+        assert_eq!(r.slice_graphemes(7..9), "🇬🇧🇫🇷");
+        assert_eq!(r.slice_graphemes(8..8), "");
+        assert_eq!(r.slice_graphemes(7..7), "🇬🇧");
+    }
+
+    const PROSE: &str = "Mr. Smith's cat can't count past 3.14, e.g. \"one.two\" \
+                         or 1,000.5!  Is that so?  Yes.\r\nNew line here... \
+                         and 🇬🇧🇫🇷 flags, a\u{301}ccents, and x_y_z.  (The end.) ";
+
+    #[test]
+    fn word_bounds_01() {
+        let r = Rope::from_str(PROSE);
+        let expected: Vec<&str> = PROSE.split_word_bounds().collect();
+        let found: Vec<String> = r.word_bounds().map(|w| w.to_string()).collect();
+        assert_eq!(found, expected);
+
+        // Backwards, from the end.
+        let mut found = Vec::new();
+        let mut bounds = r.word_bounds_at(r.len_chars());
+        while let Some(w) = bounds.prev() {
+            found.push(w.to_string());
+        }
+        found.reverse();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn word_bounds_02() {
+        // Starting at every char, on a slice.
+        let r = Rope::from_str(PROSE);
+        let s = r.slice(4..120);
+        let text = s.to_string();
+        let segments: Vec<(usize, &str)> = text
+            .split_word_bound_indices()
+            .map(|(i, w)| (text[..i].chars().count(), w))
+            .collect();
+        for char_idx in 0..s.len_chars() {
+            let (start, segment) = *segments
+                .iter()
+                .rev()
+                .find(|&&(i, _)| i <= char_idx)
+                .unwrap();
+            let mut bounds = s.word_bounds_at(char_idx);
+            assert_eq!(bounds.next().unwrap(), segment, "{}", char_idx);
+            assert_eq!(bounds.prev().unwrap(), segment, "{}", char_idx);
+            if start > 0 {
+                let prev = segments.iter().rev().find(|&&(i, _)| i < start).unwrap().1;
+                assert_eq!(bounds.prev().unwrap(), prev, "{}", char_idx);
+            }
+        }
+        assert!(s.word_bounds_at(s.len_chars()).next().is_none());
+        assert!(s.get_word_bounds_at(s.len_chars() + 1).is_none());
+    }
+
+    #[test]
+    fn word_bounds_03() {
+        // A long run of flags, with combining marks mixed in, that spans
+        // several chunks.
+        let text = format!(
+            "x {}\u{1F1EC}\u{301}\u{1F1E7}{} y",
+            "\u{1F1EC}\u{1F1E7}".repeat(5),
+            "\u{1F1EB}\u{1F1F7}".repeat(5)
+        );
+        let r = Rope::from_str(&text);
+        let expected: Vec<&str> = text.split_word_bounds().collect();
+        let found: Vec<String> = r.word_bounds().map(|w| w.to_string()).collect();
+        assert_eq!(found, expected);
+
+        let mut found = Vec::new();
+        let mut bounds = r.word_bounds_at(r.len_chars());
+        while let Some(w) = bounds.prev() {
+            found.push(w.to_string());
+        }
+        found.reverse();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn words_01() {
+        let r = Rope::from_str(PROSE);
+        let expected: Vec<&str> = PROSE.unicode_words().collect();
+        let found: Vec<String> = r.words().map(|w| w.to_string()).collect();
+        assert_eq!(found, expected);
+
+        let mut words = r.words();
+        assert_eq!(words.next().unwrap(), "Mr");
+        assert_eq!(words.next().unwrap(), "Smith's");
+        assert_eq!(words.prev().unwrap(), "Smith's");
+        assert_eq!(words.prev().unwrap(), "Mr");
+        assert_eq!(words.prev(), None);
+        assert_eq!(Rope::new().words().next(), None);
+    }
+
+    #[test]
+    fn sentences_01() {
+        let r = Rope::from_str(PROSE);
+        let expected: Vec<&str> = PROSE.split_sentence_bounds().collect();
+        let found: Vec<String> = r.sentences().map(|w| w.to_string()).collect();
+        assert_eq!(found, expected);
+
+        let mut found: Vec<String> = r
+            .slice(..)
+            .sentences()
+            .reversed()
+            .map(|w| w.to_string())
+            .collect();
+        assert!(found.is_empty());
+        let mut sentences = r.sentences();
+        while sentences.next().is_some() {}
+        found = sentences.reversed().map(|w| w.to_string()).collect();
+        found.reverse();
+        assert_eq!(found, expected);
+    }
+}
@@ -1,14 +1,27 @@
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
-use crate::iter::{Bytes, Chars, Chunks, Lines};
+use crate::iter::{Bytes, Chars, Chunks, Lines, LinesWithEndings};
+#[cfg(feature = "graphemes")]
+use crate::iter::{Graphemes, Sentences, WordBounds, Words};
+use crate::line_ending::{self, split_line_ending, LineEnding, LineEndingCounts};
+use crate::position::{self, ColumnEncoding, Position};
+#[cfg(feature = "regex")]
+use crate::regex::{self, Captures, Regex, RegexMatches, RegexRMatches};
 use crate::rope::Rope;
+use crate::search::{
+    self, Direction, Match, Matches, MultiMatches, MultiMatchesOverlapping, MultiPattern,
+    SearchOptions,
+};
+#[cfg(feature = "graphemes")]
+use crate::segment;
 use crate::str_utils::{
-    byte_to_char_idx, byte_to_line_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx,
-    char_to_line_idx, count_chars, count_line_breaks, count_utf16_surrogates, line_to_byte_idx,
-    line_to_char_idx, utf16_code_unit_to_char_idx,
+    byte_to_char_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx, count_chars,
+    count_utf16_surrogates, utf16_code_unit_to_char_idx, LineBreakMode,
 };
-use crate::tree::{Count, Node, TextInfo};
+use crate::tree::{Count, LineBreaks, Node, TextInfo};
+#[cfg(feature = "width")]
+use crate::width;
 use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
 
 /// An immutable view into part of a `Rope`.
@@ -22,7 +35,7 @@ use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
 /// of a full `Rope` created from the same text range.  Nothing should be
 /// surprising here.
 #[derive(Copy, Clone)]
-pub struct RopeSlice<'a>(pub(crate) RSEnum<'a>);
+pub struct RopeSlice<'a>(pub(crate) RSEnum<'a>, pub(crate) LineBreakMode);
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) enum RSEnum<'a> {
@@ -46,7 +59,12 @@ impl<'a> RopeSlice<'a> {
         matches!(&self.0, RSEnum::Light { .. })
     }
 
-    pub(crate) fn new_with_range(node: &'a Arc<Node>, start: usize, end: usize) -> RopeSlice<'a> {
+    pub(crate) fn new_with_range(
+        node: &'a Arc<Node>,
+        start: usize,
+        end: usize,
+        mode: LineBreakMode,
+    ) -> RopeSlice<'a> {
         assert!(start <= end);
         assert!(end <= node.text_info().chars as usize);
 
@@ -54,28 +72,34 @@ impl<'a> RopeSlice<'a> {
         if start == 0 && end == node.char_count() {
             if node.is_leaf() {
                 let text = node.leaf_text();
-                return RopeSlice(RSEnum::Light {
-                    text: text,
-                    char_count: (end - start) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(text) as Count,
-                    line_break_count: count_line_breaks(text) as Count,
-                });
-            } else {
-                return RopeSlice(RSEnum::Full {
-                    node: node,
-                    start_info: TextInfo {
-                        bytes: 0,
-                        chars: 0,
-                        utf16_surrogates: 0,
-                        line_breaks: 0,
+                return RopeSlice(
+                    RSEnum::Light {
+                        text: text,
+                        char_count: (end - start) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(text) as Count,
+                        line_break_count: mode.count_breaks(text) as Count,
                     },
-                    end_info: TextInfo {
-                        bytes: node.byte_count() as Count,
-                        chars: node.char_count() as Count,
-                        utf16_surrogates: node.utf16_surrogate_count() as Count,
-                        line_breaks: node.line_break_count() as Count,
+                    mode,
+                );
+            } else {
+                return RopeSlice(
+                    RSEnum::Full {
+                        node: node,
+                        start_info: TextInfo {
+                            bytes: 0,
+                            chars: 0,
+                            utf16_surrogates: 0,
+                            line_breaks: LineBreaks::new(),
+                        },
+                        end_info: TextInfo {
+                            bytes: node.byte_count() as Count,
+                            chars: node.char_count() as Count,
+                            utf16_surrogates: node.utf16_surrogate_count() as Count,
+                            line_breaks: node.text_info().line_breaks,
+                        },
                     },
-                });
+                    mode,
+                );
             }
         }
 
@@ -91,13 +115,18 @@ impl<'a> RopeSlice<'a> {
                     let start_byte = char_to_byte_idx(text, n_start);
                     let end_byte =
                         start_byte + char_to_byte_idx(&text[start_byte..], n_end - n_start);
-                    return RopeSlice(RSEnum::Light {
-                        text: &text[start_byte..end_byte],
-                        char_count: (n_end - n_start) as Count,
-                        utf16_surrogate_count: count_utf16_surrogates(&text[start_byte..end_byte])
-                            as Count,
-                        line_break_count: count_line_breaks(&text[start_byte..end_byte]) as Count,
-                    });
+                    return RopeSlice(
+                        RSEnum::Light {
+                            text: &text[start_byte..end_byte],
+                            char_count: (n_end - n_start) as Count,
+                            utf16_surrogate_count: count_utf16_surrogates(
+                                &text[start_byte..end_byte],
+                            ) as Count,
+                            line_break_count: mode.count_breaks(&text[start_byte..end_byte])
+                                as Count,
+                        },
+                        mode,
+                    );
                 }
 
                 Node::Internal(ref children) => {
@@ -117,28 +146,27 @@ impl<'a> RopeSlice<'a> {
         }
 
         // Create the slice
-        RopeSlice(RSEnum::Full {
-            node: node,
-            start_info: node.char_to_text_info(n_start),
-            end_info: {
-                #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
-                {
+        RopeSlice(
+            RSEnum::Full {
+                node: node,
+                start_info: node.char_to_text_info(n_start),
+                end_info: {
                     let mut info = node.char_to_text_info(n_end);
                     if node.is_crlf_split(n_end) {
-                        info.line_breaks += 1;
+                        info.line_breaks.cr += 1;
                     }
                     info
-                }
-                #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
-                node.char_to_text_info(n_end)
+                },
             },
-        })
+            mode,
+        )
     }
 
     pub(crate) fn new_with_byte_range(
         node: &'a Arc<Node>,
         start: usize,
         end: usize,
+        mode: LineBreakMode,
     ) -> Result<RopeSlice<'a>> {
         assert!(start <= end);
         assert!(end <= node.text_info().bytes as usize);
@@ -147,28 +175,34 @@ impl<'a> RopeSlice<'a> {
         if start == 0 && end == node.byte_count() {
             if node.is_leaf() {
                 let text = node.leaf_text();
-                return Ok(RopeSlice(RSEnum::Light {
-                    text,
-                    char_count: count_chars(text) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(text) as Count,
-                    line_break_count: count_line_breaks(text) as Count,
-                }));
-            } else {
-                return Ok(RopeSlice(RSEnum::Full {
-                    node,
-                    start_info: TextInfo {
-                        bytes: 0,
-                        chars: 0,
-                        utf16_surrogates: 0,
-                        line_breaks: 0,
+                return Ok(RopeSlice(
+                    RSEnum::Light {
+                        text,
+                        char_count: count_chars(text) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(text) as Count,
+                        line_break_count: mode.count_breaks(text) as Count,
                     },
-                    end_info: TextInfo {
-                        bytes: node.byte_count() as Count,
-                        chars: node.char_count() as Count,
-                        utf16_surrogates: node.utf16_surrogate_count() as Count,
-                        line_breaks: node.line_break_count() as Count,
+                    mode,
+                ));
+            } else {
+                return Ok(RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info: TextInfo {
+                            bytes: 0,
+                            chars: 0,
+                            utf16_surrogates: 0,
+                            line_breaks: LineBreaks::new(),
+                        },
+                        end_info: TextInfo {
+                            bytes: node.byte_count() as Count,
+                            chars: node.char_count() as Count,
+                            utf16_surrogates: node.utf16_surrogate_count() as Count,
+                            line_breaks: node.text_info().line_breaks,
+                        },
                     },
-                }));
+                    mode,
+                ));
             }
         }
 
@@ -186,13 +220,18 @@ impl<'a> RopeSlice<'a> {
                     if !text.is_char_boundary(start_byte) || !text.is_char_boundary(end_byte) {
                         return Err(Error::ByteRangeNotCharBoundary(Some(start), Some(end)));
                     }
-                    return Ok(RopeSlice(RSEnum::Light {
-                        text: &text[start_byte..end_byte],
-                        char_count: count_chars(&text[start_byte..end_byte]) as Count,
-                        utf16_surrogate_count: count_utf16_surrogates(&text[start_byte..end_byte])
-                            as Count,
-                        line_break_count: count_line_breaks(&text[start_byte..end_byte]) as Count,
-                    }));
+                    return Ok(RopeSlice(
+                        RSEnum::Light {
+                            text: &text[start_byte..end_byte],
+                            char_count: count_chars(&text[start_byte..end_byte]) as Count,
+                            utf16_surrogate_count: count_utf16_surrogates(
+                                &text[start_byte..end_byte],
+                            ) as Count,
+                            line_break_count: mode.count_breaks(&text[start_byte..end_byte])
+                                as Count,
+                        },
+                        mode,
+                    ));
                 }
 
                 Node::Internal(ref children) => {
@@ -217,11 +256,26 @@ impl<'a> RopeSlice<'a> {
         }
 
         // Create the slice
-        Ok(RopeSlice(RSEnum::Full {
-            node,
-            start_info: node.byte_to_text_info(n_start),
-            end_info: node.byte_to_text_info(n_end),
-        }))
+        Ok(RopeSlice(
+            RSEnum::Full {
+                node,
+                start_info: node.byte_to_text_info(n_start),
+                end_info: node.byte_to_text_info(n_end),
+            },
+            mode,
+        ))
+    }
+
+    pub(crate) fn from_str_with_mode(text: &'a str, mode: LineBreakMode) -> RopeSlice<'a> {
+        RopeSlice(
+            RSEnum::Light {
+                text: text,
+                char_count: count_chars(text) as Count,
+                utf16_surrogate_count: count_utf16_surrogates(text) as Count,
+                line_break_count: mode.count_breaks(text) as Count,
+            },
+            mode,
+        )
     }
 
     //-----------------------------------------------------------------------
@@ -233,12 +287,15 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn len_bytes(&self) -> usize {
         match *self {
-            RopeSlice(RSEnum::Full {
-                end_info,
-                start_info,
-                ..
-            }) => (end_info.bytes - start_info.bytes) as usize,
-            RopeSlice(RSEnum::Light { text, .. }) => text.len(),
+            RopeSlice(
+                RSEnum::Full {
+                    end_info,
+                    start_info,
+                    ..
+                },
+                _,
+            ) => (end_info.bytes - start_info.bytes) as usize,
+            RopeSlice(RSEnum::Light { text, .. }, _) => text.len(),
         }
     }
 
@@ -248,12 +305,15 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn len_chars(&self) -> usize {
         match *self {
-            RopeSlice(RSEnum::Full {
-                end_info,
-                start_info,
-                ..
-            }) => (end_info.chars - start_info.chars) as usize,
-            RopeSlice(RSEnum::Light { char_count, .. }) => char_count as usize,
+            RopeSlice(
+                RSEnum::Full {
+                    end_info,
+                    start_info,
+                    ..
+                },
+                _,
+            ) => (end_info.chars - start_info.chars) as usize,
+            RopeSlice(RSEnum::Light { char_count, .. }, _) => char_count as usize,
         }
     }
 
@@ -263,14 +323,20 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn len_lines(&self) -> usize {
         match *self {
-            RopeSlice(RSEnum::Full {
-                end_info,
-                start_info,
-                ..
-            }) => (end_info.line_breaks - start_info.line_breaks) as usize + 1,
-            RopeSlice(RSEnum::Light {
-                line_break_count, ..
-            }) => line_break_count as usize + 1,
+            RopeSlice(
+                RSEnum::Full {
+                    end_info,
+                    start_info,
+                    ..
+                },
+                _,
+            ) => end_info.line_breaks.get(self.1) - start_info.line_breaks.get(self.1) + 1,
+            RopeSlice(
+                RSEnum::Light {
+                    line_break_count, ..
+                },
+                _,
+            ) => line_break_count as usize + 1,
         }
     }
 
@@ -286,22 +352,47 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn len_utf16_cu(&self) -> usize {
         match *self {
-            RopeSlice(RSEnum::Full {
-                end_info,
-                start_info,
-                ..
-            }) => {
+            RopeSlice(
+                RSEnum::Full {
+                    end_info,
+                    start_info,
+                    ..
+                },
+                _,
+            ) => {
                 ((end_info.chars + end_info.utf16_surrogates)
                     - (start_info.chars + start_info.utf16_surrogates)) as usize
             }
-            RopeSlice(RSEnum::Light {
-                char_count,
-                utf16_surrogate_count,
-                ..
-            }) => (char_count + utf16_surrogate_count) as usize,
+            RopeSlice(
+                RSEnum::Light {
+                    char_count,
+                    utf16_surrogate_count,
+                    ..
+                },
+                _,
+            ) => (char_count + utf16_surrogate_count) as usize,
         }
     }
 
+    /// The [`LineBreakMode`] of the `RopeSlice`, inherited from the `Rope`
+    /// it was created from.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn line_break_mode(&self) -> LineBreakMode {
+        self.1
+    }
+
+    /// Counts each kind of line ending in the `RopeSlice`.
+    ///
+    /// A CRLF pair split by either end of the slice is counted as a lone
+    /// CR or LF.  See [`Rope::detect_line_ending()`] for more details.
+    ///
+    /// Runs in O(N) time.
+    pub fn detect_line_ending(&self) -> LineEndingCounts {
+        line_ending::detect(*self)
+    }
+
     //-----------------------------------------------------------------------
     // Index conversion methods
 
@@ -453,6 +544,140 @@ impl<'a> RopeSlice<'a> {
         self.try_line_to_char(line_idx).unwrap()
     }
 
+    /// Returns the char index of the given line/column position.
+    ///
+    /// The column is measured in the units given by `encoding`, and
+    /// doesn't include the line's line break.  A column at the end of a
+    /// line (i.e. just before its line break) is valid.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{ColumnEncoding, Position, Rope};
+    /// let rope = Rope::from_str("Hello\n\u{1F600}world!\n");
+    /// let slice = rope.slice(..);
+    /// let pos = Position::new(1, 2);
+    ///
+    /// assert_eq!(slice.position_to_char(pos, ColumnEncoding::Utf16), 7);
+    /// assert_eq!(slice.position_to_char(pos, ColumnEncoding::Char), 8);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line is out of bounds (i.e. `line >= len_lines()`), if
+    /// the column is past the end of the line, or if the column is in the
+    /// middle of a char.
+    #[inline]
+    pub fn position_to_char(&self, pos: Position, encoding: ColumnEncoding) -> usize {
+        self.try_position_to_char(pos, encoding).unwrap()
+    }
+
+    /// Returns the char index of the given line/column position, clamping
+    /// it to the text.
+    ///
+    /// Like [`position_to_char()`](RopeSlice::position_to_char), except that
+    /// rather than panicking, lines past the end are clamped to the last
+    /// line, columns past the end of a line are clamped to the end of the
+    /// line, and columns in the middle of a char are rounded down to the
+    /// start of that char.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn position_to_char_clamped(&self, pos: Position, encoding: ColumnEncoding) -> usize {
+        position::position_to_char(*self, pos, encoding, true).unwrap()
+    }
+
+    /// Returns the line/column position of the given char.
+    ///
+    /// The column is measured in the units given by `encoding`.
+    ///
+    /// Notes:
+    ///
+    /// - `char_idx` can be one-past-the-end, which will return the
+    ///   position at the end of the last line.
+    /// - A `char_idx` between the CR and LF of a CRLF line break returns the
+    ///   position at the end of that line's content, i.e. the same position
+    ///   as the CR.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn char_to_position(&self, char_idx: usize, encoding: ColumnEncoding) -> Position {
+        self.try_char_to_position(char_idx, encoding).unwrap()
+    }
+
+    /// Returns the visual column of the given char within its line, i.e.
+    /// how many columns the text before it on that line takes up in a
+    /// monospace terminal.
+    ///
+    /// Tabs advance to the next multiple of `tab_width` (or take up no
+    /// columns if `tab_width` is 0), East Asian wide chars take up two
+    /// columns, and zero-width and control chars take up none.
+    ///
+    /// Text is measured by grapheme cluster, with each cluster taking up
+    /// the width of its first char, so that e.g. combining marks and the
+    /// later emoji of an emoji ZWJ sequence add nothing.  With the
+    /// `graphemes` feature enabled these are true extended grapheme
+    /// clusters.  Otherwise they're approximated by joining zero-width
+    /// chars, emoji modifiers, and the char after a zero width joiner onto
+    /// the char before them.  A `char_idx` in the middle of a cluster
+    /// returns the column the cluster starts at.
+    ///
+    /// Only available with the `width` feature enabled.
+    ///
+    /// Runs in O(log N + M) time, where M is the length in chars of the
+    /// line up to `char_idx`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a\tこんにちは\n\tb");
+    /// let slice = rope.slice(..);
+    ///
+    /// assert_eq!(slice.visual_column(3, 4), 6);
+    /// assert_eq!(slice.visual_column(9, 4), 4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn visual_column(&self, char_idx: usize, tab_width: usize) -> usize {
+        self.try_visual_column(char_idx, tab_width).unwrap()
+    }
+
+    /// Returns the char index of the char that covers the given visual
+    /// column of a line.
+    ///
+    /// This is the inverse of [`visual_column()`](RopeSlice::visual_column),
+    /// and measures text the same way.  The returned index is always the
+    /// start of a grapheme cluster: columns in the middle of a tab or wide
+    /// cluster return its start, zero-width clusters are skipped over, and
+    /// columns past the end of the line return the end of the line (i.e.
+    /// just before its line break).
+    ///
+    /// Only available with the `width` feature enabled.
+    ///
+    /// Runs in O(log N + M) time, where M is the length in chars of the
+    /// line up to the returned char.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn char_at_visual_column(&self, line_idx: usize, column: usize, tab_width: usize) -> usize {
+        self.try_char_at_visual_column(line_idx, column, tab_width)
+            .unwrap()
+    }
+
     //-----------------------------------------------------------------------
     // Fetch methods
 
@@ -519,6 +744,31 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Returns the line at `line_idx`, without its line break, along with
+    /// the kind of line break it ended with.
+    ///
+    /// The last line never has a line ending.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{LineEnding, Rope};
+    /// let rope = Rope::from_str("Hello\r\nworld!");
+    ///
+    /// assert_eq!(rope.line_without_ending(0), ("Hello".into(), Some(LineEnding::Crlf)));
+    /// assert_eq!(rope.line_without_ending(1), ("world!".into(), None));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    #[inline]
+    pub fn line_without_ending(&self, line_idx: usize) -> (RopeSlice<'a>, Option<LineEnding>) {
+        split_line_ending(self.line(line_idx))
+    }
+
     /// Returns the chunk containing the given byte index.
     ///
     /// Also returns the byte and char indices of the beginning of the chunk
@@ -614,8 +864,8 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn as_str(&self) -> Option<&'a str> {
         match *self {
-            RopeSlice(RSEnum::Full { .. }) => None,
-            RopeSlice(RSEnum::Light { text, .. }) => Some(text),
+            RopeSlice(RSEnum::Full { .. }, _) => None,
+            RopeSlice(RSEnum::Light { text, .. }, _) => Some(text),
         }
     }
 
@@ -661,23 +911,30 @@ impl<'a> RopeSlice<'a> {
         );
 
         match *self {
-            RopeSlice(RSEnum::Full {
-                node, start_info, ..
-            }) => RopeSlice::new_with_range(
+            RopeSlice(
+                RSEnum::Full {
+                    node, start_info, ..
+                },
+                _,
+            ) => RopeSlice::new_with_range(
                 node,
                 start_info.chars as usize + start,
                 start_info.chars as usize + end,
+                self.1,
             ),
-            RopeSlice(RSEnum::Light { text, .. }) => {
+            RopeSlice(RSEnum::Light { text, .. }, _) => {
                 let start_byte = char_to_byte_idx(text, start);
                 let end_byte = char_to_byte_idx(text, end);
                 let new_text = &text[start_byte..end_byte];
-                RopeSlice(RSEnum::Light {
-                    text: new_text,
-                    char_count: (end - start) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
-                    line_break_count: count_line_breaks(new_text) as Count,
-                })
+                RopeSlice(
+                    RSEnum::Light {
+                        text: new_text,
+                        char_count: (end - start) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
+                        line_break_count: self.1.count_breaks(new_text) as Count,
+                    },
+                    self.1,
+                )
             }
         }
     }
@@ -704,6 +961,51 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Returns a sub-slice of the `RopeSlice` in the given utf16 code unit
+    /// index range.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if:
+    /// - The start of the range is greater than the end.
+    /// - The end is out of bounds (i.e. `end > len_utf16_cu()`).
+    /// - Either end of the range is in the middle of a surrogate pair.
+    pub fn slice_utf16<R>(&self, utf16_cu_range: R) -> RopeSlice<'a>
+    where
+        R: RangeBounds<usize>,
+    {
+        match self.utf16_cu_range_to_char_range(utf16_cu_range) {
+            Ok(char_range) => self.slice(char_range),
+            Err(e) => panic!("slice_utf16(): {}", e),
+        }
+    }
+
+    /// Gets a sub-slice of the `RopeSlice`, using char indices widened
+    /// outward to the nearest grapheme boundaries.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// graphemes at either end of the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    pub fn slice_graphemes<R>(&self, char_range: R) -> RopeSlice<'a>
+    where
+        R: RangeBounds<usize>,
+    {
+        self.get_slice_graphemes(char_range).unwrap()
+    }
+
     //-----------------------------------------------------------------------
     // Iterator methods
 
@@ -713,20 +1015,24 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn bytes(&self) -> Bytes<'a> {
         match *self {
-            RopeSlice(RSEnum::Full {
-                node,
-                start_info,
-                end_info,
-            }) => Bytes::new_with_range(
+            RopeSlice(
+                RSEnum::Full {
+                    node,
+                    start_info,
+                    end_info,
+                },
+                _,
+            ) => Bytes::new_with_range(
                 node,
                 (start_info.bytes as usize, end_info.bytes as usize),
                 (start_info.chars as usize, end_info.chars as usize),
                 (
-                    start_info.line_breaks as usize,
-                    end_info.line_breaks as usize + 1,
+                    start_info.line_breaks.get(self.1),
+                    end_info.line_breaks.get(self.1) + 1,
                 ),
+                self.1,
             ),
-            RopeSlice(RSEnum::Light { text, .. }) => Bytes::from_str(text),
+            RopeSlice(RSEnum::Light { text, .. }, _) => Bytes::from_str(text),
         }
     }
 
@@ -760,20 +1066,24 @@ impl<'a> RopeSlice<'a> {
     #[inline]
     pub fn chars(&self) -> Chars<'a> {
         match *self {
-            RopeSlice(RSEnum::Full {
-                node,
-                start_info,
-                end_info,
-            }) => Chars::new_with_range(
+            RopeSlice(
+                RSEnum::Full {
+                    node,
+                    start_info,
+                    end_info,
+                },
+                _,
+            ) => Chars::new_with_range(
                 node,
                 (start_info.bytes as usize, end_info.bytes as usize),
                 (start_info.chars as usize, end_info.chars as usize),
                 (
-                    start_info.line_breaks as usize,
-                    end_info.line_breaks as usize + 1,
+                    start_info.line_breaks.get(self.1),
+                    end_info.line_breaks.get(self.1) + 1,
                 ),
+                self.1,
             ),
-            RopeSlice(RSEnum::Light { text, .. }) => Chars::from_str(text),
+            RopeSlice(RSEnum::Light { text, .. }, _) => Chars::from_str(text),
         }
     }
 
@@ -801,29 +1111,166 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Creates an iterator over the chars of the `RopeSlice`, starting at
+    /// utf16 code unit `utf16_cu_idx`.
+    ///
+    /// If `utf16_cu_idx == len_utf16_cu()` then an iterator at the end of
+    /// the `RopeSlice` is created (i.e. `next()` will return `None`).
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `utf16_cu_idx` is out of bounds
+    /// (i.e. `utf16_cu_idx > len_utf16_cu()`) or is in the middle of a
+    /// surrogate pair.
+    #[inline]
+    pub fn chars_at_utf16(&self, utf16_cu_idx: usize) -> Chars<'a> {
+        match self.utf16_cu_to_char_boundary(utf16_cu_idx) {
+            Ok(char_idx) => self.chars_at(char_idx),
+            Err(e) => panic!("chars_at_utf16(): {}", e),
+        }
+    }
+
+    /// Creates an iterator over the extended grapheme clusters of the
+    /// `RopeSlice`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn graphemes(&self) -> Graphemes<'a> {
+        Graphemes::new_at_byte(*self, 0)
+    }
+
+    /// Creates an iterator over the extended grapheme clusters of the
+    /// `RopeSlice`, starting at the grapheme containing char `char_idx`.
+    ///
+    /// If `char_idx` is in the middle of a grapheme, the iterator starts
+    /// at the start of that grapheme.  If `char_idx == len_chars()` then an
+    /// iterator at the end of the `RopeSlice` is created (i.e. `next()` will
+    /// return `None`).
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn graphemes_at(&self, char_idx: usize) -> Graphemes<'a> {
+        if let Some(out) = self.get_graphemes_at(char_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of RopeSlice: char index {}, RopeSlice char length {}",
+                char_idx,
+                self.len_chars()
+            );
+        }
+    }
+
+    /// Creates an iterator over the words of the `RopeSlice`.
+    ///
+    /// Only yields the segments between word boundaries that contain
+    /// alphanumeric chars.  Use [`word_bounds()`](RopeSlice::word_bounds) to
+    /// get every segment.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn words(&self) -> Words<'a> {
+        Words::new_at_byte(*self, 0)
+    }
+
+    /// Creates an iterator over the segments between the word boundaries
+    /// of the `RopeSlice`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn word_bounds(&self) -> WordBounds<'a> {
+        WordBounds::new_at_byte(*self, 0)
+    }
+
+    /// Creates an iterator over the segments between the word boundaries
+    /// of the `RopeSlice`, starting at the segment containing char `char_idx`.
+    ///
+    /// If `char_idx` is in the middle of a segment, the iterator starts
+    /// at the start of that segment.  If `char_idx == len_chars()` then an
+    /// iterator at the end of the `RopeSlice` is created (i.e. `next()` will
+    /// return `None`).
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length of the text
+    /// segmented to find the segment's start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn word_bounds_at(&self, char_idx: usize) -> WordBounds<'a> {
+        if let Some(out) = self.get_word_bounds_at(char_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of RopeSlice: char index {}, RopeSlice char length {}",
+                char_idx,
+                self.len_chars()
+            );
+        }
+    }
+
+    /// Creates an iterator over the sentences of the `RopeSlice`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn sentences(&self) -> Sentences<'a> {
+        Sentences::new_at_byte(*self, 0)
+    }
+
     /// Creates an iterator over the lines of the `RopeSlice`.
     ///
     /// Runs in O(log N) time.
     #[inline]
     pub fn lines(&self) -> Lines<'a> {
         match *self {
-            RopeSlice(RSEnum::Full {
-                node,
-                start_info,
-                end_info,
-            }) => Lines::new_with_range(
+            RopeSlice(
+                RSEnum::Full {
+                    node,
+                    start_info,
+                    end_info,
+                },
+                _,
+            ) => Lines::new_with_range(
                 node,
                 (start_info.bytes as usize, end_info.bytes as usize),
                 (
-                    start_info.line_breaks as usize,
-                    end_info.line_breaks as usize + 1,
+                    start_info.line_breaks.get(self.1),
+                    end_info.line_breaks.get(self.1) + 1,
                 ),
+                self.1,
             ),
-            RopeSlice(RSEnum::Light {
-                text,
-                line_break_count,
-                ..
-            }) => Lines::from_str(text, line_break_count as usize + 1),
+            RopeSlice(
+                RSEnum::Light {
+                    text,
+                    line_break_count,
+                    ..
+                },
+                _,
+            ) => Lines::from_str(text, line_break_count as usize + 1, self.1),
         }
     }
 
@@ -851,121 +1298,403 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
-    /// Creates an iterator over the chunks of the `RopeSlice`.
+    /// Creates an iterator over the lines of the `RopeSlice`, with their line
+    /// breaks split off.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn lines_with_endings(&self) -> LinesWithEndings<'a> {
+        LinesWithEndings::new(self.lines())
+    }
+
+    /// Creates an iterator over the lines of the `RopeSlice`, with their line
+    /// breaks split off, starting at line `line_idx`.
+    ///
+    /// If `line_idx == len_lines()` then an iterator at the end of the
+    /// `RopeSlice` is created (i.e. `next()` will return `None`).
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    #[inline]
+    pub fn lines_with_endings_at(&self, line_idx: usize) -> LinesWithEndings<'a> {
+        LinesWithEndings::new(self.lines_at(line_idx))
+    }
+
+    /// Creates an iterator over the chunks of the `RopeSlice`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn chunks(&self) -> Chunks<'a> {
+        match *self {
+            RopeSlice(
+                RSEnum::Full {
+                    node,
+                    start_info,
+                    end_info,
+                },
+                _,
+            ) => Chunks::new_with_range(
+                node,
+                (start_info.bytes as usize, end_info.bytes as usize),
+                (start_info.chars as usize, end_info.chars as usize),
+                (
+                    start_info.line_breaks.get(self.1),
+                    end_info.line_breaks.get(self.1) + 1,
+                ),
+                self.1,
+            ),
+            RopeSlice(RSEnum::Light { text, .. }, _) => Chunks::from_str(text, false),
+        }
+    }
+
+    /// Creates an iterator over the chunks of the `RopeSlice`, with the
+    /// iterator starting at the byte containing `byte_idx`.
+    ///
+    /// Also returns the byte and char indices of the beginning of the first
+    /// chunk to be yielded, and the index of the line that chunk starts on.
+    ///
+    /// If `byte_idx == len_bytes()` an iterator at the end of the `RopeSlice`
+    /// (yielding `None` on a call to `next()`) is created.
+    ///
+    /// The return value is organized as
+    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    #[inline]
+    pub fn chunks_at_byte(&self, byte_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
+        if let Some(out) = self.get_chunks_at_byte(byte_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of RopeSlice: byte index {}, RopeSlice byte length {}",
+                byte_idx,
+                self.len_bytes()
+            );
+        }
+    }
+
+    /// Creates an iterator over the chunks of the `RopeSlice`, with the
+    /// iterator starting on the chunk containing `char_idx`.
+    ///
+    /// Also returns the byte and char indices of the beginning of the first
+    /// chunk to be yielded, and the index of the line that chunk starts on.
+    ///
+    /// If `char_idx == len_chars()` an iterator at the end of the `RopeSlice`
+    /// (yielding `None` on a call to `next()`) is created.
+    ///
+    /// The return value is organized as
+    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn chunks_at_char(&self, char_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
+        if let Some(out) = self.get_chunks_at_char(char_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of RopeSlice: char index {}, RopeSlice char length {}",
+                char_idx,
+                self.len_chars()
+            );
+        }
+    }
+
+    /// Creates an iterator over the chunks of the `RopeSlice`, with the
+    /// iterator starting at the chunk containing `line_break_idx`.
+    ///
+    /// Also returns the byte and char indices of the beginning of the first
+    /// chunk to be yielded, and the index of the line that chunk starts on.
+    ///
+    /// Note: for convenience, both the beginning and end of the `RopeSlice` are
+    /// considered line breaks for the purposes of indexing.  For example, in
+    /// the string `"Hello \n world!"` 0 would create an iterator starting on
+    /// the first chunk, 1 would create an iterator starting on the chunk
+    /// containing the newline character, and 2 would create an iterator at
+    /// the end of the `RopeSlice` (yielding `None` on a call to `next()`).
+    ///
+    /// The return value is organized as
+    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    #[inline]
+    pub fn chunks_at_line_break(&self, line_break_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
+        if let Some(out) = self.get_chunks_at_line_break(line_break_idx) {
+            out
+        } else {
+            panic!(
+                "Attempt to index past end of RopeSlice: line break index {}, RopeSlice line break max index {}",
+                line_break_idx,
+                self.len_lines()
+            );
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Grapheme methods
+
+    /// Returns the char index of the grapheme boundary before `char_idx`.
+    ///
+    /// If `char_idx` is 0, returns 0.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// grapheme before `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn prev_grapheme_boundary(&self, char_idx: usize) -> usize {
+        self.try_prev_grapheme_boundary(char_idx).unwrap()
+    }
+
+    /// Returns the char index of the grapheme boundary after `char_idx`.
+    ///
+    /// If `char_idx == len_chars()`, returns `len_chars()`.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N + K) time, where K is the length in bytes of the
+    /// grapheme after `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn next_grapheme_boundary(&self, char_idx: usize) -> usize {
+        self.try_next_grapheme_boundary(char_idx).unwrap()
+    }
+
+    /// Returns whether `char_idx` is on a grapheme boundary.
+    ///
+    /// The start and end of the `RopeSlice` are always grapheme boundaries.
+    ///
+    /// Only available with the `graphemes` feature enabled.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn is_grapheme_boundary(&self, char_idx: usize) -> bool {
+        self.try_is_grapheme_boundary(char_idx).unwrap()
+    }
+
+    //-----------------------------------------------------------------------
+    // Search methods
+
+    /// Returns the first match of `pattern` in the `RopeSlice`, if any.
+    ///
+    /// An empty `pattern` matches at the start of the `RopeSlice`.
+    ///
+    /// Runs in O(N + M) time, where M is the length of `pattern`.
+    #[inline]
+    pub fn find(&self, pattern: &str) -> Option<Match> {
+        self.matches(pattern).next()
+    }
+
+    /// Returns the last match of `pattern` in the `RopeSlice`, if any.
+    ///
+    /// An empty `pattern` matches at the end of the `RopeSlice`.
+    ///
+    /// Runs in O(N + M) time, where M is the length of `pattern`.
+    #[inline]
+    pub fn rfind(&self, pattern: &str) -> Option<Match> {
+        search::rfind(*self, pattern)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `pattern`
+    /// in the `RopeSlice`, from left to right.
+    ///
+    /// Runs in O(M + log N) time to create, and O(N) time to iterate over
+    /// all matches, where M is the length of `pattern`.
+    #[inline]
+    pub fn matches(&self, pattern: &str) -> Matches<'a> {
+        Matches::new(*self, pattern, SearchOptions::default())
+    }
+
+    /// Like `find()`, but with the given search options.
+    ///
+    /// Runs in O(N + M) time, plus O(log N) time for each match rejected
+    /// by [`whole_word`](SearchOptions::whole_word).
+    #[inline]
+    pub fn find_with(&self, pattern: &str, options: SearchOptions) -> Option<Match> {
+        self.matches_with(pattern, options).next()
+    }
+
+    /// Like `rfind()`, but with the given search options.
+    ///
+    /// Runs in O(N + M) time, plus O(log N) time for each match rejected
+    /// by [`whole_word`](SearchOptions::whole_word).
+    #[inline]
+    pub fn rfind_with(&self, pattern: &str, options: SearchOptions) -> Option<Match> {
+        search::rfind_with(*self, pattern, options)
+    }
+
+    /// Like `matches()`, but with the given search options.
     ///
-    /// Runs in O(log N) time.
+    /// Runs in O(M + log N) time to create, and O(N) time to iterate over
+    /// all matches, plus O(log N) time for each match that is
+    /// case-insensitive or checked for being a whole word.
     #[inline]
-    pub fn chunks(&self) -> Chunks<'a> {
-        match *self {
-            RopeSlice(RSEnum::Full {
-                node,
-                start_info,
-                end_info,
-            }) => Chunks::new_with_range(
-                node,
-                (start_info.bytes as usize, end_info.bytes as usize),
-                (start_info.chars as usize, end_info.chars as usize),
-                (
-                    start_info.line_breaks as usize,
-                    end_info.line_breaks as usize + 1,
-                ),
-            ),
-            RopeSlice(RSEnum::Light { text, .. }) => Chunks::from_str(text, false),
-        }
+    pub fn matches_with(&self, pattern: &str, options: SearchOptions) -> Matches<'a> {
+        Matches::new(*self, pattern, options)
     }
 
-    /// Creates an iterator over the chunks of the `RopeSlice`, with the
-    /// iterator starting at the byte containing `byte_idx`.
+    /// Returns the index of the first char satisfying `predicate`, scanning
+    /// from `char_idx` in the given direction.
     ///
-    /// Also returns the byte and char indices of the beginning of the first
-    /// chunk to be yielded, and the index of the line that chunk starts on.
+    /// Scanning forward starts with the char at `char_idx`, and scanning
+    /// backward starts with the char before it.  To bound how far the scan
+    /// can go, use [`position_where()`](RopeSlice::position_where) instead.
     ///
-    /// If `byte_idx == len_bytes()` an iterator at the end of the `RopeSlice`
-    /// (yielding `None` on a call to `next()`) is created.
+    /// Runs in O(log N + K) time, where K is the number of chars scanned.
     ///
-    /// The return value is organized as
-    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    /// # Example
     ///
-    /// Runs in O(log N) time.
+    /// ```
+    /// # use ropey::Rope;
+    /// use ropey::search::Direction;
+    ///
+    /// let rope = Rope::from_str("let x =   42;");
+    /// let slice = rope.slice(..);
+    /// let next = slice.find_char_from(7, Direction::Forward, |c| !c.is_whitespace());
+    ///
+    /// assert_eq!(next, Some(10));
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
     #[inline]
-    pub fn chunks_at_byte(&self, byte_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
-        if let Some(out) = self.get_chunks_at_byte(byte_idx) {
-            out
-        } else {
-            panic!(
-                "Attempt to index past end of RopeSlice: byte index {}, RopeSlice byte length {}",
-                byte_idx,
-                self.len_bytes()
-            );
-        }
+    pub fn find_char_from<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        predicate: F,
+    ) -> Option<usize>
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.try_find_char_from(char_idx, direction, predicate)
+            .unwrap()
     }
 
-    /// Creates an iterator over the chunks of the `RopeSlice`, with the
-    /// iterator starting on the chunk containing `char_idx`.
+    /// Returns the index of the first char satisfying `predicate`, scanning
+    /// at most `limit` chars from `char_idx` in the given direction.
     ///
-    /// Also returns the byte and char indices of the beginning of the first
-    /// chunk to be yielded, and the index of the line that chunk starts on.
-    ///
-    /// If `char_idx == len_chars()` an iterator at the end of the `RopeSlice`
-    /// (yielding `None` on a call to `next()`) is created.
-    ///
-    /// The return value is organized as
-    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    /// Like [`find_char_from()`](RopeSlice::find_char_from), except that the
+    /// scan gives up after `limit` chars if a limit is given, which keeps
+    /// scans that are driven by user input cheap on huge texts.
     ///
-    /// Runs in O(log N) time.
+    /// Runs in O(log N + K) time, where K is the number of chars scanned.
     ///
     /// # Panics
     ///
     /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
     #[inline]
-    pub fn chunks_at_char(&self, char_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
-        if let Some(out) = self.get_chunks_at_char(char_idx) {
-            out
-        } else {
-            panic!(
-                "Attempt to index past end of RopeSlice: char index {}, RopeSlice char length {}",
-                char_idx,
-                self.len_chars()
-            );
-        }
+    pub fn position_where<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        limit: Option<usize>,
+        predicate: F,
+    ) -> Option<usize>
+    where
+        F: FnMut(char) -> bool,
+    {
+        self.try_position_where(char_idx, direction, limit, predicate)
+            .unwrap()
     }
 
-    /// Creates an iterator over the chunks of the `RopeSlice`, with the
-    /// iterator starting at the chunk containing `line_break_idx`.
+    /// Creates an iterator over the leftmost-longest non-overlapping
+    /// matches of `patterns` in the `RopeSlice`, from left to right.
     ///
-    /// Also returns the byte and char indices of the beginning of the first
-    /// chunk to be yielded, and the index of the line that chunk starts on.
+    /// Yields the index of the matched pattern along with each match.  See
+    /// [`MultiMatches`] for details.
     ///
-    /// Note: for convenience, both the beginning and end of the `RopeSlice` are
-    /// considered line breaks for the purposes of indexing.  For example, in
-    /// the string `"Hello \n world!"` 0 would create an iterator starting on
-    /// the first chunk, 1 would create an iterator starting on the chunk
-    /// containing the newline character, and 2 would create an iterator at
-    /// the end of the `RopeSlice` (yielding `None` on a call to `next()`).
+    /// Runs in O(log N) time to create, and O(N * L) time to iterate over
+    /// all matches in the worst case, where L is the length of the longest
+    /// pattern.  Typically it is closer to O(N).
+    #[inline]
+    pub fn multi_find_iter<'p>(&self, patterns: &'p MultiPattern) -> MultiMatches<'a, 'p> {
+        MultiMatches::new(*self, patterns)
+    }
+
+    /// Creates an iterator over all matches of `patterns` in the `RopeSlice`,
+    /// including overlapping ones.
     ///
-    /// The return value is organized as
-    /// `(iterator, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    /// Yields the index of the matched pattern along with each match.  See
+    /// [`MultiMatchesOverlapping`] for the order they are yielded in.
     ///
-    /// Runs in O(log N) time.
+    /// Runs in O(log N) time to create, and O(N + K) time to iterate over
+    /// all matches, where K is the number of matches.
+    #[inline]
+    pub fn multi_find_overlapping_iter<'p>(
+        &self,
+        patterns: &'p MultiPattern,
+    ) -> MultiMatchesOverlapping<'a, 'p> {
+        MultiMatchesOverlapping::new(*self, patterns)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `regex` in
+    /// the `RopeSlice`, from left to right.
     ///
-    /// # Panics
+    /// Only available with the `regex` feature enabled.
     ///
-    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    /// Runs in O(log N) time to create, and O(N) time to iterate over all
+    /// matches.
+    #[cfg(feature = "regex")]
     #[inline]
-    pub fn chunks_at_line_break(&self, line_break_idx: usize) -> (Chunks<'a>, usize, usize, usize) {
-        if let Some(out) = self.get_chunks_at_line_break(line_break_idx) {
-            out
-        } else {
-            panic!(
-                "Attempt to index past end of RopeSlice: line break index {}, RopeSlice line break max index {}",
-                line_break_idx,
-                self.len_lines()
-            );
-        }
+    pub fn regex_find_iter<'r>(&self, regex: &'r Regex) -> RegexMatches<'a, 'r> {
+        RegexMatches::new(*self, regex)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `regex` in
+    /// the `RopeSlice`, from right to left.
+    ///
+    /// Only available with the `regex` feature enabled.  See
+    /// [`RegexRMatches`](crate::regex::RegexRMatches) for how the matches differ from
+    /// `regex_find_iter()`'s.
+    ///
+    /// Runs in O(log N) time to create, and O(N) time to iterate over all
+    /// matches.
+    #[cfg(feature = "regex")]
+    #[inline]
+    pub fn regex_rfind_iter<'r>(&self, regex: &'r Regex) -> RegexRMatches<'a, 'r> {
+        RegexRMatches::new(*self, regex)
+    }
+
+    /// Returns the capture groups of the first match of `regex` in the
+    /// `RopeSlice`, if any.
+    ///
+    /// Only available with the `regex` feature enabled.
+    ///
+    /// Runs in O(N) time.
+    #[cfg(feature = "regex")]
+    #[inline]
+    pub fn regex_captures(&self, regex: &Regex) -> Option<Captures> {
+        regex::captures(*self, regex)
     }
 }
 
@@ -993,7 +1722,7 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if byte_idx <= self.len_bytes() {
             let (chunk, b, _, l) = self.chunk_at_byte(byte_idx);
-            Ok(l + byte_to_line_idx(chunk, byte_idx - b))
+            Ok(l + self.1.byte_to_line_idx(chunk, byte_idx - b))
         } else {
             Err(Error::ByteIndexOutOfBounds(byte_idx, self.len_bytes()))
         }
@@ -1017,7 +1746,7 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if char_idx <= self.len_chars() {
             let (chunk, _, c, l) = self.chunk_at_char(char_idx);
-            Ok(l + char_to_line_idx(chunk, char_idx - c))
+            Ok(l + self.1.char_to_line_idx(chunk, char_idx - c))
         } else {
             Err(Error::CharIndexOutOfBounds(char_idx, self.len_chars()))
         }
@@ -1029,9 +1758,12 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if char_idx <= self.len_chars() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node, start_info, ..
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node, start_info, ..
+                    },
+                    _,
+                ) => {
                     let char_idx = char_idx + start_info.chars as usize;
 
                     let (chunk, chunk_start_info) = node.get_chunk_at_char(char_idx);
@@ -1046,7 +1778,7 @@ impl<'a> RopeSlice<'a> {
                     )
                 }
 
-                RopeSlice(RSEnum::Light { text, .. }) => {
+                RopeSlice(RSEnum::Light { text, .. }, _) => {
                     let byte_idx = char_to_byte_idx(text, char_idx);
                     let surrogate_count = byte_to_utf16_surrogate_idx(text, byte_idx);
                     Ok(char_idx + surrogate_count)
@@ -1063,9 +1795,12 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if utf16_cu_idx <= self.len_utf16_cu() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node, start_info, ..
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node, start_info, ..
+                    },
+                    _,
+                ) => {
                     let utf16_cu_idx =
                         utf16_cu_idx + (start_info.chars + start_info.utf16_surrogates) as usize;
 
@@ -1080,7 +1815,7 @@ impl<'a> RopeSlice<'a> {
                     )
                 }
 
-                RopeSlice(RSEnum::Light { text, .. }) => {
+                RopeSlice(RSEnum::Light { text, .. }, _) => {
                     Ok(utf16_code_unit_to_char_idx(text, utf16_cu_idx))
                 }
             }
@@ -1101,7 +1836,7 @@ impl<'a> RopeSlice<'a> {
                 Ok(self.len_bytes())
             } else {
                 let (chunk, b, _, l) = self.chunk_at_line_break(line_idx);
-                Ok(b + line_to_byte_idx(chunk, line_idx - l))
+                Ok(b + self.1.line_to_byte_idx(chunk, line_idx - l))
             }
         } else {
             Err(Error::LineIndexOutOfBounds(line_idx, self.len_lines()))
@@ -1117,13 +1852,102 @@ impl<'a> RopeSlice<'a> {
                 Ok(self.len_chars())
             } else {
                 let (chunk, _, c, l) = self.chunk_at_line_break(line_idx);
-                Ok(c + line_to_char_idx(chunk, line_idx - l))
+                Ok(c + self.1.line_to_char_idx(chunk, line_idx - l))
             }
         } else {
             Err(Error::LineIndexOutOfBounds(line_idx, self.len_lines()))
         }
     }
 
+    /// Non-panicking version of [`position_to_char()`](RopeSlice::position_to_char).
+    #[inline]
+    pub fn try_position_to_char(&self, pos: Position, encoding: ColumnEncoding) -> Result<usize> {
+        position::position_to_char(*self, pos, encoding, false)
+    }
+
+    /// Non-panicking version of [`char_to_position()`](RopeSlice::char_to_position).
+    #[inline]
+    pub fn try_char_to_position(
+        &self,
+        char_idx: usize,
+        encoding: ColumnEncoding,
+    ) -> Result<Position> {
+        position::char_to_position(*self, char_idx, encoding)
+    }
+
+    /// Non-panicking version of [`visual_column()`](RopeSlice::visual_column).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn try_visual_column(&self, char_idx: usize, tab_width: usize) -> Result<usize> {
+        width::visual_column(*self, char_idx, tab_width)
+    }
+
+    /// Non-panicking version of
+    /// [`char_at_visual_column()`](RopeSlice::char_at_visual_column).
+    #[cfg(feature = "width")]
+    #[inline]
+    pub fn try_char_at_visual_column(
+        &self,
+        line_idx: usize,
+        column: usize,
+        tab_width: usize,
+    ) -> Result<usize> {
+        width::char_at_visual_column(*self, line_idx, column, tab_width)
+    }
+
+    /// Non-panicking version of [`find_char_from()`](RopeSlice::find_char_from).
+    #[inline]
+    pub fn try_find_char_from<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        predicate: F,
+    ) -> Result<Option<usize>>
+    where
+        F: FnMut(char) -> bool,
+    {
+        search::position_where(*self, char_idx, direction, None, predicate)
+    }
+
+    /// Non-panicking version of [`position_where()`](RopeSlice::position_where).
+    #[inline]
+    pub fn try_position_where<F>(
+        &self,
+        char_idx: usize,
+        direction: Direction,
+        limit: Option<usize>,
+        predicate: F,
+    ) -> Result<Option<usize>>
+    where
+        F: FnMut(char) -> bool,
+    {
+        search::position_where(*self, char_idx, direction, limit, predicate)
+    }
+
+    /// Non-panicking version of
+    /// [`prev_grapheme_boundary()`](RopeSlice::prev_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_prev_grapheme_boundary(&self, char_idx: usize) -> Result<usize> {
+        segment::prev_grapheme_boundary(*self, char_idx)
+    }
+
+    /// Non-panicking version of
+    /// [`next_grapheme_boundary()`](RopeSlice::next_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_next_grapheme_boundary(&self, char_idx: usize) -> Result<usize> {
+        segment::next_grapheme_boundary(*self, char_idx)
+    }
+
+    /// Non-panicking version of
+    /// [`is_grapheme_boundary()`](RopeSlice::is_grapheme_boundary).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn try_is_grapheme_boundary(&self, char_idx: usize) -> Result<bool> {
+        segment::is_grapheme_boundary(*self, char_idx)
+    }
+
     /// Non-panicking version of [`get_byte()`](RopeSlice::get_byte).
     #[inline]
     pub fn get_byte(&self, byte_idx: usize) -> Option<u8> {
@@ -1159,17 +1983,20 @@ impl<'a> RopeSlice<'a> {
             let (chunk_1, _, c1, l1) = self.chunk_at_line_break(line_idx);
             let (chunk_2, _, c2, l2) = self.chunk_at_line_break(line_idx + 1);
             if c1 == c2 {
-                let text1 = &chunk_1[line_to_byte_idx(chunk_1, line_idx - l1)..];
-                let text2 = &text1[..line_to_byte_idx(text1, 1)];
-                Some(RopeSlice(RSEnum::Light {
-                    text: text2,
-                    char_count: count_chars(text2) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(text2) as Count,
-                    line_break_count: if line_idx == (len_lines - 1) { 0 } else { 1 },
-                }))
+                let text1 = &chunk_1[self.1.line_to_byte_idx(chunk_1, line_idx - l1)..];
+                let text2 = &text1[..self.1.line_to_byte_idx(text1, 1)];
+                Some(RopeSlice(
+                    RSEnum::Light {
+                        text: text2,
+                        char_count: count_chars(text2) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(text2) as Count,
+                        line_break_count: if line_idx == (len_lines - 1) { 0 } else { 1 },
+                    },
+                    self.1,
+                ))
             } else {
-                let start = c1 + line_to_char_idx(chunk_1, line_idx - l1);
-                let end = c2 + line_to_char_idx(chunk_2, line_idx + 1 - l2);
+                let start = c1 + self.1.line_to_char_idx(chunk_1, line_idx - l1);
+                let end = c2 + self.1.line_to_char_idx(chunk_2, line_idx + 1 - l2);
                 Some(self.slice(start..end))
             }
         } else {
@@ -1177,16 +2004,29 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Non-panicking version of
+    /// [`line_without_ending()`](RopeSlice::line_without_ending).
+    #[inline]
+    pub fn get_line_without_ending(
+        &self,
+        line_idx: usize,
+    ) -> Option<(RopeSlice<'a>, Option<LineEnding>)> {
+        self.get_line(line_idx).map(split_line_ending)
+    }
+
     /// Non-panicking version of [`chunk_at_byte()`](RopeSlice::chunk_at_byte).
     pub fn try_chunk_at_byte(&self, byte_idx: usize) -> Result<(&'a str, usize, usize, usize)> {
         // Bounds check
         if byte_idx <= self.len_bytes() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     // Get the chunk.
                     let (chunk, chunk_start_info) =
                         node.get_chunk_at_byte(byte_idx + start_info.bytes as usize);
@@ -1204,11 +2044,11 @@ impl<'a> RopeSlice<'a> {
                         chunk_start_info.chars.saturating_sub(start_info.chars) as usize,
                         chunk_start_info
                             .line_breaks
-                            .saturating_sub(start_info.line_breaks)
-                            as usize,
+                            .get(self.1)
+                            .saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light { text, .. }) => Ok((text, 0, 0, 0)),
+                RopeSlice(RSEnum::Light { text, .. }, _) => Ok((text, 0, 0, 0)),
             }
         } else {
             Err(Error::ByteIndexOutOfBounds(byte_idx, self.len_bytes()))
@@ -1220,11 +2060,14 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if char_idx <= self.len_chars() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     // Get the chunk.
                     let (chunk, chunk_start_info) =
                         node.get_chunk_at_char(char_idx + start_info.chars as usize);
@@ -1242,11 +2085,11 @@ impl<'a> RopeSlice<'a> {
                         chunk_start_info.chars.saturating_sub(start_info.chars) as usize,
                         chunk_start_info
                             .line_breaks
-                            .saturating_sub(start_info.line_breaks)
-                            as usize,
+                            .get(self.1)
+                            .saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light { text, .. }) => Some((text, 0, 0, 0)),
+                RopeSlice(RSEnum::Light { text, .. }, _) => Some((text, 0, 0, 0)),
             }
         } else {
             None
@@ -1261,11 +2104,14 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if line_break_idx <= self.len_lines() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     // Get the chunk.
                     let (chunk, chunk_start_info) = if line_break_idx == 0 {
                         node.get_chunk_at_byte(start_info.bytes as usize)
@@ -1273,7 +2119,8 @@ impl<'a> RopeSlice<'a> {
                         node.get_chunk_at_byte(end_info.bytes as usize)
                     } else {
                         node.get_chunk_at_line_break(
-                            line_break_idx + start_info.line_breaks as usize,
+                            line_break_idx + start_info.line_breaks.get(self.1),
+                            self.1,
                         )
                     };
 
@@ -1290,11 +2137,11 @@ impl<'a> RopeSlice<'a> {
                         chunk_start_info.chars.saturating_sub(start_info.chars) as usize,
                         chunk_start_info
                             .line_breaks
-                            .saturating_sub(start_info.line_breaks)
-                            as usize,
+                            .get(self.1)
+                            .saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light { text, .. }) => Some((text, 0, 0, 0)),
+                RopeSlice(RSEnum::Light { text, .. }, _) => Some((text, 0, 0, 0)),
             }
         } else {
             None
@@ -1324,23 +2171,30 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if start <= end && end <= self.len_chars() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node, start_info, ..
-                }) => Some(RopeSlice::new_with_range(
+                RopeSlice(
+                    RSEnum::Full {
+                        node, start_info, ..
+                    },
+                    _,
+                ) => Some(RopeSlice::new_with_range(
                     node,
                     start_info.chars as usize + start,
                     start_info.chars as usize + end,
+                    self.1,
                 )),
-                RopeSlice(RSEnum::Light { text, .. }) => {
+                RopeSlice(RSEnum::Light { text, .. }, _) => {
                     let start_byte = char_to_byte_idx(text, start);
                     let end_byte = char_to_byte_idx(text, end);
                     let new_text = &text[start_byte..end_byte];
-                    Some(RopeSlice(RSEnum::Light {
-                        text: new_text,
-                        char_count: (end - start) as Count,
-                        utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
-                        line_break_count: count_line_breaks(new_text) as Count,
-                    }))
+                    Some(RopeSlice(
+                        RSEnum::Light {
+                            text: new_text,
+                            char_count: (end - start) as Count,
+                            utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
+                            line_break_count: self.1.count_breaks(new_text) as Count,
+                        },
+                        self.1,
+                    ))
                 }
             }
         } else {
@@ -1356,6 +2210,26 @@ impl<'a> RopeSlice<'a> {
         self.get_byte_slice_impl(byte_range).ok()
     }
 
+    /// Non-panicking version of [`slice_utf16()`](RopeSlice::slice_utf16).
+    pub fn get_slice_utf16<R>(&self, utf16_cu_range: R) -> Option<RopeSlice<'a>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = self.utf16_cu_range_to_char_range(utf16_cu_range).ok()?;
+        self.get_slice(char_range)
+    }
+
+    /// Non-panicking version of [`slice_graphemes()`](RopeSlice::slice_graphemes).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_slice_graphemes<R>(&self, char_range: R) -> Option<RopeSlice<'a>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = segment::grapheme_range(*self, char_range).ok()?;
+        self.get_slice(char_range)
+    }
+
     pub(crate) fn get_byte_slice_impl<R>(&self, byte_range: R) -> Result<RopeSlice<'a>>
     where
         R: RangeBounds<usize>,
@@ -1406,12 +2280,16 @@ impl<'a> RopeSlice<'a> {
         );
 
         match *self {
-            RopeSlice(RSEnum::Full {
-                node, start_info, ..
-            }) => RopeSlice::new_with_byte_range(
+            RopeSlice(
+                RSEnum::Full {
+                    node, start_info, ..
+                },
+                _,
+            ) => RopeSlice::new_with_byte_range(
                 node,
                 start_info.bytes as usize + start,
                 start_info.bytes as usize + end,
+                self.1,
             )
             .map_err(|e| {
                 if let Error::ByteRangeNotCharBoundary(_, _) = e {
@@ -1420,17 +2298,20 @@ impl<'a> RopeSlice<'a> {
                     e
                 }
             }),
-            RopeSlice(RSEnum::Light { text, .. }) => {
+            RopeSlice(RSEnum::Light { text, .. }, _) => {
                 if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
                     return Err(Error::ByteRangeNotCharBoundary(start_range, end_range));
                 }
                 let new_text = &text[start..end];
-                Ok(RopeSlice(RSEnum::Light {
-                    text: new_text,
-                    char_count: count_chars(new_text) as Count,
-                    utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
-                    line_break_count: count_line_breaks(new_text) as Count,
-                }))
+                Ok(RopeSlice(
+                    RSEnum::Light {
+                        text: new_text,
+                        char_count: count_chars(new_text) as Count,
+                        utf16_surrogate_count: count_utf16_surrogates(new_text) as Count,
+                        line_break_count: self.1.count_breaks(new_text) as Count,
+                    },
+                    self.1,
+                ))
             }
         }
     }
@@ -1441,21 +2322,27 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if byte_idx <= self.len_bytes() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => Some(Bytes::new_with_range_at(
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => Some(Bytes::new_with_range_at(
                     node,
                     start_info.bytes as usize + byte_idx,
                     (start_info.bytes as usize, end_info.bytes as usize),
                     (start_info.chars as usize, end_info.chars as usize),
                     (
-                        start_info.line_breaks as usize,
-                        end_info.line_breaks as usize + 1,
+                        start_info.line_breaks.get(self.1),
+                        end_info.line_breaks.get(self.1) + 1,
                     ),
+                    self.1,
                 )),
-                RopeSlice(RSEnum::Light { text, .. }) => Some(Bytes::from_str_at(text, byte_idx)),
+                RopeSlice(RSEnum::Light { text, .. }, _) => {
+                    Some(Bytes::from_str_at(text, byte_idx))
+                }
             }
         } else {
             None
@@ -1468,54 +2355,92 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if char_idx <= self.len_chars() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => Some(Chars::new_with_range_at(
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => Some(Chars::new_with_range_at(
                     node,
                     start_info.chars as usize + char_idx,
                     (start_info.bytes as usize, end_info.bytes as usize),
                     (start_info.chars as usize, end_info.chars as usize),
                     (
-                        start_info.line_breaks as usize,
-                        end_info.line_breaks as usize + 1,
+                        start_info.line_breaks.get(self.1),
+                        end_info.line_breaks.get(self.1) + 1,
                     ),
+                    self.1,
                 )),
-                RopeSlice(RSEnum::Light { text, .. }) => Some(Chars::from_str_at(text, char_idx)),
+                RopeSlice(RSEnum::Light { text, .. }, _) => {
+                    Some(Chars::from_str_at(text, char_idx))
+                }
             }
         } else {
             None
         }
     }
 
+    /// Non-panicking version of [`chars_at_utf16()`](RopeSlice::chars_at_utf16).
+    #[inline]
+    pub fn get_chars_at_utf16(&self, utf16_cu_idx: usize) -> Option<Chars<'a>> {
+        let char_idx = self.utf16_cu_to_char_boundary(utf16_cu_idx).ok()?;
+        self.get_chars_at(char_idx)
+    }
+
+    /// Non-panicking version of [`graphemes_at()`](RopeSlice::graphemes_at).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_graphemes_at(&self, char_idx: usize) -> Option<Graphemes<'a>> {
+        let text = *self;
+        let byte_idx = segment::floor_grapheme_boundary(text, char_idx).ok()?;
+        Some(Graphemes::new_at_byte(text, byte_idx))
+    }
+
+    /// Non-panicking version of [`word_bounds_at()`](RopeSlice::word_bounds_at).
+    #[cfg(feature = "graphemes")]
+    #[inline]
+    pub fn get_word_bounds_at(&self, char_idx: usize) -> Option<WordBounds<'a>> {
+        let byte_idx = self.try_char_to_byte(char_idx).ok()?;
+        Some(WordBounds::new_at_byte(*self, byte_idx))
+    }
+
     /// Non-panicking version of [`lines_at()`](RopeSlice::lines_at).
     #[inline]
     pub fn get_lines_at(&self, line_idx: usize) -> Option<Lines<'a>> {
         // Bounds check
         if line_idx <= self.len_lines() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => Some(Lines::new_with_range_at(
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => Some(Lines::new_with_range_at(
                     node,
-                    start_info.line_breaks as usize + line_idx,
+                    start_info.line_breaks.get(self.1) + line_idx,
                     (start_info.bytes as usize, end_info.bytes as usize),
                     (
-                        start_info.line_breaks as usize,
-                        end_info.line_breaks as usize + 1,
+                        start_info.line_breaks.get(self.1),
+                        end_info.line_breaks.get(self.1) + 1,
                     ),
+                    self.1,
                 )),
-                RopeSlice(RSEnum::Light {
-                    text,
-                    line_break_count,
-                    ..
-                }) => Some(Lines::from_str_at(
+                RopeSlice(
+                    RSEnum::Light {
+                        text,
+                        line_break_count,
+                        ..
+                    },
+                    _,
+                ) => Some(Lines::from_str_at(
                     text,
                     line_idx,
                     line_break_count as usize + 1,
+                    self.1,
                 )),
             }
         } else {
@@ -1523,17 +2448,27 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Non-panicking version of
+    /// [`lines_with_endings_at()`](RopeSlice::lines_with_endings_at).
+    #[inline]
+    pub fn get_lines_with_endings_at(&self, line_idx: usize) -> Option<LinesWithEndings<'a>> {
+        self.get_lines_at(line_idx).map(LinesWithEndings::new)
+    }
+
     /// Non-panicking version of [`chunks_at_byte()`](RopeSlice::chunks_at_byte).
     #[inline]
     pub fn get_chunks_at_byte(&self, byte_idx: usize) -> Option<(Chunks<'a>, usize, usize, usize)> {
         // Bounds check
         if byte_idx <= self.len_bytes() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     let (chunks, chunk_byte_idx, chunk_char_idx, chunk_line_idx) =
                         Chunks::new_with_range_at_byte(
                             node,
@@ -1541,24 +2476,28 @@ impl<'a> RopeSlice<'a> {
                             (start_info.bytes as usize, end_info.bytes as usize),
                             (start_info.chars as usize, end_info.chars as usize),
                             (
-                                start_info.line_breaks as usize,
-                                end_info.line_breaks as usize + 1,
+                                start_info.line_breaks.get(self.1),
+                                end_info.line_breaks.get(self.1) + 1,
                             ),
+                            self.1,
                         );
 
                     Some((
                         chunks,
                         chunk_byte_idx.saturating_sub(start_info.bytes as usize),
                         chunk_char_idx.saturating_sub(start_info.chars as usize),
-                        chunk_line_idx.saturating_sub(start_info.line_breaks as usize),
+                        chunk_line_idx.saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light {
-                    text,
-                    char_count,
-                    line_break_count,
-                    ..
-                }) => {
+                RopeSlice(
+                    RSEnum::Light {
+                        text,
+                        char_count,
+                        line_break_count,
+                        ..
+                    },
+                    _,
+                ) => {
                     let chunks = Chunks::from_str(text, byte_idx == text.len());
 
                     if byte_idx == text.len() {
@@ -1584,11 +2523,14 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if char_idx <= self.len_chars() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     let (chunks, chunk_byte_idx, chunk_char_idx, chunk_line_idx) =
                         Chunks::new_with_range_at_char(
                             node,
@@ -1596,24 +2538,28 @@ impl<'a> RopeSlice<'a> {
                             (start_info.bytes as usize, end_info.bytes as usize),
                             (start_info.chars as usize, end_info.chars as usize),
                             (
-                                start_info.line_breaks as usize,
-                                end_info.line_breaks as usize + 1,
+                                start_info.line_breaks.get(self.1),
+                                end_info.line_breaks.get(self.1) + 1,
                             ),
+                            self.1,
                         );
 
                     Some((
                         chunks,
                         chunk_byte_idx.saturating_sub(start_info.bytes as usize),
                         chunk_char_idx.saturating_sub(start_info.chars as usize),
-                        chunk_line_idx.saturating_sub(start_info.line_breaks as usize),
+                        chunk_line_idx.saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light {
-                    text,
-                    char_count,
-                    line_break_count,
-                    ..
-                }) => {
+                RopeSlice(
+                    RSEnum::Light {
+                        text,
+                        char_count,
+                        line_break_count,
+                        ..
+                    },
+                    _,
+                ) => {
                     let chunks = Chunks::from_str(text, char_idx == char_count as usize);
 
                     if char_idx == char_count as usize {
@@ -1642,11 +2588,14 @@ impl<'a> RopeSlice<'a> {
         // Bounds check
         if line_break_idx <= self.len_lines() {
             match *self {
-                RopeSlice(RSEnum::Full {
-                    node,
-                    start_info,
-                    end_info,
-                }) => {
+                RopeSlice(
+                    RSEnum::Full {
+                        node,
+                        start_info,
+                        end_info,
+                    },
+                    _,
+                ) => {
                     // Get the chunk.
                     let (chunks, chunk_byte_idx, chunk_char_idx, chunk_line_idx) =
                         if line_break_idx == 0 {
@@ -1656,9 +2605,10 @@ impl<'a> RopeSlice<'a> {
                                 (start_info.bytes as usize, end_info.bytes as usize),
                                 (start_info.chars as usize, end_info.chars as usize),
                                 (
-                                    start_info.line_breaks as usize,
-                                    end_info.line_breaks as usize + 1,
+                                    start_info.line_breaks.get(self.1),
+                                    end_info.line_breaks.get(self.1) + 1,
                                 ),
+                                self.1,
                             )
                         } else if line_break_idx == self.len_lines() {
                             Chunks::new_with_range_at_byte(
@@ -1667,35 +2617,40 @@ impl<'a> RopeSlice<'a> {
                                 (start_info.bytes as usize, end_info.bytes as usize),
                                 (start_info.chars as usize, end_info.chars as usize),
                                 (
-                                    start_info.line_breaks as usize,
-                                    end_info.line_breaks as usize + 1,
+                                    start_info.line_breaks.get(self.1),
+                                    end_info.line_breaks.get(self.1) + 1,
                                 ),
+                                self.1,
                             )
                         } else {
                             Chunks::new_with_range_at_line_break(
                                 node,
-                                line_break_idx + start_info.line_breaks as usize,
+                                line_break_idx + start_info.line_breaks.get(self.1),
                                 (start_info.bytes as usize, end_info.bytes as usize),
                                 (start_info.chars as usize, end_info.chars as usize),
                                 (
-                                    start_info.line_breaks as usize,
-                                    end_info.line_breaks as usize + 1,
+                                    start_info.line_breaks.get(self.1),
+                                    end_info.line_breaks.get(self.1) + 1,
                                 ),
+                                self.1,
                             )
                         };
                     Some((
                         chunks,
                         chunk_byte_idx.saturating_sub(start_info.bytes as usize),
                         chunk_char_idx.saturating_sub(start_info.chars as usize),
-                        chunk_line_idx.saturating_sub(start_info.line_breaks as usize),
+                        chunk_line_idx.saturating_sub(start_info.line_breaks.get(self.1)),
                     ))
                 }
-                RopeSlice(RSEnum::Light {
-                    text,
-                    char_count,
-                    line_break_count,
-                    ..
-                }) => {
+                RopeSlice(
+                    RSEnum::Light {
+                        text,
+                        char_count,
+                        line_break_count,
+                        ..
+                    },
+                    _,
+                ) => {
                     let chunks =
                         Chunks::from_str(text, line_break_idx == line_break_count as usize);
 
@@ -1715,6 +2670,34 @@ impl<'a> RopeSlice<'a> {
             None
         }
     }
+
+    /// Returns the char index of the given utf16 code unit, which must be
+    /// on a char boundary.
+    fn utf16_cu_to_char_boundary(&self, utf16_cu_idx: usize) -> Result<usize> {
+        let char_idx = self.try_utf16_cu_to_char(utf16_cu_idx)?;
+        if self.char_to_utf16_cu(char_idx) == utf16_cu_idx {
+            Ok(char_idx)
+        } else {
+            Err(Error::Utf16IndexNotCharBoundary(utf16_cu_idx))
+        }
+    }
+
+    /// Converts the given utf16 code unit range to a char range, checking
+    /// that it's valid and lines up with char boundaries.
+    fn utf16_cu_range_to_char_range<R>(&self, utf16_cu_range: R) -> Result<Range<usize>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = start_bound_to_num(utf16_cu_range.start_bound()).unwrap_or(0);
+        let end =
+            end_bound_to_num(utf16_cu_range.end_bound()).unwrap_or_else(|| self.len_utf16_cu());
+        if start > end && start <= self.len_utf16_cu() {
+            return Err(Error::Utf16RangeInvalid(start, end));
+        }
+        let start = self.utf16_cu_to_char_boundary(start)?;
+        let end = self.utf16_cu_to_char_boundary(end)?;
+        Ok(start..end)
+    }
 }
 
 //==============================================================
@@ -1737,12 +2720,7 @@ impl<'a> RopeSlice<'a> {
 impl<'a> From<&'a str> for RopeSlice<'a> {
     #[inline]
     fn from(text: &'a str) -> Self {
-        RopeSlice(RSEnum::Light {
-            text: text,
-            char_count: count_chars(text) as Count,
-            utf16_surrogate_count: count_utf16_surrogates(text) as Count,
-            line_break_count: count_line_breaks(text) as Count,
-        })
+        RopeSlice::from_str_with_mode(text, LineBreakMode::default())
     }
 }
 
@@ -1842,7 +2820,7 @@ impl<'a, 'b> std::cmp::PartialEq<&'b str> for RopeSlice<'a> {
     #[inline]
     fn eq(&self, other: &&'b str) -> bool {
         match *self {
-            RopeSlice(RSEnum::Full { .. }) => {
+            RopeSlice(RSEnum::Full { .. }, _) => {
                 if self.len_bytes() != other.len() {
                     return false;
                 }
@@ -1859,7 +2837,7 @@ impl<'a, 'b> std::cmp::PartialEq<&'b str> for RopeSlice<'a> {
 
                 return true;
             }
-            RopeSlice(RSEnum::Light { text, .. }) => {
+            RopeSlice(RSEnum::Light { text, .. }, _) => {
                 return text == *other;
             }
         }
@@ -2996,6 +3974,29 @@ mod tests {
         s.byte_slice(43..);
     }
 
+    #[test]
+    fn slice_utf16_01() {
+        let r = Rope::from_str(TEXT_EMOJI);
+        let s = r.slice(13..);
+        assert_eq!(s.slice_utf16(..2), "  ");
+        assert_eq!(s.slice_utf16(19..21), "\u{1F438}");
+        assert!(s.get_slice_utf16(20..).is_none());
+        assert!(s.get_slice_utf16(..1000).is_none());
+        assert_eq!(s.slice_utf16(..), s);
+    }
+
+    #[test]
+    fn chars_at_utf16_01() {
+        let r = Rope::from_str(TEXT_EMOJI);
+        let s = r.slice(13..);
+        let mut chars = s.chars_at_utf16(21);
+        assert_eq!(chars.next(), Some(' '));
+        assert_eq!(chars.prev(), Some(' '));
+        assert_eq!(chars.prev(), Some('\u{1F438}'));
+        assert!(s.get_chars_at_utf16(20).is_none());
+        assert_eq!(s.chars_at_utf16(s.len_utf16_cu()).next(), None);
+    }
+
     #[test]
     fn eq_str_01() {
         let r = Rope::from_str(TEXT);
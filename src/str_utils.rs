@@ -17,7 +17,6 @@ use str_indices::lines_crlf as lines;
 #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
 use str_indices::lines_lf as lines;
 
-pub(crate) use self::lines::count_breaks as count_line_breaks;
 pub use self::lines::from_byte_idx as byte_to_line_idx;
 pub use self::lines::to_byte_idx as line_to_byte_idx;
 
@@ -59,11 +58,90 @@ pub(crate) fn utf16_code_unit_to_char_idx(text: &str, utf16_idx: usize) -> usize
     str_indices::chars::from_byte_idx(text, str_indices::utf16::to_byte_idx(text, utf16_idx))
 }
 
+/// Which characters are recognized as line breaks.
+///
+/// Every `Rope` and `RopeSlice` has a line break mode, which all of its
+/// line-based APIs respect.  The default is selected by the `cr_lines` and
+/// `unicode_lines` cargo features, but can be changed per-rope at runtime
+/// with [`Rope::set_line_break_mode()`](crate::Rope::set_line_break_mode).
+///
+/// See the [crate-level docs](crate#a-note-about-line-breaks) for details
+/// about each mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LineBreakMode {
+    /// Recognize LF and CRLF.
+    Lf,
+    /// Recognize LF, CRLF, and CR.
+    Crlf,
+    /// Recognize all line breaks specified in Unicode Annex #14.
+    Unicode,
+}
+
+impl LineBreakMode {
+    /// Counts the line breaks in `text`.
+    #[inline]
+    pub(crate) fn count_breaks(self, text: &str) -> usize {
+        match self {
+            LineBreakMode::Lf => str_indices::lines_lf::count_breaks(text),
+            LineBreakMode::Crlf => str_indices::lines_crlf::count_breaks(text),
+            LineBreakMode::Unicode => str_indices::lines::count_breaks(text),
+        }
+    }
+
+    /// Converts from byte-index to line-index in `text`.
+    #[inline]
+    pub(crate) fn byte_to_line_idx(self, text: &str, byte_idx: usize) -> usize {
+        match self {
+            LineBreakMode::Lf => str_indices::lines_lf::from_byte_idx(text, byte_idx),
+            LineBreakMode::Crlf => str_indices::lines_crlf::from_byte_idx(text, byte_idx),
+            LineBreakMode::Unicode => str_indices::lines::from_byte_idx(text, byte_idx),
+        }
+    }
+
+    /// Converts from line-index to byte-index in `text`.
+    #[inline]
+    pub(crate) fn line_to_byte_idx(self, text: &str, line_idx: usize) -> usize {
+        match self {
+            LineBreakMode::Lf => str_indices::lines_lf::to_byte_idx(text, line_idx),
+            LineBreakMode::Crlf => str_indices::lines_crlf::to_byte_idx(text, line_idx),
+            LineBreakMode::Unicode => str_indices::lines::to_byte_idx(text, line_idx),
+        }
+    }
+
+    /// Converts from char-index to line-index in `text`.
+    #[inline]
+    pub(crate) fn char_to_line_idx(self, text: &str, char_idx: usize) -> usize {
+        self.byte_to_line_idx(text, str_indices::chars::to_byte_idx(text, char_idx))
+    }
+
+    /// Converts from line-index to char-index in `text`.
+    #[inline]
+    pub(crate) fn line_to_char_idx(self, text: &str, line_idx: usize) -> usize {
+        str_indices::chars::from_byte_idx(text, self.line_to_byte_idx(text, line_idx))
+    }
+}
+
+impl Default for LineBreakMode {
+    #[inline]
+    fn default() -> Self {
+        #[cfg(feature = "unicode_lines")]
+        return LineBreakMode::Unicode;
+
+        #[cfg(all(feature = "cr_lines", not(feature = "unicode_lines")))]
+        return LineBreakMode::Crlf;
+
+        #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
+        return LineBreakMode::Lf;
+    }
+}
+
 /// Returns the byte index of the start of the last line of the passed text.
 ///
 /// Note: if the text ends in a line break, that means the last line is
 /// an empty line that starts at the end of the text.
-pub(crate) fn last_line_start_byte_idx(text: &str) -> usize {
+pub(crate) fn last_line_start_byte_idx(text: &str, mode: LineBreakMode) -> usize {
+    let cr_lines = mode != LineBreakMode::Lf;
+    let unicode_lines = mode == LineBreakMode::Unicode;
     let mut itr = text.bytes().enumerate().rev();
 
     while let Some((idx, byte)) = itr.next() {
@@ -71,24 +149,18 @@ pub(crate) fn last_line_start_byte_idx(text: &str) -> usize {
             0x0A => {
                 return idx + 1;
             }
-            0x0D => {
-                #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
+            0x0D if cr_lines => {
                 return idx + 1;
             }
-            0x0B | 0x0C => {
-                #[cfg(feature = "unicode_lines")]
+            0x0B | 0x0C if unicode_lines => {
                 return idx + 1;
             }
-            0x85 =>
-            {
-                #[cfg(feature = "unicode_lines")]
+            0x85 if unicode_lines => {
                 if let Some((_, 0xC2)) = itr.next() {
                     return idx + 1;
                 }
             }
-            0xA8 | 0xA9 =>
-            {
-                #[cfg(feature = "unicode_lines")]
+            0xA8 | 0xA9 if unicode_lines => {
                 if let Some((_, 0x80)) = itr.next() {
                     if let Some((_, 0xE2)) = itr.next() {
                         return idx + 1;
@@ -106,7 +178,7 @@ pub(crate) fn last_line_start_byte_idx(text: &str) -> usize {
 ///
 /// If the string doesn't end in a line break, returns the string unchanged.
 #[inline]
-pub(crate) fn trim_line_break(text: &str) -> &str {
+pub(crate) fn trim_line_break(text: &str, mode: LineBreakMode) -> &str {
     if text.is_empty() {
         return "";
     }
@@ -120,22 +192,21 @@ pub(crate) fn trim_line_break(text: &str) -> &str {
     let tail = &text[i..];
 
     // Check if it's one of the fancy unicode line breaks.
-    #[cfg(feature = "unicode_lines")]
-    if matches!(
-        tail,
-        "\u{000B}" | "\u{000C}" | "\u{0085}" | "\u{2028}" | "\u{2029}"
-    ) {
+    if mode == LineBreakMode::Unicode
+        && matches!(
+            tail,
+            "\u{000B}" | "\u{000C}" | "\u{0085}" | "\u{2028}" | "\u{2029}"
+        )
+    {
         return &text[..i];
     }
 
-    #[cfg(feature = "cr_lines")]
-    if tail == "\u{000D}" {
+    if mode != LineBreakMode::Lf && tail == "\u{000D}" {
         return &text[..i];
     }
 
     if tail == "\u{000A}" {
-        #[cfg(feature = "cr_lines")]
-        if i > 0 && text.as_bytes()[i - 1] == 0xd {
+        if mode != LineBreakMode::Lf && i > 0 && text.as_bytes()[i - 1] == 0xd {
             return &text[..(i - 1)];
         }
 
@@ -147,7 +218,7 @@ pub(crate) fn trim_line_break(text: &str) -> &str {
 
 /// Returns whether the given string ends in a line break or not.
 #[inline]
-pub(crate) fn ends_with_line_break(text: &str) -> bool {
+pub(crate) fn ends_with_line_break(text: &str, mode: LineBreakMode) -> bool {
     if text.is_empty() {
         return false;
     }
@@ -159,237 +230,233 @@ pub(crate) fn ends_with_line_break(text: &str) -> bool {
     }
 
     // Check if the last codepoint is a line break.
-    #[cfg(feature = "unicode_lines")]
-    return matches!(
-        &text[i..],
-        "\u{000A}" | "\u{000B}" | "\u{000C}" | "\u{000D}" | "\u{0085}" | "\u{2028}" | "\u{2029}"
-    );
-
-    #[cfg(all(feature = "cr_lines", not(feature = "unicode_lines")))]
-    return matches!(&text[i..], "\u{000A}" | "\u{000D}");
-
-    #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
-    return &text[i..] == "\u{000A}";
+    match mode {
+        LineBreakMode::Lf => &text[i..] == "\u{000A}",
+        LineBreakMode::Crlf => matches!(&text[i..], "\u{000A}" | "\u{000D}"),
+        LineBreakMode::Unicode => matches!(
+            &text[i..],
+            "\u{000A}"
+                | "\u{000B}"
+                | "\u{000C}"
+                | "\u{000D}"
+                | "\u{0085}"
+                | "\u{2028}"
+                | "\u{2029}"
+        ),
+    }
 }
 
 //======================================================================
 
 #[cfg(test)]
 mod tests {
+    use super::LineBreakMode::{Crlf, Lf, Unicode};
     use super::*;
 
-    #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
     #[test]
     fn last_line_start_byte_idx_lf_01() {
-        assert_eq!(0, last_line_start_byte_idx(""));
-        assert_eq!(0, last_line_start_byte_idx("Hi"));
-
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{000B}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{000C}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{000D}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{0085}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{2028}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{2029}there."));
+        assert_eq!(0, last_line_start_byte_idx("", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi", Lf));
+
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{000B}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{000C}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{000D}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{0085}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{2028}there.", Lf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{2029}there.", Lf));
     }
 
-    #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
     #[test]
     fn last_line_start_byte_idx_lf_02() {
         let mut text = "\u{000A}Hello\u{000D}\u{000A}\u{000D}せ\u{000B}か\u{000C}い\u{0085}. \
                         There\u{2028}is something.\u{2029}";
 
         assert_eq!(48, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Lf), Lf)];
         assert_eq!(8, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Lf), Lf)];
         assert_eq!(1, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Lf), Lf)];
         assert_eq!(0, text.len());
     }
 
-    #[cfg(all(feature = "cr_lines", not(feature = "unicode_lines")))]
     #[test]
     fn last_line_start_byte_idx_crlf_01() {
-        assert_eq!(0, last_line_start_byte_idx(""));
-        assert_eq!(0, last_line_start_byte_idx("Hi"));
-
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{000B}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{000C}there."));
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000D}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{0085}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{2028}there."));
-        assert_eq!(0, last_line_start_byte_idx("Hi\u{2029}there."));
+        assert_eq!(0, last_line_start_byte_idx("", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi", Crlf));
+
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there.", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{000B}there.", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{000C}there.", Crlf));
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000D}there.", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{0085}there.", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{2028}there.", Crlf));
+        assert_eq!(0, last_line_start_byte_idx("Hi\u{2029}there.", Crlf));
     }
 
-    #[cfg(all(feature = "cr_lines", not(feature = "unicode_lines")))]
     #[test]
     fn last_line_start_byte_idx_crlf_02() {
         let mut text = "\u{000A}Hello\u{000D}\u{000A}\u{000D}せ\u{000B}か\u{000C}い\u{0085}. \
                         There\u{2028}is something.\u{2029}";
 
         assert_eq!(48, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Crlf), Crlf)];
         assert_eq!(9, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Crlf), Crlf)];
         assert_eq!(8, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Crlf), Crlf)];
         assert_eq!(1, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Crlf), Crlf)];
         assert_eq!(0, text.len());
     }
 
-    #[cfg(feature = "unicode_lines")]
     #[test]
     fn last_line_start_byte_idx_unicode_01() {
-        assert_eq!(0, last_line_start_byte_idx(""));
-        assert_eq!(0, last_line_start_byte_idx("Hi"));
-
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there."));
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000B}there."));
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000C}there."));
-        assert_eq!(3, last_line_start_byte_idx("Hi\u{000D}there."));
-        assert_eq!(4, last_line_start_byte_idx("Hi\u{0085}there."));
-        assert_eq!(5, last_line_start_byte_idx("Hi\u{2028}there."));
-        assert_eq!(5, last_line_start_byte_idx("Hi\u{2029}there."));
+        assert_eq!(0, last_line_start_byte_idx("", Unicode));
+        assert_eq!(0, last_line_start_byte_idx("Hi", Unicode));
+
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000A}there.", Unicode));
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000B}there.", Unicode));
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000C}there.", Unicode));
+        assert_eq!(3, last_line_start_byte_idx("Hi\u{000D}there.", Unicode));
+        assert_eq!(4, last_line_start_byte_idx("Hi\u{0085}there.", Unicode));
+        assert_eq!(5, last_line_start_byte_idx("Hi\u{2028}there.", Unicode));
+        assert_eq!(5, last_line_start_byte_idx("Hi\u{2029}there.", Unicode));
     }
 
-    #[cfg(feature = "unicode_lines")]
     #[test]
     fn last_line_start_byte_idx_unicode_02() {
         let mut text = "\u{000A}Hello\u{000D}\u{000A}\u{000D}せ\u{000B}か\u{000C}い\u{0085}. \
                         There\u{2028}is something.\u{2029}";
 
         assert_eq!(48, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(32, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(22, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(17, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(13, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(9, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(8, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(1, text.len());
-        text = &text[..last_line_start_byte_idx(trim_line_break(text))];
+        text = &text[..last_line_start_byte_idx(trim_line_break(text, Unicode), Unicode)];
         assert_eq!(0, text.len());
     }
 
-    #[cfg(not(any(feature = "cr_lines", feature = "unicode_lines")))]
     #[test]
     fn trim_line_break_lf_01() {
-        assert_eq!("", trim_line_break(""));
-        assert_eq!("Hi", trim_line_break("Hi"));
-
-        assert_eq!("Hi", trim_line_break("Hi\u{000A}"));
-        assert_eq!("Hi\u{000B}", trim_line_break("Hi\u{000B}"));
-        assert_eq!("Hi\u{000C}", trim_line_break("Hi\u{000C}"));
-        assert_eq!("Hi\u{000D}", trim_line_break("Hi\u{000D}"));
-        assert_eq!("Hi\u{0085}", trim_line_break("Hi\u{0085}"));
-        assert_eq!("Hi\u{2028}", trim_line_break("Hi\u{2028}"));
-        assert_eq!("Hi\u{2029}", trim_line_break("Hi\u{2029}"));
-
-        assert_eq!("\r", trim_line_break("\r\n"));
-        assert_eq!("Hi\r", trim_line_break("Hi\r\n"));
+        assert_eq!("", trim_line_break("", Lf));
+        assert_eq!("Hi", trim_line_break("Hi", Lf));
+
+        assert_eq!("Hi", trim_line_break("Hi\u{000A}", Lf));
+        assert_eq!("Hi\u{000B}", trim_line_break("Hi\u{000B}", Lf));
+        assert_eq!("Hi\u{000C}", trim_line_break("Hi\u{000C}", Lf));
+        assert_eq!("Hi\u{000D}", trim_line_break("Hi\u{000D}", Lf));
+        assert_eq!("Hi\u{0085}", trim_line_break("Hi\u{0085}", Lf));
+        assert_eq!("Hi\u{2028}", trim_line_break("Hi\u{2028}", Lf));
+        assert_eq!("Hi\u{2029}", trim_line_break("Hi\u{2029}", Lf));
+
+        assert_eq!("\r", trim_line_break("\r\n", Lf));
+        assert_eq!("Hi\r", trim_line_break("Hi\r\n", Lf));
     }
 
-    #[cfg(all(feature = "cr_lines", not(feature = "unicode_lines")))]
     #[test]
     fn trim_line_break_crlf_01() {
-        assert_eq!("", trim_line_break(""));
-        assert_eq!("Hi", trim_line_break("Hi"));
-
-        assert_eq!("Hi", trim_line_break("Hi\u{000A}"));
-        assert_eq!("Hi\u{000B}", trim_line_break("Hi\u{000B}"));
-        assert_eq!("Hi\u{000C}", trim_line_break("Hi\u{000C}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{000D}"));
-        assert_eq!("Hi\u{0085}", trim_line_break("Hi\u{0085}"));
-        assert_eq!("Hi\u{2028}", trim_line_break("Hi\u{2028}"));
-        assert_eq!("Hi\u{2029}", trim_line_break("Hi\u{2029}"));
-
-        assert_eq!("", trim_line_break("\r\n"));
-        assert_eq!("Hi", trim_line_break("Hi\r\n"));
+        assert_eq!("", trim_line_break("", Crlf));
+        assert_eq!("Hi", trim_line_break("Hi", Crlf));
+
+        assert_eq!("Hi", trim_line_break("Hi\u{000A}", Crlf));
+        assert_eq!("Hi\u{000B}", trim_line_break("Hi\u{000B}", Crlf));
+        assert_eq!("Hi\u{000C}", trim_line_break("Hi\u{000C}", Crlf));
+        assert_eq!("Hi", trim_line_break("Hi\u{000D}", Crlf));
+        assert_eq!("Hi\u{0085}", trim_line_break("Hi\u{0085}", Crlf));
+        assert_eq!("Hi\u{2028}", trim_line_break("Hi\u{2028}", Crlf));
+        assert_eq!("Hi\u{2029}", trim_line_break("Hi\u{2029}", Crlf));
+
+        assert_eq!("", trim_line_break("\r\n", Crlf));
+        assert_eq!("Hi", trim_line_break("Hi\r\n", Crlf));
     }
 
-    #[cfg(feature = "unicode_lines")]
     #[test]
     fn trim_line_break_unicode_01() {
-        assert_eq!("", trim_line_break(""));
-        assert_eq!("Hi", trim_line_break("Hi"));
-
-        assert_eq!("Hi", trim_line_break("Hi\u{000A}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{000B}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{000C}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{000D}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{0085}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{2028}"));
-        assert_eq!("Hi", trim_line_break("Hi\u{2029}"));
-
-        assert_eq!("", trim_line_break("\r\n"));
-        assert_eq!("Hi", trim_line_break("Hi\r\n"));
+        assert_eq!("", trim_line_break("", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi", Unicode));
+
+        assert_eq!("Hi", trim_line_break("Hi\u{000A}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{000B}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{000C}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{000D}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{0085}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{2028}", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\u{2029}", Unicode));
+
+        assert_eq!("", trim_line_break("\r\n", Unicode));
+        assert_eq!("Hi", trim_line_break("Hi\r\n", Unicode));
     }
 
     #[test]
     fn ends_with_line_break_01() {
-        assert!(ends_with_line_break("\n"));
+        for &mode in &[Lf, Crlf, Unicode] {
+            assert!(ends_with_line_break("\n", mode));
+        }
 
-        #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
-        assert!(ends_with_line_break("\r"));
+        assert!(!ends_with_line_break("\r", Lf));
+        assert!(ends_with_line_break("\r", Crlf));
+        assert!(ends_with_line_break("\r", Unicode));
 
-        #[cfg(feature = "unicode_lines")]
-        {
-            assert!(ends_with_line_break("\u{000A}"));
-            assert!(ends_with_line_break("\u{000B}"));
-            assert!(ends_with_line_break("\u{000C}"));
-            assert!(ends_with_line_break("\u{000D}"));
-            assert!(ends_with_line_break("\u{0085}"));
-            assert!(ends_with_line_break("\u{2028}"));
-            assert!(ends_with_line_break("\u{2029}"));
+        for lb in &["\u{000B}", "\u{000C}", "\u{0085}", "\u{2028}", "\u{2029}"] {
+            assert!(!ends_with_line_break(lb, Lf));
+            assert!(!ends_with_line_break(lb, Crlf));
+            assert!(ends_with_line_break(lb, Unicode));
         }
     }
 
     #[test]
     fn ends_with_line_break_02() {
-        assert!(ends_with_line_break("Hi there!\n"));
+        for &mode in &[Lf, Crlf, Unicode] {
+            assert!(ends_with_line_break("Hi there!\n", mode));
+        }
 
-        #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
-        assert!(ends_with_line_break("Hi there!\r"));
+        assert!(!ends_with_line_break("Hi there!\r", Lf));
+        assert!(ends_with_line_break("Hi there!\r", Crlf));
+        assert!(ends_with_line_break("Hi there!\r", Unicode));
 
-        #[cfg(feature = "unicode_lines")]
-        {
-            assert!(ends_with_line_break("Hi there!\u{000A}"));
-            assert!(ends_with_line_break("Hi there!\u{000B}"));
-            assert!(ends_with_line_break("Hi there!\u{000C}"));
-            assert!(ends_with_line_break("Hi there!\u{000D}"));
-            assert!(ends_with_line_break("Hi there!\u{0085}"));
-            assert!(ends_with_line_break("Hi there!\u{2028}"));
-            assert!(ends_with_line_break("Hi there!\u{2029}"));
-        }
+        assert!(!ends_with_line_break("Hi there!\u{2028}", Crlf));
+        assert!(ends_with_line_break("Hi there!\u{000B}", Unicode));
+        assert!(ends_with_line_break("Hi there!\u{000C}", Unicode));
+        assert!(ends_with_line_break("Hi there!\u{0085}", Unicode));
+        assert!(ends_with_line_break("Hi there!\u{2028}", Unicode));
+        assert!(ends_with_line_break("Hi there!\u{2029}", Unicode));
     }
 
     #[test]
     fn ends_with_line_break_03() {
-        assert!(!ends_with_line_break(""));
-        assert!(!ends_with_line_break("a"));
-        assert!(!ends_with_line_break("Hi there!"));
+        for &mode in &[Lf, Crlf, Unicode] {
+            assert!(!ends_with_line_break("", mode));
+            assert!(!ends_with_line_break("a", mode));
+            assert!(!ends_with_line_break("Hi there!", mode));
+        }
     }
 
     #[test]
     fn ends_with_line_break_04() {
-        assert!(!ends_with_line_break("\na"));
-        assert!(!ends_with_line_break("\ra"));
-        assert!(!ends_with_line_break("\u{000A}a"));
-        assert!(!ends_with_line_break("\u{000B}a"));
-        assert!(!ends_with_line_break("\u{000C}a"));
-        assert!(!ends_with_line_break("\u{000D}a"));
-        assert!(!ends_with_line_break("\u{0085}a"));
-        assert!(!ends_with_line_break("\u{2028}a"));
-        assert!(!ends_with_line_break("\u{2029}a"));
+        for &mode in &[Lf, Crlf, Unicode] {
+            assert!(!ends_with_line_break("\na", mode));
+            assert!(!ends_with_line_break("\ra", mode));
+            assert!(!ends_with_line_break("\u{000A}a", mode));
+            assert!(!ends_with_line_break("\u{000B}a", mode));
+            assert!(!ends_with_line_break("\u{000C}a", mode));
+            assert!(!ends_with_line_break("\u{000D}a", mode));
+            assert!(!ends_with_line_break("\u{0085}a", mode));
+            assert!(!ends_with_line_break("\u{2028}a", mode));
+            assert!(!ends_with_line_break("\u{2029}a", mode));
+        }
     }
 
     #[test]
@@ -0,0 +1,340 @@
+use std::ops::{Range, RangeBounds};
+
+use crate::change_set::ChangeSet;
+use crate::rope::Rope;
+use crate::str_utils::count_chars;
+use crate::{end_bound_to_num, start_bound_to_num, Error, Result};
+
+/// Something that needs to follow the edits made to a `Rope`, such as a
+/// [`MarkerSet`](crate::MarkerSet).
+///
+/// Observers are normally driven by a [`TrackedRope`], which calls them
+/// after every edit it makes.
+///
+/// Tuples of up to four observers are observers themselves, so that a
+/// single `TrackedRope` can drive several of them.
+pub trait EditObserver {
+    /// Called after the text in `char_range` has been replaced with
+    /// `len_chars` chars of new text.
+    ///
+    /// Insertions have an empty `char_range`, and removals a `len_chars`
+    /// of zero.  `text` is the text after the edit.
+    fn replaced(&mut self, text: &Rope, char_range: Range<usize>, len_chars: usize);
+
+    /// Called after `changes` have been applied to the text.
+    ///
+    /// `text` is the text after the changes.
+    fn changed(&mut self, text: &Rope, changes: &ChangeSet);
+}
+
+macro_rules! impl_edit_observer_for_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: EditObserver),+> EditObserver for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn replaced(&mut self, text: &Rope, char_range: Range<usize>, len_chars: usize) {
+                let ($(ref mut $name,)+) = *self;
+                $($name.replaced(text, char_range.clone(), len_chars);)+
+            }
+
+            #[allow(non_snake_case)]
+            fn changed(&mut self, text: &Rope, changes: &ChangeSet) {
+                let ($(ref mut $name,)+) = *self;
+                $($name.changed(text, changes);)+
+            }
+        }
+    };
+}
+
+impl_edit_observer_for_tuple!(A, B);
+impl_edit_observer_for_tuple!(A, B, C);
+impl_edit_observer_for_tuple!(A, B, C, D);
+
+/// A `Rope` that keeps an [`EditObserver`] up to date with its edits.
+///
+/// The text can only be edited through the `TrackedRope`'s own methods,
+/// and every edit is passed on to the observer before the method returns.
+/// This makes it impossible to edit the text and forget to update, e.g.,
+/// a [`MarkerSet`](crate::MarkerSet) that points into it.
+///
+/// The text is available for reading through [`text()`](TrackedRope::text),
+/// and the observer through [`observer()`](TrackedRope::observer) and
+/// [`observer_mut()`](TrackedRope::observer_mut).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Gravity, MarkerSet, RemovePolicy, Rope, TrackedRope};
+/// #
+/// let mut doc = TrackedRope::new(Rope::from_str("Hello world!"), MarkerSet::new());
+/// let m = doc
+///     .observer_mut()
+///     .add(6, Gravity::Left, RemovePolicy::Collapse);
+///
+/// doc.insert(0, "Oh, ");
+/// assert_eq!(doc.observer().get(m), Some(10));
+/// assert_eq!(doc.text().char(10), 'w');
+///
+/// doc.replace(0..4, "");
+/// assert_eq!(doc.observer().get(m), Some(6));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TrackedRope<O: EditObserver> {
+    text: Rope,
+    observer: O,
+}
+
+impl<O: EditObserver> TrackedRope<O> {
+    /// Creates a `TrackedRope` from `text` and an `observer` that is
+    /// already in sync with it.
+    #[inline]
+    pub fn new(text: Rope, observer: O) -> Self {
+        TrackedRope {
+            text: text,
+            observer: observer,
+        }
+    }
+
+    /// Splits the `TrackedRope` back into its text and observer.
+    #[inline]
+    pub fn into_parts(self) -> (Rope, O) {
+        (self.text, self.observer)
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// The text.
+    #[inline]
+    pub fn text(&self) -> &Rope {
+        &self.text
+    }
+
+    /// The observer.
+    #[inline]
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    /// The observer, mutably, e.g. for adding markers.
+    #[inline]
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit methods
+
+    /// Inserts `text` at char index `char_idx`, and updates the observer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[inline]
+    pub fn insert(&mut self, char_idx: usize, text: &str) {
+        self.try_insert(char_idx, text).unwrap()
+    }
+
+    /// Removes the text in the given char index range, and updates the
+    /// observer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    #[inline]
+    pub fn remove<R>(&mut self, char_range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_remove(char_range).unwrap()
+    }
+
+    /// Replaces the text in the given char index range with `text`, and
+    /// updates the observer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    #[inline]
+    pub fn replace<R>(&mut self, char_range: R, text: &str)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.try_replace(char_range, text).unwrap()
+    }
+
+    /// Applies `changes` to the text, and updates the observer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`ChangeSet::apply()`], in which case
+    /// neither the text nor the observer are modified.
+    pub fn apply_changes(&mut self, changes: &ChangeSet) -> Result<()> {
+        changes.apply(&mut self.text)?;
+        self.observer.changed(&self.text, changes);
+        Ok(())
+    }
+}
+
+/// # Non-Panicking
+///
+/// The methods in this impl block provide non-panicking versions of
+/// `TrackedRope`'s panicking methods.  They return `Result::Err()` when
+/// their panicking counterparts would have panicked, in which case the
+/// observer is not updated.
+impl<O: EditObserver> TrackedRope<O> {
+    /// Non-panicking version of [`insert()`](TrackedRope::insert).
+    pub fn try_insert(&mut self, char_idx: usize, text: &str) -> Result<()> {
+        self.text.try_insert(char_idx, text)?;
+        self.observer
+            .replaced(&self.text, char_idx..char_idx, count_chars(text));
+        Ok(())
+    }
+
+    /// Non-panicking version of [`remove()`](TrackedRope::remove).
+    pub fn try_remove<R>(&mut self, char_range: R) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = self.char_range(char_range)?;
+        self.text.try_remove(char_range.clone())?;
+        self.observer.replaced(&self.text, char_range, 0);
+        Ok(())
+    }
+
+    /// Non-panicking version of [`replace()`](TrackedRope::replace).
+    pub fn try_replace<R>(&mut self, char_range: R, text: &str) -> Result<()>
+    where
+        R: RangeBounds<usize>,
+    {
+        let char_range = self.char_range(char_range)?;
+        self.text.try_replace(char_range.clone(), text)?;
+        self.observer
+            .replaced(&self.text, char_range, count_chars(text));
+        Ok(())
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Resolves `char_range` against the text, with the same bounds
+    /// checks as `Rope::try_remove()`.
+    fn char_range<R>(&self, char_range: R) -> Result<Range<usize>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start_opt = start_bound_to_num(char_range.start_bound());
+        let end_opt = end_bound_to_num(char_range.end_bound());
+        let start = start_opt.unwrap_or(0);
+        let end = end_opt.unwrap_or_else(|| self.text.len_chars());
+        if end.max(start) > self.text.len_chars() {
+            Err(Error::CharRangeOutOfBounds(
+                start_opt,
+                end_opt,
+                self.text.len_chars(),
+            ))
+        } else if start > end {
+            Err(Error::CharRangeInvalid(start, end))
+        } else {
+            Ok(start..end)
+        }
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Expand, Gravity, IntervalSet, MarkerSet, RemovePolicy};
+
+    #[test]
+    fn tracked_01() {
+        let mut doc = TrackedRope::new(Rope::from_str("Hello world!"), MarkerSet::new());
+        let a = doc
+            .observer_mut()
+            .add(6, Gravity::Left, RemovePolicy::Collapse);
+        let b = doc
+            .observer_mut()
+            .add(8, Gravity::Left, RemovePolicy::Delete);
+
+        doc.insert(6, "big ");
+        assert_eq!(doc.text(), "Hello big world!");
+        assert_eq!(doc.observer().get(a), Some(6));
+        assert_eq!(doc.observer().get(b), Some(12));
+
+        doc.replace(10..15, "there");
+        assert_eq!(doc.text(), "Hello big there!");
+        assert_eq!(doc.observer().get(a), Some(6));
+        assert_eq!(doc.observer().get(b), None);
+
+        doc.remove(..6);
+        assert_eq!(doc.text(), "big there!");
+        assert_eq!(doc.observer().get(a), Some(0));
+    }
+
+    #[test]
+    fn tracked_02() {
+        let mut doc = TrackedRope::new(
+            Rope::from_str("Hello world!"),
+            (MarkerSet::new(), MarkerSet::new()),
+        );
+        let a = doc
+            .observer_mut()
+            .0
+            .add(5, Gravity::Left, RemovePolicy::Collapse);
+        let b = doc
+            .observer_mut()
+            .1
+            .add(5, Gravity::Right, RemovePolicy::Collapse);
+
+        let mut changes = ChangeSet::new(doc.text());
+        changes
+            .retain(5)
+            .insert(",")
+            .retain(1)
+            .delete(5)
+            .insert("there");
+        doc.apply_changes(&changes).unwrap();
+        assert_eq!(doc.text(), "Hello, there!");
+        assert_eq!(doc.observer().0.get(a), Some(5));
+        assert_eq!(doc.observer().1.get(b), Some(6));
+    }
+
+    #[test]
+    fn tracked_03() {
+        let mut doc = TrackedRope::new(Rope::from_str("Hello"), MarkerSet::new());
+        let a = doc
+            .observer_mut()
+            .add(3, Gravity::Left, RemovePolicy::Collapse);
+
+        assert!(doc.try_insert(6, "!").is_err());
+        assert!(doc.try_remove(2..7).is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let result = doc.try_replace(4..2, "!");
+        assert!(result.is_err());
+
+        let changes = ChangeSet::new(&Rope::from_str("Hi"));
+        assert!(matches!(
+            doc.apply_changes(&changes),
+            Err(Error::ChangeSetLengthMismatch(2, 5))
+        ));
+
+        assert_eq!(doc.text(), "Hello");
+        assert_eq!(doc.observer().get(a), Some(3));
+    }
+
+    #[test]
+    fn tracked_04() {
+        let mut doc = TrackedRope::new(Rope::from_str("let x = 1;"), IntervalSet::new());
+        doc.observer_mut().insert(4..5, Expand::End, "ident");
+        doc.observer_mut().insert(8..9, Expand::Neither, "number");
+
+        doc.insert(5, "yz");
+        doc.replace(10..11, "2.5");
+        let spans: Vec<_> = doc.observer().iter().collect();
+        assert_eq!(doc.text(), "let xyz = 2.5;");
+        assert_eq!(spans, [(4..7, &"ident")]);
+    }
+}
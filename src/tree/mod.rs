@@ -6,7 +6,7 @@ mod text_info;
 pub(crate) use self::node::Node;
 pub(crate) use self::node_children::NodeChildren;
 pub(crate) use self::node_text::NodeText;
-pub(crate) use self::text_info::TextInfo;
+pub(crate) use self::text_info::{LineBreaks, TextInfo, Unit};
 
 // Type used for storing tree metadata, such as byte and char length.
 pub(crate) type Count = u64;
@@ -14,6 +14,7 @@ pub(crate) type Count = u64;
 // Real constants used in release builds.
 #[cfg(not(any(test, feature = "small_chunks")))]
 mod constants {
+    use super::node_children::Summaries;
     use super::{Node, TextInfo};
     use smallvec::SmallVec;
     use std::{
@@ -62,8 +63,14 @@ mod constants {
             info_list_align - node_list_align
         };
 
-        // The -NODE_CHILDREN_ALIGN is for the `len` field in `NodeChildrenInternal`.
-        let target_size = TARGET_TOTAL_SIZE - START_OFFSET - NODE_CHILDREN_ALIGN - field_gap;
+        // The -NODE_CHILDREN_ALIGN is for the `len` field in `NodeChildrenInternal`,
+        // and the -size_of::<Option<Summaries>>() for the metric summaries in
+        // `NodeChildren`.
+        let target_size = TARGET_TOTAL_SIZE
+            - START_OFFSET
+            - NODE_CHILDREN_ALIGN
+            - field_gap
+            - size_of::<Option<Summaries>>();
 
         target_size / (size_of::<Arc<u8>>() + size_of::<TextInfo>())
     };
@@ -1,15 +1,19 @@
 use std::sync::Arc;
 
 use crate::str_utils::{
-    byte_to_char_idx, byte_to_line_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx,
+    byte_to_char_idx, byte_to_utf16_surrogate_idx, char_to_byte_idx, LineBreakMode,
 };
 use crate::tree::node_text::fix_segment_seam;
 use crate::tree::{
-    Count, NodeChildren, NodeText, TextInfo, MAX_BYTES, MAX_CHILDREN, MIN_BYTES, MIN_CHILDREN,
+    Count, LineBreaks, NodeChildren, NodeText, TextInfo, Unit, MAX_BYTES, MAX_CHILDREN, MIN_BYTES,
+    MIN_CHILDREN,
 };
 
+// Node sizes are balanced via the constants in `tree/mod.rs`, except with
+// the tiny test constants, where `Internal` ends up much bigger than `Leaf`.
 #[derive(Debug, Clone)]
 #[repr(u8, C)]
+#[allow(clippy::large_enum_variant)]
 pub(crate) enum Node {
     Leaf(NodeText),
     Internal(NodeChildren),
@@ -34,10 +38,10 @@ impl Node {
         self.text_info().chars as usize
     }
 
-    /// Total number of line breaks in the Rope.
+    /// Total number of line breaks in the Rope, as recognized by `mode`.
     #[inline(always)]
-    pub fn line_break_count(&self) -> usize {
-        self.text_info().line_breaks as usize
+    pub fn line_break_count(&self, mode: LineBreakMode) -> usize {
+        self.text_info().line_breaks.get(mode)
     }
 
     /// Total number of line breaks in the Rope.
@@ -48,8 +52,9 @@ impl Node {
 
     /// Fetches a chunk mutably, and allows it to be edited via a closure.
     ///
-    /// There are three parameters:
-    /// - char_idx: the chunk that contains this char is fetched,
+    /// There are four parameters:
+    /// - idx: the chunk that contains this byte or char is fetched,
+    /// - unit: whether `idx` is a byte or char index,
     /// - node_info: this is the text info of the node it's being called on.
     ///              This makes it a little awkward to call, but is needed since
     ///              it's actually the parent node that contains the text info,
@@ -58,8 +63,8 @@ impl Node {
     ///
     /// The closure is effectively the termination case for the recursion,
     /// and takes essentially same parameters and returns the same things as
-    /// the method itself.  In particular, the closure receives the char offset
-    /// of char_idx within the given chunk and the TextInfo of the chunk.
+    /// the method itself.  In particular, the closure receives the offset of
+    /// idx within the given chunk (in `unit`s) and the TextInfo of the chunk.
     /// The main difference is that it receives a NodeText instead of a node.
     ///
     /// The closure is expected to return the updated text info of the node,
@@ -71,9 +76,10 @@ impl Node {
     /// It is up to the caller to check for that new node, and handle it by
     /// creating a new root with both the original node and the new node as
     /// children.
-    pub fn edit_chunk_at_char<F>(
+    pub fn edit_chunk_at<F>(
         &mut self,
-        char_idx: usize,
+        idx: usize,
+        unit: Unit,
         node_info: TextInfo,
         mut edit: F,
     ) -> (TextInfo, Option<(TextInfo, Arc<Node>)>)
@@ -81,7 +87,7 @@ impl Node {
         F: FnMut(usize, TextInfo, &mut NodeText) -> (TextInfo, Option<(TextInfo, Arc<Node>)>),
     {
         match *self {
-            Node::Leaf(ref mut leaf_text) => edit(char_idx, node_info, leaf_text),
+            Node::Leaf(ref mut leaf_text) => edit(idx, node_info, leaf_text),
             Node::Internal(ref mut children) => {
                 // Compact leaf children if we're very close to maximum leaf
                 // fragmentation.  This basically guards against excessive memory
@@ -97,12 +103,12 @@ impl Node {
                 }
 
                 // Find the child we care about.
-                let (child_i, acc_char_idx) = children.search_char_idx_only(char_idx);
+                let (child_i, acc_idx) = children.search_idx_only(idx, unit);
                 let info = children.info()[child_i];
 
                 // Recurse into the child.
                 let (l_info, residual) = Arc::make_mut(&mut children.nodes_mut()[child_i])
-                    .edit_chunk_at_char(char_idx - acc_char_idx, info, edit);
+                    .edit_chunk_at(idx - acc_idx, unit, info, edit);
                 children.info_mut()[child_i] = l_info;
 
                 // Handle the residual node if there is one and return.
@@ -125,81 +131,116 @@ impl Node {
         }
     }
 
-    /// Removes chars in the range `start_idx..end_idx`.
+    /// Removes the bytes or chars (depending on `unit`) in the range
+    /// `start_idx..end_idx`, and inserts `ins_text` in their place.
+    ///
+    /// This is done in a single traversal of the tree: `ins_text` goes into
+    /// the leaf where the range starts, and everything else in the range is
+    /// removed on the way.  `ins_text` must be no larger than
+    /// `MAX_BYTES - 4`, and may be empty for a plain removal.
     ///
     /// Returns (in this order):
     /// - The updated TextInfo for the node.
-    /// - Whether there's a possible CRLF seam that needs fixing.
-    /// - Whether fix_tree_seam() needs to be run after this.
+    /// - The right-hand node along with its TextInfo, if the node had to be
+    ///   split to fit `ins_text`.  As with `edit_chunk_at()`, it is up
+    ///   to the caller to handle that.
+    /// - Whether there's a possible CRLF seam that needs fixing, at either
+    ///   edge of `ins_text`.
+    /// - Whether fix_tree_seam() needs to be run after this, at the end of
+    ///   `ins_text`.
     ///
     /// WARNING: does not correctly handle all text being removed.  That
     /// should be special-cased in calling code.
-    pub fn remove_char_range(
+    #[allow(clippy::type_complexity)]
+    pub fn replace_range(
         &mut self,
         start_idx: usize,
         end_idx: usize,
+        unit: Unit,
+        ins_text: &str,
         node_info: TextInfo,
-    ) -> (TextInfo, bool, bool) {
-        if start_idx == end_idx {
-            return (node_info, false, false);
+    ) -> (TextInfo, Option<(TextInfo, Arc<Node>)>, bool, bool) {
+        if start_idx == end_idx && ins_text.is_empty() {
+            return (node_info, None, false, false);
         }
 
         match *self {
             // If it's a leaf
             Node::Leaf(ref mut leaf_text) => {
-                let byte_start = char_to_byte_idx(leaf_text, start_idx);
+                let byte_start = unit.to_byte_idx(leaf_text, start_idx);
                 let byte_end =
-                    byte_start + char_to_byte_idx(&leaf_text[byte_start..], end_idx - start_idx);
+                    byte_start + unit.to_byte_idx(&leaf_text[byte_start..], end_idx - start_idx);
+
+                // Replace text and calculate new info & seam info
+                if !ins_text.is_empty() {
+                    let old_len = leaf_text.len();
+                    leaf_text.remove_range(byte_start, byte_end);
+                    let r_text = if (leaf_text.len() + ins_text.len()) <= MAX_BYTES {
+                        leaf_text.insert_str(byte_start, ins_text);
+                        NodeText::new()
+                    } else {
+                        leaf_text.insert_str_split(byte_start, ins_text)
+                    };
+
+                    let last_text = if !r_text.is_empty() {
+                        &r_text
+                    } else {
+                        &*leaf_text
+                    };
+                    let seam = (byte_start == 0 && leaf_text.as_bytes().first() == Some(&0x0A))
+                        || (byte_end == old_len && last_text.as_bytes().last() == Some(&0x0D));
 
+                    let residual = if !r_text.is_empty() {
+                        Some((TextInfo::from_str(&r_text), Arc::new(Node::Leaf(r_text))))
+                    } else {
+                        None
+                    };
+                    (TextInfo::from_str(leaf_text), residual, seam, false)
+                }
                 // Remove text and calculate new info & seam info
-                if byte_start > 0 || byte_end < leaf_text.len() {
+                else if byte_start > 0 || byte_end < leaf_text.len() {
                     let seam = (byte_start == 0 && leaf_text.as_bytes()[byte_end] == 0x0A)
                         || (byte_end == leaf_text.len()
                             && leaf_text.as_bytes()[byte_start - 1] == 0x0D);
 
                     let seg_len = byte_end - byte_start; // Length of removal segement
                     if seg_len < (leaf_text.len() - seg_len) {
-                        #[allow(unused_mut)]
                         let mut info =
                             node_info - TextInfo::from_str(&leaf_text[byte_start..byte_end]);
 
                         // Check for CRLF pairs on the removal seams, and
                         // adjust line break counts accordingly.
-                        #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
+                        if byte_end < leaf_text.len()
+                            && leaf_text.as_bytes()[byte_end - 1] == 0x0D
+                            && leaf_text.as_bytes()[byte_end] == 0x0A
                         {
-                            if byte_end < leaf_text.len()
-                                && leaf_text.as_bytes()[byte_end - 1] == 0x0D
-                                && leaf_text.as_bytes()[byte_end] == 0x0A
-                            {
-                                info.line_breaks += 1;
+                            info.line_breaks.cr = info.line_breaks.cr.wrapping_add(1);
+                        }
+                        if byte_start > 0 && leaf_text.as_bytes()[byte_start - 1] == 0x0D {
+                            if leaf_text.as_bytes()[byte_start] == 0x0A {
+                                info.line_breaks.cr = info.line_breaks.cr.wrapping_add(1);
                             }
-                            if byte_start > 0 && leaf_text.as_bytes()[byte_start - 1] == 0x0D {
-                                if leaf_text.as_bytes()[byte_start] == 0x0A {
-                                    info.line_breaks += 1;
-                                }
-                                if byte_end < leaf_text.len()
-                                    && leaf_text.as_bytes()[byte_end] == 0x0A
-                                {
-                                    info.line_breaks -= 1;
-                                }
+                            if byte_end < leaf_text.len() && leaf_text.as_bytes()[byte_end] == 0x0A
+                            {
+                                info.line_breaks.cr = info.line_breaks.cr.wrapping_sub(1);
                             }
                         }
 
                         // Remove the text
                         leaf_text.remove_range(byte_start, byte_end);
 
-                        (info, seam, false)
+                        (info, None, seam, false)
                     } else {
                         // Remove the text
                         leaf_text.remove_range(byte_start, byte_end);
 
-                        (TextInfo::from_str(leaf_text), seam, false)
+                        (TextInfo::from_str(leaf_text), None, seam, false)
                     }
                 } else {
                     // Remove all of the text
                     leaf_text.remove_range(byte_start, byte_end);
 
-                    (TextInfo::new(), true, false)
+                    (TextInfo::new(), None, true, false)
                 }
             }
 
@@ -210,29 +251,34 @@ impl Node {
                 // - Whether there's a possible CRLF seam that needs fixing.
                 // - Whether the tree may need invariant fixing.
                 // - Updated TextInfo of the node.
-                let handle_child = |children: &mut NodeChildren,
-                                    child_i: usize,
-                                    c_char_acc: usize|
-                 -> (bool, bool, TextInfo) {
-                    // Recurse into child
-                    let tmp_info = children.info()[child_i];
-                    let tmp_chars = children.info()[child_i].chars as usize;
-                    let (new_info, seam, needs_fix) =
-                        Arc::make_mut(&mut children.nodes_mut()[child_i]).remove_char_range(
-                            start_idx - c_char_acc.min(start_idx),
-                            (end_idx - c_char_acc).min(tmp_chars),
-                            tmp_info,
-                        );
-
-                    // Handle result
-                    if new_info.bytes == 0 {
-                        children.remove(child_i);
-                    } else {
-                        children.info_mut()[child_i] = new_info;
-                    }
+                // - The child's residual node, if it had to be split.
+                let handle_child =
+                    |children: &mut NodeChildren,
+                     child_i: usize,
+                     c_acc: usize,
+                     ins_text: &str|
+                     -> (bool, bool, TextInfo, Option<(TextInfo, Arc<Node>)>) {
+                        // Recurse into child
+                        let tmp_info = children.info()[child_i];
+                        let tmp_len = unit.len(&children.info()[child_i]);
+                        let (new_info, residual, seam, needs_fix) =
+                            Arc::make_mut(&mut children.nodes_mut()[child_i]).replace_range(
+                                start_idx - c_acc.min(start_idx),
+                                (end_idx - c_acc).min(tmp_len),
+                                unit,
+                                ins_text,
+                                tmp_info,
+                            );
+
+                        // Handle result
+                        if new_info.bytes == 0 && residual.is_none() {
+                            children.remove(child_i);
+                        } else {
+                            children.info_mut()[child_i] = new_info;
+                        }
 
-                    (seam, needs_fix, new_info)
-                };
+                        (seam, needs_fix, new_info, residual)
+                    };
 
                 // Shared code for merging children
                 let merge_child = |children: &mut NodeChildren, child_i: usize| {
@@ -249,14 +295,33 @@ impl Node {
                 };
 
                 // Get child info for the two char indices
-                let ((l_child_i, l_char_acc), (r_child_i, r_char_acc)) =
-                    children.search_char_idx_range(start_idx, end_idx);
+                let ((l_child_i, l_acc), (r_child_i, r_acc)) =
+                    children.search_idx_range(start_idx, end_idx, unit);
 
                 // Both indices point into the same child
                 if l_child_i == r_child_i {
                     let info = children.info()[l_child_i];
-                    let (seam, mut needs_fix, new_info) =
-                        handle_child(children, l_child_i, l_char_acc);
+                    let (seam, mut needs_fix, new_info, residual) =
+                        handle_child(children, l_child_i, l_acc, ins_text);
+
+                    // Handle the residual node if there is one and return.
+                    // Both halves of a split node are big enough, so there's
+                    // no merging to do.
+                    if let Some((r_info, r_node)) = residual {
+                        if children.len() < MAX_CHILDREN {
+                            children.insert(l_child_i + 1, (r_info, r_node));
+                            return (node_info - info + new_info + r_info, None, seam, needs_fix);
+                        } else {
+                            let r = children.insert_split(l_child_i + 1, (r_info, r_node));
+                            let r_info = r.combined_info();
+                            return (
+                                children.combined_info(),
+                                Some((r_info, Arc::new(Node::Internal(r)))),
+                                seam,
+                                needs_fix,
+                            );
+                        }
+                    }
 
                     if children.len() > 0 {
                         merge_child(children, l_child_i);
@@ -268,7 +333,7 @@ impl Node {
                         }
                     }
 
-                    return (node_info - info + new_info, seam, needs_fix);
+                    return (node_info - info + new_info, None, seam, needs_fix);
                 }
                 // We're dealing with more than one child.
                 else {
@@ -277,8 +342,7 @@ impl Node {
                     // Calculate the start..end range of nodes to be removed.
                     let r_child_exists: bool;
                     let start_i = l_child_i + 1;
-                    let end_i = if r_char_acc + children.info()[r_child_i].chars as usize == end_idx
-                    {
+                    let end_i = if r_acc + unit.len(&children.info()[r_child_i]) == end_idx {
                         r_child_exists = false;
                         r_child_i + 1
                     } else {
@@ -293,17 +357,39 @@ impl Node {
 
                     // Handle right child
                     if r_child_exists {
-                        let (_, fix, _) = handle_child(children, l_child_i + 1, r_char_acc);
+                        let (_, fix, _, _) = handle_child(children, l_child_i + 1, r_acc, "");
                         needs_fix |= fix;
                     }
 
-                    // Handle left child
-                    let (seam, fix, _) = handle_child(children, l_child_i, l_char_acc);
+                    // Handle left child, which is where the inserted text goes
+                    let (seam, fix, _, residual) =
+                        handle_child(children, l_child_i, l_acc, ins_text);
                     needs_fix |= fix;
 
+                    // Handle the residual node if there is one.  If this node
+                    // has to be split for it, the merging is left to
+                    // fix_tree_seam(), since the seam is at the split.
+                    let has_residual = residual.is_some();
+                    if let Some((r_info, r_node)) = residual {
+                        if children.len() < MAX_CHILDREN {
+                            children.insert(l_child_i + 1, (r_info, r_node));
+                        } else {
+                            let r = children.insert_split(l_child_i + 1, (r_info, r_node));
+                            let r_info = r.combined_info();
+                            return (
+                                children.combined_info(),
+                                Some((r_info, Arc::new(Node::Internal(r)))),
+                                seam,
+                                true,
+                            );
+                        }
+                    }
+
                     if children.len() > 0 {
                         // Handle merging
-                        let merge_extent = 1 + if r_child_exists { 1 } else { 0 };
+                        let merge_extent = 1
+                            + if r_child_exists { 1 } else { 0 }
+                            + if has_residual { 1 } else { 0 };
                         for i in (l_child_i..(l_child_i + merge_extent)).rev() {
                             merge_child(children, i);
                         }
@@ -316,7 +402,7 @@ impl Node {
                     }
 
                     // Return
-                    return (children.combined_info(), seam, needs_fix);
+                    return (children.combined_info(), None, seam, needs_fix);
                 }
             }
         }
@@ -528,7 +614,11 @@ impl Node {
     ///
     /// Note: for convenience, both the beginning and end of the rope are
     /// considered line breaks for indexing.
-    pub fn get_chunk_at_line_break(&self, line_break_idx: usize) -> (&str, TextInfo) {
+    pub fn get_chunk_at_line_break(
+        &self,
+        line_break_idx: usize,
+        mode: LineBreakMode,
+    ) -> (&str, TextInfo) {
         let mut node = self;
         let mut line_break_idx = line_break_idx;
         let mut info = TextInfo::new();
@@ -539,10 +629,10 @@ impl Node {
                     return (text, info);
                 }
                 Node::Internal(ref children) => {
-                    let (child_i, acc_info) = children.search_line_break_idx(line_break_idx);
+                    let (child_i, acc_info) = children.search_line_break_idx(line_break_idx, mode);
                     info += acc_info;
                     node = &*children.nodes()[child_i];
-                    line_break_idx -= acc_info.line_breaks as usize;
+                    line_break_idx -= acc_info.line_breaks.get(mode);
                 }
             }
         }
@@ -558,7 +648,16 @@ impl Node {
             chars: char_idx as Count,
             utf16_surrogates: info.utf16_surrogates
                 + byte_to_utf16_surrogate_idx(chunk, bi) as Count,
-            line_breaks: info.line_breaks + byte_to_line_idx(chunk, bi) as Count,
+            line_breaks: info.line_breaks + LineBreaks::from_byte_idx(chunk, bi),
+        }
+    }
+
+    /// Returns the byte index of the given byte or char index.
+    #[inline(always)]
+    pub fn idx_to_byte(&self, idx: usize, unit: Unit) -> usize {
+        match unit {
+            Unit::Byte => idx,
+            Unit::Char => self.char_to_text_info(idx).bytes as usize,
         }
     }
 
@@ -573,7 +672,7 @@ impl Node {
             chars: info.chars + ci as Count,
             utf16_surrogates: info.utf16_surrogates
                 + byte_to_utf16_surrogate_idx(chunk, bi) as Count,
-            line_breaks: info.line_breaks + byte_to_line_idx(chunk, bi) as Count,
+            line_breaks: info.line_breaks + LineBreaks::from_byte_idx(chunk, bi),
         }
     }
 
@@ -589,7 +688,6 @@ impl Node {
         chunk.is_char_boundary(byte_idx - info.bytes as usize)
     }
 
-    #[cfg(any(feature = "cr_lines", feature = "unicode_lines"))]
     pub fn is_crlf_split(&self, char_idx: usize) -> bool {
         let (chunk, info) = self.get_chunk_at_char(char_idx);
         let idx = char_to_byte_idx(chunk, char_idx - info.chars as usize);
@@ -927,19 +1025,21 @@ impl Node {
         }
     }
 
-    /// Fixes up the tree after remove_char_range() or Rope::append().
+    /// Fixes up the tree after replace_range() or Rope::append().
     ///
-    /// Takes the char index of the start of the removal range.
+    /// Takes the byte or char index (depending on `unit`) of the end of the
+    /// inserted text, which for a plain removal is the start of the removal
+    /// range.
     ///
     /// Returns whether it did anything or not that would affect the
     /// parent. True: did stuff, false: didn't do stuff
-    pub fn fix_tree_seam(&mut self, char_idx: usize) -> bool {
+    pub fn fix_tree_seam(&mut self, idx: usize, unit: Unit) -> bool {
         if let Node::Internal(ref mut children) = *self {
             let mut did_stuff = false;
             loop {
                 // Do merging
                 if children.len() > 1 {
-                    let (child_i, start_info) = children.search_char_idx(char_idx);
+                    let (child_i, start_idx) = children.search_idx_only(idx, unit);
                     let mut do_merge = match *children.nodes()[child_i] {
                         Node::Leaf(ref text) => text.len() < MIN_BYTES,
                         Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
@@ -951,7 +1051,7 @@ impl Node {
                         }
                     } else {
                         do_merge = do_merge
-                            || (start_info.chars as usize == char_idx
+                            || (start_idx == idx
                                 && match *children.nodes()[child_i - 1] {
                                     Node::Leaf(ref text) => text.len() < MIN_BYTES,
                                     Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
@@ -964,19 +1064,19 @@ impl Node {
                 }
 
                 // Do recursion
-                let (child_i, start_info) = children.search_char_idx(char_idx);
+                let (child_i, start_idx) = children.search_idx_only(idx, unit);
 
-                if start_info.chars as usize == char_idx && child_i != 0 {
-                    let tmp = children.info()[child_i - 1].chars as usize;
-                    let effect_1 =
-                        Arc::make_mut(&mut children.nodes_mut()[child_i - 1]).fix_tree_seam(tmp);
+                if start_idx == idx && child_i != 0 {
+                    let tmp = unit.len(&children.info()[child_i - 1]);
+                    let effect_1 = Arc::make_mut(&mut children.nodes_mut()[child_i - 1])
+                        .fix_tree_seam(tmp, unit);
                     let effect_2 =
-                        Arc::make_mut(&mut children.nodes_mut()[child_i]).fix_tree_seam(0);
+                        Arc::make_mut(&mut children.nodes_mut()[child_i]).fix_tree_seam(0, unit);
                     if (!effect_1) && (!effect_2) {
                         break;
                     }
                 } else if !Arc::make_mut(&mut children.nodes_mut()[child_i])
-                    .fix_tree_seam(char_idx - start_info.chars as usize)
+                    .fix_tree_seam(idx - start_idx, unit)
                 {
                     break;
                 }
@@ -993,7 +1093,7 @@ impl Node {
 
 #[cfg(test)]
 mod tests {
-    use crate::Rope;
+    use crate::{LineBreakMode, Rope};
 
     // 133 chars, 209 bytes
     const TEXT: &str = "\r\nHello there!  How're you doing?  It's a fine day, \
@@ -1005,7 +1105,7 @@ mod tests {
     fn line_to_byte_01() {
         let r = Rope::from_str(TEXT);
 
-        assert_eq!(3, r.root.line_break_count());
+        assert_eq!(3, r.root.line_break_count(LineBreakMode::Unicode));
         assert_eq!(0, r.line_to_byte(0));
         assert_eq!(2, r.line_to_byte(1));
         assert_eq!(93, r.line_to_byte(2));
@@ -1016,7 +1116,7 @@ mod tests {
     fn line_to_char_01() {
         let r = Rope::from_str(TEXT);
 
-        assert_eq!(3, r.root.line_break_count());
+        assert_eq!(3, r.root.line_break_count(LineBreakMode::Unicode));
         assert_eq!(0, r.line_to_char(0));
         assert_eq!(2, r.line_to_char(1));
         assert_eq!(93, r.line_to_char(2));
@@ -1038,6 +1138,8 @@ mod tests {
         let root = Node::Internal(children);
         let mut rope = Rope {
             root: Arc::new(root),
+            line_break_mode: LineBreakMode::default(),
+            metric: None,
         };
         assert_eq!(rope.char(0), '\n');
         assert_eq!(rope.len_chars(), MAX_BYTES * 2 - 1);
@@ -1061,6 +1163,8 @@ mod tests {
         let root = Node::Internal(children);
         let mut rope = Rope {
             root: Arc::new(root),
+            line_break_mode: LineBreakMode::default(),
+            metric: None,
         };
         assert_eq!(rope.char(0), '\r');
         assert_eq!(rope.len_chars(), MAX_BYTES * 2 - 1);
@@ -1,10 +1,12 @@
+use std::any::Any;
 use std::fmt;
 use std::iter::{Iterator, Zip};
 use std::slice;
 use std::sync::Arc;
 
 use crate::crlf;
-use crate::tree::{self, Node, TextInfo, MAX_BYTES};
+use crate::str_utils::LineBreakMode;
+use crate::tree::{self, Node, TextInfo, Unit, MAX_BYTES};
 
 const MAX_LEN: usize = tree::MAX_CHILDREN;
 
@@ -12,14 +14,22 @@ const MAX_LEN: usize = tree::MAX_CHILDREN;
 ///
 /// The unsafe guts of this are implemented in NodeChildrenInternal
 /// lower down in this file.
+///
+/// Alongside the children, it holds the children's summaries for the
+/// `Metric` attached to the rope, if any (see `metric.rs`).  These are
+/// dropped whenever the children are accessed mutably, since they may no
+/// longer match them afterwards, and are recomputed on demand.
 #[derive(Clone)]
 #[repr(C)]
-pub(crate) struct NodeChildren(inner::NodeChildrenInternal);
+pub(crate) struct NodeChildren(inner::NodeChildrenInternal, Option<Summaries>);
+
+/// Type-erased metric summaries of a node's children.
+pub(crate) type Summaries = Arc<dyn Any + Send + Sync>;
 
 impl NodeChildren {
     /// Creates a new empty array.
     pub fn new() -> Self {
-        NodeChildren(inner::NodeChildrenInternal::new())
+        NodeChildren(inner::NodeChildrenInternal::new(), None)
     }
 
     /// Current length of the array.
@@ -39,7 +49,7 @@ impl NodeChildren {
 
     /// Mutable access to the nodes array.
     pub fn nodes_mut(&mut self) -> &mut [Arc<Node>] {
-        self.0.nodes_mut()
+        self.inner_mut().nodes_mut()
     }
 
     /// Access to the info array.
@@ -49,17 +59,17 @@ impl NodeChildren {
 
     /// Mutable access to the info array.
     pub fn info_mut(&mut self) -> &mut [TextInfo] {
-        self.0.info_mut()
+        self.inner_mut().info_mut()
     }
 
     /// Mutable access to both the info and nodes arrays simultaneously.
     pub fn data_mut(&mut self) -> (&mut [TextInfo], &mut [Arc<Node>]) {
-        self.0.data_mut()
+        self.inner_mut().data_mut()
     }
 
     /// Updates the text info of the child at `idx`.
     pub fn update_child_info(&mut self, idx: usize) {
-        let (info, nodes) = self.0.data_mut();
+        let (info, nodes) = self.inner_mut().data_mut();
         info[idx] = nodes[idx].text_info();
     }
 
@@ -67,7 +77,7 @@ impl NodeChildren {
     ///
     /// Increases length by one.  Panics if already full.
     pub fn push(&mut self, item: (TextInfo, Arc<Node>)) {
-        self.0.push(item)
+        self.inner_mut().push(item)
     }
 
     /// Pushes an element onto the end of the array, and then splits it in half,
@@ -199,7 +209,7 @@ impl NodeChildren {
     ///
     /// Decreases length by one.  Panics if already empty.
     pub fn pop(&mut self) -> (TextInfo, Arc<Node>) {
-        self.0.pop()
+        self.inner_mut().pop()
     }
 
     /// Inserts an item into the the array at the given index.
@@ -207,7 +217,7 @@ impl NodeChildren {
     /// Increases length by one.  Panics if already full.  Preserves ordering
     /// of the other items.
     pub fn insert(&mut self, idx: usize, item: (TextInfo, Arc<Node>)) {
-        self.0.insert(idx, item)
+        self.inner_mut().insert(idx, item)
     }
 
     /// Inserts an element into a the array, and then splits it in half, returning
@@ -232,7 +242,7 @@ impl NodeChildren {
     ///
     /// Decreases length by one.  Preserves ordering of the other items.
     pub fn remove(&mut self, idx: usize) -> (TextInfo, Arc<Node>) {
-        self.0.remove(idx)
+        self.inner_mut().remove(idx)
     }
 
     /// Splits the array in two at `idx`, returning the right part of the split.
@@ -295,6 +305,16 @@ impl NodeChildren {
         acc
     }
 
+    /// The metric summaries of the children, if they're up to date.
+    pub fn summaries(&self) -> Option<&(dyn Any + Send + Sync)> {
+        self.1.as_deref()
+    }
+
+    /// Sets the metric summaries of the children.
+    pub fn set_summaries(&mut self, summaries: Summaries) {
+        self.1 = Some(summaries);
+    }
+
     /// Returns the child index and left-side-accumulated text info of the
     /// first child that matches the given predicate.
     ///
@@ -371,33 +391,34 @@ impl NodeChildren {
         (idx, accum)
     }
 
-    /// Same as `search_char_idx()` above, except that it only calulates the
-    /// left-side-accumulated _char_ index rather than the full text info.
+    /// Same as `search_char_idx()` above, except that the index can be in
+    /// either bytes or chars, and it only calulates the left-side-accumulated
+    /// index in that unit rather than the full text info.
     ///
-    /// Return is (child_index, left_acc_char_index)
+    /// Return is (child_index, left_acc_index)
     ///
     /// One-past-the end is valid, and will return the last child.
     #[inline(always)]
-    pub fn search_char_idx_only(&self, char_idx: usize) -> (usize, usize) {
+    pub fn search_idx_only(&self, idx: usize, unit: Unit) -> (usize, usize) {
         debug_assert!(self.len() > 0);
 
-        let mut accum_char_idx = 0;
-        let mut idx = 0;
+        let mut accum_idx = 0;
+        let mut child_i = 0;
         for info in self.info()[0..(self.len() - 1)].iter() {
-            let next_accum = accum_char_idx + info.chars as usize;
-            if char_idx < next_accum {
+            let next_accum = accum_idx + unit.len(info);
+            if idx < next_accum {
                 break;
             }
-            accum_char_idx = next_accum;
-            idx += 1;
+            accum_idx = next_accum;
+            child_i += 1;
         }
 
         debug_assert!(
-            char_idx <= (accum_char_idx + self.info()[idx].chars as usize) as usize,
+            idx <= accum_idx + unit.len(&self.info()[child_i]),
             "Index out of bounds."
         );
 
-        (idx, accum_char_idx)
+        (child_i, accum_idx)
     }
 
     /// Returns the child index and left-side-accumulated text info of the
@@ -408,32 +429,39 @@ impl NodeChildren {
     /// text info.
     ///
     /// One-past-the end is valid, and will return the last child.
-    pub fn search_line_break_idx(&self, line_break_idx: usize) -> (usize, TextInfo) {
-        let (idx, accum) = self.search_by(|_, end| line_break_idx <= end.line_breaks as usize);
+    pub fn search_line_break_idx(
+        &self,
+        line_break_idx: usize,
+        mode: LineBreakMode,
+    ) -> (usize, TextInfo) {
+        let (idx, accum) = self.search_by(|_, end| line_break_idx <= end.line_breaks.get(mode));
 
         debug_assert!(
-            line_break_idx <= (accum.line_breaks + self.info()[idx].line_breaks + 1) as usize,
+            line_break_idx
+                <= accum.line_breaks.get(mode) + self.info()[idx].line_breaks.get(mode) + 1,
             "Index out of bounds."
         );
 
         (idx, accum)
     }
 
-    /// Returns the child indices at the start and end of the given char
-    /// range, and returns their left-side-accumulated char indices as well.
+    /// Returns the child indices at the start and end of the given byte or
+    /// char range, and returns their left-side-accumulated indices in that
+    /// unit as well.
     ///
     /// Return is:
     /// (
-    ///     (left_node_index, left_acc_left_side_char_index),
-    ///     (right_node_index, right_acc_left_side_char_index),
+    ///     (left_node_index, left_acc_left_side_index),
+    ///     (right_node_index, right_acc_left_side_index),
     /// )
     ///
     /// One-past-the end is valid, and corresponds to the last child.
     #[inline(always)]
-    pub fn search_char_idx_range(
+    pub fn search_idx_range(
         &self,
         start_idx: usize,
         end_idx: usize,
+        unit: Unit,
     ) -> ((usize, usize), (usize, usize)) {
         debug_assert!(start_idx <= end_idx);
         debug_assert!(self.len() > 0);
@@ -443,7 +471,7 @@ impl NodeChildren {
 
         // Find left child and info
         for info in self.info()[..(self.len() - 1)].iter() {
-            let next_accum = accum_char_idx + info.chars as usize;
+            let next_accum = accum_char_idx + unit.len(info);
             if start_idx < next_accum {
                 break;
             }
@@ -455,7 +483,7 @@ impl NodeChildren {
 
         // Find right child and info
         for info in self.info()[idx..(self.len() - 1)].iter() {
-            let next_accum = accum_char_idx + info.chars as usize;
+            let next_accum = accum_char_idx + unit.len(info);
             if end_idx <= next_accum {
                 break;
             }
@@ -465,13 +493,20 @@ impl NodeChildren {
 
         #[cfg(any(test, debug_assertions))]
         assert!(
-            end_idx <= accum_char_idx + self.info()[idx].chars as usize,
+            end_idx <= accum_char_idx + unit.len(&self.info()[idx]),
             "Index out of bounds."
         );
 
         ((l_child_i, l_acc_info), (idx, accum_char_idx))
     }
 
+    /// Mutable access to the array, dropping the metric summaries.
+    #[inline(always)]
+    fn inner_mut(&mut self) -> &mut inner::NodeChildrenInternal {
+        self.1 = None;
+        &mut self.0
+    }
+
     // Debug function, to help verify tree integrity
     pub fn is_info_accurate(&self) -> bool {
         for (info, node) in self.info().iter().zip(self.nodes().iter()) {
@@ -739,6 +774,7 @@ mod inner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::str_utils::LineBreakMode::Lf;
     use crate::tree::{Node, NodeText, TextInfo};
     use std::sync::Arc;
 
@@ -828,10 +864,10 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        let at_0_0 = children.search_char_idx_range(0, 0);
-        let at_6_6 = children.search_char_idx_range(6, 6);
-        let at_12_12 = children.search_char_idx_range(12, 12);
-        let at_18_18 = children.search_char_idx_range(18, 18);
+        let at_0_0 = children.search_idx_range(0, 0, Unit::Char);
+        let at_6_6 = children.search_idx_range(6, 6, Unit::Char);
+        let at_12_12 = children.search_idx_range(12, 12, Unit::Char);
+        let at_18_18 = children.search_idx_range(18, 18, Unit::Char);
 
         assert_eq!(0, (at_0_0.0).0);
         assert_eq!(0, (at_0_0.1).0);
@@ -853,9 +889,9 @@ mod tests {
         assert_eq!(12, (at_18_18.0).1);
         assert_eq!(12, (at_18_18.1).1);
 
-        let at_0_6 = children.search_char_idx_range(0, 6);
-        let at_6_12 = children.search_char_idx_range(6, 12);
-        let at_12_18 = children.search_char_idx_range(12, 18);
+        let at_0_6 = children.search_idx_range(0, 6, Unit::Char);
+        let at_6_12 = children.search_idx_range(6, 12, Unit::Char);
+        let at_12_18 = children.search_idx_range(12, 18, Unit::Char);
 
         assert_eq!(0, (at_0_6.0).0);
         assert_eq!(0, (at_0_6.1).0);
@@ -872,8 +908,8 @@ mod tests {
         assert_eq!(12, (at_12_18.0).1);
         assert_eq!(12, (at_12_18.1).1);
 
-        let at_5_7 = children.search_char_idx_range(5, 7);
-        let at_11_13 = children.search_char_idx_range(11, 13);
+        let at_5_7 = children.search_idx_range(5, 7, Unit::Char);
+        let at_11_13 = children.search_idx_range(11, 13, Unit::Char);
 
         assert_eq!(0, (at_5_7.0).0);
         assert_eq!(1, (at_5_7.1).0);
@@ -907,7 +943,7 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        children.search_char_idx_range(18, 19);
+        children.search_idx_range(18, 19, Unit::Char);
     }
 
     #[test]
@@ -930,23 +966,41 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        assert_eq!(0, children.search_line_break_idx(0).0);
-        assert_eq!(0, children.search_line_break_idx(0).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(0, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(0, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(0, children.search_line_break_idx(1).0);
-        assert_eq!(0, children.search_line_break_idx(1).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(1, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(1, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(1, children.search_line_break_idx(2).0);
-        assert_eq!(1, children.search_line_break_idx(2).1.line_breaks);
+        assert_eq!(1, children.search_line_break_idx(2, Lf).0);
+        assert_eq!(
+            1,
+            children.search_line_break_idx(2, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(1, children.search_line_break_idx(3).0);
-        assert_eq!(1, children.search_line_break_idx(3).1.line_breaks);
+        assert_eq!(1, children.search_line_break_idx(3, Lf).0);
+        assert_eq!(
+            1,
+            children.search_line_break_idx(3, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(2, children.search_line_break_idx(4).0);
-        assert_eq!(3, children.search_line_break_idx(4).1.line_breaks);
+        assert_eq!(2, children.search_line_break_idx(4, Lf).0);
+        assert_eq!(
+            3,
+            children.search_line_break_idx(4, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(2, children.search_line_break_idx(5).0);
-        assert_eq!(3, children.search_line_break_idx(5).1.line_breaks);
+        assert_eq!(2, children.search_line_break_idx(5, Lf).0);
+        assert_eq!(
+            3,
+            children.search_line_break_idx(5, Lf).1.line_breaks.get(Lf)
+        );
     }
 
     #[test]
@@ -969,14 +1023,23 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        assert_eq!(0, children.search_line_break_idx(0).0);
-        assert_eq!(0, children.search_line_break_idx(0).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(0, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(0, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(0, children.search_line_break_idx(1).0);
-        assert_eq!(0, children.search_line_break_idx(1).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(1, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(1, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(2, children.search_line_break_idx(2).0);
-        assert_eq!(1, children.search_line_break_idx(2).1.line_breaks);
+        assert_eq!(2, children.search_line_break_idx(2, Lf).0);
+        assert_eq!(
+            1,
+            children.search_line_break_idx(2, Lf).1.line_breaks.get(Lf)
+        );
     }
 
     #[test]
@@ -989,11 +1052,17 @@ mod tests {
 
         children.update_child_info(0);
 
-        assert_eq!(0, children.search_line_break_idx(0).0);
-        assert_eq!(0, children.search_line_break_idx(0).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(0, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(0, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(0, children.search_line_break_idx(1).0);
-        assert_eq!(0, children.search_line_break_idx(1).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(1, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(1, Lf).1.line_breaks.get(Lf)
+        );
     }
 
     #[test]
@@ -1008,13 +1077,22 @@ mod tests {
 
         children.update_child_info(0);
 
-        assert_eq!(0, children.search_line_break_idx(0).0);
-        assert_eq!(0, children.search_line_break_idx(0).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(0, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(0, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(0, children.search_line_break_idx(1).0);
-        assert_eq!(0, children.search_line_break_idx(1).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(1, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(1, Lf).1.line_breaks.get(Lf)
+        );
 
-        assert_eq!(0, children.search_line_break_idx(2).0);
-        assert_eq!(0, children.search_line_break_idx(2).1.line_breaks);
+        assert_eq!(0, children.search_line_break_idx(2, Lf).0);
+        assert_eq!(
+            0,
+            children.search_line_break_idx(2, Lf).1.line_breaks.get(Lf)
+        );
     }
 }
@@ -1,6 +1,6 @@
 use std::ops::{Add, AddAssign, Sub, SubAssign};
 
-use crate::str_utils::{count_chars, count_line_breaks, count_utf16_surrogates};
+use crate::str_utils::{char_to_byte_idx, count_chars, count_utf16_surrogates, LineBreakMode};
 use crate::tree::Count;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -8,7 +8,7 @@ pub struct TextInfo {
     pub(crate) bytes: Count,
     pub(crate) chars: Count,
     pub(crate) utf16_surrogates: Count,
-    pub(crate) line_breaks: Count,
+    pub(crate) line_breaks: LineBreaks,
 }
 
 impl TextInfo {
@@ -18,7 +18,7 @@ impl TextInfo {
             bytes: 0,
             chars: 0,
             utf16_surrogates: 0,
-            line_breaks: 0,
+            line_breaks: LineBreaks::new(),
         }
     }
 
@@ -28,7 +28,7 @@ impl TextInfo {
             bytes: text.len() as Count,
             chars: count_chars(text) as Count,
             utf16_surrogates: count_utf16_surrogates(text) as Count,
-            line_breaks: count_line_breaks(text) as Count,
+            line_breaks: LineBreaks::from_str(text),
         }
     }
 }
@@ -72,3 +72,257 @@ impl SubAssign for TextInfo {
         *self = *self - other;
     }
 }
+
+//-------------------------------------------------------------
+
+/// The unit of an index that the tree editing code descends by.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Unit {
+    Byte,
+    Char,
+}
+
+impl Unit {
+    /// The length of text with the given info, in this unit.
+    #[inline(always)]
+    pub fn len(self, info: &TextInfo) -> usize {
+        match self {
+            Unit::Byte => info.bytes as usize,
+            Unit::Char => info.chars as usize,
+        }
+    }
+
+    /// Converts an index in this unit into `text` to a byte index.
+    #[inline(always)]
+    pub fn to_byte_idx(self, text: &str, idx: usize) -> usize {
+        match self {
+            Unit::Byte => idx,
+            Unit::Char => char_to_byte_idx(text, idx),
+        }
+    }
+}
+
+//-------------------------------------------------------------
+
+/// Line break counts, broken down so that the count for any
+/// `LineBreakMode` can be derived from them.
+///
+/// CRLF pairs are counted in `lf`, and `cr` only counts CRs that aren't
+/// followed by an LF.  That way, fixing up counts when a CRLF pair is
+/// split or joined only ever touches `cr`.
+///
+/// Arithmetic on these is wrapping, because `cr` can transiently go
+/// "negative" in the middle of such a fix-up (e.g. after subtracting a
+/// segment that ends with the CR of a CRLF pair).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LineBreaks {
+    /// LF and CRLF.
+    pub(crate) lf: Count,
+    /// Lone CR.
+    pub(crate) cr: Count,
+    /// VT, FF, NEL, Line Separator, and Paragraph Separator.
+    pub(crate) unicode: Count,
+}
+
+impl LineBreaks {
+    #[inline]
+    pub fn new() -> LineBreaks {
+        LineBreaks {
+            lf: 0,
+            cr: 0,
+            unicode: 0,
+        }
+    }
+
+    #[inline]
+    pub fn from_str(text: &str) -> LineBreaks {
+        count_line_breaks(text.as_bytes(), text.len())
+    }
+
+    /// The line breaks before `byte_idx` in `text`, with the same
+    /// semantics as `LineBreakMode::byte_to_line_idx()`.
+    #[inline]
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> LineBreaks {
+        let mut i = byte_idx.min(text.len());
+        while !text.is_char_boundary(i) {
+            i -= 1;
+        }
+        count_line_breaks(text.as_bytes(), i)
+    }
+
+    /// The number of line breaks recognized by `mode`.
+    #[inline]
+    pub fn get(&self, mode: LineBreakMode) -> usize {
+        (match mode {
+            LineBreakMode::Lf => self.lf,
+            LineBreakMode::Crlf => self.lf + self.cr,
+            LineBreakMode::Unicode => self.lf + self.cr + self.unicode,
+        }) as usize
+    }
+}
+
+impl Add for LineBreaks {
+    type Output = Self;
+    #[inline]
+    fn add(self, rhs: LineBreaks) -> LineBreaks {
+        LineBreaks {
+            lf: self.lf.wrapping_add(rhs.lf),
+            cr: self.cr.wrapping_add(rhs.cr),
+            unicode: self.unicode.wrapping_add(rhs.unicode),
+        }
+    }
+}
+
+impl AddAssign for LineBreaks {
+    #[inline]
+    fn add_assign(&mut self, other: LineBreaks) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for LineBreaks {
+    type Output = Self;
+    #[inline]
+    fn sub(self, rhs: LineBreaks) -> LineBreaks {
+        LineBreaks {
+            lf: self.lf.wrapping_sub(rhs.lf),
+            cr: self.cr.wrapping_sub(rhs.cr),
+            unicode: self.unicode.wrapping_sub(rhs.unicode),
+        }
+    }
+}
+
+impl SubAssign for LineBreaks {
+    #[inline]
+    fn sub_assign(&mut self, other: LineBreaks) {
+        *self = *self - other;
+    }
+}
+
+/// Counts the line breaks of each kind that start before `end` in
+/// `bytes`, in a single pass.
+///
+/// `end` must be on a char boundary.  A CR just before `end` is only
+/// counted if it isn't followed by an LF, even if that LF is past `end`.
+fn count_line_breaks(bytes: &[u8], end: usize) -> LineBreaks {
+    const LO: u64 = 0x0101_0101_0101_0101;
+    const HI: u64 = 0x8080_8080_8080_8080;
+    const LOW7: u64 = LO * 0x7F;
+
+    // Sets the high bit of each byte of `word` that equals `byte`.
+    #[inline(always)]
+    fn eq_bytes(word: u64, byte: u8) -> u64 {
+        let x = word ^ (LO * byte as u64);
+        !(((x & LOW7) + LOW7) | x) & HI
+    }
+
+    let mut lf: Count = 0;
+    let mut cr: Count = 0;
+    let mut unicode: Count = 0;
+
+    // Handles a byte that may start a line break other than LF.
+    let mut other_break = |i: usize| match bytes[i] {
+        0x0B | 0x0C => unicode += 1,
+        0x0D if bytes.get(i + 1) != Some(&0x0A) => cr += 1,
+        0xC2 if bytes[i + 1] == 0x85 => unicode += 1,
+        0xE2 if bytes[i + 1] == 0x80 && (bytes[i + 2] | 1) == 0xA9 => unicode += 1,
+        _ => {}
+    };
+
+    // Work a block of words at a time, without branching within a block
+    // so that it can be vectorized.  LFs are counted directly, but the
+    // other line breaks are rare, so blocks that may have one are just
+    // checked byte by byte.
+    const BLOCK: usize = 64;
+    let blocks = bytes[..end].chunks_exact(BLOCK);
+    let tail_start = end - blocks.remainder().len();
+    for (block_idx, block) in blocks.enumerate() {
+        let mut lf_count = 0;
+        let mut other_bytes = 0;
+        for word in block.chunks_exact(8) {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(word);
+            let word = u64::from_le_bytes(buf);
+            lf_count += (eq_bytes(word, 0x0A) >> 7).wrapping_mul(LO) >> 56;
+            // Bytes in 0x0B-0x0D, and the lead bytes of NEL (0xC2) and
+            // LS/PS (0xE2).
+            let x = word & LOW7;
+            other_bytes |= (x + LO * (0x80 - 0x0B)) & !(x + LO * (0x80 - 0x0E)) & !word
+                | eq_bytes(word | (LO * 0x20), 0xE2);
+        }
+        lf += lf_count as Count;
+        if other_bytes & HI != 0 {
+            for i in (block_idx * BLOCK)..((block_idx + 1) * BLOCK) {
+                other_break(i);
+            }
+        }
+    }
+    for (i, &byte) in bytes[..end].iter().enumerate().skip(tail_start) {
+        if byte == 0x0A {
+            lf += 1;
+        } else {
+            other_break(i);
+        }
+    }
+
+    LineBreaks {
+        lf: lf,
+        cr: cr,
+        unicode: unicode,
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_breaks_01() {
+        // Single-pass counts match counting each mode separately, at every
+        // index, including ones in the middle of CRLF pairs and multi-byte
+        // line breaks.
+        let text = "a\r\nb\rc\nd\u{0B}\u{0C}é\u{85}\u{2028}\u{2029}\u{2027}\u{20A9}\r\r\n\
+                    long run of text without any line breaks at all, long enough\n\
+                    to span a few of the blocks that are checked at once, with\r\n\
+                    line breaks\r\nat\u{2028}the edges\nof them\u{85}\u{A9}\u{C2}\u{E2}\r";
+        for end in 0..=text.len() {
+            for start in 0..=end {
+                if !text.is_char_boundary(start) {
+                    continue;
+                }
+                let sub = &text[start..];
+                let idx = end - start;
+                let breaks = LineBreaks::from_byte_idx(sub, idx);
+                for &mode in [
+                    LineBreakMode::Lf,
+                    LineBreakMode::Crlf,
+                    LineBreakMode::Unicode,
+                ]
+                .iter()
+                {
+                    assert_eq!(
+                        breaks.get(mode),
+                        mode.byte_to_line_idx(sub, idx),
+                        "{} {}",
+                        start,
+                        end
+                    );
+                }
+            }
+            if text.is_char_boundary(end) {
+                let breaks = LineBreaks::from_str(&text[..end]);
+                for &mode in [
+                    LineBreakMode::Lf,
+                    LineBreakMode::Crlf,
+                    LineBreakMode::Unicode,
+                ]
+                .iter()
+                {
+                    assert_eq!(breaks.get(mode), mode.count_breaks(&text[..end]), "{}", end);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Display width of text, as rendered in a monospace terminal, built on
+//! `unicode-width`.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::iter::Chars;
+use crate::position::line_content_len_chars;
+#[cfg(feature = "graphemes")]
+use crate::segment::GraphemeWalker;
+use crate::slice::RopeSlice;
+use crate::{Error, Result};
+
+/// Returns the number of columns `c` takes up when it starts at visual
+/// column `column`.
+///
+/// Tabs advance to the next multiple of `tab_width`, and control chars
+/// (including line breaks) take up no columns.
+#[inline]
+pub(crate) fn char_width(c: char, column: usize, tab_width: usize) -> usize {
+    if c == '\t' {
+        if tab_width == 0 {
+            0
+        } else {
+            tab_width - (column % tab_width)
+        }
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// An iterator over the clusters of chars that are displayed as a single
+/// unit, yielding the first char and the length in chars of each.
+///
+/// A cluster takes up as many columns as its first char does.
+///
+/// With the `graphemes` feature enabled, the clusters are extended
+/// grapheme clusters.  Otherwise they're approximated: zero-width chars
+/// (e.g. combining marks and variation selectors) and emoji skin tone
+/// modifiers are joined onto the char before them, and a zero width joiner
+/// also joins the char after it.
+#[derive(Debug, Clone)]
+pub(crate) struct Clusters<'a> {
+    chars: Chars<'a>,
+    remaining: usize,
+    #[cfg(not(feature = "graphemes"))]
+    peeked: Option<char>,
+    #[cfg(feature = "graphemes")]
+    walker: GraphemeWalker<'a>,
+}
+
+impl<'a> Clusters<'a> {
+    /// Creates an iterator over the clusters in the `len_chars` chars of
+    /// `text` starting at `char_idx`, which must be a cluster boundary.
+    ///
+    /// The last cluster is cut short at the end of those chars.
+    pub(crate) fn new(text: RopeSlice<'a>, char_idx: usize, len_chars: usize) -> Clusters<'a> {
+        Clusters {
+            chars: text.chars_at(char_idx),
+            remaining: len_chars,
+            #[cfg(not(feature = "graphemes"))]
+            peeked: None,
+            #[cfg(feature = "graphemes")]
+            walker: GraphemeWalker::new(text, text.char_to_byte(char_idx)),
+        }
+    }
+
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        #[cfg(not(feature = "graphemes"))]
+        {
+            if let Some(c) = self.peeked.take() {
+                return Some(c);
+            }
+        }
+        self.chars.next()
+    }
+}
+
+impl<'a> Iterator for Clusters<'a> {
+    type Item = (char, usize);
+
+    #[cfg(feature = "graphemes")]
+    fn next(&mut self) -> Option<(char, usize)> {
+        let start = self.walker.byte_idx();
+        let first = self.next_char()?;
+        let end = self.walker.next_boundary().unwrap();
+
+        let mut len_chars = 1;
+        let mut byte_idx = start + first.len_utf8();
+        while byte_idx < end {
+            match self.next_char() {
+                Some(c) => {
+                    byte_idx += c.len_utf8();
+                    len_chars += 1;
+                }
+                None => break,
+            }
+        }
+        Some((first, len_chars))
+    }
+
+    #[cfg(not(feature = "graphemes"))]
+    fn next(&mut self) -> Option<(char, usize)> {
+        let first = self.next_char()?;
+        let mut len_chars = 1;
+
+        // Control chars, including line breaks, are always on their own.
+        if first.width().is_some() {
+            let mut after_zwj = first == '\u{200D}';
+            while let Some(c) = self.next_char() {
+                let joins = c != '\t'
+                    && (c.width() == Some(0)
+                        || ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+                        || (after_zwj && c.width().is_some()));
+                if !joins {
+                    self.peeked = Some(c);
+                    self.remaining += 1;
+                    break;
+                }
+                len_chars += 1;
+                after_zwj = c == '\u{200D}';
+            }
+        }
+        Some((first, len_chars))
+    }
+}
+
+/// Shared implementation of `visual_column()` for `Rope` and `RopeSlice`.
+pub(crate) fn visual_column(text: RopeSlice, char_idx: usize, tab_width: usize) -> Result<usize> {
+    let line_idx = text.try_char_to_line(char_idx)?;
+    let line_start = text.line_to_char(line_idx);
+
+    // Only clusters that end at or before `char_idx` count, so an index in
+    // the middle of one gets the column the cluster starts at.
+    let mut column = 0;
+    let mut end = line_start;
+    for (c, len_chars) in Clusters::new(text, line_start, text.len_chars() - line_start) {
+        end += len_chars;
+        if end > char_idx {
+            break;
+        }
+        column += char_width(c, column, tab_width);
+    }
+    Ok(column)
+}
+
+/// Shared implementation of `char_at_visual_column()` for `Rope` and
+/// `RopeSlice`.
+pub(crate) fn char_at_visual_column(
+    text: RopeSlice,
+    line_idx: usize,
+    column: usize,
+    tab_width: usize,
+) -> Result<usize> {
+    if line_idx >= text.len_lines() {
+        return Err(Error::LineIndexOutOfBounds(line_idx, text.len_lines()));
+    }
+
+    let line_start = text.line_to_char(line_idx);
+    let content_len = line_content_len_chars(text.line(line_idx));
+    let mut current = 0;
+    let mut start = line_start;
+    for (c, len_chars) in Clusters::new(text, line_start, content_len) {
+        current += char_width(c, current, tab_width);
+        if current > column {
+            return Ok(start);
+        }
+        start += len_chars;
+    }
+    Ok(line_start + content_len)
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::Rope;
+
+    // Tabs, wide CJK chars, a combining mark, and an emoji ZWJ sequence.
+    const TEXT: &str = "a\tbc\tこんにちは\n\
+                        e\u{301}\u{1F468}\u{200D}\u{1F469}x\r\n\
+                        \t\tend";
+
+    #[test]
+    fn visual_column_01() {
+        let r = Rope::from_str(TEXT);
+        let columns = [0, 1, 4, 5, 6, 8, 10, 12, 14, 16, 18];
+        for (i, &column) in columns.iter().enumerate() {
+            assert_eq!(r.visual_column(i, 4), column, "{}", i);
+        }
+
+        // Line 1 starts at char 11.
+        let columns = [0, 0, 1, 1, 1, 3, 4, 4];
+        for (i, &column) in columns.iter().enumerate() {
+            assert_eq!(r.visual_column(11 + i, 4), column, "{}", i);
+        }
+
+        // Line 2 starts at char 19.
+        assert_eq!(r.visual_column(19, 4), 0);
+        assert_eq!(r.visual_column(20, 4), 4);
+        assert_eq!(r.visual_column(21, 4), 8);
+        assert_eq!(r.visual_column(21, 3), 6);
+        assert_eq!(r.visual_column(21, 0), 0);
+        assert_eq!(r.visual_column(r.len_chars(), 8), 19);
+        assert!(r.try_visual_column(r.len_chars() + 1, 4).is_err());
+    }
+
+    #[test]
+    fn char_at_visual_column_01() {
+        let r = Rope::from_str(TEXT);
+        let chars = [0, 1, 1, 1, 2, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10];
+        for (column, &char_idx) in chars.iter().enumerate() {
+            assert_eq!(
+                r.char_at_visual_column(0, column, 4),
+                char_idx,
+                "{}",
+                column
+            );
+        }
+
+        // Clusters are never split, and columns past the end of the line
+        // clamp to just before its line break.
+        let chars = [11, 13, 13, 16, 17, 17, 17, 17];
+        for (column, &char_idx) in chars.iter().enumerate() {
+            assert_eq!(
+                r.char_at_visual_column(1, column, 4),
+                char_idx,
+                "{}",
+                column
+            );
+        }
+
+        assert_eq!(r.char_at_visual_column(2, 7, 4), 20);
+        assert_eq!(r.char_at_visual_column(2, 8, 4), 21);
+        assert_eq!(r.char_at_visual_column(2, 100, 4), r.len_chars());
+        assert!(r.try_char_at_visual_column(3, 0, 4).is_err());
+    }
+
+    #[test]
+    fn visual_column_02() {
+        // On a slice that starts mid-line, and round-tripping.
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(2..);
+        assert_eq!(s.visual_column(1, 4), 1);
+        assert_eq!(s.visual_column(2, 4), 2);
+        assert_eq!(s.visual_column(3, 4), 4);
+        assert_eq!(s.char_at_visual_column(0, 4, 4), 3);
+        for char_idx in 0..s.len_chars() {
+            let line_idx = s.char_to_line(char_idx);
+            let column = s.visual_column(char_idx, 4);
+            let found = s.char_at_visual_column(line_idx, column, 4);
+            assert_eq!(s.visual_column(found, 4), column, "{}", char_idx);
+        }
+    }
+
+    #[test]
+    fn visual_column_03() {
+        // Combining sequences, a skin tone modifier, and a ZWJ sequence of
+        // three emoji each take up the width of their first char.
+        let r = Rope::from_str(
+            "a\u{301}\u{302}\u{1F44D}\u{1F3FD}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}b",
+        );
+        let columns = [0, 0, 0, 1, 1, 3, 3, 3, 3, 3, 5, 6];
+        for (i, &column) in columns.iter().enumerate() {
+            assert_eq!(r.visual_column(i, 4), column, "{}", i);
+        }
+
+        let chars = [0, 3, 3, 5, 5, 10, 11];
+        for (column, &char_idx) in chars.iter().enumerate() {
+            assert_eq!(
+                r.char_at_visual_column(0, column, 4),
+                char_idx,
+                "{}",
+                column
+            );
+        }
+    }
+}
@@ -0,0 +1,816 @@
+use std::ops::Range;
+
+use crate::change_set::ChangeSet;
+use crate::position::line_content_len_chars;
+use crate::rope::Rope;
+use crate::slice::RopeSlice;
+use crate::width::{char_width, Clusters};
+
+/// How a [`WrapIndex`] soft-wraps lines.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WrapOptions {
+    /// The width of a visual row, in columns.  A width of 0 is treated as
+    /// 1.
+    pub width: usize,
+    /// The distance between tab stops, in columns.  Tab stops are measured
+    /// from the start of each visual row.
+    pub tab_width: usize,
+    /// Wrap after whitespace where possible, rather than wherever a row
+    /// fills up.  Whitespace at the end of a row is allowed to hang past
+    /// the width rather than starting a new row.
+    pub word_wrap: bool,
+}
+
+impl WrapOptions {
+    /// Creates `WrapOptions` for wrapping at `width` columns, with a tab
+    /// width of 4 and word wrapping turned on.
+    #[inline]
+    pub fn new(width: usize) -> Self {
+        WrapOptions {
+            width: width,
+            tab_width: 4,
+            word_wrap: true,
+        }
+    }
+
+    /// Sets the distance between tab stops.
+    #[inline]
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Sets whether to wrap after whitespace where possible.
+    #[inline]
+    pub fn word_wrap(mut self, yes: bool) -> Self {
+        self.word_wrap = yes;
+        self
+    }
+}
+
+/// An index of the visual rows of a `Rope` when its lines are soft-wrapped.
+///
+/// This converts between visual rows and char indices in O(log N) time,
+/// where N is the length of the text.  Columns are measured the same way
+/// as [`Rope::visual_column()`], rows are never broken in the middle of a
+/// grapheme cluster, and each line of the text takes up at least one row.
+///
+/// As with [`MarkerSet`](crate::MarkerSet), edits to the text are passed
+/// on via the `update_for_*()` methods, which take the text _after_ the
+/// edit.  Only the lines an edit touches are re-wrapped, and each edit
+/// takes O(log N) time plus the re-wrapping.
+///
+/// Only available with the `width` feature enabled.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, WrapIndex, WrapOptions};
+/// #
+/// let mut rope = Rope::from_str("The quick brown fox\njumps.");
+/// let mut wrap = WrapIndex::new(&rope, WrapOptions::new(10));
+/// assert_eq!(wrap.len_rows(), 3);
+/// assert_eq!(wrap.row_to_char(&rope, 1), 10);
+///
+/// rope.insert(0, "Look! ");
+/// wrap.update_for_insert(&rope, 0, 6);
+/// assert_eq!(
+///     wrap.rows(&rope).collect::<Vec<_>>(),
+///     ["Look! The ", "quick ", "brown fox\n", "jumps."]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct WrapIndex {
+    options: WrapOptions,
+    /// The lines of the text, in order.
+    root: Link,
+    /// State of the generator for node priorities.
+    seed: u64,
+}
+
+type Link = Option<Box<Node>>;
+
+/// A node of the tree of lines, which is a treap: ordered by line index
+/// as a binary search tree (implicitly, by the number of lines in each
+/// subtree), and by `priority` as a max-heap.
+#[derive(Debug, Clone)]
+struct Node {
+    /// The char offsets within the line at which its continuation rows
+    /// start.
+    breaks: Vec<usize>,
+    /// The number of lines in the subtree rooted at this node.
+    lines: usize,
+    /// The number of rows in the subtree rooted at this node.
+    rows: usize,
+    priority: u64,
+    left: Link,
+    right: Link,
+}
+
+impl WrapIndex {
+    /// Creates a `WrapIndex` for `text`.
+    ///
+    /// Runs in O(N) time.
+    pub fn new(text: &Rope, options: WrapOptions) -> Self {
+        let mut index = WrapIndex {
+            options: options,
+            root: None,
+            seed: 0x2545_f491_4f6c_dd1d,
+        };
+        index.root = index.build(text.lines().map(|line| wrap_line(line, &options)));
+        index
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// The options the text is wrapped with.
+    #[inline]
+    pub fn options(&self) -> WrapOptions {
+        self.options
+    }
+
+    /// Total number of visual rows.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn len_rows(&self) -> usize {
+        self.root.as_ref().map_or(0, |node| node.rows)
+    }
+
+    //-----------------------------------------------------------------------
+    // Index conversion methods
+
+    /// Returns the visual row that the given line starts on.
+    ///
+    /// Notes:
+    ///
+    /// - `line_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end row index.
+    ///
+    /// Runs in O(log L) time, where L is the number of lines.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    pub fn line_to_row(&self, line_idx: usize) -> usize {
+        assert!(
+            line_idx <= self.len_lines(),
+            "Attempt to index past end of WrapIndex: line index {}, WrapIndex line length {}",
+            line_idx,
+            self.len_lines()
+        );
+        self.rows_before(line_idx)
+    }
+
+    /// Returns the line that the given visual row is part of.
+    ///
+    /// Notes:
+    ///
+    /// - `row_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end line index.
+    ///
+    /// Runs in O(log L) time, where L is the number of lines.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_idx` is out of bounds (i.e. `row_idx > len_rows()`).
+    pub fn row_to_line(&self, row_idx: usize) -> usize {
+        self.find_row(row_idx).0
+    }
+
+    /// Returns the char index of the start of the given visual row.
+    ///
+    /// Notes:
+    ///
+    /// - `row_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end char index.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_idx` is out of bounds (i.e. `row_idx > len_rows()`).
+    pub fn row_to_char(&self, text: &Rope, row_idx: usize) -> usize {
+        let (line_idx, row_in_line) = self.find_row(row_idx);
+        if line_idx == self.len_lines() {
+            return text.len_chars();
+        }
+        text.line_to_char(line_idx) + self.row_start_offset(line_idx, row_in_line)
+    }
+
+    /// Returns the visual row that the given char is on.
+    ///
+    /// Notes:
+    ///
+    /// - `char_idx` can be one-past-the-end, which will return the last
+    ///   row.
+    /// - A char at a wrap point is on the row that starts with it.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_row(&self, text: &Rope, char_idx: usize) -> usize {
+        let line_idx = text.char_to_line(char_idx);
+        let offset = char_idx - text.line_to_char(line_idx);
+        let row_in_line = self.line_breaks(line_idx).partition_point(|&b| b <= offset);
+        self.rows_before(line_idx) + row_in_line
+    }
+
+    //-----------------------------------------------------------------------
+    // Iterator methods
+
+    /// Creates an iterator over the visual rows of `text`.
+    ///
+    /// Each row is yielded as a slice of the text, with the last row of
+    /// each line including that line's line break.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn rows<'a>(&'a self, text: &'a Rope) -> VisualRows<'a> {
+        self.rows_at(text, 0)
+    }
+
+    /// Creates an iterator over the visual rows of `text`, starting at
+    /// row `row_idx`.
+    ///
+    /// If `row_idx == len_rows()` then an iterator at the end of the
+    /// rows is created (i.e. `next()` will return `None`).
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row_idx` is out of bounds (i.e. `row_idx > len_rows()`).
+    pub fn rows_at<'a>(&'a self, text: &'a Rope, row_idx: usize) -> VisualRows<'a> {
+        let (line_idx, row_in_line) = self.find_row(row_idx);
+        VisualRows {
+            text: text,
+            index: self,
+            line_idx: line_idx,
+            row_in_line: row_in_line,
+            line_start: text.line_to_char(line_idx),
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit tracking methods
+
+    /// Updates the index for `len_chars` chars of text having been
+    /// inserted at char index `char_idx`.
+    ///
+    /// `text` is the text after the insertion.
+    pub fn update_for_insert(&mut self, text: &Rope, char_idx: usize, len_chars: usize) {
+        self.rewrap(text, char_idx..(char_idx + len_chars));
+    }
+
+    /// Updates the index for the text in `char_range` having been removed.
+    ///
+    /// `text` is the text after the removal.
+    pub fn update_for_remove(&mut self, text: &Rope, char_range: Range<usize>) {
+        self.rewrap(text, char_range.start..char_range.start);
+    }
+
+    /// Updates the index for `changes` having been applied to the text.
+    ///
+    /// `text` is the text after the changes.  Everything from the first
+    /// change to the last one is re-wrapped, so for changes that are far
+    /// apart it can be cheaper to build a new `WrapIndex`.
+    pub fn update_for_changes(&mut self, text: &Rope, changes: &ChangeSet) {
+        let ranges = changes.changed_ranges();
+        if let (Some(first), Some(last)) = (ranges.first(), ranges.last()) {
+            self.rewrap(text, first.start..last.end);
+        }
+    }
+
+    /// Re-wraps the whole text, e.g. after the options have changed.
+    ///
+    /// Runs in O(N) time.
+    pub fn set_options(&mut self, text: &Rope, options: WrapOptions) {
+        *self = WrapIndex::new(text, options);
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Re-wraps the lines touching `char_range`, where `char_range` is the
+    /// only part of `text` that differs from what the index was built for.
+    fn rewrap(&mut self, text: &Rope, char_range: Range<usize>) {
+        let old_len_lines = self.len_lines();
+        let new_len_lines = text.len_lines();
+
+        // Start a line early, in case the edit put an LF after a CR that
+        // ended the previous line.
+        let first = text.char_to_line(char_range.start).saturating_sub(1);
+        let last = text.char_to_line(char_range.end);
+        let old_last = (last + old_len_lines)
+            .checked_sub(new_len_lines)
+            .expect("WrapIndex is out of sync with the text it was updated with");
+
+        // Swap the old lines out for the re-wrapped ones.
+        let options = self.options;
+        let (left, rest) = split(self.root.take(), first);
+        let (_, right) = split(rest, old_last + 1 - first);
+        let middle =
+            self.build((first..=last).map(|line_idx| wrap_line(text.line(line_idx), &options)));
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Total number of lines.
+    #[inline]
+    fn len_lines(&self) -> usize {
+        self.root.as_ref().map_or(0, |node| node.lines)
+    }
+
+    /// The wrap points of line `line_idx`.
+    fn line_breaks(&self, mut line_idx: usize) -> &[usize] {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let left_lines = node.left.as_ref().map_or(0, |left| left.lines);
+            if line_idx < left_lines {
+                link = &node.left;
+            } else if line_idx == left_lines {
+                return &node.breaks;
+            } else {
+                line_idx -= left_lines + 1;
+                link = &node.right;
+            }
+        }
+        panic!("Attempt to index past end of WrapIndex");
+    }
+
+    /// The char offset within line `line_idx` of its `row_in_line`th row.
+    #[inline]
+    fn row_start_offset(&self, line_idx: usize, row_in_line: usize) -> usize {
+        if row_in_line == 0 {
+            0
+        } else {
+            self.line_breaks(line_idx)[row_in_line - 1]
+        }
+    }
+
+    /// The total number of rows of the lines before `line_idx`.
+    fn rows_before(&self, mut line_idx: usize) -> usize {
+        let mut link = &self.root;
+        let mut rows = 0;
+        while let Some(ref node) = *link {
+            let left_lines = node.left.as_ref().map_or(0, |left| left.lines);
+            if line_idx <= left_lines {
+                link = &node.left;
+            } else {
+                rows += node.rows - node.right.as_ref().map_or(0, |right| right.rows);
+                line_idx -= left_lines + 1;
+                link = &node.right;
+            }
+        }
+        rows
+    }
+
+    /// Returns the line that row `row_idx` is part of, and which row of
+    /// that line it is.
+    fn find_row(&self, row_idx: usize) -> (usize, usize) {
+        let len_rows = self.len_rows();
+        assert!(
+            row_idx <= len_rows,
+            "Attempt to index past end of WrapIndex: row index {}, WrapIndex row length {}",
+            row_idx,
+            len_rows
+        );
+        if row_idx == len_rows {
+            return (self.len_lines(), 0);
+        }
+
+        let mut link = &self.root;
+        let mut line_idx = 0;
+        let mut remaining = row_idx;
+        while let Some(ref node) = *link {
+            let left_rows = node.left.as_ref().map_or(0, |left| left.rows);
+            let own_rows = node.breaks.len() + 1;
+            if remaining < left_rows {
+                link = &node.left;
+            } else if remaining < left_rows + own_rows {
+                line_idx += node.left.as_ref().map_or(0, |left| left.lines);
+                return (line_idx, remaining - left_rows);
+            } else {
+                line_idx += node.left.as_ref().map_or(0, |left| left.lines) + 1;
+                remaining -= left_rows + own_rows;
+                link = &node.right;
+            }
+        }
+        unreachable!()
+    }
+
+    /// Builds a tree from the wrap points of consecutive lines.
+    ///
+    /// Runs in O(L) time, where L is the number of lines, by keeping the
+    /// right spine of the tree on a stack.
+    fn build<I>(&mut self, lines: I) -> Link
+    where
+        I: Iterator<Item = Vec<usize>>,
+    {
+        let mut spine: Vec<Box<Node>> = Vec::new();
+        for breaks in lines {
+            let mut node = Box::new(Node {
+                lines: 1,
+                rows: breaks.len() + 1,
+                breaks: breaks,
+                priority: self.next_priority(),
+                left: None,
+                right: None,
+            });
+
+            // Nodes with a lower priority become the new node's left
+            // subtree, as they come before it.
+            let mut left = None;
+            while let Some(mut top) = spine.pop() {
+                if top.priority >= node.priority {
+                    spine.push(top);
+                    break;
+                }
+                top.right = left;
+                top.update();
+                left = Some(top);
+            }
+            node.left = left;
+            spine.push(node);
+        }
+
+        let mut link = None;
+        while let Some(mut top) = spine.pop() {
+            top.right = link;
+            top.update();
+            link = Some(top);
+        }
+        link
+    }
+
+    /// Returns a pseudo-random priority for a new node (xorshift64*).
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+impl Node {
+    /// Recomputes the line and row counts from the children.
+    #[inline]
+    fn update(&mut self) {
+        self.lines = 1;
+        self.rows = self.breaks.len() + 1;
+        for child in [&self.left, &self.right] {
+            if let Some(ref child) = *child {
+                self.lines += child.lines;
+                self.rows += child.rows;
+            }
+        }
+    }
+}
+
+/// Splits the tree into its first `lines` lines and the rest.
+fn split(link: Link, lines: usize) -> (Link, Link) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            let left_lines = node.left.as_ref().map_or(0, |left| left.lines);
+            if left_lines < lines {
+                let (left, right) = split(node.right.take(), lines - left_lines - 1);
+                node.right = left;
+                node.update();
+                (Some(node), right)
+            } else {
+                let (left, right) = split(node.left.take(), lines);
+                node.left = right;
+                node.update();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+/// Joins two trees, where all lines of `a` come before those of `b`.
+fn merge(a: Link, b: Link) -> Link {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority >= b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+/// Returns the char offsets within `line` at which its continuation rows
+/// start.
+fn wrap_line(line: RopeSlice, options: &WrapOptions) -> Vec<usize> {
+    let width = options.width.max(1);
+    let mut breaks = Vec::new();
+    let mut row_start = 0;
+    let mut column = 0;
+
+    // The offset just after the last whitespace on the current row, and
+    // the column there.  Chars after it have no tabs, so their widths
+    // don't depend on where they start.
+    let mut word_break: Option<(usize, usize)> = None;
+
+    let mut i = 0;
+    for (c, len_chars) in Clusters::new(line, 0, line_content_len_chars(line)) {
+        let is_space = options.word_wrap && c.is_whitespace();
+        let mut char_cols = char_width(c, column, options.tab_width);
+        if char_cols > 0 && !is_space && column + char_cols > width && i > row_start {
+            match word_break {
+                Some((offset, offset_column)) if offset > row_start => {
+                    row_start = offset;
+                    column -= offset_column;
+                }
+                _ => {
+                    row_start = i;
+                    column = 0;
+                }
+            }
+            breaks.push(row_start);
+            word_break = None;
+
+            // The word carried over to the new row may still be too long.
+            char_cols = char_width(c, column, options.tab_width);
+            if column + char_cols > width && i > row_start {
+                row_start = i;
+                column = 0;
+                breaks.push(row_start);
+            }
+        }
+
+        column += char_cols;
+        i += len_chars;
+        if is_space {
+            word_break = Some((i, column));
+        }
+    }
+    breaks
+}
+
+/// An iterator over the visual rows of a `Rope`.
+///
+/// Created by [`WrapIndex::rows()`] and [`WrapIndex::rows_at()`].
+#[derive(Debug, Clone)]
+pub struct VisualRows<'a> {
+    text: &'a Rope,
+    index: &'a WrapIndex,
+    line_idx: usize,
+    row_in_line: usize,
+    line_start: usize,
+}
+
+impl<'a> Iterator for VisualRows<'a> {
+    type Item = RopeSlice<'a>;
+
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        if self.line_idx >= self.index.len_lines() {
+            return None;
+        }
+        let breaks = self.index.line_breaks(self.line_idx);
+        let start = self.line_start + self.index.row_start_offset(self.line_idx, self.row_in_line);
+        let end = if self.row_in_line < breaks.len() {
+            self.row_in_line += 1;
+            self.line_start + breaks[self.row_in_line - 1]
+        } else {
+            self.line_idx += 1;
+            self.row_in_line = 0;
+            self.line_start = self.text.line_to_char(self.line_idx);
+            self.line_start
+        };
+        Some(self.text.slice(start..end))
+    }
+}
+
+//===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "The quick brown fox jumps over the lazy dog.\n\
+                        \tTabbed\tline\there.\r\n\
+                        こんにちは世界、こんにちは\n\
+                        \n\
+                        Supercalifragilisticexpialidocious words.";
+
+    fn rows(index: &WrapIndex, text: &Rope) -> Vec<String> {
+        index.rows(text).map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn wrap_01() {
+        let r = Rope::from_str(TEXT);
+        let index = WrapIndex::new(&r, WrapOptions::new(12));
+        assert_eq!(
+            rows(&index, &r),
+            [
+                "The quick ",
+                "brown fox ",
+                "jumps over ",
+                "the lazy ",
+                "dog.\n",
+                "\tTabbed\t",
+                "line\t",
+                "here.\r\n",
+                "こんにちは世",
+                "界、こんにち",
+                "は\n",
+                "\n",
+                "Supercalifra",
+                "gilisticexpi",
+                "alidocious ",
+                "words.",
+            ]
+        );
+        assert_eq!(index.len_rows(), 16);
+        assert_eq!(rows(&index, &r).concat(), TEXT);
+    }
+
+    #[test]
+    fn wrap_02() {
+        // Without word wrapping.
+        let r = Rope::from_str(TEXT);
+        let index = WrapIndex::new(&r, WrapOptions::new(12).word_wrap(false).tab_width(8));
+        assert_eq!(
+            rows(&index, &r)[..8],
+            [
+                "The quick br",
+                "own fox jump",
+                "s over the l",
+                "azy dog.\n",
+                "\tTabb",
+                "ed\tline",
+                "\there",
+                ".\r\n",
+            ]
+        );
+        assert_eq!(rows(&index, &r).concat(), TEXT);
+    }
+
+    #[test]
+    fn row_to_char_01() {
+        let r = Rope::from_str(TEXT);
+        let index = WrapIndex::new(&r, WrapOptions::new(12));
+        let mut char_idx = 0;
+        for (row_idx, row) in index.rows(&r).enumerate() {
+            assert_eq!(index.row_to_char(&r, row_idx), char_idx);
+            for i in 0..row.len_chars() {
+                assert_eq!(index.char_to_row(&r, char_idx + i), row_idx);
+            }
+            char_idx += row.len_chars();
+        }
+        assert_eq!(index.row_to_char(&r, index.len_rows()), r.len_chars());
+        assert_eq!(index.char_to_row(&r, r.len_chars()), index.len_rows() - 1);
+
+        assert_eq!(index.line_to_row(0), 0);
+        assert_eq!(index.line_to_row(1), 5);
+        assert_eq!(index.line_to_row(4), 12);
+        assert_eq!(index.line_to_row(5), 16);
+        assert_eq!(index.row_to_line(4), 0);
+        assert_eq!(index.row_to_line(5), 1);
+        assert_eq!(index.row_to_line(10), 2);
+        assert_eq!(index.row_to_line(11), 3);
+        assert_eq!(index.row_to_line(16), 5);
+    }
+
+    #[test]
+    fn rows_at_01() {
+        let r = Rope::from_str(TEXT);
+        let index = WrapIndex::new(&r, WrapOptions::new(12));
+        let all = rows(&index, &r);
+        for row_idx in 0..=all.len() {
+            let found: Vec<String> = index
+                .rows_at(&r, row_idx)
+                .map(|row| row.to_string())
+                .collect();
+            assert_eq!(found, all[row_idx..]);
+        }
+    }
+
+    #[test]
+    fn update_01() {
+        // Incremental updates match a freshly built index.
+        let mut r = Rope::from_str(TEXT);
+        let options = WrapOptions::new(9);
+        let mut index = WrapIndex::new(&r, options);
+        let edits = [
+            (5, 0, "\n"),
+            (0, 3, ""),
+            (20, 10, "long inserted text that wraps"),
+            (45, 0, "\r"),
+            (47, 1, "\n"),
+            (10, 30, ""),
+            (0, 0, "a\r\nb\nc "),
+            (3, 2, ""),
+        ];
+        for &(start, len, text) in edits.iter() {
+            let start = start.min(r.len_chars());
+            let end = (start + len).min(r.len_chars());
+            r.remove(start..end);
+            index.update_for_remove(&r, start..end);
+            r.insert(start, text);
+            index.update_for_insert(&r, start, text.chars().count());
+
+            let expected = WrapIndex::new(&r, options);
+            assert_eq!(rows(&index, &r), rows(&expected, &r));
+            assert_eq!(index.len_rows(), expected.len_rows());
+            for row_idx in 0..=expected.len_rows() {
+                assert_eq!(
+                    index.row_to_char(&r, row_idx),
+                    expected.row_to_char(&r, row_idx)
+                );
+            }
+        }
+
+        index.set_options(&r, WrapOptions::new(4));
+        assert_eq!(
+            rows(&index, &r),
+            rows(&WrapIndex::new(&r, WrapOptions::new(4)), &r)
+        );
+    }
+
+    #[test]
+    fn update_02() {
+        let mut r = Rope::from_str(TEXT);
+        let options = WrapOptions::new(10);
+        let mut index = WrapIndex::new(&r, options);
+
+        let mut changes = ChangeSet::new(&r);
+        changes
+            .retain(4)
+            .delete(6)
+            .insert("slow\n")
+            .retain(30)
+            .insert("x");
+        changes.apply(&mut r).unwrap();
+        index.update_for_changes(&r, &changes);
+        assert_eq!(rows(&index, &r), rows(&WrapIndex::new(&r, options), &r));
+    }
+
+    /// Checks the heap order and counts of the tree, returning its depth.
+    fn check_tree(link: &Link) -> usize {
+        match *link {
+            None => 0,
+            Some(ref node) => {
+                let mut lines = 1;
+                let mut rows = node.breaks.len() + 1;
+                let mut depth = 0;
+                for child in [&node.left, &node.right] {
+                    if let Some(ref child) = *child {
+                        assert!(child.priority <= node.priority);
+                        lines += child.lines;
+                        rows += child.rows;
+                    }
+                    depth = depth.max(check_tree(child));
+                }
+                assert_eq!((node.lines, node.rows), (lines, rows));
+                depth + 1
+            }
+        }
+    }
+
+    #[test]
+    fn update_03() {
+        // Many edits that add and remove lines, on a text with many lines.
+        let mut r = Rope::from_str(&TEXT.repeat(40));
+        let options = WrapOptions::new(11);
+        let mut index = WrapIndex::new(&r, options);
+        assert!(check_tree(&index.root) < 40);
+
+        let mut seed = 3usize;
+        let mut next = |n: usize| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 8) % n
+        };
+        for i in 0..200 {
+            let start = next(r.len_chars() + 1);
+            let end = (start + next(80)).min(r.len_chars());
+            let text = ["\n", "a b\n\nc", "", "wrapping words here\r\n"][i % 4];
+            r.remove(start..end);
+            index.update_for_remove(&r, start..end);
+            r.insert(start, text);
+            index.update_for_insert(&r, start, text.chars().count());
+
+            check_tree(&index.root);
+            let expected = WrapIndex::new(&r, options);
+            assert_eq!(index.len_rows(), expected.len_rows());
+            for line_idx in 0..=r.len_lines() {
+                assert_eq!(index.line_to_row(line_idx), expected.line_to_row(line_idx));
+            }
+        }
+        assert_eq!(rows(&index, &r), rows(&WrapIndex::new(&r, options), &r));
+        assert!(check_tree(&index.root) < 60);
+    }
+}